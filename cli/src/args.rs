@@ -0,0 +1,179 @@
+use std::path::PathBuf;
+
+use d_merge_gui_shared::settings::DataMode;
+use skyrim_data_dir::Runtime;
+
+/// Headless `d_merge` behavior generator.
+///
+/// Every option overrides the corresponding value of the settings file.
+#[derive(Debug, clap::Parser)]
+#[command(version, about, args_conflicts_with_subcommands = true)]
+pub(crate) struct Args {
+    #[command(subcommand)]
+    pub(crate) command: Option<Command>,
+
+    /// Path of the GUI settings file. (default: `./.d_merge/d_merge_settings.json` if exists)
+    #[arg(long, short)]
+    pub(crate) settings: Option<PathBuf>,
+
+    /// Mod discovery mode.
+    #[arg(long, value_enum)]
+    pub(crate) mode: Option<ModeArg>,
+
+    /// Skyrim data directory (VFS) or mods directory glob (manual). e.g. `MO2/mods/*`
+    #[arg(long, short)]
+    pub(crate) data_dir: Option<String>,
+
+    /// Directory where the generated behaviors are written.
+    #[arg(long, short)]
+    pub(crate) output_dir: Option<String>,
+
+    /// Directory containing the hkx templates. (e.g. `./assets/templates`)
+    #[arg(long, short)]
+    pub(crate) template_dir: Option<String>,
+
+    /// Target Skyrim runtime.
+    #[arg(long, short, value_enum)]
+    pub(crate) runtime: Option<RuntimeArg>,
+
+    /// Enable every discovered mod, including ones not yet in the settings mod list.
+    #[arg(long)]
+    pub(crate) enable_all: bool,
+
+    /// Remove `<output_dir>/meshes` before generation.
+    #[arg(long)]
+    pub(crate) remove_meshes: bool,
+
+    /// Output debug patch/merged json and merged xml under `<output_dir>/.d_merge/.debug`.
+    #[arg(long)]
+    pub(crate) debug_output: bool,
+
+    /// Generate a dummy `FNIS.esp`.
+    #[arg(long)]
+    pub(crate) fnis_esp: bool,
+
+    /// Fail if an `<!-- ORIGINAL -->` block of a Nemesis patch does not match the template.
+    ///
+    /// Otherwise the mismatches are only written to `<output_dir>/.d_merge/stale_patches.json`.
+    #[arg(long)]
+    pub(crate) strict_original: bool,
+
+    /// Also read the Nemesis/FNIS patches packed in the `*.bsa` of the data dirs. (loose files take precedence)
+    ///
+    /// The vanilla archives(`Skyrim - *.bsa`) are not read.
+    #[arg(long)]
+    pub(crate) read_bsa: bool,
+
+    /// Pack the generated files into `<output_dir>/<NAME>.bsa` with a dummy `<NAME>.esp` that loads it.
+    ///
+    /// The archive format follows `--runtime`. Loose files override archived ones, so remove the previously
+    /// generated `meshes`(e.g. `--remove-meshes`).
+    #[arg(long, value_name = "NAME")]
+    pub(crate) bsa: Option<String>,
+
+    /// Pack the generated files into the zip archive at `PATH`, so that it can be installed as a mod as is.
+    #[arg(long, value_name = "PATH", conflicts_with = "bsa")]
+    pub(crate) zip: Option<PathBuf>,
+
+    /// Do not stream progress to stdout.
+    #[arg(long, short)]
+    pub(crate) quiet: bool,
+
+    /// Dry run. Print what would be generated as JSON to stdout, and write nothing.
+    ///
+    /// `--remove-meshes` is ignored and progress is not streamed.
+    #[arg(long)]
+    pub(crate) plan: bool,
+
+    /// Generate twice in memory and print the files whose bytes differ between the runs as JSON to stdout.
+    ///
+    /// Exits with `1` if any file differs. `--remove-meshes` is ignored and progress is not streamed.
+    #[arg(long, conflicts_with = "plan")]
+    pub(crate) check_reproducible: bool,
+}
+
+#[derive(Debug, clap::Subcommand)]
+pub(crate) enum Command {
+    /// Diff two behavior files(hkx/xml/json) or two output dirs class by class, and print it as JSON to stdout.
+    ///
+    /// Objects are lined up by name and graph position, not by the `#NNNN` index.
+    /// For dirs, prints the number of added/removed/changed objects of each file.
+    Diff {
+        /// Old file or dir. (e.g. vanilla template)
+        old: PathBuf,
+        /// New file or dir. (e.g. generated behavior)
+        new: PathBuf,
+    },
+
+    /// Write the changes of edited behavior files(hkx/xml) as a Nemesis patch mod `<OUTPUT_DIR>/Nemesis_Engine/mod/<ID>`.
+    ///
+    /// Each file is compared with its template in `--template-dir`.
+    /// Prints the written files as JSON to stdout.
+    NemesisMod {
+        /// Edited behavior files under `meshes`. (e.g. `meshes/actors/character/behaviors/0_master.hkx`)
+        #[arg(required = true)]
+        files: Vec<PathBuf>,
+        /// Mod code. ASCII alphanumerics or `_`, starting with a letter. (e.g. `slide`)
+        #[arg(long)]
+        id: String,
+        /// Mod name in `info.ini`. (default: `<ID>`)
+        #[arg(long)]
+        name: Option<String>,
+        /// Author in `info.ini`.
+        #[arg(long, default_value = "")]
+        author: String,
+        /// Site in `info.ini`.
+        #[arg(long, default_value = "")]
+        site: String,
+        /// Directory containing the hkx templates. (e.g. `./assets/templates`)
+        #[arg(long, short)]
+        template_dir: PathBuf,
+        /// Directory where `Nemesis_Engine` is written.
+        #[arg(long, short)]
+        output_dir: PathBuf,
+    },
+
+    /// Lint Nemesis patch files(`#NNNN.txt`) without merging, and print the findings
+    /// as `<FILE>:<LINE>:<COLUMN>: <SEVERITY>: <MESSAGE>` to stdout.
+    ///
+    /// Exits with `1` if any error is found.
+    Lint {
+        /// Patch files or dirs(e.g. `Nemesis_Engine/mod/slide`). Dirs are searched recursively for `#*.txt`.
+        #[arg(required = true)]
+        paths: Vec<PathBuf>,
+    },
+}
+
+#[derive(Debug, Clone, Copy, clap::ValueEnum)]
+pub(crate) enum ModeArg {
+    Vfs,
+    Manual,
+}
+
+impl From<ModeArg> for DataMode {
+    #[inline]
+    fn from(value: ModeArg) -> Self {
+        match value {
+            ModeArg::Vfs => Self::Vfs,
+            ModeArg::Manual => Self::Manual,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, clap::ValueEnum)]
+pub(crate) enum RuntimeArg {
+    Le,
+    Se,
+    Vr,
+}
+
+impl From<RuntimeArg> for Runtime {
+    #[inline]
+    fn from(value: RuntimeArg) -> Self {
+        match value {
+            RuntimeArg::Le => Self::Le,
+            RuntimeArg::Se => Self::Se,
+            RuntimeArg::Vr => Self::Vr,
+        }
+    }
+}
@@ -0,0 +1,104 @@
+//! errors of `This crate`
+use std::path::PathBuf;
+
+/// Process exit codes.
+pub(crate) mod exit_code {
+    /// All behaviors were generated.
+    pub(crate) const SUCCESS: u8 = 0;
+    /// Generation finished, but some patches/templates failed. See `<output_dir>/.d_merge/d_merge_errors.json`.
+    ///
+    /// Also used when a check(`lint`, `--check-reproducible`) finds problems.
+    pub(crate) const PARTIAL_FAILURE: u8 = 1;
+    /// Invalid arguments or settings. Nothing was generated.
+    ///
+    /// NOTE: Same as the `clap` usage error code.
+    pub(crate) const CONFIG_ERROR: u8 = 2;
+    /// Generation stopped(e.g. I/O error, cancelled). The outputs may be incomplete.
+    pub(crate) const FATAL: u8 = 3;
+}
+
+/// CLI Error
+#[derive(Debug, snafu::Snafu)]
+#[snafu(visibility(pub(crate)))]
+pub(crate) enum Error {
+    /// Not found settings file
+    #[snafu(display("Not found settings file: {}", path.display()))]
+    NotFoundSettings { path: PathBuf },
+
+    /// Failed to load settings file
+    #[snafu(display("{message}"))]
+    InvalidSettings { message: String },
+
+    /// Required option is not specified in either the arguments or the settings.
+    #[snafu(display("`{name}` is not specified. Pass `--{flag}` or set it in the settings file."))]
+    MissingOption { name: &'static str, flag: &'static str },
+
+    /// Failed to serialize the generation plan
+    #[snafu(display("Failed to serialize the generation plan: {source}"))]
+    SerializePlan { source: sonic_rs::Error },
+
+    /// Failed to serialize the reproducibility report
+    #[snafu(display("Failed to serialize the reproducibility report: {source}"))]
+    SerializeReproducibilityReport { source: sonic_rs::Error },
+
+    /// Failed to serialize the behavior diff
+    #[snafu(display("Failed to serialize the behavior diff: {source}"))]
+    SerializeDiff { source: sonic_rs::Error },
+
+    /// Failed to serialize the written Nemesis mod files
+    #[snafu(display("Failed to serialize the written Nemesis mod files: {source}"))]
+    SerializeNemesisMod { source: sonic_rs::Error },
+
+    /// Failed to read the files to lint
+    #[snafu(display("Failed to read {}: {source}", path.display()))]
+    ReadLintTarget { source: std::io::Error, path: PathBuf },
+
+    /// Failed to create the zip archive of `--zip`
+    #[snafu(display("Failed to create {}: {source}", path.display()))]
+    CreateZip { source: std::io::Error, path: PathBuf },
+
+    /// The lints found errors in the patches.
+    #[snafu(display("{len} error(s) found in the patches."))]
+    LintFailed { len: usize },
+
+    /// Some outputs differ between two runs with the same inputs.
+    #[snafu(display("{len} output(s) differ between two runs with the same inputs."))]
+    NotReproducible { len: usize },
+
+    #[snafu(transparent)]
+    ModInfo { source: mod_info::error::Error },
+
+    #[snafu(transparent)]
+    BehaviorGen { source: nemesis_merge::errors::Error },
+
+    #[snafu(transparent)]
+    Diff { source: serde_hkx_for_gui::diff::DiffError },
+}
+
+impl Error {
+    /// Get the process exit code corresponding to this error.
+    pub(crate) const fn exit_code(&self) -> u8 {
+        match self {
+            Self::BehaviorGen { source: nemesis_merge::errors::Error::MissingSkyrimDataDirGlob }
+            | Self::NotFoundSettings { .. }
+            | Self::InvalidSettings { .. }
+            | Self::MissingOption { .. }
+            | Self::ModInfo { .. }
+            | Self::Diff { .. }
+            | Self::ReadLintTarget { .. } => exit_code::CONFIG_ERROR,
+            Self::BehaviorGen {
+                source: nemesis_merge::errors::Error::FailedToGenerateBehaviors { .. },
+            }
+            | Self::NotReproducible { .. }
+            | Self::LintFailed { .. } => exit_code::PARTIAL_FAILURE,
+            Self::BehaviorGen { .. }
+            | Self::SerializePlan { .. }
+            | Self::SerializeReproducibilityReport { .. }
+            | Self::SerializeDiff { .. }
+            | Self::SerializeNemesisMod { .. }
+            | Self::CreateZip { .. } => exit_code::FATAL,
+        }
+    }
+}
+
+pub(crate) type Result<T, E = Error> = core::result::Result<T, E>;
@@ -0,0 +1,34 @@
+//! Headless behavior generator.
+//!
+//! Loads the same settings JSON as the GUIs (or explicit flags), collects the mod list and runs
+//! `nemesis_merge::behavior_gen`. Progress is streamed to stdout as newline-delimited JSON.
+mod args;
+mod error;
+mod run;
+
+use std::process::ExitCode;
+
+use clap::Parser as _;
+
+use crate::{args::Args, error::exit_code};
+
+fn main() -> ExitCode {
+    let args = Args::parse();
+
+    let runtime = match tokio::runtime::Builder::new_multi_thread().enable_all().build() {
+        Ok(runtime) => runtime,
+        Err(err) => {
+            eprintln!("Failed to start async runtime: {err}");
+            return ExitCode::from(exit_code::CONFIG_ERROR);
+        }
+    };
+
+    match runtime.block_on(run::run(args)) {
+        Ok(()) => ExitCode::from(exit_code::SUCCESS),
+        Err(err) => {
+            tracing::error!("{err}");
+            eprintln!("{err}");
+            ExitCode::from(err.exit_code())
+        }
+    }
+}
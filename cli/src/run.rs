@@ -0,0 +1,313 @@
+use std::{
+    io::Write as _,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use d_merge_gui_shared::{
+    log::LOG_FILENAME,
+    mod_item::{inherit_reorder_cast, to_patches},
+    settings::{DataMode, Settings},
+};
+use nemesis_merge::{
+    BsaSink, CancellationToken, Config, DebugOptions, HackOptions, NemesisModInfo, OutputSink,
+    Status, ZipSink, behavior_gen, check_reproducibility, generate_nemesis_mod, plan_behavior_gen,
+};
+use snafu::ResultExt as _;
+
+use crate::{
+    args::{Args, Command},
+    error::{
+        CreateZipSnafu, InvalidSettingsSnafu, LintFailedSnafu, MissingOptionSnafu,
+        NotFoundSettingsSnafu, NotReproducibleSnafu, ReadLintTargetSnafu, Result,
+        SerializeDiffSnafu, SerializeNemesisModSnafu, SerializePlanSnafu,
+        SerializeReproducibilityReportSnafu,
+    },
+};
+
+/// Run the behavior generation with the settings file and the arguments.
+///
+/// # Errors
+/// - Invalid settings/arguments.
+/// - Failed to generate some behaviors.
+pub(crate) async fn run(args: Args) -> Result<()> {
+    match &args.command {
+        Some(Command::Diff { old, new }) => return diff(old, new),
+        Some(Command::Lint { paths }) => return lint(paths),
+        Some(Command::NemesisMod { files, id, name, author, site, template_dir, output_dir }) => {
+            let info = NemesisModInfo {
+                id: id.clone(),
+                name: name.clone().unwrap_or_else(|| id.clone()),
+                author: author.clone(),
+                site: site.clone(),
+            };
+            return nemesis_mod(&info, files, template_dir, output_dir);
+        }
+        None => {}
+    }
+
+    let settings = load_settings(&args)?;
+
+    let _ = tracing_rotation::global::init_with_level(
+        settings.log.dir_path.as_str(),
+        LOG_FILENAME,
+        5,
+        settings.log.level,
+    );
+
+    let is_vfs = settings.behavior.mode == DataMode::Vfs;
+    let skyrim_data_dir = settings.current_skyrim_data_dir();
+    if skyrim_data_dir.trim().is_empty() {
+        return MissingOptionSnafu { name: "skyrim_data_dir", flag: "data-dir" }.fail();
+    }
+    let output_dir = settings.current_output_dir();
+    if output_dir.trim().is_empty() {
+        return MissingOptionSnafu { name: "output_dir", flag: "output-dir" }.fail();
+    }
+
+    let mod_list = {
+        let mod_infos = mod_info::get_all(skyrim_data_dir, is_vfs, settings.behavior.read_bsa)?;
+        let mut mod_list = inherit_reorder_cast(settings.mod_list(), mod_infos);
+        if args.enable_all {
+            for item in &mut mod_list {
+                item.enabled = true;
+            }
+        }
+        mod_list
+    };
+    let patches = to_patches(skyrim_data_dir, is_vfs, &mod_list);
+
+    let is_dry_run = args.plan || args.check_reproducible;
+    if args.remove_meshes && !is_dry_run {
+        remove_meshes_dir_all(output_dir, skyrim_data_dir);
+    }
+
+    let enable_debug_output = settings.behavior.enable_debug_output;
+    let output_target = settings.behavior.output_target();
+    let config = Config {
+        resource_dir: settings.behavior.template_dir.to_string().into(),
+        output_dir: output_dir.into(),
+        output_target,
+        status_report: (!args.quiet && !is_dry_run).then(|| {
+            // NOTE: This is necessary because the coercion does not happen automatically through `Option` returned by `then()`.
+            let f: Box<dyn Fn(Status) + Send + Sync> = Box::new(write_ndjson_status);
+            f
+        }),
+        hack_options: Some(HackOptions::enable_all()),
+        strict_original: args.strict_original,
+        debug: DebugOptions {
+            output_patch_json: enable_debug_output,
+            output_merged_json: enable_debug_output,
+            output_merged_xml: enable_debug_output,
+            output_provenance: enable_debug_output,
+        },
+        skyrim_data_dir_glob: Some(skyrim_data_dir.to_string()),
+        read_bsa: settings.behavior.read_bsa,
+        generate_fnis_esp: settings.behavior.generate_fnis_esp,
+        cancel_token: CancellationToken::new(),
+        output_sink: output_sink(&args, output_dir, output_target, is_dry_run)?,
+        patch_source: None,
+    };
+    tracing::info!("Starting patch with config: {config:#?}");
+
+    if args.plan {
+        let plan = plan_behavior_gen(&patches, &config).await?;
+        let json = sonic_rs::to_string_pretty(&plan).context(SerializePlanSnafu)?;
+        println!("{json}");
+        return Ok(());
+    }
+
+    if args.check_reproducible {
+        let report = check_reproducibility(&patches, &config).await?;
+        let json =
+            sonic_rs::to_string_pretty(&report).context(SerializeReproducibilityReportSnafu)?;
+        println!("{json}");
+        if !report.is_reproducible() {
+            return NotReproducibleSnafu { len: report.mismatches.len() }.fail();
+        }
+        return Ok(());
+    }
+
+    behavior_gen(patches, config).await?;
+    Ok(())
+}
+
+/// Print the semantic diff of two behavior files, or the per-file summary of two dirs.
+/// `--bsa` or `--zip`. (`None`: write files under `output_dir`)
+///
+/// The zip archive is not created on a dry run, so that it is not truncated.
+fn output_sink(
+    args: &Args,
+    output_dir: &str,
+    output_target: nemesis_merge::OutPutTarget,
+    is_dry_run: bool,
+) -> Result<Option<Arc<dyn OutputSink>>> {
+    if let Some(name) = &args.bsa {
+        return Ok(Some(Arc::new(BsaSink::new(output_dir, name, output_target))));
+    }
+    match &args.zip {
+        Some(path) if !is_dry_run => {
+            let sink =
+                ZipSink::create(path).with_context(|_| CreateZipSnafu { path: path.clone() })?;
+            Ok(Some(Arc::new(sink)))
+        }
+        _ => Ok(None),
+    }
+}
+
+fn diff(old: &Path, new: &Path) -> Result<()> {
+    use serde_hkx_for_gui::diff::{diff_dirs, diff_files};
+
+    let json = if old.is_dir() && new.is_dir() {
+        sonic_rs::to_string_pretty(&diff_dirs(old, new)?)
+    } else {
+        sonic_rs::to_string_pretty(&diff_files(old, new)?)
+    }
+    .context(SerializeDiffSnafu)?;
+    println!("{json}");
+    Ok(())
+}
+
+/// Write the Nemesis patch mod of the edited behaviors, and print the written files.
+fn nemesis_mod(
+    info: &NemesisModInfo,
+    files: &[PathBuf],
+    template_dir: &Path,
+    output_dir: &Path,
+) -> Result<()> {
+    let config = Config {
+        resource_dir: template_dir.to_path_buf(),
+        output_dir: output_dir.to_path_buf(),
+        ..Default::default()
+    };
+    let written = generate_nemesis_mod(&config, info, files)?;
+    let json = sonic_rs::to_string_pretty(&written).context(SerializeNemesisModSnafu)?;
+    println!("{json}");
+    Ok(())
+}
+
+/// Lint the patch files, and print the findings.
+fn lint(paths: &[PathBuf]) -> Result<()> {
+    use nemesis_xml::{
+        hack::{HackOptions, HackRules},
+        lint::{Severity, lint_patches},
+    };
+
+    let mut files = Vec::new();
+    for path in paths {
+        if path.is_dir() {
+            collect_patch_files(path, &mut files)?;
+        } else {
+            files.push(path.clone());
+        }
+    }
+
+    let mut sources = Vec::with_capacity(files.len());
+    for path in files {
+        let src = std::fs::read_to_string(&path).context(ReadLintTargetSnafu { path: &path })?;
+        sources.push((path, src));
+    }
+
+    // The known mistakes are warnings, since they are accepted with the hack options.
+    let rules = HackRules::builtin(HackOptions::enable_all());
+    let diagnostics =
+        lint_patches(sources.iter().map(|(path, src)| (path.as_path(), src.as_str())), &rules);
+    let mut stdout = std::io::stdout().lock();
+    for diagnostic in &diagnostics {
+        let _ = writeln!(stdout, "{diagnostic}");
+    }
+
+    let len =
+        diagnostics.iter().filter(|diagnostic| diagnostic.severity == Severity::Error).count();
+    if len > 0 {
+        return LintFailedSnafu { len }.fail();
+    }
+    Ok(())
+}
+
+/// Collect `#*.txt`(e.g. `#0052.txt`, `#slide$1.txt`) under `dir` in path order.
+///
+/// Other `.txt`(e.g. `animationdatasinglefile`) are not Nemesis XML patches.
+fn collect_patch_files(dir: &Path, files: &mut Vec<PathBuf>) -> Result<()> {
+    let mut entries = std::fs::read_dir(dir)
+        .and_then(|entries| {
+            entries.map(|entry| entry.map(|entry| entry.path())).collect::<Result<Vec<_>, _>>()
+        })
+        .context(ReadLintTargetSnafu { path: dir })?;
+    entries.sort();
+
+    for path in entries {
+        if path.is_dir() {
+            collect_patch_files(&path, files)?;
+        } else if path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("txt"))
+            && path.file_name().is_some_and(|name| name.to_string_lossy().starts_with('#'))
+        {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
+
+/// Load settings file and override it by the arguments.
+fn load_settings(args: &Args) -> Result<Settings> {
+    let mut settings = match &args.settings {
+        Some(path) => {
+            if !path.exists() {
+                return NotFoundSettingsSnafu { path }.fail();
+            }
+            Settings::load_from(path)
+        }
+        None => Settings::load(),
+    }
+    .map_err(|message| InvalidSettingsSnafu { message }.build())?;
+
+    if let Some(mode) = args.mode {
+        settings.behavior.mode = mode.into();
+    }
+    if let Some(runtime) = args.runtime {
+        settings.behavior.target_runtime = runtime.into();
+    }
+    if let Some(template_dir) = &args.template_dir {
+        settings.behavior.template_dir = template_dir.clone().into();
+    }
+    if let Some(data_dir) = &args.data_dir {
+        data_dir.clone_into(settings.current_skyrim_data_dir_mut());
+    }
+    if let Some(output_dir) = &args.output_dir {
+        output_dir.clone_into(settings.current_output_dir_mut());
+    }
+    settings.behavior.enable_debug_output |= args.debug_output;
+    settings.behavior.generate_fnis_esp |= args.fnis_esp;
+    settings.behavior.read_bsa |= args.read_bsa;
+
+    Ok(settings)
+}
+
+/// Write a `Status` as one line of JSON to stdout.
+fn write_ndjson_status(status: Status) {
+    match sonic_rs::to_string(&status) {
+        Ok(json) => {
+            let mut stdout = std::io::stdout().lock();
+            let _ = writeln!(stdout, "{json}");
+            let _ = stdout.flush();
+        }
+        Err(err) => tracing::error!("Failed to serialize status: {err}"),
+    }
+}
+
+/// Removes `<output_dir>/meshes` (and the debug cache) before patching.
+///
+/// Skipped with a warning when the output directory equals the Skyrim data
+/// directory, because deleting `meshes/` there would destroy installed mods.
+fn remove_meshes_dir_all(output_dir: &str, skyrim_data_dir: &str) {
+    use nemesis_merge::cache_remover;
+
+    if cache_remover::is_dangerous_remove(output_dir, skyrim_data_dir) {
+        tracing::warn!(
+            "0/6: `--remove-meshes` is specified but output dir equals the Skyrim data \
+             directory — skipping to avoid destroying installed mods."
+        );
+        return;
+    }
+    cache_remover::remove_meshes_dir_all(output_dir);
+}
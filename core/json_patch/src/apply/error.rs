@@ -77,6 +77,27 @@ pub enum JsonPatchError {
 
     #[snafu(display("Expected Seq. but got {unexpected:#?}"))]
     ExpectedSeq { unexpected: crate::Action },
+
+    /// The `from` of move/copy is neither a path nor a range.
+    #[snafu(display(
+        "Invalid `from`. Expected a path(e.g. `[\"#0001\", \"hkbFoo\", \"bar\"]`) or a range(e.g. `\"[1:3]\"`)\n{path}\n{value}"
+    ))]
+    InvalidFrom { path: String, value: String },
+
+    /// Tried to move a value into its own child.
+    #[snafu(display("Cannot move `{from}` into its own child `{path}`"))]
+    MoveIntoItself { from: String, path: String },
+
+    /// The value of the test operation did not match.
+    #[snafu(display("Test failed: {path}\nexpected: {expected}\nactual: {actual}"))]
+    TestFailed {
+        /// The path in the JSON structure.
+        path: String,
+        /// The JSON patch value debug representation.
+        expected: String,
+        /// The current value debug representation.
+        actual: String,
+    },
 }
 
 impl JsonPatchError {
@@ -150,4 +171,20 @@ impl JsonPatchError {
         let (path, value) = Self::format_path_value(path, value);
         Self::TryType { source, path, value }
     }
+
+    /// Creates an `InvalidFrom` error from the given path and value.
+    pub fn invalid_from_from<'a>(path: &[Cow<'a, str>], value: impl core::fmt::Debug) -> Self {
+        let (path, value) = Self::format_path_value(path, value);
+        Self::InvalidFrom { path, value }
+    }
+
+    /// Creates a `TestFailed` error from the given path, expected and actual value.
+    pub fn test_failed_from<'a>(
+        path: &[Cow<'a, str>],
+        expected: impl core::fmt::Debug,
+        actual: impl core::fmt::Debug,
+    ) -> Self {
+        let (path, expected) = Self::format_path_value(path, expected);
+        Self::TestFailed { path, expected, actual: format!("{actual:#?}") }
+    }
 }
@@ -1,3 +1,4 @@
 pub(crate) mod error;
 pub(crate) mod one_op;
+pub(crate) mod resolve;
 pub(crate) mod seq;
@@ -1,8 +1,10 @@
 use simd_json::{StaticNode, borrowed::Value, derived::ValueTryAsScalar as _};
 
+use super::index_of;
 use crate::{
     JsonPath,
     apply::error::{JsonPatchError, Result},
+    range::{Range, parse::parse_range},
 };
 
 /// Adds a new key (for objects) or a new index (for arrays) if they don't exist.
@@ -13,6 +15,7 @@ use crate::{
 /// # Note
 /// - Support `Object` or `Array`
 /// - Unsupported range remove. use `apply_range` instead.
+/// - An array index of a selector(e.g. `[?name=="Run"]+1`) inserts the value, instead of overwriting.
 pub(crate) fn apply_add<'value>(
     json: &mut Value<'value>,
     path: JsonPath<'value>,
@@ -38,7 +41,18 @@ pub(crate) fn apply_add<'value>(
                 }
             }
             Value::Array(list) => {
-                if let Ok(index) = token.parse::<usize>() {
+                // A content-addressed selector is an anchor to insert at. e.g. `[?stateId==5]+1`: after it
+                if i == last_index
+                    && let Ok(range @ Range::Find { .. }) = parse_range(&token)
+                {
+                    let index = range.resolve_index(list).ok_or_else(|| {
+                        JsonPatchError::not_found_target_from(&[token.clone()], &value)
+                    })?;
+                    list.insert(index.min(list.len()), value);
+                    return Ok(());
+                }
+
+                if let Some(index) = index_of(&token, list) {
                     while list.len() <= index {
                         list.push(Default::default()); // Push a placeholder to extend the array
                     }
@@ -184,4 +198,20 @@ mod tests {
         });
         assert_eq!(target, expected);
     }
+
+    #[test]
+    fn should_insert_after_selected_element() {
+        let mut target = json_typed!(borrowed, {
+            "states": [{ "stateId": 3 }, { "stateId": 5 }, { "stateId": 7 }]
+        });
+
+        let value = json_typed!(borrowed, { "stateId": 6 });
+        apply_add(&mut target, json_path!("states", "[?stateId==5]+1"), value)
+            .unwrap_or_else(|err| panic!("{err}"));
+
+        let expected = json_typed!(borrowed, {
+            "states": [{ "stateId": 3 }, { "stateId": 5 }, { "stateId": 6 }, { "stateId": 7 }]
+        });
+        assert_eq!(target, expected);
+    }
 }
@@ -1,12 +1,25 @@
 mod add;
+mod move_copy;
 mod remove;
 mod replace;
+mod test;
+
+use std::borrow::Cow;
 
 use simd_json::BorrowedValue;
 
-use self::{add::apply_add, remove::apply_remove, replace::apply_replace};
+use self::{
+    add::apply_add,
+    move_copy::{apply_copy, apply_move},
+    remove::apply_remove,
+    replace::apply_replace,
+    test::apply_test,
+};
 use super::error::Result;
-use crate::{Action, JsonPatch, JsonPatchError, JsonPath, ValueWithPriority, operation::Op};
+use crate::{
+    Action, JsonPatch, JsonPatchError, JsonPath, ValueWithPriority, operation::Op,
+    range::parse::parse_range,
+};
 
 /// Applies a JSON patch operation to a mutable reference to a JSON value.
 ///
@@ -24,6 +37,26 @@ pub fn apply_one_field<'v>(
         Action::Pure { op: Op::Add } => apply_add(json, path, value),
         Action::Pure { op: Op::Remove } => apply_remove(json, path),
         Action::Pure { op: Op::Replace } => apply_replace(json, path, value),
+        Action::Pure { op: Op::Move } => apply_move(json, path, value),
+        Action::Pure { op: Op::Copy } => apply_copy(json, path, value),
+        Action::Pure { op: Op::Test } => apply_test(json, &path, &value),
         unexpected => Err(JsonPatchError::mismatch_apply_type_from(unexpected, &path, &value)),
     }
 }
+
+/// Get the value of the path.
+fn get<'v, 'a>(
+    json: &'v BorrowedValue<'a>,
+    path: &[Cow<'_, str>],
+) -> Option<&'v BorrowedValue<'a>> {
+    path.iter().try_fold(json, |target, token| match target {
+        BorrowedValue::Object(map) => map.get(token.as_ref()),
+        BorrowedValue::Array(list) => list.get(index_of(token, list)?),
+        _ => None,
+    })
+}
+
+/// Array index of a path token. e.g. `1`, `[1]`, `[-1]`, `[?name=="Run"]`
+fn index_of(token: &str, list: &[BorrowedValue<'_>]) -> Option<usize> {
+    token.parse().ok().or_else(|| parse_range(token).ok()?.resolve_index(list))
+}
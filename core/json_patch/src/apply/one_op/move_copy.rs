@@ -0,0 +1,129 @@
+use std::borrow::Cow;
+
+use simd_json::{ValueBuilder as _, borrowed::Value};
+
+use super::{add::apply_add, get, remove::remove};
+use crate::{
+    JsonPath,
+    apply::error::{JsonPatchError, Result},
+};
+
+/// Remove the value at `from` and add it to `path`.
+///
+/// # Errors
+/// - If `from` is not a path or not found.
+/// - If `path` is a child of `from`.
+pub(crate) fn apply_move<'a>(
+    json: &mut Value<'a>,
+    path: JsonPath<'a>,
+    from: Value<'a>,
+) -> Result<()> {
+    let from = to_from_path(&path, from)?;
+    if from == path {
+        return Ok(());
+    }
+    if path.starts_with(&from) {
+        return Err(JsonPatchError::MoveIntoItself { from: from.join("/"), path: path.join("/") });
+    }
+
+    let value = remove(json, &from)
+        .ok_or_else(|| JsonPatchError::not_found_target_from(&from, Value::null()))?;
+    apply_add(json, path, value)
+}
+
+/// Add a copy of the value at `from` to `path`.
+///
+/// # Errors
+/// If `from` is not a path or not found.
+pub(crate) fn apply_copy<'a>(
+    json: &mut Value<'a>,
+    path: JsonPath<'a>,
+    from: Value<'a>,
+) -> Result<()> {
+    let from = to_from_path(&path, from)?;
+    let value = get(json, &from)
+        .ok_or_else(|| JsonPatchError::not_found_target_from(&from, Value::null()))?
+        .clone();
+    apply_add(json, path, value)
+}
+
+/// `["#0001", "hkbFoo", "bar"]` -> json path
+fn to_from_path<'a>(path: &[Cow<'a, str>], from: Value<'a>) -> Result<JsonPath<'a>> {
+    let Value::Array(components) = from else {
+        return Err(JsonPatchError::invalid_from_from(path, &from));
+    };
+    if components.is_empty() || !components.iter().all(|c| matches!(c, Value::String(_))) {
+        return Err(JsonPatchError::invalid_from_from(path, Value::Array(components)));
+    }
+
+    Ok(components
+        .into_iter()
+        .filter_map(|component| match component {
+            Value::String(component) => Some(component),
+            _ => None,
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use simd_json::json_typed;
+
+    use super::*;
+    use crate::json_path;
+
+    #[test]
+    fn move_object_key() {
+        let mut target = json_typed!(borrowed, {
+            "a": { "name": "John" },
+            "b": {}
+        });
+
+        let from = json_typed!(borrowed, ["a", "name"]);
+        apply_move(&mut target, json_path!["b", "name"], from)
+            .unwrap_or_else(|err| panic!("{err}"));
+
+        let expected = json_typed!(borrowed, {
+            "a": {},
+            "b": { "name": "John" }
+        });
+        assert_eq!(target, expected);
+    }
+
+    #[test]
+    fn copy_array_element() {
+        let mut target = json_typed!(borrowed, {
+            "items": ["a", "b"],
+            "first": null
+        });
+
+        let from = json_typed!(borrowed, ["items", "[0]"]);
+        apply_copy(&mut target, json_path!["first"], from).unwrap_or_else(|err| panic!("{err}"));
+
+        let expected = json_typed!(borrowed, {
+            "items": ["a", "b"],
+            "first": "a"
+        });
+        assert_eq!(target, expected);
+    }
+
+    #[test]
+    fn should_fail_move_into_itself() {
+        let mut target = json_typed!(borrowed, { "a": { "b": 1 } });
+
+        let from = json_typed!(borrowed, ["a"]);
+        let result = apply_move(&mut target, json_path!["a", "c"], from);
+        assert_eq!(
+            result,
+            Err(JsonPatchError::MoveIntoItself { from: "a".to_string(), path: "a/c".to_string() })
+        );
+    }
+
+    #[test]
+    fn should_fail_invalid_from() {
+        let mut target = json_typed!(borrowed, { "a": 1 });
+
+        let result = apply_copy(&mut target, json_path!["b"], json_typed!(borrowed, "a"));
+        assert!(matches!(result, Err(JsonPatchError::InvalidFrom { .. })));
+    }
+}
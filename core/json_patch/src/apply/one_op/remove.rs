@@ -2,6 +2,7 @@ use std::borrow::Cow;
 
 use simd_json::{ValueBuilder, borrowed::Value};
 
+use super::index_of;
 use crate::{
     JsonPath,
     apply::error::{JsonPatchError, Result},
@@ -19,7 +20,10 @@ pub(crate) fn apply_remove<'a>(json: &mut Value<'a>, path: JsonPath<'a>) -> Resu
 }
 
 /// Removes the value at the given `ptr` path and returns it, if it exists.
-fn remove<'value>(target: &mut Value<'value>, path: &[Cow<'value, str>]) -> Option<Value<'value>> {
+pub(super) fn remove<'value>(
+    target: &mut Value<'value>,
+    path: &[Cow<'value, str>],
+) -> Option<Value<'value>> {
     if path.is_empty() {
         return None;
     }
@@ -32,7 +36,7 @@ fn remove<'value>(target: &mut Value<'value>, path: &[Cow<'value, str>]) -> Opti
         return match target {
             Value::Object(map) => map.remove(last),
             Value::Array(list) => {
-                let index = last.parse::<usize>().ok()?;
+                let index = index_of(last, list)?;
                 if index < list.len() { Some(list.remove(index)) } else { None }
             }
             _ => None,
@@ -42,7 +46,10 @@ fn remove<'value>(target: &mut Value<'value>, path: &[Cow<'value, str>]) -> Opti
     // Navigate to the second-to-last element in the path
     let parent = path.try_fold(target, |target, token| match target {
         Value::Object(map) => map.get_mut(token),
-        Value::Array(list) => list.get_mut(last.parse::<usize>().ok()?),
+        Value::Array(list) => {
+            let index = index_of(token, list)?;
+            list.get_mut(index)
+        }
         _ => None,
     })?;
 
@@ -50,7 +57,7 @@ fn remove<'value>(target: &mut Value<'value>, path: &[Cow<'value, str>]) -> Opti
     match parent {
         Value::Object(map) => map.remove(last),
         Value::Array(list) => {
-            let index = last.parse::<usize>().ok()?;
+            let index = index_of(last, list)?;
             if index < list.len() { Some(list.remove(index)) } else { None }
         }
         _ => None,
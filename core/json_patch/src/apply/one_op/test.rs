@@ -0,0 +1,46 @@
+use std::borrow::Cow;
+
+use simd_json::borrowed::Value;
+
+use super::get;
+use crate::apply::error::{JsonPatchError, Result};
+
+/// Test that the value at `path` is equal to `expected`.
+///
+/// # Errors
+/// If not found or not equal.
+pub(crate) fn apply_test<'a>(
+    json: &Value<'a>,
+    path: &[Cow<'a, str>],
+    expected: &Value<'a>,
+) -> Result<()> {
+    let actual =
+        get(json, path).ok_or_else(|| JsonPatchError::not_found_target_from(path, expected))?;
+
+    if actual == expected {
+        Ok(())
+    } else {
+        Err(JsonPatchError::test_failed_from(path, expected, actual))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use simd_json::json_typed;
+
+    use super::*;
+    use crate::json_path;
+
+    #[test]
+    fn test_value() {
+        let target = json_typed!(borrowed, {
+            "data": { "items": [1, 2] }
+        });
+
+        let path = json_path!["data", "items", "[1]"];
+        assert_eq!(apply_test(&target, &path, &json_typed!(borrowed, 2)), Ok(()));
+
+        let result = apply_test(&target, &path, &json_typed!(borrowed, 3));
+        assert!(matches!(result, Err(JsonPatchError::TestFailed { .. })));
+    }
+}
@@ -0,0 +1,174 @@
+use std::borrow::Cow;
+
+use simd_json::borrowed::Value;
+
+use crate::{
+    Action, JsonPatch, JsonPath, Op,
+    apply::error::{JsonPatchError, Result},
+    range::{Range, parse::parse_range},
+};
+
+/// Resolve the content-addressed selectors(e.g. `[?name=="Run"]`, `[-1]`) of `path` to the array indices of `json`.
+///
+/// This makes the patches that select the same element by different selectors have the same path,
+/// so resolve them against the template before patching, and before resolving conflicts by priority.
+///
+/// # Note
+/// - `Seq`: The last selector is the anchor of the range. The path becomes its array, and the range is shifted by
+///   its index. e.g. `[.., "eventNames", "[?@==\"Run\"]+1"]` with `Add(0..0)` -> `[.., "eventNames"]` with `Add(2..2)`
+/// - `Pure` add: The last `[?key==value]` is kept, since it is an anchor to insert at. (See `apply_add`)
+/// - The path below a missing field is kept as is, since it may be added by another patch.
+///
+/// # Errors
+/// If a selector matches no element.
+pub fn resolve_selectors<'a>(
+    json: &Value<'_>,
+    mut path: JsonPath<'a>,
+    patch: &mut JsonPatch<'_>,
+) -> Result<JsonPath<'a>> {
+    if !has_selectors(&path) {
+        return Ok(path);
+    }
+
+    let last_index = path.len() - 1;
+    let mut target = json;
+    for i in 0..path.len() {
+        let list = match target {
+            Value::Object(map) => match map.get(path[i].as_ref()) {
+                Some(next) => {
+                    target = next;
+                    continue;
+                }
+                None => break,
+            },
+            Value::Array(list) => list,
+            _ => break,
+        };
+
+        let index = if is_selector(&path[i]) {
+            let selector = parse_range(&path[i])
+                .map_err(|_| JsonPatchError::not_found_target_from(&path[..=i], &patch.value))?;
+            let index = selector
+                .resolve_index(list)
+                .ok_or_else(|| JsonPatchError::not_found_target_from(&path[..=i], &patch.value))?;
+
+            if i == last_index {
+                match &mut patch.action {
+                    Action::Seq { range, .. } => {
+                        *range = range.start + index..range.end + index;
+                        path.pop();
+                        return Ok(path);
+                    }
+                    Action::Pure { op: Op::Add } if matches!(selector, Range::Find { .. }) => {
+                        return Ok(path);
+                    }
+                    _ => {}
+                }
+            }
+            path[i] = Cow::Owned(format!("[{index}]"));
+            index
+        } else {
+            let token = path[i].as_ref();
+            match token.parse().ok().or_else(|| parse_range(token).ok()?.resolve_index(list)) {
+                Some(index) => index,
+                None => break,
+            }
+        };
+
+        match list.get(index) {
+            Some(next) => target = next,
+            None => break,
+        }
+    }
+
+    Ok(path)
+}
+
+/// Does `path` have a content-addressed selector? e.g. `[?name=="Run"]`, `[-1]`
+pub fn has_selectors(path: &[Cow<'_, str>]) -> bool {
+    path.iter().any(|token| is_selector(token))
+}
+
+/// Is `token` a content-addressed selector? e.g. `[?name=="Run"]`, `[-1]`
+fn is_selector(token: &str) -> bool {
+    token.starts_with("[?") || token.starts_with("[-")
+}
+
+#[cfg(test)]
+mod tests {
+    use simd_json::json_typed;
+
+    use super::*;
+    use crate::json_path;
+
+    fn template() -> Value<'static> {
+        json_typed!(borrowed, {
+            "#0001": {
+                "hkbBehaviorGraphStringData": {
+                    "eventNames": ["Walk", "Run", "Sprint"],
+                    "eventInfos": [{ "name": "Walk", "flags": 0 }, { "name": "Run", "flags": 0 }]
+                }
+            }
+        })
+    }
+
+    #[test]
+    fn resolve_one_field_selectors() {
+        let mut patch =
+            JsonPatch { action: Action::Pure { op: Op::Replace }, value: json_typed!(borrowed, 1) };
+        let path = json_path![
+            "#0001",
+            "hkbBehaviorGraphStringData",
+            "eventInfos",
+            r#"[?name=="Run"]"#,
+            "flags"
+        ];
+
+        let resolved = resolve_selectors(&template(), path, &mut patch).unwrap();
+        assert_eq!(
+            resolved,
+            json_path!["#0001", "hkbBehaviorGraphStringData", "eventInfos", "[1]", "flags"]
+        );
+
+        let path = json_path!["#0001", "hkbBehaviorGraphStringData", "eventNames", "[-1]"];
+        let resolved = resolve_selectors(&template(), path, &mut patch).unwrap();
+        assert_eq!(
+            resolved,
+            json_path!["#0001", "hkbBehaviorGraphStringData", "eventNames", "[2]"]
+        );
+
+        let path =
+            json_path!["#0001", "hkbBehaviorGraphStringData", "eventNames", r#"[?@=="Jump"]"#];
+        assert!(matches!(
+            resolve_selectors(&template(), path, &mut patch),
+            Err(JsonPatchError::NotFoundTarget { .. })
+        ));
+    }
+
+    #[test]
+    fn resolve_seq_anchor() {
+        let mut patch = JsonPatch {
+            action: Action::Seq { op: Op::Add, range: 0..0 },
+            value: json_typed!(borrowed, ["Jog"]),
+        };
+        let path =
+            json_path!["#0001", "hkbBehaviorGraphStringData", "eventNames", r#"[?@=="Walk"]+1"#];
+
+        let resolved = resolve_selectors(&template(), path, &mut patch).unwrap();
+        assert_eq!(resolved, json_path!["#0001", "hkbBehaviorGraphStringData", "eventNames"]);
+        assert_eq!(patch.action, Action::Seq { op: Op::Add, range: 1..1 });
+    }
+
+    #[test]
+    fn keep_insert_anchor() {
+        let mut patch = JsonPatch {
+            action: Action::Pure { op: Op::Add },
+            value: json_typed!(borrowed, { "name": "Jog", "flags": 0 }),
+        };
+        let path =
+            json_path!["#0001", "hkbBehaviorGraphStringData", "eventInfos", r#"[?name=="Walk"]+1"#];
+
+        let resolved = resolve_selectors(&template(), path.clone(), &mut patch).unwrap();
+        assert_eq!(resolved, path);
+    }
+}
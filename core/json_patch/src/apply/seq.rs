@@ -1,6 +1,7 @@
 use core::ops::Range;
 use std::{
     borrow::Cow,
+    collections::HashSet,
     sync::atomic::{AtomicUsize, Ordering},
 };
 
@@ -11,7 +12,7 @@ use simd_json::borrowed::Value;
 use crate::{
     Action, JsonPatch, JsonPatchError, JsonPath, Op, Result, ValueWithPriority,
     ptr_mut::PointerMut as _,
-    range::split_range::split_range_at_len,
+    range::{Range as RangeToken, parse::parse_range, split_range::split_range_at_len},
     vec_utils::{SmartExtend as _, SmartIntoIter as _, SmartIterMut as _},
 };
 
@@ -74,6 +75,10 @@ pub fn apply_seq_by_priority<'a>(
 /// # Behavior Notes
 /// - Patches are applied in ascending order of `priority`.
 /// - `Replace` with fewer elements than its range implicitly removes the extra elements.
+/// - `Move`/`Copy` insert the elements of the source range(the patch value. e.g. `"[1:3]"`, `"[?name==\"Run\"]"`)
+///   before `range.start`.
+///   `Move` removes the source.
+/// - `Test` is a guard. If the elements of its range are not the patch value, the other patches of the same priority are skipped.
 /// - This function directly modifies the array in place.
 ///
 /// # Errors
@@ -183,6 +188,9 @@ pub fn apply_seq_array_directly<'a>(
 }
 
 // Separate sorted ops into Add and others
+//
+// NOTE: The sort must be stable. Patches of the same priority and op(e.g. pushes from one mod) are applied in the given order,
+//       otherwise the resulting array order would change between runs.
 fn sort_by_priority<'a>(patches: &mut [ValueWithPriority<'a>]) {
     let cmp_fn = |a: &ValueWithPriority<'a>, b: &ValueWithPriority<'a>| {
         let ValueWithPriority { patch: a, priority: a_priority } = a;
@@ -190,18 +198,21 @@ fn sort_by_priority<'a>(patches: &mut [ValueWithPriority<'a>]) {
 
         let op_rank = |patch: &JsonPatch<'_>| match &patch.action {
             Action::Seq { op, .. } | Action::Pure { op } => match op {
-                Op::Replace => 0,
-                Op::Remove => 1,
-                Op::Add => 2,
+                Op::Test => 0,
+                Op::Replace => 1,
+                Op::Remove => 2,
+                Op::Move => 3,
+                Op::Copy => 4,
+                Op::Add => 5,
             },
-            Action::SeqPush => 3,
+            Action::SeqPush => 6,
         };
 
         a_priority.cmp(b_priority).then(op_rank(a).cmp(&op_rank(b)))
     };
 
     #[cfg(feature = "rayon")]
-    patches.par_sort_unstable_by(cmp_fn);
+    patches.par_sort_by(cmp_fn);
     #[cfg(not(feature = "rayon"))]
     patches.sort_by(cmp_fn);
 }
@@ -214,21 +225,88 @@ fn apply_ops_parallel<'a>(
     mut base: Vec<Value<'a>>,
     patches: Vec<ValueWithPriority<'a>>,
 ) -> Result<Vec<Value<'a>>> {
+    // The elements before patching, for tests and move/copy of the elements already changed by a lower priority.
+    let original = patches
+        .iter()
+        .any(|ValueWithPriority { patch, .. }| {
+            matches!(patch.action, Action::Seq { op: Op::Test | Op::Move | Op::Copy, .. })
+        })
+        .then(|| base.clone());
+    // Priorities whose test failed.
+    let mut failed_priorities = HashSet::new();
+
     let (non_add_ops, mut add_ops): (Vec<_>, Vec<_>) =
         patches.smart_iter().partition(|ValueWithPriority { patch, .. }| match &patch.action {
-            Action::Pure { op } => matches!(op, Op::Replace | Op::Remove),
-            Action::Seq { op, .. } => matches!(op, Op::Replace | Op::Remove),
+            Action::Pure { op } | Action::Seq { op, .. } => *op != Op::Add,
             Action::SeqPush => false,
         });
 
-    // Apply Replace and Remove operations
+    // Apply Test, Replace, Remove, Move and Copy operations
     for ValueWithPriority { patch, priority } in non_add_ops {
+        if failed_priorities.contains(&priority) {
+            continue;
+        }
         let JsonPatch { action, value } = patch;
 
         // Get sequence information if the action targets a sequence
         let (op, range) = action.try_as_seq()?;
 
         match op {
+            Op::Test => {
+                // Compare with the template, not with the result of lower priorities. (Same as one-field tests)
+                let target = original.as_deref().unwrap_or(&base);
+                let is_matched = match (target.get(range.clone()), &value) {
+                    (Some(slice), Value::Array(expected)) => slice == expected.as_slice(),
+                    _ => false,
+                };
+                if !is_matched {
+                    #[cfg(feature = "tracing")]
+                    tracing::info!(
+                        "[Seq: Test] Range {range:?} is not {value:?}, skipping the patches of priority {priority}"
+                    );
+                    failed_priorities.insert(priority);
+                }
+            }
+            Op::Move | Op::Copy => {
+                let from = to_source_range(&value, &base)?;
+                let Some(slice) = base.get_mut(from.clone()) else {
+                    return Err(JsonPatchError::UnexpectedRange {
+                        patch_range: from,
+                        actual_len: base.len(),
+                    });
+                };
+
+                let values: Vec<_> = slice
+                    .iter_mut()
+                    .enumerate()
+                    .map(|(i, element)| {
+                        let element = if op == Op::Move {
+                            core::mem::replace(element, MARK_AS_REMOVED)
+                        } else {
+                            element.clone()
+                        };
+                        match &original {
+                            Some(original) if element == MARK_AS_REMOVED => {
+                                original.get(from.start + i).cloned().unwrap_or(element)
+                            }
+                            _ => element,
+                        }
+                    })
+                    .collect();
+
+                // Apply it together with the adds of the same priority. (`add_ops` is sorted by priority)
+                let at = add_ops.partition_point(|add| add.priority <= priority);
+                add_ops.insert(
+                    at,
+                    ValueWithPriority {
+                        patch: JsonPatch {
+                            action: Action::Seq { op: Op::Add, range: range.start..range.start },
+                            value: values.into(),
+                        },
+                        priority,
+                    },
+                );
+            }
             Op::Replace => {
                 let values = value_as_array(value)?;
 
@@ -257,6 +335,9 @@ fn apply_ops_parallel<'a>(
     // Apply Add/SeqPush operations
     let mut offset = 0;
     for value in add_ops {
+        if failed_priorities.contains(&value.priority) {
+            continue;
+        }
         match &value.patch.action {
             Action::Seq { op: Op::Add, range } => {
                 let values = value_as_array(value.patch.value)?;
@@ -281,6 +362,31 @@ fn apply_ops_parallel<'a>(
     Ok(base)
 }
 
+/// The source range of move/copy. e.g. `"[1:3]"` -> `1..3`, `"[?name==\"Run\"]"` -> `1..2`
+fn to_source_range(value: &Value<'_>, base: &[Value<'_>]) -> Result<Range<usize>> {
+    let from = match value {
+        Value::String(from) => parse_range(from).ok(),
+        _ => None,
+    };
+
+    Ok(match from {
+        Some(RangeToken::FromTo(range)) => range,
+        Some(RangeToken::To(range)) => 0..range.end,
+        Some(RangeToken::From(range)) => range.start..base.len(),
+        Some(RangeToken::Full) => 0..base.len(),
+        Some(one) => match one.resolve_index(base) {
+            Some(index) => index..index + 1,
+            None => {
+                return Err(JsonPatchError::not_found_target_from(
+                    &[one.to_string().into()],
+                    value,
+                ));
+            }
+        },
+        None => return Err(JsonPatchError::invalid_from_from(&["".into()], value)),
+    })
+}
+
 /// Convert a `simd_json::Value` to a reference to an array (`Vec<Value>`).
 ///
 /// # Why manual type checking?
@@ -402,13 +508,19 @@ fn visualize_ops(patches: &[ValueWithPriority<'_>], target_array_len: usize) ->
     const REPLACE_SYMBOL: &str = " [*] ";
     const REMOVE_SYMBOL: &str = " [-] ";
     const PUSH_SYMBOL: &str = " [>] ";
+    const MOVE_SYMBOL: &str = " [~] ";
+    const COPY_SYMBOL: &str = " [=] ";
+    const TEST_SYMBOL: &str = " [?] ";
     const ELLIPSIS: &str = " ... ";
     const GAP_THRESHOLD: usize = 20;
 
     #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
     enum ActionType {
+        Test,
         Replace,
         Remove,
+        Move,
+        Copy,
         Add,
         Push,
     }
@@ -416,8 +528,11 @@ fn visualize_ops(patches: &[ValueWithPriority<'_>], target_array_len: usize) ->
     impl ActionType {
         const fn symbol(&self) -> &'static str {
             match self {
+                Self::Test => TEST_SYMBOL,
                 Self::Replace => REPLACE_SYMBOL,
                 Self::Remove => REMOVE_SYMBOL,
+                Self::Move => MOVE_SYMBOL,
+                Self::Copy => COPY_SYMBOL,
                 Self::Add => ADD_SYMBOL,
                 Self::Push => PUSH_SYMBOL,
             }
@@ -425,8 +540,11 @@ fn visualize_ops(patches: &[ValueWithPriority<'_>], target_array_len: usize) ->
 
         const fn as_str(&self) -> &'static str {
             match self {
+                Self::Test => "Test",
                 Self::Replace => "Replace",
                 Self::Remove => "Remove",
+                Self::Move => "Move",
+                Self::Copy => "Copy",
                 Self::Add => "Add",
                 Self::Push => "Push",
             }
@@ -434,10 +552,13 @@ fn visualize_ops(patches: &[ValueWithPriority<'_>], target_array_len: usize) ->
 
         const fn rank(&self) -> usize {
             match self {
-                Self::Replace => 0,
-                Self::Remove => 1,
-                Self::Add => 2,
-                Self::Push => 3,
+                Self::Test => 0,
+                Self::Replace => 1,
+                Self::Remove => 2,
+                Self::Move => 3,
+                Self::Copy => 4,
+                Self::Add => 5,
+                Self::Push => 6,
             }
         }
     }
@@ -460,6 +581,9 @@ fn visualize_ops(patches: &[ValueWithPriority<'_>], target_array_len: usize) ->
                         Op::Add => ActionType::Add,
                         Op::Replace => ActionType::Replace,
                         Op::Remove => ActionType::Remove,
+                        Op::Move => ActionType::Move,
+                        Op::Copy => ActionType::Copy,
+                        Op::Test => ActionType::Test,
                     };
                     max_index.fetch_max(range.end, Ordering::Relaxed);
                     Some(TableRow {
@@ -669,4 +793,90 @@ Push    |   1 |                     [>] |\n\
         println!("{visual}");
         assert_eq!(visual, EXPECTED_VISUAL);
     }
+
+    #[test]
+    fn test_same_priority_push_keeps_order() {
+        let push = |value| ValueWithPriority {
+            patch: JsonPatch { action: Action::SeqPush, value },
+            priority: 1,
+        };
+        let patches: Vec<ValueWithPriority<'_>> =
+            (0..64).map(|i| push(json_typed!(borrowed, [i.to_string()]))).collect();
+
+        let mut actual = json_typed!(borrowed, []);
+        apply_seq_array_directly(actual.try_as_array_mut().unwrap(), patches).unwrap();
+
+        let expected: Vec<_> = (0..64).map(|i| Value::from(i.to_string())).collect();
+        assert_eq!(actual, Value::from(expected));
+    }
+
+    #[test]
+    fn test_multi_mod_adds_keep_priority_order() {
+        let seq = |op, range, value, priority| ValueWithPriority {
+            patch: JsonPatch { action: Action::Seq { op, range }, value },
+            priority,
+        };
+        let patches: Vec<ValueWithPriority<'_>> = vec![
+            seq(Op::Add, 1..1, json_typed!(borrowed, ["b"]), 1),
+            seq(Op::Add, 3..3, json_typed!(borrowed, ["a"]), 0),
+            ValueWithPriority {
+                patch: JsonPatch { action: Action::SeqPush, value: json_typed!(borrowed, ["p"]) },
+                priority: 0,
+            },
+        ];
+
+        let mut actual = json_typed!(borrowed, ["0", "1", "2", "3"]);
+        apply_seq_array_directly(actual.try_as_array_mut().unwrap(), patches).unwrap();
+
+        // Applied in the order of priority, not of index.
+        let expected = json_typed!(borrowed, ["0", "1", "b", "2", "a", "3", "p"]);
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_move_copy_and_test_guard() {
+        let seq = |op, range, value, priority| ValueWithPriority {
+            patch: JsonPatch { action: Action::Seq { op, range }, value },
+            priority,
+        };
+        let patches: Vec<ValueWithPriority<'_>> = vec![
+            seq(Op::Move, 5..5, json_typed!(borrowed, "[1:3]"), 0),
+            seq(Op::Copy, 0..0, json_typed!(borrowed, "[0]"), 1),
+            // Skipped by the failed test
+            seq(Op::Test, 0..1, json_typed!(borrowed, ["X"]), 2),
+            seq(Op::Replace, 0..1, json_typed!(borrowed, ["Y"]), 2),
+            // Applied by the passed test
+            seq(Op::Test, 3..4, json_typed!(borrowed, ["3"]), 3),
+            seq(Op::Remove, 3..4, json_typed!(borrowed, []), 3),
+        ];
+
+        let mut actual = json_typed!(borrowed, ["0", "1", "2", "3", "4", "5"]);
+        apply_seq_array_directly(actual.try_as_array_mut().unwrap(), patches).unwrap();
+
+        let expected = json_typed!(borrowed, ["0", "0", "4", "1", "2", "5"]);
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_guard_compares_with_original() {
+        let seq = |op, range, value, priority| ValueWithPriority {
+            patch: JsonPatch { action: Action::Seq { op, range }, value },
+            priority,
+        };
+        let patches: Vec<ValueWithPriority<'_>> = vec![
+            // The lower priority removes the tested element.
+            seq(Op::Remove, 3..4, json_typed!(borrowed, []), 0),
+            seq(Op::Test, 3..4, json_typed!(borrowed, ["3"]), 1),
+            ValueWithPriority {
+                patch: JsonPatch { action: Action::SeqPush, value: json_typed!(borrowed, ["p"]) },
+                priority: 1,
+            },
+        ];
+
+        let mut actual = json_typed!(borrowed, ["0", "1", "2", "3"]);
+        apply_seq_array_directly(actual.try_as_array_mut().unwrap(), patches).unwrap();
+
+        let expected = json_typed!(borrowed, ["0", "1", "2", "p"]);
+        assert_eq!(actual, expected);
+    }
 }
@@ -18,6 +18,7 @@ pub use self::{
     apply::{
         error::{JsonPatchError, Result},
         one_op::apply_one_field,
+        resolve::{has_selectors, resolve_selectors},
         seq::{apply_seq_array_directly, apply_seq_by_priority},
     },
     json_path::JsonPath,
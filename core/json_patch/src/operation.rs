@@ -10,4 +10,20 @@ pub enum Op {
     Remove,
     /// Replace the value at the specified path with a new value.
     Replace,
+    /// Remove the value at `from` and add it to the specified path.
+    ///
+    /// The patch value is `from`.
+    /// - `Pure`: The path as an array of strings. e.g. `["#0001", "hkbFoo", "bar"]`
+    /// - `Seq`: The source range of the same array. e.g. `"[1:3]"`, `"[4]"`. It is inserted before `range.start`.
+    Move,
+    /// Copy the value at `from` to the specified path.
+    ///
+    /// The patch value is `from`. Same as [`Op::Move`].
+    Copy,
+    /// Test that the value at the specified path is equal to the patch value.
+    /// Both compare with the template before any patch is applied.
+    ///
+    /// - `Pure`: A mismatch is an error. The caller uses it as a guard of the other patches of the same priority.
+    /// - `Seq`: A guard. On a mismatch, the other patches of the same priority to the array are skipped.
+    Test,
 }
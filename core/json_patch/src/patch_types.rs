@@ -43,12 +43,15 @@ pub enum Action {
     },
 
     /// Operation on a contiguous range of an array.
-    /// Supports `add`, `replace`, and `remove`.
+    /// Supports `add`, `replace`, `remove`, `move`, `copy` and `test`.
     Seq {
         /// The type of operation (Add, Remove, Replace).
         op: Op,
 
         /// The target index range in the array (0-based, exclusive at the end).
+        ///
+        /// If the path ends with a selector(e.g. `[?name=="Run"]`), it is relative to the selected element.
+        /// (See [`crate::resolve_selectors`])
         range: Range<usize>,
     },
 
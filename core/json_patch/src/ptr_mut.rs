@@ -1,6 +1,6 @@
 use simd_json::BorrowedValue;
 
-use crate::range::{Range, parse::parse_range};
+use crate::range::parse::parse_range;
 
 /// A trait that provides a mutable reference to a `BorrowedValue`
 /// given a sequence of strings (representing the path or pointer).
@@ -16,7 +16,7 @@ pub trait PointerMut {
     /// Get the `&mut Self` corresponding to the specified json path.
     ///
     /// # Note
-    /// The range specification can be used only for Index (e.g. `[1]`).
+    /// The range specification can be used only for one element. (e.g. `[1]`, `[-1]`, `[?name=="Run"]`)
     fn ptr_mut<I>(&mut self, ptr: I) -> Option<&mut Self>
     where
         I: IntoIterator,
@@ -32,12 +32,8 @@ impl PointerMut for BorrowedValue<'_> {
         ptr.into_iter().try_fold(self, move |target, token| match target {
             Self::Object(map) => map.get_mut(token.as_ref()),
             Self::Array(list) => {
-                let token = token.as_ref();
-                let range = parse_range(token).ok()?;
-                match range {
-                    Range::Index(index) => list.get_mut(index),
-                    _ => None,
-                }
+                let index = parse_range(token.as_ref()).ok()?.resolve_index(list)?;
+                list.get_mut(index)
             }
             _v => None,
         })
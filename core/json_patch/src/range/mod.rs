@@ -1,16 +1,26 @@
 pub(crate) mod error;
 pub(crate) mod parse;
+pub(crate) mod selector;
 pub(crate) mod split_range;
 
 use core::fmt;
 
-use self::error::RangeError;
+use self::{error::RangeError, selector::Selector};
 
 /// Represents either an index or a range in a patch operation.
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum Range {
     /// `[index]`
     Index(usize),
+    /// `[-nth]`: `nth` from the end. e.g. `[-1]` is the last element.
+    FromEnd(usize),
+    /// `[?key==value]+offset`: `offset` from the first element that matches the selector.
+    ///
+    /// The selector matches the array of the template before patching. If nothing matches there
+    /// (e.g. an event added by another mod), it is matched against the patched array when applied.
+    ///
+    /// See [`selector`].
+    Find { selector: Selector, offset: isize },
     /// `[start..end]`
     FromTo(std::ops::Range<usize>),
     /// `..end`
@@ -25,6 +35,9 @@ impl fmt::Display for Range {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             Self::Index(index) => write!(f, "[{index}]"),
+            Self::FromEnd(nth) => write!(f, "[-{nth}]"),
+            Self::Find { selector, offset: 0 } => write!(f, "[?{selector}]"),
+            Self::Find { selector, offset } => write!(f, "[?{selector}]{offset:+}"),
             Self::FromTo(range) => write!(f, "[{}..{}]", range.start, range.end),
             Self::From(range) => write!(f, "[..{}]", range.start),
             Self::To(range) => write!(f, "[..{}]", range.end),
@@ -65,7 +78,8 @@ impl Range {
                     });
                 }
             }
-            Self::Full => {} // The full range is always valid
+            // The full range is always valid, and the selectors are checked when resolved.
+            Self::Full | Self::FromEnd(_) | Self::Find { .. } => {}
         }
         Ok(())
     }
@@ -1,25 +1,26 @@
 use winnow::{
     ModalResult, Parser,
     ascii::digit1,
-    combinator::{alt, delimited, opt},
+    combinator::{alt, delimited, opt, preceded},
     token::take_till,
 };
 
-use super::{Range, error::RangeError};
+use super::{
+    Range,
+    error::RangeError,
+    selector::{Literal, Selector},
+};
 
 /// Parses a string segment to determine if it represents an index or a range.
 ///
-/// e.g., `[1:3]`, `[3:]`, `[:3]`, `[:]`, `[*]`
+/// e.g., `[1:3]`, `[3:]`, `[:3]`, `[:]`, `[*]`, `[-1]`, `[?name=="Run"]`, `[?stateId==5]+1`
 ///
 /// # Errors
 /// Returns `PatchError::InvalidOperation` if the segment does not conform to the
 /// expected format or contains invalid numeric values.
-pub fn parse_range(mut segment: &str) -> Result<Range, RangeError> {
-    let input = &mut segment;
-
-    _parse_range
-        .parse_next(input)
-        .map_err(|_| RangeError::InvalidRange { range: segment.to_string() })
+pub fn parse_range(segment: &str) -> Result<Range, RangeError> {
+    // NOTE: `parse` requires the whole segment. e.g. `[1]x` is not `[1]`.
+    _parse_range.parse(segment).map_err(|_| RangeError::InvalidRange { range: segment.to_string() })
 }
 
 fn _parse_range(input: &mut &str) -> ModalResult<Range> {
@@ -32,9 +33,41 @@ fn _parse_range(input: &mut &str) -> ModalResult<Range> {
             (None, None) => Range::Full,
         }),
         digit1.parse_to().map(Range::Index),
+        preceded("-", digit1.parse_to()).verify(|nth: &usize| *nth > 0).map(Range::FromEnd),
     ));
 
-    delimited("[", range, "]").parse_next(input)
+    alt((find, delimited("[", range, "]"))).parse_next(input)
+}
+
+/// Parse a selector with an optional offset, e.g. `[?name=="Run"]`, `[?stateId==5]+1`
+fn find(input: &mut &str) -> ModalResult<Range> {
+    let selector = delimited("[?", selector, "]").parse_next(input)?;
+    let offset = opt(offset).parse_next(input)?.unwrap_or(0);
+    Ok(Range::Find { selector, offset })
+}
+
+/// Parse `key==value`, e.g. `name=="Run"`, `@.stateId==5`, `@=="Run"`
+fn selector(input: &mut &str) -> ModalResult<Selector> {
+    let key = take_till(1.., |c| c == '=').parse_next(input)?;
+    "==".parse_next(input)?;
+    let value = alt((
+        delimited("\"", take_till(0.., |c| c == '"'), "\"")
+            .map(|value: &str| Literal::Str(value.to_string())),
+        take_till(1.., |c| c == ']').map(|value: &str| Literal::Raw(value.trim().to_string())),
+    ))
+    .parse_next(input)?;
+
+    let key = key.trim();
+    let key = key.strip_prefix('@').map_or(key, |key| key.trim_start_matches('.'));
+    let keys = if key.is_empty() { vec![] } else { key.split('.').map(str::to_string).collect() };
+    Ok(Selector { keys, value })
+}
+
+/// Parse `+1`, `-1`
+fn offset(input: &mut &str) -> ModalResult<isize> {
+    let sign = alt(("+".value(1), "-".value(-1))).parse_next(input)?;
+    let offset: isize = digit1.parse_to().parse_next(input)?;
+    Ok(sign * offset)
 }
 
 /// Parse a range, e.g., "1:3", "3:", ":3", ":"
@@ -102,6 +135,31 @@ mod tests {
         assert_eq!(result, Range::FromTo(1..10));
     }
 
+    #[test]
+    fn test_parse_range_from_end() {
+        let result = parse_range("[-1]");
+        assert_eq!(result, Ok(Range::FromEnd(1)));
+    }
+
+    #[test]
+    fn test_parse_selector() {
+        let result = parse_range(r#"[?name=="Run"]"#);
+        let selector =
+            Selector { keys: vec!["name".to_string()], value: Literal::Str("Run".to_string()) };
+        assert_eq!(result, Ok(Range::Find { selector, offset: 0 }));
+
+        let result = parse_range("[?@.info.stateId==5]+1");
+        let selector = Selector {
+            keys: vec!["info".to_string(), "stateId".to_string()],
+            value: Literal::Raw("5".to_string()),
+        };
+        assert_eq!(result, Ok(Range::Find { selector, offset: 1 }));
+
+        let result = parse_range(r#"[?@=="bRun"]-1"#);
+        let selector = Selector { keys: vec![], value: Literal::Str("bRun".to_string()) };
+        assert_eq!(result, Ok(Range::Find { selector, offset: -1 }));
+    }
+
     ////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
     // Fail tests
 
@@ -123,6 +181,19 @@ mod tests {
         assert!(result.is_err());
     }
 
+    #[test]
+    fn should_fail_to_parse_range_from_end_zero() {
+        let result = parse_range("[-0]");
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn should_fail_to_parse_range_trailing_input() {
+        assert!(parse_range("[1]x").is_err());
+        assert!(parse_range("[1:3]]").is_err());
+        assert!(parse_range(r#"[?name=="Run"]+1abc"#).is_err());
+    }
+
     #[test]
     fn should_fail_to_parse_range_empty_range() {
         let result = parse_range("[]");
@@ -0,0 +1,125 @@
+//! Content-addressed array selectors.
+//!
+//! Indices of a template array are shifted by the other mods, so a patch can select an element by its content
+//! instead. The selector is resolved against the array when the patch is applied.
+//!
+//! - `[?name=="Run"]`: The first element whose `name` field is `"Run"`.
+//! - `[?@=="Run"]`: The first element that is `"Run"`. (e.g. `variableNames`)
+//! - `[?stateId==5]+1`: The next index of the first element whose `stateId` is `5`. (an anchor to insert after it)
+use core::fmt;
+
+use simd_json::{StaticNode, borrowed::Value};
+
+use super::Range;
+
+/// `key==value` of `[?key==value]`
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Selector {
+    /// Field names to the compared value. Empty for `@`(the element itself).
+    ///
+    /// e.g. `@.a.b` or `a.b` -> `["a", "b"]`
+    pub keys: Vec<String>,
+    /// The value to compare with.
+    pub value: Literal,
+}
+
+/// The value of [`Selector`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Literal {
+    /// `"Run"`
+    Str(String),
+    /// Unquoted. e.g. `5`, `true`, `null`
+    ///
+    /// Compared by type, so `5` never matches the string `"5"`.
+    Raw(String),
+}
+
+impl Selector {
+    /// Does the element match this selector?
+    pub fn is_match(&self, element: &Value<'_>) -> bool {
+        let target = self.keys.iter().try_fold(element, |value, key| match value {
+            Value::Object(map) => map.get(key.as_str()),
+            _ => None,
+        });
+
+        match (&self.value, target) {
+            (Literal::Str(expected), Some(Value::String(actual))) => expected == actual,
+            (Literal::Raw(raw), Some(Value::Static(node))) => match node {
+                StaticNode::I64(n) => raw.parse() == Ok(*n),
+                StaticNode::U64(n) => raw.parse() == Ok(*n),
+                StaticNode::F64(n) => {
+                    raw.parse::<f64>().is_ok_and(|raw| (raw - n).abs() < f64::EPSILON)
+                }
+                StaticNode::Bool(b) => raw.parse() == Ok(*b),
+                StaticNode::Null => raw == "null",
+            },
+            _ => false,
+        }
+    }
+}
+
+impl fmt::Display for Selector {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.keys.as_slice() {
+            [] => write!(f, "@")?,
+            keys => write!(f, "{}", keys.join("."))?,
+        }
+        match &self.value {
+            Literal::Str(value) => write!(f, "==\"{value}\""),
+            Literal::Raw(value) => write!(f, "=={value}"),
+        }
+    }
+}
+
+impl Range {
+    /// Resolve `[index]`, `[-1]` and `[?key==value]` to the index of `list`.
+    ///
+    /// Returns `None` if it is a slice, or not found.
+    pub fn resolve_index(&self, list: &[Value<'_>]) -> Option<usize> {
+        match self {
+            Self::Index(index) => Some(*index),
+            Self::FromEnd(nth) => list.len().checked_sub(*nth),
+            Self::Find { selector, offset } => {
+                let found = list.iter().position(|element| selector.is_match(element))?;
+                found.checked_add_signed(*offset)
+            }
+            Self::FromTo(_) | Self::To(_) | Self::From(_) | Self::Full => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use simd_json::json_typed;
+
+    use super::*;
+    use crate::range::parse::parse_range;
+
+    #[test]
+    fn resolve_selectors() {
+        let infos = json_typed!(borrowed, [
+            { "name": "Walk", "stateId": 3 },
+            { "name": "Run", "stateId": 5 },
+            { "name": "Sprint", "stateId": 7 }
+        ]);
+        let infos = simd_json::derived::ValueTryAsArray::try_as_array(&infos).unwrap();
+
+        let resolve = |token| parse_range(token).unwrap().resolve_index(infos);
+        assert_eq!(resolve(r#"[?name=="Run"]"#), Some(1));
+        assert_eq!(resolve("[?stateId==5]+1"), Some(2));
+        assert_eq!(resolve("[?stateId==3]-1"), None);
+        assert_eq!(resolve(r#"[?name=="Jump"]"#), None);
+        assert_eq!(resolve("[-1]"), Some(2));
+        assert_eq!(resolve(r#"[?stateId=="5"]"#), None);
+
+        let names = json_typed!(borrowed, ["iState", "bRun"]);
+        let names = simd_json::derived::ValueTryAsArray::try_as_array(&names).unwrap();
+        assert_eq!(parse_range(r#"[?@=="bRun"]"#).unwrap().resolve_index(names), Some(1));
+
+        // Typed: a number never matches a string.
+        let ids = json_typed!(borrowed, ["5", 5]);
+        let ids = simd_json::derived::ValueTryAsArray::try_as_array(&ids).unwrap();
+        assert_eq!(parse_range("[?@==5]").unwrap().resolve_index(ids), Some(1));
+        assert_eq!(parse_range(r#"[?@=="5"]"#).unwrap().resolve_index(ids), Some(0));
+    }
+}
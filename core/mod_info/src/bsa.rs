@@ -0,0 +1,91 @@
+//! Mods packed in BSA archives.
+use std::path::Path;
+
+use rayon::prelude::*;
+use skyrim_bsa::BsaArchive;
+
+use crate::{ModInfo, ModType, pandora::from_info_xml};
+
+/// Collect the Nemesis/NemesisExt/Pandora/FNIS mods in `{skyrim_data_dir}/*.bsa`.
+///
+/// The `id` is the same as that of the loose mod, so that `nemesis_merge` can find the archived patches
+/// at the path the loose files would have.
+/// - Nemesis(manual): e.g. `<archive dir>/Nemesis_Engine/mod/aaaa`
+///
+/// Archives that cannot be read are skipped.
+pub(crate) fn get_all_archived(skyrim_data_dir: &str, is_vfs: bool) -> Vec<ModInfo> {
+    let mut archive_paths = jwalk_glob::glob_files(&format!("{skyrim_data_dir}/*.bsa"));
+    archive_paths.par_sort_unstable();
+
+    archive_paths
+        .par_iter()
+        .flat_map_iter(|path| {
+            let Ok(archive) = BsaArchive::open(path) else {
+                return vec![];
+            };
+            let data_dir = path.parent().unwrap_or_else(|| Path::new(""));
+            archived_mods(&archive, data_dir, is_vfs)
+        })
+        .collect()
+}
+
+fn archived_mods(archive: &BsaArchive, data_dir: &Path, is_vfs: bool) -> Vec<ModInfo> {
+    let nemesis_id = |engine_dir: &str, id: &str| {
+        if is_vfs {
+            id.to_string()
+        } else {
+            data_dir.join(engine_dir).join("mod").join(id).display().to_string()
+        }
+    };
+
+    archive
+        .paths()
+        .filter_map(|path| {
+            // Paths in an archive are normalized. e.g. `nemesis_engine/mod/aaaa/info.ini`
+            let segments: Vec<&str> = path.split('/').collect();
+            match segments.as_slice() {
+                ["nemesis_engine", "mod", id, "info.ini"] => {
+                    read_archived_mod_info(archive, path, nemesis_id("Nemesis_Engine", id))
+                }
+                ["nemesis_engineext", "mod", id, "info.ini"] => {
+                    let id = nemesis_id("Nemesis_EngineExt", id);
+                    let mut mod_info = read_archived_mod_info(archive, path, id)?;
+                    mod_info.mod_type = ModType::NemesisExt;
+                    Some(mod_info)
+                }
+                ["pandora_engine", "mod", id, "info.ini"] => {
+                    let id = nemesis_id("Pandora_Engine", id);
+                    let mut mod_info = read_archived_mod_info(archive, path, id)?;
+                    mod_info.mod_type = ModType::Pandora;
+                    Some(mod_info)
+                }
+                // Pandora's own metadata. `info.ini` takes precedence.
+                ["pandora_engine", "mod", id, "info.xml"]
+                    if !archive.contains(&format!("pandora_engine/mod/{id}/info.ini")) =>
+                {
+                    let bytes = archive.read(path).ok()?;
+                    let id = nemesis_id("Pandora_Engine", id);
+                    from_info_xml(&String::from_utf8_lossy(&bytes), id)
+                }
+                ["meshes", .., "animations", id, file_name]
+                    if file_name.starts_with("fnis_") && file_name.ends_with("_list.txt") =>
+                {
+                    Some(ModInfo {
+                        id: id.to_string(),
+                        name: id.to_string(),
+                        mod_type: ModType::Fnis,
+                        ..Default::default()
+                    })
+                }
+                _ => None,
+            }
+        })
+        .collect()
+}
+
+fn read_archived_mod_info(archive: &BsaArchive, path: &str, id: String) -> Option<ModInfo> {
+    let bytes = archive.read(path).ok()?;
+    let mut mod_info: ModInfo = serde_ini::from_str(&String::from_utf8_lossy(&bytes)).ok()?;
+    mod_info.id = id;
+    Some(mod_info)
+}
@@ -1,12 +1,14 @@
+mod bsa;
 pub mod error;
+mod pandora;
 
-use std::{fs, path::Path};
+use std::{collections::HashSet, fs, path::Path};
 
 use rayon::prelude::*;
 
 use crate::error::Error;
 
-/// Collect both Nemesis and FNIS mods into a single vector.
+/// Collect Nemesis, Pandora and FNIS mods into a single vector.
 ///
 /// # Nemesis
 /// | is_vfs | glob pattern                                              | id extracted as                                                               |
@@ -20,11 +22,25 @@ use crate::error::Error;
 /// | true   | `{skyrim_data_dir}/Nemesis_EngineExt/mod/*/info.ini`      | `<id>` from `Nemesis_Engine/mod/<id>/info.ini`                                |
 /// | false  | `{skyrim_data_dir}/Nemesis_EngineExt/mod/*/info.ini`      | full parent path (e.g. `MO2/mod/mod_name/meshes/.../Nemesis_Engine/mod/aaaa`) |
 ///
+/// # Pandora
+/// | is_vfs | glob pattern                                              | id extracted as                                                               |
+/// |--------|-----------------------------------------------------------|-------------------------------------------------------------------------------|
+/// | true   | `{skyrim_data_dir}/Pandora_Engine/mod/*/info.(ini\|xml)`  | `<id>` from `Pandora_Engine/mod/<id>/info.ini`                                |
+/// | false  | `{skyrim_data_dir}/Pandora_Engine/mod/*/info.(ini\|xml)`  | full parent path (e.g. `MO2/mod/mod_name/meshes/.../Pandora_Engine/mod/aaaa`) |
+///
+/// `info.xml` is Pandora's own metadata, and is read only if there is no `info.ini`.
+///
 /// # FNIS
 /// | is_vfs | glob pattern                                                         | id extracted as     |
 /// |--------|----------------------------------------------------------------------|---------------------|
 /// |  any   | `{skyrim_data_dir}/meshes/**/animations/*/FNIS_*_List.txt` | `<id>` from `animations/<id>` |
 ///
+/// # BSA
+/// If `read_bsa`, the mods above packed in `{skyrim_data_dir}/*.bsa` are also collected with the same `id` as if they were loose.
+/// Like the game, loose files take precedence: an archived mod is skipped if the same mod exists as loose files.
+///
+/// This must match `nemesis_merge::Config::read_bsa`, otherwise the archived mods are listed but not patched.
+///
 /// - `is_vfs`:
 ///   Whether the lookup is done in the virtualized `Data` directory (true) or directly under the mods directory (false).
 ///
@@ -36,16 +52,28 @@ use crate::error::Error;
 /// use mod_info::get_all_mods;
 ///
 /// // VFS mode(real Skyrim Data dir)
-/// let mods_vfs = get_all_mods("C:/Games/Skyrim Special Edition/Data", true)?;
+/// let mods_vfs = get_all_mods("C:/Games/Skyrim Special Edition/Data", true, false)?;
 ///
 /// // Manual mode(MO2-managed mods dir)
-/// let mods_mo2 = get_all_mods("C:/Modding/MO2/mods/*", false)?;
+/// let mods_mo2 = get_all_mods("C:/Modding/MO2/mods/*", false, false)?;
 /// ```
-pub fn get_all(skyrim_data_dir: &str, is_vfs: bool) -> Result<Vec<ModInfo>, Error> {
+pub fn get_all(skyrim_data_dir: &str, is_vfs: bool, read_bsa: bool) -> Result<Vec<ModInfo>, Error> {
     let mut mods = Vec::new();
     mods.par_extend(get_all_nemesis(skyrim_data_dir, is_vfs)?);
     mods.par_extend(get_all_nemesis_ext(skyrim_data_dir, is_vfs)?);
+    mods.par_extend(pandora::get_all_pandora(skyrim_data_dir, is_vfs)?);
     mods.par_extend(get_all_fnis(skyrim_data_dir)?);
+    if !read_bsa {
+        return Ok(mods);
+    }
+
+    let mut known: HashSet<_> =
+        mods.iter().map(|mod_info| (mod_info.mod_type, mod_info.id.to_lowercase())).collect();
+    mods.extend(
+        bsa::get_all_archived(skyrim_data_dir, is_vfs)
+            .into_iter()
+            .filter(|mod_info| known.insert((mod_info.mod_type, mod_info.id.to_lowercase()))),
+    );
     Ok(mods)
 }
 
@@ -110,7 +138,7 @@ fn read_mod_info(path: &Path, id: String) -> Option<ModInfo> {
     Some(mod_info)
 }
 
-/// Get `<id>` from `Nemesis_Engine/mods/<id>/info.ini`
+/// Get `<id>` from `Nemesis_Engine/mods/<id>/info.ini`(or `Pandora_Engine/mod/<id>/info.xml`)
 fn extract_nemesis_id_from_path(path: &Path) -> Option<&str> {
     path.parent().and_then(Path::file_name).and_then(|os_str| os_str.to_str())
 }
@@ -120,8 +148,6 @@ fn extract_nemesis_id_from_path(path: &Path) -> Option<&str> {
 /// # Errors
 /// If invalid glob pattern.
 fn get_all_fnis(skyrim_data_dir: &str) -> Result<Vec<ModInfo>, Error> {
-    use std::collections::HashSet;
-
     fn collect_from_fnis_list(fnis_list_pattern: &str) -> Result<HashSet<ModInfo>, Error> {
         let mods = jwalk_glob::glob_files(fnis_list_pattern)
             .par_iter()
@@ -213,6 +239,14 @@ pub enum ModType {
     /// A patch for the exact path from meshes (note that the file extension is .bin, not .hkx)
     /// - e.g., `Nemesis_EngineExt/mod/aaaa/meshes/actors/troll/characters/troll.bin/#0029.txt`
     NemesisExt,
+
+    /// GUI developers must add the following to the paths array in `nemesis_merge::behavior_gen`.
+    /// - `<skyrim data dir>/Pandora_Engine/mod/aaaa`
+    ///
+    /// Pandora Behavior Engine's layout. Both the Nemesis and the NemesisExt patch paths are in the same folder.
+    /// - e.g., `Pandora_Engine/mod/aaaa/0_master/#0106.txt`
+    /// - e.g., `Pandora_Engine/mod/aaaa/meshes/actors/troll/characters/troll.bin/#0029.txt`
+    Pandora,
 }
 
 impl ModType {
@@ -228,6 +262,7 @@ impl ModType {
         match self {
             Self::Nemesis => "Nemesis",
             Self::NemesisExt => "NemesisExt",
+            Self::Pandora => "Pandora",
             Self::Fnis => "FNIS",
         }
     }
@@ -244,6 +279,7 @@ impl ModType {
         match self {
             Self::Nemesis => "nemesis",
             Self::NemesisExt => "nemesisext",
+            Self::Pandora => "pandora",
             Self::Fnis => "fnis",
         }
     }
@@ -268,12 +304,17 @@ where
     D: serde::Deserializer<'de>,
 {
     let s: std::borrow::Cow<'de, str> = serde::Deserialize::deserialize(deserializer)?;
+    Ok(normalize_site(&s))
+}
+
+/// Remove "null" and prepend "https://" if starts with "www."
+fn normalize_site(s: &str) -> String {
     if s.eq_ignore_ascii_case("null") || s.trim().is_empty() {
-        Ok(String::new())
+        String::new()
     } else if s.starts_with("www.") {
-        Ok(format!("https://{s}"))
+        format!("https://{s}")
     } else {
-        Ok(s.to_string())
+        s.to_string()
     }
 }
 
@@ -289,7 +330,7 @@ mod tests {
     #[test]
     fn get_mod_info() {
         let pattern = "D:/GAME/ModOrganizer Skyrim SE/mods/*";
-        let info = get_all(pattern, false).unwrap();
+        let info = get_all(pattern, false, false).unwrap();
 
         dbg!(info.len());
         std::fs::create_dir_all("../../dummy").unwrap();
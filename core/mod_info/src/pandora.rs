@@ -0,0 +1,126 @@
+//! Mods in Pandora Behavior Engine's folder layout. (`Pandora_Engine/mod/<id>`)
+//!
+//! Pandora reads the same patches as Nemesis, so only the discovery and the metadata differ.
+//! The metadata is `info.ini`(same as Nemesis), or `info.xml` written for Pandora.
+//!
+//! ```xml
+//! <?xml version="1.0" encoding="utf-8"?>
+//! <PandoraMod>
+//!   <Name>Some Mod</Name>
+//!   <Author>author</Author>
+//!   <Version>1.0.0</Version>
+//!   <Link>https://www.nexusmods.com/skyrimspecialedition/mods/0</Link>
+//! </PandoraMod>
+//! ```
+use std::{collections::HashSet, fs, path::Path};
+
+use rayon::prelude::*;
+
+use crate::{
+    ModInfo, ModType, error::Error, extract_nemesis_id_from_path, normalize_site, read_mod_info,
+};
+
+/// Get `info.ini`(or `info.xml` if there is no `info.ini`) of `<skyrim data dir>/Pandora_Engine/mod/*`.
+///
+/// The `id` is the same as that of Nemesis. See [`crate::get_all`].
+///
+/// # Errors
+/// If invalid glob pattern.
+pub(crate) fn get_all_pandora(skyrim_data_dir: &str, is_vfs: bool) -> Result<Vec<ModInfo>, Error> {
+    let ini_paths =
+        jwalk_glob::glob_files(&format!("{skyrim_data_dir}/Pandora_Engine/mod/*/info.ini"));
+    let ini_dirs: HashSet<_> = ini_paths.iter().filter_map(|path| path.parent()).collect();
+    let xml_paths: Vec<_> =
+        jwalk_glob::glob_files(&format!("{skyrim_data_dir}/Pandora_Engine/mod/*/info.xml"))
+            .into_iter()
+            .filter(|path| path.parent().is_none_or(|dir| !ini_dirs.contains(dir)))
+            .collect();
+
+    let to_id = |path: &Path| {
+        Some(if is_vfs {
+            extract_nemesis_id_from_path(path)?.to_string()
+        } else {
+            path.parent().unwrap_or(path).display().to_string()
+        })
+    };
+
+    let mut mods: Vec<_> =
+        ini_paths.par_iter().filter_map(|path| read_mod_info(path, to_id(path)?)).collect();
+    mods.par_extend(xml_paths.par_iter().filter_map(|path| {
+        let contents = fs::read_to_string(path).ok()?;
+        from_info_xml(&contents, to_id(path)?)
+    }));
+    mods.par_iter_mut().for_each(|mod_info| mod_info.mod_type = ModType::Pandora);
+
+    Ok(mods)
+}
+
+/// Read Pandora's `info.xml`.
+///
+/// Returns `None` if it has no `<Name>`.
+pub(crate) fn from_info_xml(contents: &str, id: String) -> Option<ModInfo> {
+    let name = find_tag(contents, "Name")?;
+    Some(ModInfo {
+        id,
+        name,
+        author: find_tag(contents, "Author").unwrap_or_default(),
+        site: find_tag(contents, "Link").as_deref().map(normalize_site).unwrap_or_default(),
+        mod_type: ModType::Pandora,
+        ..Default::default()
+    })
+}
+
+/// Text of the first `<tag>text</tag>`(case-insensitive).
+fn find_tag(contents: &str, tag: &str) -> Option<String> {
+    let lower = contents.to_ascii_lowercase();
+    let tag = tag.to_ascii_lowercase();
+
+    let start = lower.find(&format!("<{tag}>"))? + tag.len() + 2;
+    let end = start + lower[start..].find(&format!("</{tag}>"))?;
+    let text = contents[start..end].trim();
+
+    // `&amp;` must be the last so as not to unescape twice.
+    let text = text
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&");
+    (!text.is_empty()).then_some(text)
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn read_info_xml() {
+        let contents = r#"<?xml version="1.0" encoding="utf-8"?>
+<PandoraMod>
+  <Name>Slide &amp; Run</Name>
+  <Author> someone </Author>
+  <Version>1.0.0</Version>
+  <Link>www.nexusmods.com/skyrimspecialedition/mods/0</Link>
+</PandoraMod>"#;
+
+        let mod_info = from_info_xml(contents, "slide".to_string());
+        assert_eq!(
+            mod_info,
+            Some(ModInfo {
+                id: "slide".to_string(),
+                name: "Slide & Run".to_string(),
+                author: "someone".to_string(),
+                site: "https://www.nexusmods.com/skyrimspecialedition/mods/0".to_string(),
+                mod_type: ModType::Pandora,
+                ..Default::default()
+            })
+        );
+
+        assert_eq!(
+            from_info_xml("<PandoraMod><Author>a</Author></PandoraMod>", String::new()),
+            None
+        );
+    }
+}
@@ -0,0 +1,288 @@
+//! Patch conflict report. (`<output_dir>/.d_merge/conflicts.json`)
+//!
+//! Records which mod's patch beat which one:
+//! - One field: the same JSON path is patched by multiple mods, and only the highest priority one is applied.
+//! - Seq: the array ranges of multiple mods overlap, and the higher priority one is applied later.
+use std::{
+    ops::Range,
+    path::{Path, PathBuf},
+};
+
+use json_patch::{Action, JsonPath, ValueWithPriority};
+use rapidhash::fast::RapidHashMap as HashMap;
+use rayon::prelude::*;
+use snafu::ResultExt as _;
+
+use crate::{
+    PatchMaps,
+    behaviors::tasks::{patches::types::BehaviorPatchesMap, templates::key::TemplateKey},
+    errors::{FailedIoSnafu, JsonSnafu, Result},
+};
+
+/// A one field patch that lost to a patch with another priority on the same JSON path.
+///
+/// - format: (template, json path, priority of the lost patch)
+pub(crate) type OneFieldLoser<'a> = (TemplateKey<'static>, JsonPath<'a>, usize);
+
+/// Conflicts between the patches of different mods.
+///
+/// Written to `<output_dir>/.d_merge/conflicts.json` by [`crate::behavior_gen`].
+/// Every list is sorted by template, path and mod ids.
+#[derive(Debug, Clone, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[cfg_attr(feature = "ts_serde", serde(rename_all = "camelCase"))]
+pub struct ConflictReport {
+    /// One field patches overwritten by a higher priority mod.
+    pub one: Vec<OneFieldConflict>,
+
+    /// Seq(array) patches whose ranges overlap with another mod's.
+    pub seq: Vec<SeqConflict>,
+}
+
+/// A one field patch overwritten by a higher priority mod.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[cfg_attr(feature = "ts_serde", serde(rename_all = "camelCase"))]
+pub struct OneFieldConflict {
+    /// e.g. `meshes/actors/character/behaviors/0_master.bin`
+    pub template: String,
+    /// e.g. `#0001/hkbBehaviorGraphData/variableInfos`
+    pub path: String,
+    /// Mod id of the applied patch. (key of [`PatchMaps`])
+    pub winner: String,
+    /// Mod id of the discarded patch. (key of [`PatchMaps`])
+    pub loser: String,
+}
+
+/// Seq patches of two mods whose ranges overlap.
+///
+/// Both are applied, but the winner is applied later, so it decides the result of the overlapped range.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[cfg_attr(feature = "ts_serde", serde(rename_all = "camelCase"))]
+pub struct SeqConflict {
+    /// e.g. `meshes/actors/character/behaviors/0_master.bin`
+    pub template: String,
+    /// e.g. `#0001/hkbStateMachine/states`
+    pub path: String,
+    /// Mod id of the higher priority patch. (key of [`PatchMaps`])
+    pub winner: String,
+    /// Range of the template array patched by the winner.
+    pub winner_range: Range<usize>,
+    /// Mod id of the lower priority patch. (key of [`PatchMaps`])
+    pub loser: String,
+    /// Range of the template array patched by the loser.
+    pub loser_range: Range<usize>,
+}
+
+impl ConflictReport {
+    /// Build the report from the collected patches.
+    ///
+    /// - `one_losers`: returned when inserting or merging one field patches.
+    pub(crate) fn new<'a>(
+        borrowed_patches: &BehaviorPatchesMap<'a>,
+        one_losers: &[OneFieldLoser<'a>],
+        patches: &PatchMaps,
+    ) -> Self {
+        let mod_ids = ModIds::new(patches);
+
+        let mut one: Vec<_> = one_losers
+            .par_iter()
+            .filter_map(|(key, path, loser)| {
+                let template = borrowed_patches.0.get(key)?;
+                let winner = template.one.priority_of(path)?;
+                (winner != *loser).then(|| OneFieldConflict {
+                    template: key.as_str().to_string(),
+                    path: path.join("/"),
+                    winner: mod_ids.get(winner),
+                    loser: mod_ids.get(*loser),
+                })
+            })
+            .collect();
+        one.par_sort_unstable_by(|a, b| {
+            (&a.template, &a.path, &a.winner, &a.loser).cmp(&(
+                &b.template,
+                &b.path,
+                &b.winner,
+                &b.loser,
+            ))
+        });
+        one.dedup();
+
+        let mut seq: Vec<_> = borrowed_patches
+            .0
+            .par_iter()
+            .flat_map_iter(|template| {
+                let key = template.key().as_str().to_string();
+                template
+                    .value()
+                    .seq
+                    .0
+                    .iter()
+                    .flat_map(|entry| seq_conflicts(&key, entry.key(), entry.value(), &mod_ids))
+                    .collect::<Vec<_>>()
+            })
+            .collect();
+        seq.par_sort_unstable_by(|a, b| {
+            (&a.template, &a.path, a.winner_range.start, a.loser_range.start, &a.winner, &a.loser)
+                .cmp(&(
+                    &b.template,
+                    &b.path,
+                    b.winner_range.start,
+                    b.loser_range.start,
+                    &b.winner,
+                    &b.loser,
+                ))
+        });
+        seq.dedup();
+
+        Self { one, seq }
+    }
+
+    /// Returns `true` if there are no conflicts.
+    #[inline]
+    pub const fn is_empty(&self) -> bool {
+        self.one.is_empty() && self.seq.is_empty()
+    }
+
+    /// Read `<output_dir>/.d_merge/conflicts.json` written by the last [`crate::behavior_gen`].
+    ///
+    /// # Errors
+    /// If the file does not exist or cannot be parsed.
+    pub fn load(output_dir: impl AsRef<Path>) -> Result<Self> {
+        let path = conflicts_path(output_dir.as_ref());
+        let bytes = std::fs::read(&path).context(FailedIoSnafu { path: &path })?;
+        sonic_rs::from_slice(&bytes).with_context(|_| JsonSnafu { path })
+    }
+
+    /// Write the report to `<output_dir>/.d_merge/conflicts.json`.
+    ///
+    /// # Errors
+    /// If failed to write.
+    pub(crate) fn save(&self, output_dir: &Path) -> Result<()> {
+        let path = conflicts_path(output_dir);
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent).context(FailedIoSnafu { path: parent })?;
+        }
+        let json = sonic_rs::to_string_pretty(self).with_context(|_| JsonSnafu { path: &path })?;
+        std::fs::write(&path, json).context(FailedIoSnafu { path })
+    }
+}
+
+/// `<output_dir>/.d_merge/conflicts.json`
+fn conflicts_path(output_dir: &Path) -> PathBuf {
+    output_dir.join(".d_merge").join("conflicts.json")
+}
+
+/// priority -> mod id
+pub(crate) struct ModIds<'a>(HashMap<usize, &'a str>);
+
+impl<'a> ModIds<'a> {
+    pub(crate) fn new(patches: &'a PatchMaps) -> Self {
+        let PatchMaps { nemesis_entries, fnis_entries } = patches;
+        Self(
+            nemesis_entries
+                .iter()
+                .chain(fnis_entries)
+                .map(|(id, priority)| (*priority, id.as_str()))
+                .collect(),
+        )
+    }
+
+    /// Unknown priority is shown as is. (e.g. `usize::MAX` for a patch whose id could not be resolved)
+    pub(crate) fn get(&self, priority: usize) -> String {
+        self.0.get(&priority).map_or_else(|| priority.to_string(), |id| (*id).to_string())
+    }
+}
+
+/// Find every pair of seq patches from different mods whose ranges overlap.
+fn seq_conflicts(
+    template: &str,
+    path: &JsonPath<'_>,
+    patches: &[ValueWithPriority<'_>],
+    mod_ids: &ModIds<'_>,
+) -> Vec<SeqConflict> {
+    let ranges: Vec<_> = patches
+        .iter()
+        .filter_map(|patch| match &patch.patch.action {
+            Action::Seq { range, .. } => Some((patch.priority, range)),
+            Action::Pure { .. } | Action::SeqPush => None,
+        })
+        .collect();
+
+    let mut conflicts = vec![];
+    for (i, (a_priority, a_range)) in ranges.iter().enumerate() {
+        for (b_priority, b_range) in &ranges[i + 1..] {
+            if a_priority == b_priority || !is_overlapped(a_range, b_range) {
+                continue;
+            }
+
+            let ((winner, winner_range), (loser, loser_range)) = if a_priority > b_priority {
+                ((a_priority, a_range), (b_priority, b_range))
+            } else {
+                ((b_priority, b_range), (a_priority, a_range))
+            };
+            conflicts.push(SeqConflict {
+                template: template.to_string(),
+                path: path.join("/"),
+                winner: mod_ids.get(*winner),
+                winner_range: (*winner_range).clone(),
+                loser: mod_ids.get(*loser),
+                loser_range: (*loser_range).clone(),
+            });
+        }
+    }
+    conflicts
+}
+
+/// Do two seq ranges touch the same elements of the template array?
+///
+/// Insertions at the same index(e.g. `3..3` and `3..5`) also conflict, because the order of them decides the result.
+const fn is_overlapped(a: &Range<usize>, b: &Range<usize>) -> bool {
+    (a.start < b.end && b.start < a.end) || a.start == b.start
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn overlapped_ranges() {
+        assert!(is_overlapped(&(1..4), &(3..5)));
+        assert!(is_overlapped(&(3..3), &(3..5)));
+        assert!(is_overlapped(&(2..2), &(2..2)));
+        assert!(!is_overlapped(&(1..3), &(3..5)));
+        assert!(!is_overlapped(&(4..4), &(1..4)));
+    }
+
+    #[test]
+    fn seq_conflicts_only_between_mods() {
+        use json_patch::{JsonPatch, Op};
+
+        let seq = |op, range, priority| ValueWithPriority {
+            patch: JsonPatch { action: Action::Seq { op, range }, value: Default::default() },
+            priority,
+        };
+        let patches = [
+            seq(Op::Replace, 1..4, 0),
+            seq(Op::Remove, 2..3, 0), // same mod
+            seq(Op::Add, 3..3, 1),
+            seq(Op::Replace, 10..12, 1), // no overlap
+        ];
+        let entries = PatchMaps {
+            nemesis_entries: [("a".to_string(), 0), ("b".to_string(), 1)].into_iter().collect(),
+            ..Default::default()
+        };
+
+        let path = vec!["#0001".into(), "hkbStateMachine".into(), "states".into()];
+        let conflicts = seq_conflicts("0_master", &path, &patches, &ModIds::new(&entries));
+        assert_eq!(
+            conflicts,
+            [SeqConflict {
+                template: "0_master".to_string(),
+                path: "#0001/hkbStateMachine/states".to_string(),
+                winner: "b".to_string(),
+                winner_range: 3..3,
+                loser: "a".to_string(),
+                loser_range: 1..4,
+            }]
+        );
+    }
+}
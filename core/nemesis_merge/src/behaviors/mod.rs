@@ -1,38 +1,129 @@
 //! Processes a list of Nemesis XML paths and generates JSON output in the specified directory.
-mod priority_ids;
+pub(crate) mod conflicts;
+pub(crate) mod nemesis_mod;
+pub(crate) mod plan;
+pub(crate) mod priority_ids;
+pub(crate) mod profile;
+pub(crate) mod provenance;
+pub(crate) mod reproducibility;
+pub(crate) mod stale_patches;
 pub(crate) mod tasks;
 
+use std::{
+    collections::HashSet,
+    path::PathBuf,
+    sync::Arc,
+    time::{Instant, SystemTime},
+};
+
 use rayon::prelude::*;
+use snafu::ResultExt as _;
 pub use tasks::templates::gen_bin::create_bin_templates;
 pub(crate) use tasks::{
     adsf::path_parser::ParseError as AsdfPathParseError,
     asdsf::path_parser::ParseError as AsdsfPathParseError,
 };
 
-pub use crate::behaviors::priority_ids::types::{PatchMaps, PriorityMap};
+pub use crate::behaviors::{
+    conflicts::{ConflictReport, OneFieldConflict, SeqConflict},
+    nemesis_mod::{NemesisModInfo, generate_nemesis_mod},
+    plan::{AnimDataPlan, BehaviorGenPlan, FnisIoJobKind, FnisIoJobPlan, TemplatePlan},
+    priority_ids::types::{PatchMaps, PriorityMap},
+    profile::{BehaviorGenProfile, ItemTiming, Phase, PhaseTiming},
+    provenance::{PatchTrace, PathExplanation, ProvenanceReport},
+    reproducibility::{ReproducibilityReport, check_reproducibility},
+    stale_patches::{StalePatch, StalePatchReport},
+};
 use crate::{
-    behaviors::tasks::{
-        adsf::apply_adsf_patches,
-        asdsf::apply_asdsf_patches,
-        fnis,
-        hkx::generate::generate_hkx_files,
-        patches::{
-            apply::apply_patches,
-            collect::{collect_borrowed_patches, collect_owned_patches},
-            types::{OwnedPatchMap, OwnedPatches, PatchCollection},
+    behaviors::{
+        profile::Profiler,
+        tasks::{
+            adsf::{ADSF_INNER_PATH, apply_adsf_patches, plan_adsf_patches},
+            asdsf::{ASDSF_INNER_PATH, apply_asdsf_patches, plan_asdsf_patches},
+            fnis::{self, collect::owned::OwnedFnisInjection},
+            hkx::generate::generate_hkx_files,
+            manifest::{
+                self, ADSF_OUTPUT_PATH, ASDSF_OUTPUT_PATH, Manifest, TemplateHashes,
+                hkx_output_path,
+            },
+            patches::{
+                apply::apply_patches,
+                collect::{collect_borrowed_patches, collect_owned_patches},
+                resolve::{
+                    read_selector_templates, resolve_patch_selectors, resolve_source_selectors,
+                },
+                types::{OwnedPatchMap, OwnedPatches, PatchCollection},
+            },
+            templates::collect::{borrowed, owned},
         },
-        templates::collect::{borrowed, owned},
     },
     config::{Config, Status},
-    errors::{BehaviorGenerationError, Error, Result, writer::write_errors},
+    errors::{
+        BehaviorGenerationError, Error, FailedIoSnafu, Result,
+        report::{ErrorReport, error_report_path},
+        writer::write_errors,
+    },
 };
 
 /// - `resource_dir`: Path of the template from which the patch was applied.(e.g. `../templates/` => `../templates/meshes`)
 ///
+/// # Conflict report
+/// Overwritten one field patches and overlapped seq patches between mods are written to
+/// `<output_dir>/.d_merge/conflicts.json`. See [`ConflictReport`].
+///
+/// # Stale patch report
+/// Nemesis patches whose `<!-- ORIGINAL -->` block does not match the template are written to
+/// `<output_dir>/.d_merge/stale_patches.json`(errors with `config.strict_original`). See [`StalePatchReport`].
+///
+/// # Error report
+/// Errors are written to `<output_dir>/.d_merge/d_merge_errors.log`, and to `d_merge_errors.json`
+/// as one record per error. See [`ErrorReport`].
+///
+/// # Incremental generation
+/// The input hashes of each output are recorded in `<output_dir>/.d_merge/manifest.json`.
+/// Templates and adsf/asdsf whose inputs are unchanged since the last run (and whose outputs still exist) are skipped.
+///
+/// # Profile
+/// The wall time of each phase and the slowest templates/patch files are returned and written to
+/// `<output_dir>/.d_merge/profile.json`(also on errors). See [`BehaviorGenProfile`].
+///
+/// # Output sink
+/// The generated files are written through `config.output_sink`(default: files under `output_dir`).
+/// With another sink, every output is generated on each run and no manifest is written.
+/// [`crate::OutputSink::finish`] is called at the end of the run, also when some outputs failed to generate
+/// (not when cancelled).
+///
+/// # Patch source
+/// The patch files, FNIS list files and animations are read through `config.patch_source`(default: the filesystem).
+/// e.g. [`crate::MemoryPatchSource`] to generate from patches that only exist in memory.
+///
+/// # Cancellation
+/// When `config.cancel_token` is cancelled, the generation stops at the next check point,
+/// removes the outputs written in this run(only when writing to `output_dir`) and returns [`Error::Cancelled`].
+/// The manifest is not updated, so the next run regenerates the removed outputs.
+///
 /// # Errors
 /// Returns an error if file parsing, I/O operations, or JSON serialization fails.
-pub async fn behavior_gen(patches: PatchMaps, config: Config) -> Result<()> {
-    let PatchMaps { nemesis_entries, fnis_entries } = &patches;
+pub async fn behavior_gen(patches: PatchMaps, config: Config) -> Result<BehaviorGenProfile> {
+    let run_started = Instant::now();
+    let profiler = Profiler::new();
+    let result = gen_behaviors(&patches, &config, &profiler).await;
+    if matches!(result, Err(Error::Cancelled)) {
+        config.on_report_status(Status::Error(Error::Cancelled.to_string()));
+        // The timings of a cancelled run are incomplete.
+        return Err(Error::Cancelled);
+    }
+
+    let profile = profiler.finish(run_started.elapsed());
+    if let Err(_err) = profile.save(&config.output_dir) {
+        #[cfg(feature = "tracing")]
+        tracing::error!("Failed to write profile: {_err}");
+    }
+    result.map(|()| profile)
+}
+
+async fn gen_behaviors(patches: &PatchMaps, config: &Config, profiler: &Profiler) -> Result<()> {
+    let PatchMaps { nemesis_entries, fnis_entries } = patches;
 
     #[cfg(feature = "tracing")]
     {
@@ -48,29 +139,96 @@ pub async fn behavior_gen(patches: PatchMaps, config: Config) -> Result<()> {
         });
     }
 
+    let source = config.source();
     let (owned_fnis_patches, mut fnis_errors) = if fnis_entries.is_empty() {
         (vec![], vec![])
     } else {
         let skyrim_data_dir_glob =
             config.skyrim_data_dir_glob.as_ref().ok_or(Error::MissingSkyrimDataDirGlob)?;
-        fnis::collect::collect_all_fnis_injections(skyrim_data_dir_glob, fnis_entries).await
+        let collect_started = Instant::now();
+        let collected = fnis::collect::collect_all_fnis_injections(
+            Arc::clone(&source),
+            skyrim_data_dir_glob,
+            fnis_entries,
+        )
+        .await;
+        profiler.record_phase(Phase::FnisCollect, collect_started.elapsed());
+        collected
     };
+    config.check_cancelled()?;
+
+    let writer = config.output_writer();
+    // NOTE: Outputs of another sink cannot be reused, so generate all of them.
+    let prev_manifest = if writer.is_output_dir() {
+        Manifest::load(&config.output_dir).unwrap_or_else(Manifest::new)
+    } else {
+        Manifest::new()
+    };
+    let global_hash = manifest::global_hash(config);
+    let fnis_hash = manifest::fnis_hash(&owned_fnis_patches);
 
     let (fnis_hkx_patches, fnis_adsf_patches, io_job_runner) = {
-        let (fnis_hkx_patches, fnis_adsf_patches, io_job_runner, errors) =
-            fnis::patch_gen::collect_borrowed_patches(&owned_fnis_patches, &config);
+        let (fnis_hkx_patches, fnis_adsf_patches, io_job_runner, errors) = profiler
+            .phase(Phase::FnisPatchGen, || {
+                fnis::patch_gen::collect_borrowed_patches(&owned_fnis_patches, config, &source)
+            });
         fnis_errors.par_extend(errors);
 
         (fnis_hkx_patches, fnis_adsf_patches, io_job_runner)
     };
+    config.check_cancelled()?;
 
     // Collect all patches file.
+    let read_started = Instant::now();
     let OwnedPatches {
         owned_patches,
         adsf_patches: owned_adsf_patches,
         asdsf_patches: owned_asdsf_patches,
         errors: owned_file_errors,
-    } = collect_owned_patches(nemesis_entries, &config).await;
+    } = collect_owned_patches(nemesis_entries, config, &source).await;
+    profiler.record_phase(Phase::ReadPatches, read_started.elapsed());
+    config.check_cancelled()?;
+
+    let mut next_manifest = Manifest::new();
+    next_manifest.inputs = Some(manifest::inputs_hash(
+        global_hash,
+        fnis_hash,
+        &owned_patches,
+        &owned_adsf_patches.0,
+        &owned_asdsf_patches.0,
+    ));
+
+    let adsf_hash = manifest::anim_data_hash(
+        global_hash,
+        config,
+        ADSF_INNER_PATH,
+        &owned_adsf_patches.0,
+        (!fnis_adsf_patches.is_empty()).then_some(fnis_hash),
+    );
+    let asdsf_hash = manifest::anim_data_hash(
+        global_hash,
+        config,
+        ASDSF_INNER_PATH,
+        &owned_asdsf_patches.0,
+        None,
+    );
+    let is_unchanged = |hash: Option<(u64, u64)>, prev: Option<u64>, output_inner: &str| {
+        hash.is_some_and(|(_, hash)| prev == Some(hash))
+            && config.output_dir.join(output_inner).exists()
+    };
+    let skip_adsf = is_unchanged(adsf_hash, prev_manifest.adsf, ADSF_OUTPUT_PATH);
+    let skip_asdsf = is_unchanged(asdsf_hash, prev_manifest.asdsf, ASDSF_OUTPUT_PATH);
+
+    // Outputs that may be written from here on. (to remove them on cancel)
+    let started = SystemTime::now();
+    let mut written_candidates: Vec<_> =
+        io_job_runner.plan().into_iter().map(|plan| plan.output_path).collect();
+    if !skip_adsf {
+        written_candidates.push(config.output_dir.join(ADSF_OUTPUT_PATH));
+    }
+    if !skip_asdsf {
+        written_candidates.push(config.output_dir.join(ASDSF_OUTPUT_PATH));
+    }
 
     let mut adsf_errors = vec![];
     let mut asdsf_errors = vec![];
@@ -78,31 +236,112 @@ pub async fn behavior_gen(patches: PatchMaps, config: Config) -> Result<()> {
     let mut fnis_convert_errors = vec![];
 
     rayon::scope(|s| {
-        s.spawn(|_| fnis_convert_errors = io_job_runner.convert());
         s.spawn(|_| {
-            adsf_errors =
-                apply_adsf_patches(owned_adsf_patches, &patches, &config, fnis_adsf_patches);
-        });
-        s.spawn(|_| {
-            asdsf_errors = apply_asdsf_patches(owned_asdsf_patches, nemesis_entries, &config);
+            fnis_convert_errors = profiler.phase(Phase::FnisIoJobs, || io_job_runner.convert());
         });
+        if !skip_adsf {
+            s.spawn(|_| {
+                adsf_errors = profiler.phase(Phase::Adsf, || {
+                    apply_adsf_patches(owned_adsf_patches, patches, config, fnis_adsf_patches)
+                });
+            });
+        }
+        if !skip_asdsf {
+            s.spawn(|_| {
+                asdsf_errors = profiler.phase(Phase::Asdsf, || {
+                    apply_asdsf_patches(owned_asdsf_patches, nemesis_entries, config)
+                });
+            });
+        }
         s.spawn(|_| {
-            patched_hkx_errors =
-                Some(apply_and_gen_patched_hkx(&owned_patches, &config, fnis_hkx_patches));
+            patched_hkx_errors = Some(apply_and_gen_patched_hkx(
+                &owned_patches,
+                patches,
+                config,
+                fnis_hkx_patches,
+                &owned_fnis_patches,
+                Incremental { prev_manifest: &prev_manifest, hashes: (global_hash, fnis_hash) },
+                profiler,
+            ));
         });
     });
 
+    // Manifest: record only the outputs generated without errors.
+    let Errors {
+        patch_errors_len,
+        apply_errors_len,
+        hkx_errors_len,
+        hkx_errors,
+        template_hashes,
+        conflicts,
+        mut stale_patches,
+        provenance,
+    } = patched_hkx_errors.unwrap_or_default();
+
+    if config.is_cancelled() && writer.is_output_dir() {
+        written_candidates.extend(
+            template_hashes
+                .templates
+                .keys()
+                .filter(|key| !template_hashes.unchanged.contains_key(*key))
+                .map(|key| hkx_output_path(&config.output_dir, key)),
+        );
+        remove_written_outputs(&written_candidates, started);
+    }
+    config.check_cancelled()?;
+
+    if let Err(_err) = conflicts.save(&config.output_dir) {
+        #[cfg(feature = "tracing")]
+        tracing::error!("Failed to write conflicts report: {_err}");
+    }
+    if let Ok(prev) = StalePatchReport::load(&config.output_dir) {
+        stale_patches.keep_unchanged(prev, &template_hashes.unchanged);
+    }
+    #[cfg(feature = "tracing")]
+    if !stale_patches.is_empty() {
+        tracing::warn!(
+            "{} Nemesis patches do not match the templates. See `.d_merge/stale_patches.json`",
+            stale_patches.patches.len()
+        );
+    }
+    if let Err(_err) = stale_patches.save(&config.output_dir) {
+        #[cfg(feature = "tracing")]
+        tracing::error!("Failed to write stale patch report: {_err}");
+    }
+    if let Some(provenance) = provenance
+        && let Err(_err) = provenance.save(&config.output_dir)
+    {
+        #[cfg(feature = "tracing")]
+        tracing::error!("Failed to write provenance: {_err}");
+    }
+
+    {
+        let TemplateHashes { resources, templates, unchanged } = template_hashes;
+        next_manifest.resources = resources;
+        next_manifest.templates = if hkx_errors.is_empty() { templates } else { unchanged };
+
+        for (hash, errors, inner, output) in [
+            (adsf_hash, &adsf_errors, ADSF_INNER_PATH, &mut next_manifest.adsf),
+            (asdsf_hash, &asdsf_errors, ASDSF_INNER_PATH, &mut next_manifest.asdsf),
+        ] {
+            if let Some((resource_hash, hash)) = hash
+                && errors.is_empty()
+            {
+                next_manifest.resources.insert(inner.to_string(), resource_hash);
+                *output = Some(hash);
+            }
+        }
+    }
+
     // Error process
     {
-        let Errors { patch_errors_len, apply_errors_len, hkx_errors_len, hkx_errors } =
-            patched_hkx_errors.unwrap_or_default();
         let fnis_errors_errors_len = fnis_errors.len() + fnis_convert_errors.len();
 
         let owned_file_errors_len = owned_file_errors.len();
         let adsf_errors_len = adsf_errors.len();
         let asdsf_errors_len = asdsf_errors.len();
 
-        let all_errors = {
+        let mut all_errors = {
             let mut all_errors = vec![];
 
             all_errors.par_extend(fnis_errors);
@@ -116,6 +355,13 @@ pub async fn behavior_gen(patches: PatchMaps, config: Config) -> Result<()> {
         };
 
         if !all_errors.is_empty() {
+            // The outputs generated without errors are still usable, so complete the sink(e.g. the zip archive) too.
+            if let Err(source) = writer.finish() {
+                all_errors.push(Error::FailedIo { path: config.output_dir.clone(), source });
+            }
+            next_manifest.inputs = None;
+            save_manifest(&next_manifest, config, writer.is_output_dir());
+
             let err = BehaviorGenerationError {
                 fnis_errors_errors_len,
                 owned_file_errors_len,
@@ -127,63 +373,332 @@ pub async fn behavior_gen(patches: PatchMaps, config: Config) -> Result<()> {
             };
             config.on_report_status(Status::Error(err.to_string()));
 
-            write_errors(&config, &all_errors).await?;
+            write_errors(config, &all_errors, patches).await?;
             return Err(Error::FailedToGenerateBehaviors { source: err });
         };
     }
 
+    writer.finish().context(FailedIoSnafu { path: &config.output_dir })?;
+    save_manifest(&next_manifest, config, writer.is_output_dir());
+    // Not to show the errors of the last run as the current ones.
+    let _ = std::fs::remove_file(error_report_path(&config.output_dir));
     config.on_report_status(Status::Done);
     Ok(())
 }
 
+/// Returns `true` if the output in `config.output_dir` is out of date with the given patches and options.
+///
+/// This only reads the patch files, FNIS lists and the resources recorded in the manifest(no parsing/applying),
+/// and compares their hashes with `<output_dir>/.d_merge/manifest.json` written by [`behavior_gen`].
+///
+/// Also returns `true` if there is no manifest, the last run had errors, or any output was removed.
+///
+/// # Note
+/// `config.status_report` is called while reading patches. Pass `None` if it is not needed.
+pub async fn is_output_stale(patches: &PatchMaps, config: &Config) -> bool {
+    let PatchMaps { nemesis_entries, fnis_entries } = patches;
+
+    let Some(prev_manifest) = Manifest::load(&config.output_dir) else {
+        return true;
+    };
+    let Some(prev_inputs) = prev_manifest.inputs else {
+        return true;
+    };
+
+    let source = config.source();
+    let owned_fnis_patches = if fnis_entries.is_empty() {
+        vec![]
+    } else {
+        let Some(skyrim_data_dir_glob) = config.skyrim_data_dir_glob.as_ref() else {
+            return true;
+        };
+        let (owned, errors) = fnis::collect::collect_all_fnis_injections(
+            Arc::clone(&source),
+            skyrim_data_dir_glob,
+            fnis_entries,
+        )
+        .await;
+        if !errors.is_empty() {
+            return true;
+        }
+        owned
+    };
+
+    let OwnedPatches { owned_patches, adsf_patches, asdsf_patches, errors } =
+        collect_owned_patches(nemesis_entries, config, &source).await;
+    if !errors.is_empty() {
+        return true;
+    }
+
+    let inputs = manifest::inputs_hash(
+        manifest::global_hash(config),
+        manifest::fnis_hash(&owned_fnis_patches),
+        &owned_patches,
+        &adsf_patches.0,
+        &asdsf_patches.0,
+    );
+
+    inputs != prev_inputs || !prev_manifest.are_outputs_fresh(config)
+}
+
+/// Dry-run of [`behavior_gen`].
+///
+/// Parses every Nemesis/FNIS input and resolves priorities in the same way as [`behavior_gen`],
+/// but writes nothing(no hkx, adsf/asdsf, FNIS conversion, debug output or manifest).
+///
+/// Parse failures are not returned as `Err`, but are listed in [`BehaviorGenPlan::errors`].
+///
+/// # Note
+/// `config.status_report` is called while reading/parsing patches. Pass `None` if it is not needed.
+///
+/// # Errors
+/// Returns an error if there are FNIS entries but `config.skyrim_data_dir_glob` is `None`.
+pub async fn plan_behavior_gen(patches: &PatchMaps, config: &Config) -> Result<BehaviorGenPlan> {
+    let PatchMaps { nemesis_entries, fnis_entries } = patches;
+
+    let source = config.source();
+    let (owned_fnis_patches, mut errors) = if fnis_entries.is_empty() {
+        (vec![], vec![])
+    } else {
+        let skyrim_data_dir_glob =
+            config.skyrim_data_dir_glob.as_ref().ok_or(Error::MissingSkyrimDataDirGlob)?;
+        fnis::collect::collect_all_fnis_injections(
+            Arc::clone(&source),
+            skyrim_data_dir_glob,
+            fnis_entries,
+        )
+        .await
+    };
+
+    let (fnis_hkx_patches, fnis_adsf_patches, io_job_runner, fnis_errors) =
+        fnis::patch_gen::collect_borrowed_patches(&owned_fnis_patches, config, &source);
+    errors.par_extend(fnis_errors);
+
+    let OwnedPatches {
+        owned_patches,
+        adsf_patches: owned_adsf_patches,
+        asdsf_patches: owned_asdsf_patches,
+        errors: owned_file_errors,
+    } = collect_owned_patches(nemesis_entries, config, &source).await;
+    errors.par_extend(owned_file_errors);
+
+    let (templates, conflicts) = {
+        let (PatchCollection { borrowed_patches, mut one_losers, .. }, _, _, patch_errors) =
+            collect_borrowed_patches(
+                &owned_patches,
+                config,
+                fnis_hkx_patches,
+                false,
+                &Profiler::new(),
+            );
+        errors.par_extend(patch_errors);
+
+        let (templates, template_errors) = plan::plan_templates(&borrowed_patches, config);
+        errors.par_extend(template_errors);
+
+        // Same as `behavior_gen`: resolve the selectors before reporting the conflicts.
+        let owned_templates = read_selector_templates(&borrowed_patches, config);
+        let (selector_templates, selector_template_errors) =
+            borrowed::collect_templates(&owned_templates);
+        errors.par_extend(selector_template_errors);
+        one_losers.extend(resolve_patch_selectors(&borrowed_patches, &selector_templates));
+
+        (templates, ConflictReport::new(&borrowed_patches, &one_losers, patches))
+    };
+
+    let (adsf, adsf_errors) = plan_adsf_patches(&owned_adsf_patches, patches, fnis_adsf_patches);
+    let (asdsf, asdsf_errors) = plan_asdsf_patches(&owned_asdsf_patches, nemesis_entries);
+    errors.par_extend(adsf_errors);
+    errors.par_extend(asdsf_errors);
+    for (plans, inner) in [(&adsf, ADSF_INNER_PATH), (&asdsf, ASDSF_INNER_PATH)] {
+        let template_path = config.template_path(inner);
+        if !plans.is_empty() && !template_path.is_file() {
+            errors.push(Error::NotFoundTemplate {
+                template_name: template_path.display().to_string(),
+            });
+        }
+    }
+
+    let mut fnis_jobs = io_job_runner.plan();
+    fnis_jobs.par_sort_unstable_by(|a, b| a.output_path.cmp(&b.output_path));
+
+    // The parse results of a cancelled run are incomplete.
+    config.check_cancelled()?;
+
+    let ErrorReport { mut errors } = ErrorReport::new(&errors, patches);
+    errors.par_sort_unstable_by(|a, b| (&a.path, &a.message).cmp(&(&b.path, &b.message)));
+
+    Ok(BehaviorGenPlan { templates, adsf, asdsf, fnis_jobs, conflicts, errors })
+}
+
+/// Collect which mod and patch file touched each JSON path of the behaviors, without generating anything.
+///
+/// Parses the Nemesis patches and FNIS lists in the same way as [`behavior_gen`]. Inputs that fail
+/// to parse are left out of the report.
+/// [`behavior_gen`] writes the same report to `<output_dir>/.d_merge/.debug/provenance.json`
+/// if `config.debug.output_provenance` is enabled.
+///
+/// # Example
+/// ```no_run
+/// # async fn f(patches: nemesis_merge::PatchMaps, config: nemesis_merge::Config) -> nemesis_merge::errors::Result<()> {
+/// let report = nemesis_merge::collect_provenance(&patches, &config).await?;
+/// for field in report.explain("meshes/actors/character/behaviors/0_master.bin", "#0001") {
+///     println!("{}: {:?}", field.path, field.patches);
+/// }
+/// # Ok(())
+/// # }
+/// ```
+///
+/// # Errors
+/// Returns an error if there are FNIS entries but `config.skyrim_data_dir_glob` is `None`.
+pub async fn collect_provenance(patches: &PatchMaps, config: &Config) -> Result<ProvenanceReport> {
+    let PatchMaps { nemesis_entries, fnis_entries } = patches;
+
+    let source = config.source();
+    let owned_fnis_patches = if fnis_entries.is_empty() {
+        vec![]
+    } else {
+        let skyrim_data_dir_glob =
+            config.skyrim_data_dir_glob.as_ref().ok_or(Error::MissingSkyrimDataDirGlob)?;
+        fnis::collect::collect_all_fnis_injections(
+            Arc::clone(&source),
+            skyrim_data_dir_glob,
+            fnis_entries,
+        )
+        .await
+        .0
+    };
+    let (fnis_hkx_patches, _, _, _) =
+        fnis::patch_gen::collect_borrowed_patches(&owned_fnis_patches, config, &source);
+
+    let OwnedPatches { owned_patches, .. } =
+        collect_owned_patches(nemesis_entries, config, &source).await;
+    let (PatchCollection { borrowed_patches, .. }, mut sources, _, _) =
+        collect_borrowed_patches(&owned_patches, config, fnis_hkx_patches, true, &Profiler::new());
+    config.check_cancelled()?;
+
+    // Same as `behavior_gen`: the patches and their sources are keyed by the resolved selectors.
+    let owned_templates = read_selector_templates(&borrowed_patches, config);
+    let (templates, _) = borrowed::collect_templates(&owned_templates);
+    resolve_patch_selectors(&borrowed_patches, &templates);
+    resolve_source_selectors(&mut sources, &templates);
+
+    Ok(ProvenanceReport::new(&sources, &borrowed_patches, patches, &owned_fnis_patches))
+}
+
+/// Remove the outputs written by the cancelled run.
+///
+/// Only the files modified after `started` are removed, so the outputs of the last run that were
+/// not overwritten yet are kept.
+fn remove_written_outputs(candidates: &[PathBuf], started: SystemTime) {
+    candidates.par_iter().for_each(|path| {
+        let is_written = std::fs::metadata(path)
+            .and_then(|metadata| metadata.modified())
+            .is_ok_and(|modified| modified >= started);
+        if is_written && let Err(_err) = std::fs::remove_file(path) {
+            #[cfg(feature = "tracing")]
+            tracing::error!("Failed to remove partial output {}: {_err}", path.display());
+        }
+    });
+}
+
+/// - `is_output_dir`: The manifest records the files in `output_dir`, so it is not written for another sink.
+fn save_manifest(manifest: &Manifest, config: &Config, is_output_dir: bool) {
+    if !is_output_dir {
+        return;
+    }
+    if let Err(_err) = manifest.save(&config.output_dir) {
+        #[cfg(feature = "tracing")]
+        tracing::error!("Failed to write manifest: {_err}");
+    }
+}
+
 #[derive(Default)]
 struct Errors {
     patch_errors_len: usize,
     apply_errors_len: usize,
     hkx_errors_len: usize,
     hkx_errors: Vec<Error>,
+    template_hashes: TemplateHashes,
+    conflicts: ConflictReport,
+    stale_patches: StalePatchReport,
+    /// `Some` if `config.debug.output_provenance`
+    provenance: Option<ProvenanceReport>,
+}
+
+/// Inputs to skip the templates unchanged since the last run.
+struct Incremental<'a> {
+    prev_manifest: &'a Manifest,
+    /// (global, FNIS)
+    hashes: (u64, u64),
 }
 
 fn apply_and_gen_patched_hkx<'a>(
     owned_patches: &'a OwnedPatchMap,
+    patches: &PatchMaps,
     config: &Config,
     fnis_patches: PatchCollection<'a>,
+    fnis_injections: &[OwnedFnisInjection],
+    Incremental { prev_manifest, hashes }: Incremental<'_>,
+    profiler: &Profiler,
 ) -> Errors {
     let mut all_errors = vec![];
 
+    // NOTE: FNIS patches are merged into Nemesis patches, so remember which templates FNIS patches.
+    let fnis_keys: HashSet<_, rapidhash::fast::RandomState> =
+        fnis_patches.borrowed_patches.0.par_iter().map(|entry| entry.key().clone()).collect();
+
     // 1/3: Parse nemesis patches
+    let track_sources = config.debug.output_provenance;
     let (
-        PatchCollection { borrowed_patches, behavior_graph_data_map: variable_class_map },
-        patch_errors_len,
-    ) = {
-        let (borrowed_patches, errors) =
-            collect_borrowed_patches(owned_patches, config, fnis_patches);
-
-        let patch_errors_len = errors.len();
-        all_errors.par_extend(errors);
-
-        (borrowed_patches, patch_errors_len)
-    };
+        PatchCollection {
+            borrowed_patches,
+            behavior_graph_data_map: variable_class_map,
+            mut one_losers,
+        },
+        mut sources,
+        patch_originals,
+        errors,
+    ) = profiler.phase(Phase::ParsePatches, || {
+        collect_borrowed_patches(owned_patches, config, fnis_patches, track_sources, profiler)
+    });
+    let patch_errors_len = errors.len();
+    all_errors.par_extend(errors);
+    if config.is_cancelled() {
+        return Errors::default();
+    }
 
+    let load_started = Instant::now();
     let mut template_error_len;
-    let owned_templates = {
+    let mut owned_templates = {
         // NOTE: Since `DashSet` cannot solve the lifetime error of `contain`, we have no choice but to replace it with `HashSet`.
         let needed_template_names =
             borrowed_patches.0.par_iter().map(|entry| entry.key().clone()).collect();
 
-        let (owned_templates, errors) =
-            owned::collect_templates(&config.resource_dir, needed_template_names);
+        let (owned_templates, errors) = owned::collect_templates(config, needed_template_names);
         template_error_len = errors.len();
         all_errors.par_extend(errors);
         owned_templates
     };
 
+    // Incremental: skip unchanged templates.
+    let template_hashes = manifest::retain_changed_templates(
+        prev_manifest,
+        config,
+        hashes,
+        &fnis_keys,
+        owned_patches,
+        &mut owned_templates,
+        &borrowed_patches,
+    );
+
     let mut templates = {
         let (templates, errors) = borrowed::collect_templates(&owned_templates);
         template_error_len += errors.len();
         all_errors.par_extend(errors);
         templates
     };
+    profiler.record_phase(Phase::LoadTemplates, load_started.elapsed());
 
     #[cfg(feature = "tracing")]
     tracing::debug!(
@@ -191,16 +706,43 @@ fn apply_and_gen_patched_hkx<'a>(
         owned_templates.keys()
     );
 
+    // Selectors(e.g. `[?name=="Run"]`) point at the elements of the templates before they are patched,
+    // so resolve them before reporting the conflicts.
+    one_losers.extend(resolve_patch_selectors(&borrowed_patches, &templates));
+
+    let conflicts = ConflictReport::new(&borrowed_patches, &one_losers, patches);
+    let provenance = track_sources.then(|| {
+        resolve_source_selectors(&mut sources, &templates);
+        ProvenanceReport::new(&sources, &borrowed_patches, patches, fnis_injections)
+    });
+
+    // Compare the `<!-- ORIGINAL -->` blocks with the templates before they are patched.
+    let stale_patches = StalePatchReport::new(&patch_originals, &templates, patches);
+
     // 2/3: Apply patches & Replace variables to indexes
     let mut apply_errors_len = template_error_len;
-    if let Err(errors) = apply_patches(&mut templates, borrowed_patches, config) {
-        apply_errors_len = errors.len();
+    let apply_result = profiler.phase(Phase::ApplyPatches, || {
+        apply_patches(&mut templates, borrowed_patches, config, profiler)
+    });
+    if let Err(errors) = apply_result {
+        apply_errors_len += errors.len();
         all_errors.par_extend(errors);
     };
+    if config.strict_original {
+        let stale_errors = stale_patches.to_errors();
+        apply_errors_len += stale_errors.len();
+        all_errors.par_extend(stale_errors);
+    }
+    if config.is_cancelled() {
+        return Errors::default();
+    }
 
     // 3/3: Generate hkx files.
     let hkx_errors_len = {
-        if let Err(hkx_errors) = generate_hkx_files(config, templates, variable_class_map) {
+        let hkx_result = profiler.phase(Phase::GenerateHkx, || {
+            generate_hkx_files(config, templates, variable_class_map, profiler)
+        });
+        if let Err(hkx_errors) = hkx_result {
             let errors_len = hkx_errors.len();
             all_errors.par_extend(hkx_errors);
             errors_len
@@ -209,5 +751,14 @@ fn apply_and_gen_patched_hkx<'a>(
         }
     };
 
-    Errors { patch_errors_len, apply_errors_len, hkx_errors_len, hkx_errors: all_errors }
+    Errors {
+        patch_errors_len,
+        apply_errors_len,
+        hkx_errors_len,
+        hkx_errors: all_errors,
+        template_hashes,
+        conflicts,
+        stale_patches,
+        provenance,
+    }
 }
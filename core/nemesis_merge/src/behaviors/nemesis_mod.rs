@@ -0,0 +1,310 @@
+//! Generate a Nemesis patch mod(`Nemesis_Engine/mod/<id>/`) from behaviors edited in external tools.
+//!
+//! Each edited behavior is compared with its template, and the differences are written as `#NNNN.txt`
+//! with `MOD_CODE`/`ORIGINAL`/`CLOSE` markers, as mod authors do by hand.
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use serde_hkx::HavokSort as _;
+use serde_hkx_features::ClassMap;
+use simd_json::BorrowedValue;
+use snafu::ResultExt as _;
+
+use crate::{
+    behaviors::tasks::templates::{
+        collect::borrowed::{template_bin_to_value, template_xml_to_value},
+        gen_bin::get_meshes_relative_path,
+        key::TemplateKey,
+    },
+    config::Config,
+    errors::{Error, FailedIoSnafu, HkxDeSnafu, HkxSerSnafu, NemesisXmlWriteErrSnafu, Result},
+};
+
+/// `info.ini` of the generated mod.
+#[derive(Debug, Clone, Default)]
+pub struct NemesisModInfo {
+    /// Mod code. e.g. `slide`
+    ///
+    /// ASCII alphanumerics or `_`, starting with a letter.
+    pub id: String,
+    pub name: String,
+    pub author: String,
+    pub site: String,
+}
+
+/// Generate `<output_dir>/Nemesis_Engine/mod/<id>/` from the edited behaviors.
+///
+/// - `edited_files`: `.hkx` or `.xml` under `meshes`. e.g. `<any>/meshes/actors/character/behaviors/0_master.hkx`
+///
+/// The template of each behavior is read from `resource_dir` in the same way as the generation.
+/// Objects added by the edit are written as `#<id>$N.txt`. Objects removed by the edit are ignored,
+/// since Nemesis patches cannot remove objects.
+///
+/// # Returns
+/// The written files.
+///
+/// # Errors
+/// - If the id is invalid.
+/// - If an edited file is not a Nemesis template behavior, or cannot be read.
+/// - If a template cannot be read.
+/// - If writing fails.
+pub fn generate_nemesis_mod(
+    config: &Config,
+    info: &NemesisModInfo,
+    edited_files: &[PathBuf],
+) -> Result<Vec<PathBuf>> {
+    if !is_valid_mod_id(&info.id) {
+        return Err(Error::InvalidNemesisModId { id: info.id.clone() });
+    }
+
+    let mod_dir = config.output_dir.join("Nemesis_Engine").join("mod").join(&info.id);
+    let mut written = vec![];
+    for path in edited_files {
+        let (template_key, template_name, is_1st_person) = nemesis_template_of(path)?;
+
+        let template_path = config.template_path(template_key.as_meshes_inner_path());
+        let template_bytes =
+            fs::read(&template_path).context(FailedIoSnafu { path: &template_path })?;
+        let template = template_bin_to_value(&template_bytes, &template_path)?;
+
+        let edited_xml = read_as_xml(path)?;
+        let edited = template_xml_to_value(edited_xml.as_bytes(), path)?;
+        let signatures = class_signatures(&edited_xml);
+
+        let files = patch_files(&template, &edited, &signatures, &info.id, path)?;
+
+        let mut template_dir = mod_dir.clone();
+        if is_1st_person {
+            template_dir.push("_1stperson");
+        }
+        template_dir.push(template_name);
+        // Remove the patches of the previous generation.
+        if template_dir.exists() {
+            fs::remove_dir_all(&template_dir).context(FailedIoSnafu { path: &template_dir })?;
+        }
+        if files.is_empty() {
+            continue;
+        }
+
+        fs::create_dir_all(&template_dir).context(FailedIoSnafu { path: &template_dir })?;
+        for (file_name, content) in files {
+            let file_path = template_dir.join(file_name);
+            fs::write(&file_path, content).context(FailedIoSnafu { path: &file_path })?;
+            written.push(file_path);
+        }
+    }
+
+    let info_path = mod_dir.join("info.ini");
+    fs::create_dir_all(&mod_dir).context(FailedIoSnafu { path: &mod_dir })?;
+    let info_ini =
+        format!("name={}\nauthor={}\nsite={}\nauto=\n", info.name, info.author, info.site);
+    fs::write(&info_path, info_ini).context(FailedIoSnafu { path: &info_path })?;
+    written.push(info_path);
+
+    Ok(written)
+}
+
+/// Mod code that the Nemesis patch parser accepts. (e.g. `#slide$0`)
+fn is_valid_mod_id(id: &str) -> bool {
+    id.starts_with(|c: char| c.is_ascii_alphabetic())
+        && id.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// Returns (template key, Nemesis template name, is_1st_person).
+fn nemesis_template_of(path: &Path) -> Result<(TemplateKey<'static>, &'static str, bool)> {
+    let not_found = || Error::NotFoundNemesisTemplate { path: path.to_path_buf() };
+
+    let mut inner_path = get_meshes_relative_path(path).ok_or_else(not_found)?;
+    inner_path.set_extension("bin");
+    let inner_path = inner_path.to_str().ok_or_else(not_found)?.replace('\\', "/");
+
+    let key = TemplateKey::new(inner_path.into()).ok_or_else(not_found)?;
+    let (template_name, is_1st_person) = key.to_nemesis_file().ok_or_else(not_found)?;
+    let key = TemplateKey::from_nemesis_file(template_name, is_1st_person).ok_or_else(not_found)?;
+    Ok((key, template_name, is_1st_person))
+}
+
+/// Read `.hkx` or `.xml` as XML.
+fn read_as_xml(path: &Path) -> Result<String> {
+    let bytes = fs::read(path).context(FailedIoSnafu { path })?;
+    if !path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("hkx")) {
+        return String::from_utf8(bytes)
+            .map_err(|_| Error::NonUtf8Path { path: path.to_path_buf() });
+    }
+
+    let mut class_map: ClassMap =
+        serde_hkx::from_bytes(&bytes).with_context(|_| HkxDeSnafu { path })?;
+    let ptr = class_map.sort_for_xml().with_context(|_| HkxSerSnafu { path })?;
+    serde_hkx::to_string(&class_map, &ptr).with_context(|_| HkxSerSnafu { path })
+}
+
+/// Returns class name -> signature. (from `<hkobject name="#0001" class="..." signature="...">`)
+fn class_signatures(xml: &str) -> HashMap<&str, &str> {
+    fn attribute<'a>(tag: &'a str, name: &str) -> Option<&'a str> {
+        let pattern = format!(" {name}=\"");
+        let start = tag.find(&pattern)? + pattern.len();
+        let len = tag[start..].find('"')?;
+        Some(&tag[start..start + len])
+    }
+
+    xml.split("<hkobject")
+        .skip(1)
+        .filter_map(|tag| {
+            let tag = &tag[..tag.find('>')?];
+            Some((attribute(tag, "class")?, attribute(tag, "signature")?))
+        })
+        .collect()
+}
+
+/// Returns (file name, content) of the patches that change `template` into `edited`.
+///
+/// - The objects in both(same index and class): `#NNNN.txt` if changed.
+/// - Others: `#<id>$N.txt` in index order, and the pointers to them are renamed.
+fn patch_files(
+    template: &BorrowedValue<'_>,
+    edited: &BorrowedValue<'_>,
+    signatures: &HashMap<&str, &str>,
+    mod_id: &str,
+    path: &Path,
+) -> Result<Vec<(String, String)>> {
+    let template: HashMap<_, _> = nemesis_xml::ser::class_objects(template)
+        .into_iter()
+        .map(|(ptr, class, fields)| (ptr, (class, fields)))
+        .collect();
+    let edited = nemesis_xml::ser::class_objects(edited);
+
+    let mut renames = HashMap::new();
+    for (ptr, class, _) in &edited {
+        if template.get(ptr).is_none_or(|(template_class, _)| template_class != class) {
+            let new_ptr = format!("#{mod_id}${}", renames.len());
+            renames.insert(*ptr, new_ptr);
+        }
+    }
+
+    let mut files = vec![];
+    for (ptr, class, fields) in edited {
+        let signature = signatures.get(class).ok_or_else(|| Error::MissingClassSignature {
+            class_name: class.to_string(),
+            path: path.to_path_buf(),
+        })?;
+        let mut fields = fields.clone();
+        rename_ptrs(&mut fields, &renames);
+
+        if let Some(new_ptr) = renames.get(ptr) {
+            let content = nemesis_xml::ser::write_new_object(new_ptr, class, signature, &fields)
+                .context(NemesisXmlWriteErrSnafu { path })?;
+            files.push((format!("{new_ptr}.txt"), content));
+        } else if let Some((_, template_fields)) = template.get(ptr) {
+            let content = nemesis_xml::ser::write_object_patch(
+                ptr,
+                class,
+                signature,
+                mod_id,
+                template_fields,
+                &fields,
+            )
+            .context(NemesisXmlWriteErrSnafu { path })?;
+            if let Some(content) = content {
+                files.push((format!("{ptr}.txt"), content));
+            }
+        }
+    }
+    Ok(files)
+}
+
+fn rename_ptrs(value: &mut BorrowedValue<'_>, renames: &HashMap<&str, String>) {
+    match value {
+        BorrowedValue::String(s) => {
+            let ptr: &str = s;
+            if let Some(new_ptr) = renames.get(ptr) {
+                *s = new_ptr.clone().into();
+            }
+        }
+        BorrowedValue::Array(array) => {
+            for value in array.iter_mut() {
+                rename_ptrs(value, renames);
+            }
+        }
+        BorrowedValue::Object(object) => {
+            for value in object.values_mut() {
+                rename_ptrs(value, renames);
+            }
+        }
+        BorrowedValue::Static(_) => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use json_patch::{Action, JsonPatch, Op, json_path};
+    use nemesis_xml::patch::parse_nemesis_patch;
+    use simd_json::json_typed;
+
+    use super::*;
+
+    #[test]
+    fn patch_files_roundtrip() {
+        let template = json_typed!(borrowed, {
+            "#0001": { "hkbStateMachine": { "__ptr": "#0001", "name": "Root", "startStateId": 0, "states": ["#0002"] } },
+            "#0002": { "hkbStateMachineStateInfo": { "__ptr": "#0002", "name": "Idle", "stateId": 0 } }
+        });
+        let edited = json_typed!(borrowed, {
+            "#0001": { "hkbStateMachine": { "__ptr": "#0001", "name": "Root", "startStateId": 0, "states": ["#0002", "#0003"] } },
+            "#0002": { "hkbStateMachineStateInfo": { "__ptr": "#0002", "name": "Idle", "stateId": 0 } },
+            "#0003": { "hkbStateMachineStateInfo": { "__ptr": "#0003", "name": "Slide", "stateId": 1 } }
+        });
+        let signatures = HashMap::from([
+            ("hkbStateMachine", "0x816c1dcb"),
+            ("hkbStateMachineStateInfo", "0xed7f9d0"),
+        ]);
+
+        let files =
+            patch_files(&template, &edited, &signatures, "slide", Path::new("0_master.xml"))
+                .unwrap();
+        let names: Vec<_> = files.iter().map(|(name, _)| name.as_str()).collect();
+        assert_eq!(names, ["#0001.txt", "#slide$0.txt"]);
+
+        let (patches, _) = parse_nemesis_patch(&files[0].1, None).unwrap_or_else(|e| panic!("{e}"));
+        assert_eq!(
+            patches.get(&json_path!["#0001", "hkbStateMachine", "states"]),
+            Some(&JsonPatch {
+                action: Action::SeqPush,
+                value: json_typed!(borrowed, ["#slide$0"])
+            })
+        );
+
+        let (patches, _) = parse_nemesis_patch(&files[1].1, None).unwrap_or_else(|e| panic!("{e}"));
+        assert_eq!(
+            patches.get(&json_path!["#slide$0", "hkbStateMachineStateInfo"]),
+            Some(&JsonPatch {
+                action: Action::Pure { op: Op::Add },
+                value: json_typed!(borrowed, { "__ptr": "#slide$0", "name": "Slide", "stateId": 1 }),
+            })
+        );
+    }
+
+    #[test]
+    fn scrape_class_signatures() {
+        let xml = r##"
+		<hkobject name="#0001" class="hkRootLevelContainer" signature="0x2772c11e">
+			<hkparam name="namedVariants" numelements="1">
+				<hkobject>
+					<hkparam name="name">hkbBehaviorGraph</hkparam>
+				</hkobject>
+			</hkparam>
+		</hkobject>
+"##;
+        assert_eq!(class_signatures(xml), HashMap::from([("hkRootLevelContainer", "0x2772c11e")]));
+    }
+
+    #[test]
+    fn mod_id() {
+        assert!(is_valid_mod_id("slide_2"));
+        assert!(!is_valid_mod_id("2slide"));
+        assert!(!is_valid_mod_id("sl ide"));
+        assert!(!is_valid_mod_id(""));
+    }
+}
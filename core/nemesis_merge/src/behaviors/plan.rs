@@ -0,0 +1,201 @@
+//! Dry-run result of behavior generation.
+use std::{collections::BTreeMap, path::PathBuf};
+
+use rayon::{iter::Either, prelude::*};
+
+use crate::{
+    Config,
+    behaviors::{
+        conflicts::ConflictReport,
+        tasks::{
+            manifest::hkx_output_path,
+            patches::types::{BehaviorPatchesMap, HkxPatchMaps},
+        },
+    },
+    errors::{Error, report::ErrorRecord},
+};
+
+/// What [`crate::behavior_gen`] would generate with the same patches and config.
+///
+/// Returned by [`crate::plan_behavior_gen`]. Every list is sorted by its path/name.
+#[derive(Debug, Clone, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[cfg_attr(feature = "ts_serde", serde(rename_all = "camelCase"))]
+pub struct BehaviorGenPlan {
+    /// Templates that would be patched and written as hkx.
+    pub templates: Vec<TemplatePlan>,
+
+    /// Projects of `animationdatasinglefile.txt` that would be patched.
+    ///
+    /// Empty if there is no adsf patch.
+    pub adsf: Vec<AnimDataPlan>,
+
+    /// Projects of `animationsetdatasinglefile.txt` that would be patched.
+    ///
+    /// Empty if there is no asdsf patch.
+    pub asdsf: Vec<AnimDataPlan>,
+
+    /// FNIS I/O jobs(hkx conversions, config writes) that would run.
+    pub fnis_jobs: Vec<FnisIoJobPlan>,
+
+    /// Conflicts between the patches of different mods.
+    pub conflicts: ConflictReport,
+
+    /// Inputs that failed to parse or resolve. (sorted by path and message)
+    ///
+    /// These are the same errors that `behavior_gen` would write into `d_merge_errors.json`,
+    /// except for apply/hkx generation errors, which can only be detected by generating.
+    pub errors: Vec<ErrorRecord>,
+}
+
+impl BehaviorGenPlan {
+    /// Returns `true` if there are no inputs that failed to parse.
+    #[inline]
+    pub const fn is_ok(&self) -> bool {
+        self.errors.is_empty()
+    }
+}
+
+/// A template that would be generated.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[cfg_attr(feature = "ts_serde", serde(rename_all = "camelCase"))]
+pub struct TemplatePlan {
+    /// Template path inside `resource_dir`.
+    /// - e.g. `meshes/actors/character/behaviors/0_master.bin`
+    pub template: String,
+
+    /// Output path.
+    /// - e.g. `<output_dir>/meshes/actors/character/behaviors/0_master.hkx`
+    pub output_path: PathBuf,
+
+    /// Number of one field patches after priority resolution.(highest priority wins)
+    pub one_patches: usize,
+
+    /// Number of array(seq) patches. Every seq patch is applied, so this is the sum of all of them.
+    pub seq_patches: usize,
+}
+
+/// A project of `animationdatasinglefile.txt`/`animationsetdatasinglefile.txt` that would be patched.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[cfg_attr(feature = "ts_serde", serde(rename_all = "camelCase"))]
+pub struct AnimDataPlan {
+    /// Patch target.
+    /// - adsf: e.g. `DefaultMale~1`, `$header$`
+    /// - asdsf: e.g. `DefaultMaleData~DefaultMale`, `$header$`
+    pub target: String,
+
+    /// Number of patches after priority resolution.
+    pub patches: usize,
+}
+
+impl AnimDataPlan {
+    /// Count the patches for each target. (sorted by target)
+    pub(crate) fn count_by_target<'a>(targets: impl Iterator<Item = &'a str>) -> Vec<Self> {
+        let mut counts = BTreeMap::<&str, usize>::new();
+        for target in targets {
+            *counts.entry(target).or_default() += 1;
+        }
+        counts
+            .into_iter()
+            .map(|(target, patches)| Self { target: target.to_string(), patches })
+            .collect()
+    }
+}
+
+/// A FNIS I/O job that would run.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[cfg_attr(feature = "ts_serde", serde(rename_all = "camelCase"))]
+pub struct FnisIoJobPlan {
+    pub kind: FnisIoJobKind,
+    /// Source file. `None` if the file is generated from scratch.
+    pub input_path: Option<PathBuf>,
+    pub output_path: PathBuf,
+}
+
+/// Kind of [`FnisIoJobPlan`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, serde::Serialize, serde::Deserialize)]
+pub enum FnisIoJobKind {
+    /// Dummy `FNIS.esp`
+    DummyEsp,
+    /// `SKSE/Plugins/fnis_aa/config.json`
+    FnisAAConfig,
+    /// Animation/behavior hkx conversion for the output target.
+    Hkx,
+    /// FNIS alternate animation hkx conversion into the OAR directory.
+    FnisAAHkx,
+    /// Namespace-level OAR `config.json`
+    FnisAANamespaceConfig,
+    /// Per-slot OAR `config.json`
+    FnisAASlotConfig,
+}
+
+/// Summarize the resolved patches of each template.
+///
+/// Templates not found in `resource_dir` are returned as errors, as `behavior_gen` does.
+pub(crate) fn plan_templates(
+    borrowed_patches: &BehaviorPatchesMap<'_>,
+    config: &Config,
+) -> (Vec<TemplatePlan>, Vec<Error>) {
+    let (mut templates, errors): (Vec<_>, Vec<_>) =
+        borrowed_patches.0.par_iter().partition_map(|entry| {
+            let key = entry.key();
+            let template_path = config.template_path(key.as_meshes_inner_path());
+            if !template_path.is_file() {
+                return Either::Right(Error::NotFoundTemplate {
+                    template_name: template_path.display().to_string(),
+                });
+            }
+
+            let HkxPatchMaps { one, seq } = entry.value();
+            Either::Left(TemplatePlan {
+                template: key.as_str().to_string(),
+                output_path: hkx_output_path(&config.output_dir, key.as_str()),
+                one_patches: one.len(),
+                seq_patches: seq.0.iter().map(|patches| patches.value().len()).sum(),
+            })
+        });
+    templates.par_sort_unstable_by(|a, b| a.template.cmp(&b.template));
+
+    (templates, errors)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{PatchMaps, plan_behavior_gen};
+
+    #[test]
+    fn count_by_target_sorted() {
+        let plans = AnimDataPlan::count_by_target(
+            ["DefaultMale~1", "$header$", "DefaultMale~1", "DefaultFemale~1"].into_iter(),
+        );
+        let expected = [("$header$", 1), ("DefaultFemale~1", 1), ("DefaultMale~1", 2)]
+            .map(|(target, patches)| AnimDataPlan { target: target.to_string(), patches });
+        assert_eq!(plans, expected);
+    }
+
+    #[tokio::test]
+    async fn plan_writes_nothing() {
+        let output_dir = PathBuf::from("../../dummy/plan_behavior_gen/output");
+        let _ = std::fs::remove_dir_all(&output_dir);
+
+        let config = Config {
+            output_dir: output_dir.clone(),
+            generate_fnis_esp: true,
+            ..Default::default()
+        };
+        let plan = plan_behavior_gen(&PatchMaps::default(), &config).await.unwrap();
+
+        assert!(plan.is_ok());
+        assert!(plan.templates.is_empty());
+        assert!(plan.conflicts.is_empty());
+        assert_eq!(
+            plan.fnis_jobs,
+            [FnisIoJobPlan {
+                kind: FnisIoJobKind::DummyEsp,
+                input_path: None,
+                output_path: output_dir.join("FNIS.esp"),
+            }]
+        );
+        assert!(!output_dir.exists());
+    }
+}
@@ -1,4 +1,4 @@
-//! Utilities for extracting mod identifiers from paths pointing to `Nemesis_Engine` folders.
+//! Utilities for extracting mod identifiers from paths pointing to `Nemesis_Engine`(or `Pandora_Engine`) folders.
 //!
 //! This module includes functionality to extract a unique mod code path from
 //! a file path, and convert multiple such paths into a priority map indexed by
@@ -39,42 +39,39 @@ use winnow_ext::ReadableError;
 /// ```txt
 /// `D:\\...\\Nemesis_EngineExt\\mod\\abc\\meshes\\somefile.txt` -> `D:\\...\\Nemesis_EngineExt\\mod\\abc`
 /// ```
-pub(super) fn get_nemesis_id(input: &str) -> Result<&str, ReadableError> {
-    alt((_get_nemesis_id, _get_nemesis_ext_id))
+///
+/// - Pandora
+/// ```txt
+/// `D:\\...\\Pandora_Engine\\mod\\abc\\0_master\\somefile.txt` -> `D:\\...\\Pandora_Engine\\mod\\abc`
+/// ```
+pub(crate) fn get_nemesis_id(input: &str) -> Result<&str, ReadableError> {
+    alt((_get_nemesis_id, _get_nemesis_ext_id, _get_pandora_id))
         .parse(input)
         .map_err(|e| ReadableError::from_parse(e))
 }
 
 fn _get_nemesis_id<'a>(input: &mut &'a str) -> ModalResult<&'a str> {
-    // Match either '/' or '\\' as path separator
-    let mut sep =
-        alt(('/', '\\')).context(Expected(CharLiteral('/'))).context(Expected(CharLiteral('\\')));
+    get_engine_mod_id(input, "Nemesis_Engine")
+}
 
-    // Build parser for: <any>* Nemesis_Engine/mod/<mod_code>
-    let mut parser = seq! {
-        winnow_ext::take_until_ext(0.., Caseless("Nemesis_Engine")).context(Expected(StringLiteral("Nemesis_Engine"))),
-        Caseless("Nemesis_Engine"),
-        sep,
-        "mod".context(Expected(StringLiteral("mod"))),
-        sep,
-        take_while(1.., |c: char| c != '/' && c != '\\').context(Expected(Description("mod code"))),
-    }
-    .take();
+fn _get_nemesis_ext_id<'a>(input: &mut &'a str) -> ModalResult<&'a str> {
+    get_engine_mod_id(input, "Nemesis_EngineExt")
+}
 
-    let id = parser.parse_next(input)?;
-    repeat::<_, _, (), _, _>(0.., any).parse_next(input)?; // consume remaining input
-    Ok(id)
+fn _get_pandora_id<'a>(input: &mut &'a str) -> ModalResult<&'a str> {
+    get_engine_mod_id(input, "Pandora_Engine")
 }
 
-fn _get_nemesis_ext_id<'a>(input: &mut &'a str) -> ModalResult<&'a str> {
+/// `<any>* <engine>/mod/<mod_code>`
+fn get_engine_mod_id<'a>(input: &mut &'a str, engine: &'static str) -> ModalResult<&'a str> {
     // Match either '/' or '\\' as path separator
     let mut sep =
         alt(('/', '\\')).context(Expected(CharLiteral('/'))).context(Expected(CharLiteral('\\')));
 
-    // Build parser for: <any>* Nemesis_Engine/mod/<mod_code>
+    // Build parser for: <any>* <engine>/mod/<mod_code>
     let mut parser = seq! {
-        winnow_ext::take_until_ext(0.., Caseless("Nemesis_EngineExt")).context(Expected(StringLiteral("Nemesis_EngineExt"))),
-        Caseless("Nemesis_EngineExt"),
+        winnow_ext::take_until_ext(0.., Caseless(engine)).context(Expected(StringLiteral(engine))),
+        Caseless(engine),
         sep,
         "mod".context(Expected(StringLiteral("mod"))),
         sep,
@@ -98,6 +95,13 @@ mod tests {
         assert_eq!(id, r"D:\GAME\ModOrganizer Skyrim SE\mods\SomeMod\Nemesis_Engine\mod\abc");
     }
 
+    #[test]
+    fn test_get_pandora_id() {
+        let input = "/mods/SomeMod/Pandora_Engine/mod/abc/meshes/actors/character/behaviors/0_master.bin/#0001.txt";
+        let id = get_nemesis_id(input).unwrap_or_else(|e| panic!("{e}"));
+        assert_eq!(id, "/mods/SomeMod/Pandora_Engine/mod/abc");
+    }
+
     #[test]
     fn test_invalid_path() {
         let input = r"D:\Invalid\Path\To\Something";
@@ -13,6 +13,7 @@ pub type PriorityMap = HashMap<String, usize>;
 pub struct PatchMaps {
     /// Nemesis patch path
     /// - key: path until mod_code(e.g. `<skyrim_data_dir>/meshes/Nemesis_Engine/mod/slide`)
+    ///   `Nemesis_EngineExt` and `Pandora_Engine` are also accepted.
     /// - value: priority
     pub nemesis_entries: PriorityMap,
     /// FNIS patch path
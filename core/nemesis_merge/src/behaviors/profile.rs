@@ -0,0 +1,224 @@
+//! Wall time of each phase of a run, and the slowest templates and patch files.
+//! (`<output_dir>/.d_merge/profile.json`)
+//!
+//! adsf, asdsf, FNIS io jobs and hkx generation run in parallel, so the sum of the phases may exceed
+//! [`BehaviorGenProfile::total_ms`].
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+    sync::{Mutex, PoisonError},
+    time::{Duration, Instant},
+};
+
+use snafu::ResultExt as _;
+
+use crate::errors::{FailedIoSnafu, JsonSnafu, Result};
+
+/// Number of the slowest templates/patch files kept in the profile.
+const SLOWEST_LEN: usize = 10;
+
+/// A phase of [`crate::behavior_gen`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Phase {
+    /// Read FNIS lists.
+    FnisCollect,
+    /// Generate patches from FNIS lists.
+    FnisPatchGen,
+    /// Read Nemesis patch files.
+    ReadPatches,
+    /// Parse Nemesis patch files.
+    ParsePatches,
+    /// Read and parse the templates.
+    LoadTemplates,
+    /// Apply patches to the templates.
+    ApplyPatches,
+    /// Serialize the patched templates to hkx.
+    GenerateHkx,
+    /// `animationdatasinglefile.txt`
+    Adsf,
+    /// `animationsetdatasinglefile.txt`
+    Asdsf,
+    /// FNIS conversions and copies.(e.g. Alternate Animation, hkx conversion)
+    FnisIoJobs,
+}
+
+/// Timing profile of one [`crate::behavior_gen`] run.
+///
+/// Written to `<output_dir>/.d_merge/profile.json` on every run that was not cancelled.
+#[derive(Debug, Clone, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+#[cfg_attr(feature = "ts_serde", serde(rename_all = "camelCase"))]
+pub struct BehaviorGenProfile {
+    /// Wall time of the whole run.
+    pub total_ms: f64,
+    /// Wall time of each phase, in [`Phase`] order. Skipped phases are omitted.
+    pub phases: Vec<PhaseTiming>,
+    /// Templates that took the longest to apply patches and generate hkx. (slowest first)
+    pub slowest_templates: Vec<ItemTiming>,
+    /// Nemesis patch files that took the longest to parse. (slowest first)
+    pub slowest_patch_files: Vec<ItemTiming>,
+}
+
+/// Wall time of a [`Phase`].
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+#[cfg_attr(feature = "ts_serde", serde(rename_all = "camelCase"))]
+pub struct PhaseTiming {
+    pub phase: Phase,
+    pub ms: f64,
+}
+
+/// Time spent on a template or a patch file.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+#[cfg_attr(feature = "ts_serde", serde(rename_all = "camelCase"))]
+pub struct ItemTiming {
+    /// Template key(e.g. `meshes/actors/character/behaviors/0_master.bin`) or patch file path.
+    pub name: String,
+    pub ms: f64,
+}
+
+impl BehaviorGenProfile {
+    /// Returns the wall time of `phase`, if it was run.
+    pub fn phase_ms(&self, phase: Phase) -> Option<f64> {
+        self.phases.iter().find(|timing| timing.phase == phase).map(|timing| timing.ms)
+    }
+
+    /// Read `<output_dir>/.d_merge/profile.json` written by the last [`crate::behavior_gen`].
+    ///
+    /// # Errors
+    /// If the file does not exist or cannot be parsed.
+    pub fn load(output_dir: impl AsRef<Path>) -> Result<Self> {
+        let path = profile_path(output_dir.as_ref());
+        let bytes = std::fs::read(&path).context(FailedIoSnafu { path: &path })?;
+        sonic_rs::from_slice(&bytes).with_context(|_| JsonSnafu { path })
+    }
+
+    /// Write the profile to `<output_dir>/.d_merge/profile.json`.
+    ///
+    /// # Errors
+    /// If failed to write.
+    pub(crate) fn save(&self, output_dir: &Path) -> Result<()> {
+        let path = profile_path(output_dir);
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent).context(FailedIoSnafu { path: parent })?;
+        }
+        let json = sonic_rs::to_string_pretty(self).with_context(|_| JsonSnafu { path: &path })?;
+        std::fs::write(&path, json).context(FailedIoSnafu { path })
+    }
+}
+
+/// `<output_dir>/.d_merge/profile.json`
+fn profile_path(output_dir: &Path) -> PathBuf {
+    output_dir.join(".d_merge").join("profile.json")
+}
+
+/// Collects the timings from the parallel tasks of a run.
+#[derive(Debug, Default)]
+pub(crate) struct Profiler {
+    phases: Mutex<BTreeMap<Phase, Duration>>,
+    /// Template key -> time of apply + hkx generation
+    templates: Mutex<BTreeMap<String, Duration>>,
+    patch_files: Mutex<Vec<(PathBuf, Duration)>>,
+}
+
+impl Profiler {
+    pub(crate) fn new() -> Self {
+        Self::default()
+    }
+
+    /// Run `f` and record its wall time as `phase`.
+    pub(crate) fn phase<T>(&self, phase: Phase, f: impl FnOnce() -> T) -> T {
+        let started = Instant::now();
+        let ret = f();
+        self.record_phase(phase, started.elapsed());
+        ret
+    }
+
+    pub(crate) fn record_phase(&self, phase: Phase, elapsed: Duration) {
+        *self.phases.lock().unwrap_or_else(PoisonError::into_inner).entry(phase).or_default() +=
+            elapsed;
+    }
+
+    /// Add `elapsed` to the time of `template`.(It is recorded by both apply and hkx generation.)
+    pub(crate) fn record_template(&self, template: &str, elapsed: Duration) {
+        let mut templates = self.templates.lock().unwrap_or_else(PoisonError::into_inner);
+        match templates.get_mut(template) {
+            Some(total) => *total += elapsed,
+            None => {
+                templates.insert(template.to_string(), elapsed);
+            }
+        }
+    }
+
+    pub(crate) fn record_patch_file(&self, path: &Path, elapsed: Duration) {
+        self.patch_files
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .push((path.to_path_buf(), elapsed));
+    }
+
+    /// - `total`: Wall time of the whole run.
+    pub(crate) fn finish(self, total: Duration) -> BehaviorGenProfile {
+        let phases = self.phases.into_inner().unwrap_or_else(PoisonError::into_inner);
+        let templates = self.templates.into_inner().unwrap_or_else(PoisonError::into_inner);
+        let patch_files = self.patch_files.into_inner().unwrap_or_else(PoisonError::into_inner);
+
+        BehaviorGenProfile {
+            total_ms: to_ms(total),
+            phases: phases
+                .into_iter()
+                .map(|(phase, elapsed)| PhaseTiming { phase, ms: to_ms(elapsed) })
+                .collect(),
+            slowest_templates: slowest(templates),
+            slowest_patch_files: slowest(
+                patch_files
+                    .into_iter()
+                    .map(|(path, elapsed)| (path.display().to_string(), elapsed)),
+            ),
+        }
+    }
+}
+
+/// Returns the [`SLOWEST_LEN`] slowest items, slowest first.(ties are ordered by name)
+fn slowest(items: impl IntoIterator<Item = (String, Duration)>) -> Vec<ItemTiming> {
+    let mut items: Vec<_> = items.into_iter().collect();
+    items.sort_by(|(a_name, a), (b_name, b)| b.cmp(a).then_with(|| a_name.cmp(b_name)));
+    items
+        .into_iter()
+        .take(SLOWEST_LEN)
+        .map(|(name, elapsed)| ItemTiming { name, ms: to_ms(elapsed) })
+        .collect()
+}
+
+const fn to_ms(duration: Duration) -> f64 {
+    duration.as_secs_f64() * 1000.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finish_sums_templates_and_keeps_slowest() {
+        let profiler = Profiler::new();
+        profiler.record_phase(Phase::GenerateHkx, Duration::from_millis(3));
+        profiler.record_phase(Phase::FnisCollect, Duration::from_millis(1));
+        profiler.record_template("0_master", Duration::from_millis(2));
+        profiler.record_template("0_master", Duration::from_millis(2));
+        for i in 0..SLOWEST_LEN + 2 {
+            profiler.record_template(&format!("template_{i:02}"), Duration::from_millis(1));
+            profiler
+                .record_patch_file(Path::new(&format!("#{i:04}.txt")), Duration::from_millis(1));
+        }
+        let profile = profiler.finish(Duration::from_millis(5));
+
+        let phases: Vec<_> = profile.phases.iter().map(|timing| timing.phase).collect();
+        assert_eq!(phases, [Phase::FnisCollect, Phase::GenerateHkx]);
+        assert_eq!(profile.phase_ms(Phase::Adsf), None);
+
+        assert_eq!(profile.slowest_templates.len(), SLOWEST_LEN);
+        assert_eq!(profile.slowest_templates[0].name, "0_master");
+        assert!((profile.slowest_templates[0].ms - 4.0).abs() < 1e-9);
+        assert_eq!(profile.slowest_patch_files.len(), SLOWEST_LEN);
+        assert_eq!(profile.slowest_patch_files[0].name, "#0000.txt");
+    }
+}
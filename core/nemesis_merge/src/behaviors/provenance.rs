@@ -0,0 +1,298 @@
+//! Which mod and patch file wrote each JSON path of the merged behaviors.
+//! (`<output_dir>/.d_merge/.debug/provenance.json`)
+//!
+//! - One field: only the highest priority patch is applied. The others are recorded as not applied.
+//! - Seq: every patch is applied in priority order, so the last one is the last writer.
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+};
+
+use json_patch::{Action, JsonPath, Op};
+use rapidhash::fast::RapidHashMap as HashMap;
+use rayon::prelude::*;
+use snafu::ResultExt as _;
+
+use crate::{
+    PatchMaps,
+    behaviors::{
+        conflicts::ModIds,
+        tasks::{
+            fnis::collect::owned::OwnedFnisInjection, patches::types::BehaviorPatchesMap,
+            templates::key::TemplateKey,
+        },
+    },
+    errors::{FailedIoSnafu, JsonSnafu, Result},
+};
+
+/// A parsed patch and the file it came from.
+#[derive(Debug, Clone)]
+pub(crate) struct PatchOrigin<'a> {
+    pub template: TemplateKey<'static>,
+    pub path: JsonPath<'a>,
+    pub action: Action,
+    pub priority: usize,
+    /// Nemesis patch file. `None` for FNIS generated patches.
+    pub file: Option<&'a Path>,
+}
+
+/// Record the FNIS generated patches, which have no patch file.
+pub(crate) fn fnis_sources<'a>(fnis_patches: &BehaviorPatchesMap<'a>) -> Vec<PatchOrigin<'a>> {
+    fnis_patches
+        .0
+        .par_iter()
+        .flat_map_iter(|entry| {
+            let template = entry.key();
+            let one = entry.value().one.iter().map(|patch| PatchOrigin {
+                template: template.clone(),
+                path: patch.key().clone(),
+                action: patch.value().patch.action.clone(),
+                priority: patch.value().priority,
+                file: None,
+            });
+            let seq = entry.value().seq.0.iter().flat_map(|patches| {
+                patches
+                    .value()
+                    .iter()
+                    .map(|patch| PatchOrigin {
+                        template: template.clone(),
+                        path: patches.key().clone(),
+                        action: patch.patch.action.clone(),
+                        priority: patch.priority,
+                        file: None,
+                    })
+                    .collect::<Vec<_>>()
+            });
+            one.chain(seq).collect::<Vec<_>>()
+        })
+        .collect()
+}
+
+/// Patches that touched each JSON path of the merged behaviors.
+///
+/// Written to `<output_dir>/.d_merge/.debug/provenance.json` by [`crate::behavior_gen`]
+/// if `DebugOptions::output_provenance` is enabled, and returned by [`crate::collect_provenance`].
+#[derive(Debug, Clone, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[cfg_attr(feature = "ts_serde", serde(rename_all = "camelCase"))]
+pub struct ProvenanceReport {
+    /// template(e.g. `meshes/actors/character/behaviors/0_master.bin`)
+    /// -> JSON path(e.g. `#0001/hkbStateMachine/states`)
+    /// -> patches in the applied order.
+    pub templates: BTreeMap<String, BTreeMap<String, Vec<PatchTrace>>>,
+}
+
+/// A patch that touched a JSON path.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[cfg_attr(feature = "ts_serde", serde(rename_all = "camelCase"))]
+pub struct PatchTrace {
+    /// Mod id of the patch. (key of [`PatchMaps`])
+    pub mod_id: String,
+    /// Nemesis patch file, or FNIS list file for FNIS generated patches.
+    pub patch_file: Option<PathBuf>,
+    /// Operation of the patch.
+    pub action: Action,
+    pub priority: usize,
+    /// `false` if this one field patch was discarded for a higher priority one.
+    pub applied: bool,
+}
+
+/// Result of [`ProvenanceReport::explain`].
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[cfg_attr(feature = "ts_serde", serde(rename_all = "camelCase"))]
+pub struct PathExplanation {
+    /// e.g. `#0001/hkbStateMachine/states`
+    pub path: String,
+    /// Patches in the applied order. The last applied one is the last writer.
+    pub patches: Vec<PatchTrace>,
+}
+
+impl ProvenanceReport {
+    /// Build the report from the collected patches.
+    ///
+    /// - `borrowed_patches`: merged patches, to know which one field patch won.
+    pub(crate) fn new(
+        sources: &[PatchOrigin<'_>],
+        borrowed_patches: &BehaviorPatchesMap<'_>,
+        patches: &PatchMaps,
+        fnis_injections: &[OwnedFnisInjection],
+    ) -> Self {
+        let mod_ids = ModIds::new(patches);
+        let fnis_lists: HashMap<_, _> = fnis_injections
+            .par_iter()
+            .map(|injection| (injection.priority, injection.to_list_path()))
+            .collect();
+
+        let traces: Vec<_> = sources
+            .par_iter()
+            .map(|source| {
+                let applied = match &source.action {
+                    // A test is a guard, so it never loses to another patch.
+                    Action::Pure { op: Op::Test } => true,
+                    Action::Pure { .. } => {
+                        borrowed_patches.0.get(&source.template).is_some_and(|template| {
+                            template.one.priority_of(&source.path) == Some(source.priority)
+                        })
+                    }
+                    Action::Seq { .. } | Action::SeqPush => true,
+                };
+                let patch_file = source.file.map_or_else(
+                    || fnis_lists.get(&source.priority).cloned(),
+                    |file| Some(file.to_path_buf()),
+                );
+
+                let trace = PatchTrace {
+                    mod_id: mod_ids.get(source.priority),
+                    patch_file,
+                    action: source.action.clone(),
+                    priority: source.priority,
+                    applied,
+                };
+                (source.template.as_str(), source.path.join("/"), trace)
+            })
+            .collect();
+
+        let mut templates = BTreeMap::<String, BTreeMap<String, Vec<PatchTrace>>>::new();
+        for (template, path, trace) in traces {
+            templates.entry(template.to_string()).or_default().entry(path).or_default().push(trace);
+        }
+        templates.par_iter_mut().for_each(|(_, paths)| {
+            for traces in paths.values_mut() {
+                traces.sort_by(|a, b| {
+                    (a.applied, a.priority, &a.patch_file).cmp(&(
+                        b.applied,
+                        b.priority,
+                        &b.patch_file,
+                    ))
+                });
+            }
+        });
+
+        Self { templates }
+    }
+
+    /// Returns the patch that last wrote `path` of `template`.
+    ///
+    /// See [`Self::explain`] for the accepted `template`/`path`.
+    pub fn last_writer(&self, template: &str, path: &str) -> Option<&PatchTrace> {
+        let path = normalize_path(path);
+        self.find_template(template)?.get(&path)?.iter().rev().find(|trace| trace.applied)
+    }
+
+    /// Returns the chain of patches that touched `path` of `template`, and the paths under it.
+    ///
+    /// - `template`: e.g. `meshes/actors/character/behaviors/0_master.bin`.
+    ///   The extension(`.bin`/`.hkx`/`.xml`), `\` separators and case are ignored.
+    /// - `path`: Object(e.g. `#0001`) or field(e.g. `#0001/hkbStateMachine/states`).
+    ///
+    /// Returns an empty `Vec` if nothing touched it.
+    pub fn explain(&self, template: &str, path: &str) -> Vec<PathExplanation> {
+        let Some(paths) = self.find_template(template) else {
+            return vec![];
+        };
+        let path = normalize_path(path);
+        let nested = format!("{path}/");
+
+        paths
+            .range(path.clone()..)
+            .take_while(|(key, _)| *key == &path || key.starts_with(&nested))
+            .map(|(key, patches)| PathExplanation { path: key.clone(), patches: patches.clone() })
+            .collect()
+    }
+
+    /// Read `<output_dir>/.d_merge/.debug/provenance.json` written by the last [`crate::behavior_gen`].
+    ///
+    /// # Errors
+    /// If the file does not exist or cannot be parsed.
+    pub fn load(output_dir: impl AsRef<Path>) -> Result<Self> {
+        let path = provenance_path(output_dir.as_ref());
+        let bytes = std::fs::read(&path).context(FailedIoSnafu { path: &path })?;
+        sonic_rs::from_slice(&bytes).with_context(|_| JsonSnafu { path })
+    }
+
+    /// Write the report to `<output_dir>/.d_merge/.debug/provenance.json`.
+    ///
+    /// # Errors
+    /// If failed to write.
+    pub(crate) fn save(&self, output_dir: &Path) -> Result<()> {
+        let path = provenance_path(output_dir);
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent).context(FailedIoSnafu { path: parent })?;
+        }
+        let json = sonic_rs::to_string_pretty(self).with_context(|_| JsonSnafu { path: &path })?;
+        std::fs::write(&path, json).context(FailedIoSnafu { path })
+    }
+
+    fn find_template(&self, template: &str) -> Option<&BTreeMap<String, Vec<PatchTrace>>> {
+        let template = normalize_template(template);
+        self.templates
+            .iter()
+            .find(|(key, _)| normalize_template(key) == template)
+            .map(|(_, paths)| paths)
+    }
+}
+
+/// `<output_dir>/.d_merge/.debug/provenance.json`
+fn provenance_path(output_dir: &Path) -> PathBuf {
+    output_dir.join(".d_merge").join(".debug").join("provenance.json")
+}
+
+/// e.g. `Meshes\actors\character\behaviors\0_master.hkx` -> `meshes/actors/character/behaviors/0_master`
+fn normalize_template(template: &str) -> String {
+    let template = template.replace('\\', "/").to_ascii_lowercase();
+    match template.rfind('.') {
+        Some(dot) if !template[dot..].contains('/') => template[..dot].to_string(),
+        _ => template,
+    }
+}
+
+/// e.g. `/#0001/hkbStateMachine/states/` -> `#0001/hkbStateMachine/states`
+fn normalize_path(path: &str) -> String {
+    path.trim_matches('/').to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use json_patch::Op;
+
+    use super::*;
+
+    fn trace(mod_id: &str, priority: usize, applied: bool) -> PatchTrace {
+        PatchTrace {
+            mod_id: mod_id.to_string(),
+            patch_file: None,
+            action: Action::Pure { op: Op::Replace },
+            priority,
+            applied,
+        }
+    }
+
+    #[test]
+    fn explain_object_and_field() {
+        let template = "meshes/actors/character/behaviors/0_master.bin";
+        let paths = [
+            ("#0001/hkbStateMachine/name", vec![trace("a", 0, false), trace("b", 1, true)]),
+            ("#0001/hkbStateMachine/states", vec![trace("a", 0, true)]),
+            ("#00010/hkbStateMachine/name", vec![trace("c", 2, true)]),
+        ];
+        let report = ProvenanceReport {
+            templates: [(
+                template.to_string(),
+                paths.into_iter().map(|(path, traces)| (path.to_string(), traces)).collect(),
+            )]
+            .into_iter()
+            .collect(),
+        };
+
+        let object = report.explain(r"Meshes\actors\character\behaviors\0_master.hkx", "#0001");
+        let object_paths: Vec<_> = object.iter().map(|e| e.path.as_str()).collect();
+        assert_eq!(object_paths, ["#0001/hkbStateMachine/name", "#0001/hkbStateMachine/states"]);
+
+        let field = report.explain(template, "/#0001/hkbStateMachine/name");
+        assert_eq!(field.len(), 1);
+        assert_eq!(field[0].patches.len(), 2);
+
+        let last = report.last_writer(template, "#0001/hkbStateMachine/name");
+        assert_eq!(last.map(|trace| trace.mod_id.as_str()), Some("b"));
+        assert!(report.explain("unknown", "#0001").is_empty());
+    }
+}
@@ -0,0 +1,197 @@
+//! Check that [`crate::behavior_gen`] generates the same bytes on every run with the same inputs.
+use std::{
+    collections::BTreeMap,
+    path::PathBuf,
+    sync::{
+        Arc,
+        atomic::{AtomicUsize, Ordering},
+    },
+};
+
+use crate::{
+    PatchMaps,
+    behaviors::{gen_behaviors, profile::Profiler},
+    config::{Config, DebugOptions, MemorySink, PatchSource},
+    errors::Result,
+};
+
+/// Result of [`check_reproducibility`].
+#[derive(Debug, Clone, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[cfg_attr(feature = "ts_serde", serde(rename_all = "camelCase"))]
+pub struct ReproducibilityReport {
+    /// Number of files generated by the first run.
+    pub outputs_len: usize,
+    /// Files whose bytes differ between the two runs, or that only one of them generated.
+    /// (relative to `output_dir`, sorted)
+    pub mismatches: Vec<PathBuf>,
+}
+
+impl ReproducibilityReport {
+    /// Returns `true` if both runs generated the same files with the same bytes.
+    #[inline]
+    pub const fn is_reproducible(&self) -> bool {
+        self.mismatches.is_empty()
+    }
+}
+
+/// Run the generation twice in memory with the same inputs and compare the outputs byte for byte.
+///
+/// Nothing is written to `output_dir`: the generated files are kept in memory, and the reports
+/// (e.g. `conflicts.json`) of each run are written to a temporary directory that is removed afterwards.
+/// `config.output_sink`, `config.status_report` and `config.debug` are ignored.
+///
+/// # Example
+/// ```no_run
+/// # async fn f(patches: nemesis_merge::PatchMaps, config: nemesis_merge::Config) -> nemesis_merge::errors::Result<()> {
+/// let report = nemesis_merge::check_reproducibility(&patches, &config).await?;
+/// for path in &report.mismatches {
+///     println!("Differs between runs: {}", path.display());
+/// }
+/// # Ok(())
+/// # }
+/// ```
+///
+/// # Errors
+/// Returns the error of a failed run. The outputs of a partially failed run are not compared.
+pub async fn check_reproducibility(
+    patches: &PatchMaps,
+    config: &Config,
+) -> Result<ReproducibilityReport> {
+    // Both runs read the same source. (e.g. not to open the archives twice)
+    let source = config.source();
+    let first = gen_in_memory(patches, config, &source).await?;
+    let second = gen_in_memory(patches, config, &source).await?;
+
+    Ok(ReproducibilityReport {
+        outputs_len: first.len(),
+        mismatches: diff_outputs(&first, &second),
+    })
+}
+
+/// Returns `inner_path` -> bytes of every generated file.
+async fn gen_in_memory(
+    patches: &PatchMaps,
+    config: &Config,
+    source: &Arc<dyn PatchSource>,
+) -> Result<BTreeMap<PathBuf, Vec<u8>>> {
+    let sink = Arc::new(MemorySink::new());
+    let scratch_dir = ScratchDir::new();
+    let config = Config {
+        resource_dir: config.resource_dir.clone(),
+        output_dir: scratch_dir.0.clone(),
+        output_target: config.output_target,
+        status_report: None,
+        hack_options: config.hack_options,
+        strict_original: config.strict_original,
+        debug: DebugOptions {
+            output_patch_json: false,
+            output_merged_json: false,
+            output_merged_xml: false,
+            output_provenance: false,
+        },
+        skyrim_data_dir_glob: config.skyrim_data_dir_glob.clone(),
+        read_bsa: config.read_bsa,
+        generate_fnis_esp: config.generate_fnis_esp,
+        cancel_token: config.cancel_token.clone(),
+        output_sink: Some(sink.clone()),
+        patch_source: Some(Arc::clone(source)),
+    };
+
+    gen_behaviors(patches, &config, &Profiler::new()).await?;
+    Ok(sink.take())
+}
+
+/// `output_dir` of a run, to keep its reports out of the real `output_dir`. Removed on drop.
+#[derive(Debug)]
+struct ScratchDir(PathBuf);
+
+impl ScratchDir {
+    fn new() -> Self {
+        // Unique in the process, even if the checks run concurrently.
+        static COUNT: AtomicUsize = AtomicUsize::new(0);
+        let count = COUNT.fetch_add(1, Ordering::Relaxed);
+        let path = std::env::temp_dir()
+            .join(format!("d_merge_reproducibility_{}_{count}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&path);
+        Self(path)
+    }
+}
+
+impl Drop for ScratchDir {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
+
+/// Returns the paths that differ between `a` and `b`, sorted.
+fn diff_outputs(a: &BTreeMap<PathBuf, Vec<u8>>, b: &BTreeMap<PathBuf, Vec<u8>>) -> Vec<PathBuf> {
+    let mut mismatches: Vec<_> = a
+        .iter()
+        .filter(|(path, bytes)| b.get(*path) != Some(*bytes))
+        .map(|(path, _)| path.clone())
+        .collect();
+    mismatches.extend(b.keys().filter(|path| !a.contains_key(*path)).cloned());
+    mismatches.sort_unstable();
+    mismatches
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::*;
+    use crate::tests::fixture::{self, TEMPLATES};
+
+    #[test]
+    fn diff_outputs_lists_changed_and_missing() {
+        let files = |entries: &[(&str, &str)]| -> BTreeMap<PathBuf, Vec<u8>> {
+            entries
+                .iter()
+                .map(|(path, bytes)| (PathBuf::from(path), bytes.as_bytes().to_vec()))
+                .collect()
+        };
+        let a = files(&[("a.hkx", "a"), ("b.hkx", "b"), ("c.hkx", "c")]);
+        let b = files(&[("a.hkx", "a"), ("b.hkx", "B"), ("d.hkx", "d")]);
+
+        assert_eq!(
+            diff_outputs(&a, &b),
+            [PathBuf::from("b.hkx"), PathBuf::from("c.hkx"), PathBuf::from("d.hkx")]
+        );
+        assert!(diff_outputs(&a, &a).is_empty());
+    }
+
+    #[tokio::test]
+    async fn overlapping_patches_are_reproducible() {
+        let dir = Path::new("../../dummy/reproducibility");
+        let output_dir = dir.join("output");
+        let _ = std::fs::remove_dir_all(dir);
+        fixture::write_templates(&dir.join("resource"));
+
+        // Every mod replaces the same field and pushes to the same array.
+        let mod_codes = ["aaaa", "bbbb", "cccc"];
+        let (patches, mut source) =
+            fixture::mods(&[("aaaa", "ModA", 0), ("bbbb", "ModB", 1), ("cccc", "ModC", 2)]);
+        for mod_code in mod_codes {
+            for (template_name, _) in TEMPLATES {
+                source.insert(
+                    fixture::patch_path(mod_code, template_name, "#0003"),
+                    fixture::push_patch(mod_code, &format!("{mod_code}Event")),
+                );
+            }
+        }
+        let config = Config {
+            resource_dir: dir.join("resource"),
+            output_dir: output_dir.clone(),
+            generate_fnis_esp: true,
+            patch_source: Some(Arc::new(source)),
+            ..Default::default()
+        };
+
+        let report = check_reproducibility(&patches, &config).await.unwrap();
+        // templates, `FNIS.esp`, `animationdatasinglefile.txt` and `animationsetdatasinglefile.txt`
+        assert_eq!(report.outputs_len, TEMPLATES.len() + 3);
+        assert!(report.is_reproducible(), "Outputs differ between runs: {:#?}", report.mismatches);
+        // Neither the outputs nor the reports are written to `output_dir`.
+        assert!(!output_dir.exists());
+    }
+}
@@ -0,0 +1,270 @@
+//! Stale patch report. (`<output_dir>/.d_merge/stale_patches.json`)
+//!
+//! A Nemesis patch replaces(or removes) the values written in its `<!-- ORIGINAL -->` block.
+//! If the template has other values there, the patch was written against an older template or another mod's
+//! output, and may not do what its author intended. The blocks are compared with the templates before applying.
+use std::{
+    borrow::Cow,
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+};
+
+use nemesis_xml::patch::Original;
+use rayon::prelude::*;
+use simd_json::{BorrowedValue, StaticNode};
+use snafu::ResultExt as _;
+
+use crate::{
+    PatchMaps,
+    behaviors::{
+        conflicts::ModIds,
+        tasks::templates::{key::TemplateKey, types::BorrowedTemplateMap},
+    },
+    errors::{Error, FailedIoSnafu, JsonSnafu, Result},
+};
+
+/// An `<!-- ORIGINAL -->` block and the patch file it came from.
+#[derive(Debug, Clone)]
+pub(crate) struct PatchOriginal<'a> {
+    pub template: TemplateKey<'static>,
+    pub priority: usize,
+    pub file: &'a Path,
+    pub original: Original<'a>,
+}
+
+/// Nemesis patches whose `<!-- ORIGINAL -->` block does not match the template.
+///
+/// Written to `<output_dir>/.d_merge/stale_patches.json` by [`crate::behavior_gen`].
+/// Sorted by template, path and mod id.
+///
+/// With [`crate::Config::strict_original`], each of them is an error as well.
+#[derive(Debug, Clone, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[cfg_attr(feature = "ts_serde", serde(rename_all = "camelCase"))]
+pub struct StalePatchReport {
+    pub patches: Vec<StalePatch>,
+}
+
+/// A patch whose `<!-- ORIGINAL -->` block does not match the template.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[cfg_attr(feature = "ts_serde", serde(rename_all = "camelCase"))]
+pub struct StalePatch {
+    /// Mod id of the patch. (key of [`PatchMaps`])
+    pub mod_id: String,
+    /// e.g. `<data>/Nemesis_Engine/mod/slide/_1stperson/0_master/#0106.txt`
+    pub file: PathBuf,
+    /// e.g. `meshes/actors/character/behaviors/0_master.bin`
+    pub template: String,
+    /// e.g. `#0009/hkbProjectStringData/characterFilenames/[2:4]`
+    pub path: String,
+    /// JSON of the `ORIGINAL` block.
+    pub expected: String,
+    /// JSON of the template. `None` if the path is not in the template.
+    pub actual: Option<String>,
+}
+
+impl StalePatchReport {
+    /// Compare the `ORIGINAL` blocks with the templates(before applying any patch).
+    ///
+    /// The templates not in `templates`(e.g. skipped as unchanged) are not checked.
+    pub(crate) fn new(
+        originals: &[PatchOriginal<'_>],
+        templates: &BorrowedTemplateMap<'_>,
+        patches: &PatchMaps,
+    ) -> Self {
+        let mod_ids = ModIds::new(patches);
+
+        let mut stale: Vec<_> = originals
+            .par_iter()
+            .filter_map(|PatchOriginal { template, priority, file, original }| {
+                let template_value = templates.get(template)?;
+                let actual = find_actual(&template_value, original);
+                if actual.as_ref().is_some_and(|actual| matches_template(&original.value, actual)) {
+                    return None;
+                }
+
+                let mut path = original.path.join("/");
+                if let Some(range) = &original.range {
+                    path.push_str(&format!("/[{}:{}]", range.start, range.end));
+                }
+                Some(StalePatch {
+                    mod_id: mod_ids.get(*priority),
+                    file: file.to_path_buf(),
+                    template: template.as_str().to_string(),
+                    path,
+                    expected: to_json(&original.value),
+                    actual: actual.map(|actual| to_json(&actual)),
+                })
+            })
+            .collect();
+        sort(&mut stale);
+
+        Self { patches: stale }
+    }
+
+    /// Take over the records of the templates skipped as unchanged(key of `unchanged`) from the last run,
+    /// since they were not checked in this run.
+    pub(crate) fn keep_unchanged(&mut self, prev: Self, unchanged: &BTreeMap<String, u64>) {
+        self.patches.extend(
+            prev.patches.into_iter().filter(|stale| unchanged.contains_key(&stale.template)),
+        );
+        sort(&mut self.patches);
+    }
+
+    /// Returns `true` if no patch is stale.
+    #[inline]
+    pub const fn is_empty(&self) -> bool {
+        self.patches.is_empty()
+    }
+
+    /// Errors for [`crate::Config::strict_original`].
+    pub(crate) fn to_errors(&self) -> Vec<Error> {
+        self.patches
+            .iter()
+            .map(|stale| Error::StalePatch {
+                path: stale.file.clone(),
+                template: stale.template.clone(),
+                json_path: stale.path.clone(),
+                expected: stale.expected.clone(),
+                actual: stale.actual.clone().unwrap_or_else(|| "(not found)".to_string()),
+            })
+            .collect()
+    }
+
+    /// Read `<output_dir>/.d_merge/stale_patches.json` written by the last [`crate::behavior_gen`].
+    ///
+    /// # Errors
+    /// If the file does not exist or cannot be parsed.
+    pub fn load(output_dir: impl AsRef<Path>) -> Result<Self> {
+        let path = stale_patches_path(output_dir.as_ref());
+        let bytes = std::fs::read(&path).context(FailedIoSnafu { path: &path })?;
+        sonic_rs::from_slice(&bytes).with_context(|_| JsonSnafu { path })
+    }
+
+    /// Write the report to `<output_dir>/.d_merge/stale_patches.json`.
+    ///
+    /// # Errors
+    /// If failed to write.
+    pub(crate) fn save(&self, output_dir: &Path) -> Result<()> {
+        let path = stale_patches_path(output_dir);
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent).context(FailedIoSnafu { path: parent })?;
+        }
+        let json = sonic_rs::to_string_pretty(self).with_context(|_| JsonSnafu { path: &path })?;
+        std::fs::write(&path, json).context(FailedIoSnafu { path })
+    }
+}
+
+fn sort(patches: &mut [StalePatch]) {
+    patches.par_sort_unstable_by(|a, b| {
+        (&a.template, &a.path, &a.mod_id, &a.file).cmp(&(&b.template, &b.path, &b.mod_id, &b.file))
+    });
+}
+
+/// `<output_dir>/.d_merge/stale_patches.json`
+fn stale_patches_path(output_dir: &Path) -> PathBuf {
+    output_dir.join(".d_merge").join("stale_patches.json")
+}
+
+/// Get the template value at the path(and the range) of the original.
+fn find_actual<'v, 'a>(
+    template: &'v BorrowedValue<'a>,
+    original: &Original<'_>,
+) -> Option<Cow<'v, BorrowedValue<'a>>> {
+    let target = original.path.iter().try_fold(template, |target, token| match target {
+        BorrowedValue::Object(map) => map.get(token.as_ref()),
+        BorrowedValue::Array(list) => {
+            let index = token.strip_prefix('[')?.strip_suffix(']')?.parse::<usize>().ok()?;
+            list.get(index)
+        }
+        BorrowedValue::Static(_) | BorrowedValue::String(_) => None,
+    })?;
+
+    match (&original.range, target) {
+        (None, _) => Some(Cow::Borrowed(target)),
+        (Some(range), BorrowedValue::Array(list)) => {
+            let elements = list.get(range.clone())?.to_vec();
+            Some(Cow::Owned(BorrowedValue::Array(Box::new(elements))))
+        }
+        (Some(_), _) => None,
+    }
+}
+
+/// Does the template value match the value of the `ORIGINAL` block?
+///
+/// - The fields not written in the block are not compared.
+/// - Numbers are compared with the precision of XML(`0.000000`).
+fn matches_template(expected: &BorrowedValue<'_>, actual: &BorrowedValue<'_>) -> bool {
+    match (expected, actual) {
+        (BorrowedValue::Array(expected), BorrowedValue::Array(actual)) => {
+            expected.len() == actual.len()
+                && expected.iter().zip(actual.iter()).all(|(e, a)| matches_template(e, a))
+        }
+        (BorrowedValue::Object(expected), BorrowedValue::Object(actual)) => expected
+            .iter()
+            .all(|(key, e)| actual.get(key.as_ref()).is_some_and(|a| matches_template(e, a))),
+        // Nemesis variables(e.g. `$eventID[Jump]$`) are not resolved yet.
+        (BorrowedValue::String(expected), _) if expected.contains('$') => true,
+        (BorrowedValue::String(expected), BorrowedValue::String(actual)) => expected == actual,
+        (BorrowedValue::Static(expected), BorrowedValue::Static(actual)) => {
+            match (to_f64(*expected), to_f64(*actual)) {
+                (Some(e), Some(a)) => (e - a).abs() <= 1e-5 * e.abs().max(1.0),
+                _ => expected == actual,
+            }
+        }
+        _ => false,
+    }
+}
+
+const fn to_f64(node: StaticNode) -> Option<f64> {
+    match node {
+        StaticNode::I64(n) => Some(n as f64),
+        StaticNode::U64(n) => Some(n as f64),
+        StaticNode::F64(n) => Some(n),
+        StaticNode::Bool(_) | StaticNode::Null => None,
+    }
+}
+
+fn to_json(value: &BorrowedValue<'_>) -> String {
+    sonic_rs::to_string(value).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use simd_json::json_typed;
+
+    use super::*;
+
+    #[test]
+    fn compare_with_template() {
+        let template = json_typed!(borrowed, {
+            "#0009": {
+                "hkbProjectStringData": {
+                    "characterFilenames": ["a", "b", "c"],
+                    "stringData": "#0010",
+                    "worldUpWS": { "x": 0.0, "y": 0.100_000_001_490_116_12 },
+                }
+            }
+        });
+        let original = |path: &[&'static str], range, value| Original {
+            path: path.iter().map(|s| Cow::Borrowed(*s)).collect(),
+            range,
+            value,
+        };
+        let matches = |original: &Original<'_>| {
+            find_actual(&template, original).is_some_and(|a| matches_template(&original.value, &a))
+        };
+
+        let root = ["#0009", "hkbProjectStringData"];
+        let filenames = [root[0], root[1], "characterFilenames"];
+        assert!(matches(&original(&filenames, Some(1..3), json_typed!(borrowed, ["b", "c"]))));
+        assert!(!matches(&original(&filenames, Some(1..3), json_typed!(borrowed, ["b", "x"]))));
+        assert!(find_actual(&template, &original(&filenames, Some(2..4), ().into())).is_none());
+
+        let y = [root[0], root[1], "worldUpWS", "y"];
+        assert!(matches(&original(&y, None, 0.1.into())));
+        let object = [root[0], root[1], "worldUpWS"];
+        assert!(matches(&original(&object, None, json_typed!(borrowed, { "x": 0 }))));
+        let string_data = [root[0], root[1], "stringData"];
+        assert!(!matches(&original(&string_data, None, "#0009".into())));
+    }
+}
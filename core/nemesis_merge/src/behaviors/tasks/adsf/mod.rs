@@ -27,12 +27,13 @@ use snafu::ResultExt as _;
 
 use self::{
     path_parser::{ParsedAdsfPatchPath, ParserType, parse_adsf_path},
-    sort::dedup_patches_by_priority_parallel,
+    sort::dedup_patches_by_priority,
     types::OwnedAdsfPatchMap,
 };
 use crate::{
     Config, PatchMaps,
-    behaviors::tasks::hkx::generate::write_patched_json,
+    behaviors::{plan::AnimDataPlan, tasks::hkx::generate::write_patched_json},
+    config::OutputWriter,
     errors::{
         AnimPatchErrKind, AnimPatchErrSubKind, Error, FailedDiffLinesPatchSnafu, FailedIoSnafu,
         FailedParseAdsfAnimDataHeaderPatchSnafu, FailedParseAdsfPatchSnafu,
@@ -98,7 +99,7 @@ impl<'a> Default for PatchKind<'a> {
     }
 }
 
-const ADSF_INNER_PATH: &str = "meshes/animationdatasinglefile.bin";
+pub(crate) const ADSF_INNER_PATH: &str = "meshes/animationdatasinglefile.bin";
 
 // "dmco", "slide"
 /// Patch to `animationdatasinglefile.txt`
@@ -111,6 +112,12 @@ pub(crate) fn apply_adsf_patches(
     config: &Config,
     fnis_adsf_patches: Vec<AdsfPatch<'_>>,
 ) -> Vec<Error> {
+    // Skyrim VR: the patches are written against the SE project names.
+    let vr_project_names = match config.vr_project_names() {
+        Ok(names) => names,
+        Err(err) => return vec![err],
+    };
+
     // 1/5 Parse adsf patch (1 loop with par_iter)
     let (mut borrowed_patches, mut errors): (Vec<_>, Vec<Error>) = owned_anim_data_patches
         .0
@@ -120,10 +127,13 @@ pub(crate) fn apply_adsf_patches(
             Err(e) => Either::Right(e),
         });
     borrowed_patches.par_extend(fnis_adsf_patches);
+    for patch in &mut borrowed_patches {
+        patch.target = vr_project_names.adsf(patch.target);
+    }
 
     // 2/5 Sort by priority ids.(to vec 2 loop) => borrowed_map
     sort_patches_by_priority(&mut borrowed_patches, entries);
-    let borrowed_patches = dedup_patches_by_priority_parallel(borrowed_patches);
+    let borrowed_patches = dedup_patches_by_priority(borrowed_patches);
 
     if config.debug.output_patch_json && !borrowed_patches.is_empty() {
         output_debug_patch_json(&borrowed_patches, config);
@@ -141,10 +151,12 @@ pub(crate) fn apply_adsf_patches(
         };
     }
 
+    bail!(config.check_cancelled());
+
     // 3/5 read template adsf.
     let alt_adsf_bytes = bail!(read_adsf_file(config));
     let mut alt_adsf: AltAdsf = bail!(rmp_serde::from_slice(&alt_adsf_bytes).with_context(|_| {
-        FailedParseAdsfTemplateSnafu { path: config.resource_dir.join(ADSF_INNER_PATH) }
+        FailedParseAdsfTemplateSnafu { path: config.template_path(ADSF_INNER_PATH) }
     }));
 
     let mut project_names_header_patches = DiffLines::DEFAULT;
@@ -204,13 +216,42 @@ pub(crate) fn apply_adsf_patches(
     }
 
     // 5/5 Write adsf.
+    bail!(config.check_cancelled());
     let mut output_path = config.output_dir.join(ADSF_INNER_PATH);
     output_path.set_extension("txt");
-    bail!(write_alt_adsf_file(output_path, alt_adsf, project_names_header_patches));
+    bail!(write_alt_adsf_file(
+        &config.output_writer(),
+        output_path,
+        alt_adsf,
+        project_names_header_patches
+    ));
 
     errors
 }
 
+/// Parse adsf patches and resolve their priorities without applying/writing anything.
+///
+/// Returns the number of patches per target project.
+pub(crate) fn plan_adsf_patches<'a>(
+    owned_anim_data_patches: &'a OwnedAdsfPatchMap,
+    entries: &PatchMaps,
+    fnis_adsf_patches: Vec<AdsfPatch<'a>>,
+) -> (Vec<AnimDataPlan>, Vec<Error>) {
+    let (mut borrowed_patches, errors): (Vec<_>, Vec<Error>) = owned_anim_data_patches
+        .0
+        .par_iter()
+        .partition_map(|path| match parse_anim_data_patch(path) {
+            Ok(v) => Either::Left(v),
+            Err(e) => Either::Right(e),
+        });
+    borrowed_patches.par_extend(fnis_adsf_patches);
+
+    sort_patches_by_priority(&mut borrowed_patches, entries);
+    let borrowed_patches = dedup_patches_by_priority(borrowed_patches);
+
+    (AnimDataPlan::count_by_target(borrowed_patches.iter().map(|patch| patch.target)), errors)
+}
+
 fn parse_anim_data_patch<'a>(
     (path, (adsf_patch, priority)): (&'a PathBuf, &'a (String, usize)),
 ) -> Result<AdsfPatch<'a>, Error> {
@@ -273,7 +314,7 @@ fn sort_patches_by_priority(patches: &mut [AdsfPatch], id_orders: &PatchMaps) {
 
 /// Read the ADSF file from the resource directory
 fn read_adsf_file(config: &Config) -> Result<Vec<u8>, Error> {
-    let adsf_read_path = config.resource_dir.join(ADSF_INNER_PATH);
+    let adsf_read_path = config.template_path(ADSF_INNER_PATH);
     let adsf_string =
         std::fs::read(&adsf_read_path).with_context(|_| FailedIoSnafu { path: adsf_read_path })?;
     Ok(adsf_string)
@@ -281,6 +322,7 @@ fn read_adsf_file(config: &Config) -> Result<Vec<u8>, Error> {
 
 /// Write a single adsf file
 fn write_alt_adsf_file(
+    writer: &OutputWriter,
     path: impl AsRef<Path>,
     alt_adsf: AltAdsf,
     patches: DiffLines,
@@ -294,10 +336,8 @@ fn write_alt_adsf_file(
             path,
         })?;
 
-    if let Some(parent_dir) = path.parent() {
-        let _ = std::fs::create_dir_all(parent_dir);
-    }
-    std::fs::write(path, serialized)
+    writer
+        .write(path, serialized.as_bytes())
         .with_context(|_| FailedIoSnafu { path: path.to_path_buf() })?;
     #[cfg(feature = "tracing")]
     tracing::info!("Generated: {}", path.display());
@@ -1,5 +1,4 @@
-use rapidhash::fast::RapidHashMap as HashMap;
-use rayon::prelude::*;
+use indexmap::{IndexMap, map::Entry};
 
 use super::{AdsfPatch, PatchKind};
 
@@ -42,7 +41,7 @@ enum PatchKey<'a> {
     },
 }
 
-/// Deduplicates a list of `AdsfPatch` instances in parallel,
+/// Deduplicates a list of `AdsfPatch` instances,
 /// merging and prioritizing patches as needed.
 ///
 /// ## Behavior
@@ -60,93 +59,60 @@ enum PatchKey<'a> {
 /// - `AddAnim` and `AddMotion` patches are treated uniquely based on their index,
 ///   making them distinct entries.
 ///
+/// This is done sequentially on purpose: the merge result and the order of the returned patches
+/// (= the order of the added clips in the output) must not depend on thread scheduling.
+///
 /// ## Returns
 ///
 /// A `Vec<AdsfPatch<'a>>` containing only the relevant, deduplicated patches,
-/// with priority-based merging applied.
-pub(super) fn dedup_patches_by_priority_parallel<'a>(
-    patches: Vec<AdsfPatch<'a>>,
-) -> Vec<AdsfPatch<'a>> {
-    patches
-        .into_par_iter()
-        .enumerate()
-        .fold(HashMap::default, |mut map: HashMap<PatchKey<'_>, AdsfPatch<'a>>, (idx, patch)| {
-            let key = match &patch.patch {
-                PatchKind::ProjectNamesHeader(_) => {
-                    PatchKey::ProjectNamesHeader { target: patch.target, id: patch.id }
-                }
-                PatchKind::AnimDataHeader(_) => {
-                    PatchKey::AnimDataHeader { target: patch.target, id: patch.id }
-                }
-                PatchKind::EditAnim(edit) => PatchKey::EditAnim {
-                    target: patch.target,
-                    id: patch.id,
-                    clip_name: edit.name_clip,
-                },
-                PatchKind::EditMotion(edit) => PatchKey::EditMotion {
-                    target: patch.target,
-                    id: patch.id,
-                    clip_id: edit.clip_id,
-                },
-                PatchKind::AddAnim(_) => {
-                    PatchKey::AddAnim { target: patch.target, id: patch.id, index: idx }
-                }
-                PatchKind::AddMotion(_) => {
-                    PatchKey::AddMotion { target: patch.target, id: patch.id, index: idx }
-                }
-            };
-            match map.entry(key) {
-                std::collections::hash_map::Entry::Occupied(mut entry) => {
-                    match (&mut entry.get_mut().patch, patch.patch) {
-                        (PatchKind::EditAnim(existing_edit), PatchKind::EditAnim(new_edit))
-                            if new_edit.priority > existing_edit.priority =>
-                        {
-                            existing_edit.patch.merge(new_edit.patch);
-                            existing_edit.name_clip = new_edit.name_clip;
-                            existing_edit.priority = new_edit.priority;
-                        }
-                        (PatchKind::EditMotion(existing_edit), PatchKind::EditMotion(new_edit))
-                            if new_edit.priority > existing_edit.priority =>
-                        {
-                            existing_edit.patch.merge(new_edit.patch);
-                            existing_edit.clip_id = new_edit.clip_id;
-                            existing_edit.priority = new_edit.priority;
-                        }
-                        _ => {} // Do nothing if the new patch has lower or equal priority.
-                    }
-                }
-                std::collections::hash_map::Entry::Vacant(entry) => {
-                    entry.insert(patch);
-                }
+/// with priority-based merging applied, in the order of their first occurrence.
+pub(super) fn dedup_patches_by_priority<'a>(patches: Vec<AdsfPatch<'a>>) -> Vec<AdsfPatch<'a>> {
+    let mut map: IndexMap<PatchKey<'_>, AdsfPatch<'a>> = IndexMap::with_capacity(patches.len());
+
+    for (idx, patch) in patches.into_iter().enumerate() {
+        let key = match &patch.patch {
+            PatchKind::ProjectNamesHeader(_) => {
+                PatchKey::ProjectNamesHeader { target: patch.target, id: patch.id }
             }
-            map
-        })
-        .reduce(HashMap::default, |mut map1, map2| {
-            for (key, patch2) in map2 {
-                match map1.entry(key) {
-                    std::collections::hash_map::Entry::Occupied(mut entry) => {
-                        match (&mut entry.get_mut().patch, &patch2.patch) {
-                            (PatchKind::EditAnim(existing_edit), PatchKind::EditAnim(new_edit))
-                                if new_edit.priority > existing_edit.priority =>
-                            {
-                                entry.insert(patch2);
-                            }
-                            (
-                                PatchKind::EditMotion(existing_edit),
-                                PatchKind::EditMotion(new_edit),
-                            ) if new_edit.priority > existing_edit.priority => {
-                                entry.insert(patch2);
-                            }
-                            _ => {} // Do nothing for lower or equal priority.
-                        }
-                    }
-                    std::collections::hash_map::Entry::Vacant(entry) => {
-                        entry.insert(patch2);
-                    }
+            PatchKind::AnimDataHeader(_) => {
+                PatchKey::AnimDataHeader { target: patch.target, id: patch.id }
+            }
+            PatchKind::EditAnim(edit) => {
+                PatchKey::EditAnim { target: patch.target, id: patch.id, clip_name: edit.name_clip }
+            }
+            PatchKind::EditMotion(edit) => {
+                PatchKey::EditMotion { target: patch.target, id: patch.id, clip_id: edit.clip_id }
+            }
+            PatchKind::AddAnim(_) => {
+                PatchKey::AddAnim { target: patch.target, id: patch.id, index: idx }
+            }
+            PatchKind::AddMotion(_) => {
+                PatchKey::AddMotion { target: patch.target, id: patch.id, index: idx }
+            }
+        };
+        match map.entry(key) {
+            Entry::Occupied(mut entry) => match (&mut entry.get_mut().patch, patch.patch) {
+                (PatchKind::EditAnim(existing_edit), PatchKind::EditAnim(new_edit))
+                    if new_edit.priority > existing_edit.priority =>
+                {
+                    existing_edit.patch.merge(new_edit.patch);
+                    existing_edit.name_clip = new_edit.name_clip;
+                    existing_edit.priority = new_edit.priority;
+                }
+                (PatchKind::EditMotion(existing_edit), PatchKind::EditMotion(new_edit))
+                    if new_edit.priority > existing_edit.priority =>
+                {
+                    existing_edit.patch.merge(new_edit.patch);
+                    existing_edit.clip_id = new_edit.clip_id;
+                    existing_edit.priority = new_edit.priority;
                 }
+                _ => {} // Do nothing if the new patch has lower or equal priority.
+            },
+            Entry::Vacant(entry) => {
+                entry.insert(patch);
             }
-            map1
-        })
-        .into_values()
-        .collect()
+        }
+    }
+
+    map.into_values().collect()
 }
@@ -20,12 +20,16 @@ use winnow::Parser;
 
 use self::{
     path_parser::{ParsedAsdsfPatchPath, ParserType, parse_asdsf_path},
-    sort::dedup_patches_by_priority_parallel,
+    sort::dedup_patches_by_priority,
     types::OwnedAsdsfPatchMap,
 };
 use crate::{
     Config,
-    behaviors::{priority_ids::types::PriorityMap, tasks::hkx::generate::write_patched_json},
+    behaviors::{
+        plan::AnimDataPlan, priority_ids::types::PriorityMap,
+        tasks::hkx::generate::write_patched_json,
+    },
+    config::OutputWriter,
     errors::{
         AnimPatchErrKind, AnimPatchErrSubKind, Error, FailedDiffLinesPatchSnafu, FailedIoSnafu,
         FailedParseAdsfTemplateSnafu, FailedParseAsdsfPatchSnafu, FailedParseEditAsdsfPatchSnafu,
@@ -78,7 +82,7 @@ impl<'a> Default for PatchKind<'a> {
     }
 }
 
-const ASDSF_INNER_PATH: &str = "meshes/animationsetdatasinglefile.bin";
+pub(crate) const ASDSF_INNER_PATH: &str = "meshes/animationsetdatasinglefile.bin";
 
 /// Patch to `animationsetdatasinglefile.txt`
 pub(crate) fn apply_asdsf_patches(
@@ -86,6 +90,12 @@ pub(crate) fn apply_asdsf_patches(
     id_order: &PriorityMap,
     config: &Config,
 ) -> Vec<Error> {
+    // Skyrim VR: the patches are written against the SE project names.
+    let vr_project_names = match config.vr_project_names() {
+        Ok(names) => names,
+        Err(err) => return vec![err],
+    };
+
     // 1/5 Parse adsf patch
     let (mut borrowed_patches, mut errors): (Vec<_>, Vec<Error>) = owned_anim_data_patches
         .0
@@ -94,10 +104,13 @@ pub(crate) fn apply_asdsf_patches(
             Ok(v) => Either::Left(v),
             Err(e) => Either::Right(e),
         });
+    for patch in &mut borrowed_patches {
+        patch.target = vr_project_names.asdsf(patch.target);
+    }
 
     // 2/5 Sort by priority ids.(to vec 2 loop) => borrowed_map
     sort_patches_by_priority(&mut borrowed_patches, id_order);
-    let borrowed_patches = dedup_patches_by_priority_parallel(borrowed_patches);
+    let borrowed_patches = dedup_patches_by_priority(borrowed_patches);
 
     if config.debug.output_patch_json && !borrowed_patches.is_empty() {
         output_debug_patch_json(&borrowed_patches, config);
@@ -115,11 +128,13 @@ pub(crate) fn apply_asdsf_patches(
         };
     }
 
+    bail!(config.check_cancelled());
+
     // 3/5 read template asdsf.
     let alt_asdsf_bytes = bail!(read_asdsf_file(config));
     let mut alt_adsf: AltAsdsf =
         bail!(rmp_serde::from_slice(&alt_asdsf_bytes).with_context(|_| {
-            FailedParseAdsfTemplateSnafu { path: config.resource_dir.join(ASDSF_INNER_PATH) }
+            FailedParseAdsfTemplateSnafu { path: config.template_path(ASDSF_INNER_PATH) }
         }));
 
     let mut txt_project_header_patches = DiffLines::DEFAULT;
@@ -175,9 +190,11 @@ pub(crate) fn apply_asdsf_patches(
     }
 
     // 5/5 Write adsf.
+    bail!(config.check_cancelled());
     let mut output_path = config.output_dir.join(ASDSF_INNER_PATH);
     output_path.set_extension("txt");
     bail!(write_alt_asdsf_file(
+        &config.output_writer(),
         output_path,
         alt_adsf,
         txt_project_header_patches,
@@ -233,6 +250,27 @@ fn parse_anim_data_patch<'a>(
     Ok(AsdsfPatch { target, id, patch })
 }
 
+/// Parse asdsf patches and resolve their priorities without applying/writing anything.
+///
+/// Returns the number of patches per target project.
+pub(crate) fn plan_asdsf_patches(
+    owned_anim_data_patches: &OwnedAsdsfPatchMap,
+    id_order: &PriorityMap,
+) -> (Vec<AnimDataPlan>, Vec<Error>) {
+    let (mut borrowed_patches, errors): (Vec<_>, Vec<Error>) = owned_anim_data_patches
+        .0
+        .par_iter()
+        .partition_map(|path| match parse_anim_data_patch(path) {
+            Ok(v) => Either::Left(v),
+            Err(e) => Either::Right(e),
+        });
+
+    sort_patches_by_priority(&mut borrowed_patches, id_order);
+    let borrowed_patches = dedup_patches_by_priority(borrowed_patches);
+
+    (AnimDataPlan::count_by_target(borrowed_patches.iter().map(|patch| patch.target)), errors)
+}
+
 /// Sorts AdsfPatch list based on the given ID priority list.
 fn sort_patches_by_priority(patches: &mut [AsdsfPatch], id_order: &PriorityMap) {
     patches.par_sort_by_key(|patch| id_order.get(patch.id).copied().unwrap_or(usize::MAX));
@@ -240,7 +278,7 @@ fn sort_patches_by_priority(patches: &mut [AsdsfPatch], id_order: &PriorityMap)
 
 /// Read `animationsetdatasinglefile.txt` from the resource directory
 fn read_asdsf_file(config: &Config) -> Result<Vec<u8>, Error> {
-    let adsf_read_path = config.resource_dir.join(ASDSF_INNER_PATH);
+    let adsf_read_path = config.template_path(ASDSF_INNER_PATH);
     let adsf_string =
         std::fs::read(&adsf_read_path).with_context(|_| FailedIoSnafu { path: adsf_read_path })?;
     Ok(adsf_string)
@@ -248,6 +286,7 @@ fn read_asdsf_file(config: &Config) -> Result<Vec<u8>, Error> {
 
 /// Write a `animationsetdatasinglefile.txt` file
 fn write_alt_asdsf_file(
+    writer: &OutputWriter,
     path: impl AsRef<Path>,
     alt_asdsf: AltAsdsf,
     patches: DiffLines,
@@ -262,10 +301,8 @@ fn write_alt_asdsf_file(
             path,
         })?;
 
-    if let Some(parent_dir) = path.parent() {
-        let _ = std::fs::create_dir_all(parent_dir);
-    }
-    std::fs::write(path, serialized)
+    writer
+        .write(path, serialized.as_bytes())
         .with_context(|_| FailedIoSnafu { path: path.to_path_buf() })?;
 
     #[cfg(feature = "tracing")]
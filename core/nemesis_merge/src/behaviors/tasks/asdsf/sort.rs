@@ -1,6 +1,4 @@
-use std::collections::{HashMap, hash_map::Entry};
-
-use rayon::prelude::*;
+use indexmap::{IndexMap, map::Entry};
 
 use super::{AsdsfPatch, PatchKind};
 
@@ -16,62 +14,44 @@ enum PatchKey<'a> {
     AddAnim(&'a str, &'a str, &'a str),
 }
 
-pub(super) fn dedup_patches_by_priority_parallel<'a>(
-    patches: Vec<AsdsfPatch<'a>>,
-) -> Vec<AsdsfPatch<'a>> {
-    patches
-        .into_par_iter()
-        .fold(HashMap::new, |mut map: HashMap<PatchKey<'_>, AsdsfPatch<'a>>, patch| {
-            let key = match &patch.patch {
-                PatchKind::EditAnimSet(edit) => {
-                    PatchKey::EditAnim(patch.target, patch.id, edit.file_name)
-                }
-                PatchKind::AddAnimSet { file_name, .. } => {
-                    PatchKey::AddAnim(patch.target, patch.id, file_name)
-                }
-                PatchKind::TxtProjectHeader(_) => PatchKey::TxtProjectHeader,
-                PatchKind::SubTxtHeader(_) => PatchKey::SubTxtHeader,
-            };
-
-            match map.entry(key) {
-                Entry::Occupied(mut entry) => match (&mut entry.get_mut().patch, patch.patch) {
-                    (PatchKind::EditAnimSet(existing_edit), PatchKind::EditAnimSet(new_edit))
-                        if new_edit.priority > existing_edit.priority =>
-                    {
-                        existing_edit.patch.merge(new_edit.patch);
-                        existing_edit.file_name = new_edit.file_name;
-                        existing_edit.priority = new_edit.priority;
-                    }
-                    _ => {}
-                },
-                Entry::Vacant(entry) => {
-                    entry.insert(patch);
-                }
+/// Deduplicates a list of `AsdsfPatch` instances.
+///
+/// - Duplicated `EditAnimSet` patches are merged into the first one in priority order.
+/// - The other duplicates are discarded.
+///
+/// This is done sequentially on purpose: the merge result and the order of the returned patches
+/// (= the order of the added entries in the output) must not depend on thread scheduling.
+pub(super) fn dedup_patches_by_priority<'a>(patches: Vec<AsdsfPatch<'a>>) -> Vec<AsdsfPatch<'a>> {
+    let mut map: IndexMap<PatchKey<'_>, AsdsfPatch<'a>> = IndexMap::with_capacity(patches.len());
+
+    for patch in patches {
+        let key = match &patch.patch {
+            PatchKind::EditAnimSet(edit) => {
+                PatchKey::EditAnim(patch.target, patch.id, edit.file_name)
             }
-
-            map
-        })
-        .reduce(HashMap::new, |mut map1, map2| {
-            for (key, patch2) in map2 {
-                match map1.entry(key) {
-                    std::collections::hash_map::Entry::Occupied(mut entry) => {
-                        match (&entry.get().patch, &patch2.patch) {
-                            (
-                                PatchKind::EditAnimSet(edit_anim),
-                                PatchKind::EditAnimSet(edit_anim2),
-                            ) if edit_anim2.priority > edit_anim.priority => {
-                                entry.insert(patch2);
-                            }
-                            _ => {}
-                        }
-                    }
-                    std::collections::hash_map::Entry::Vacant(entry) => {
-                        entry.insert(patch2);
-                    }
+            PatchKind::AddAnimSet { file_name, .. } => {
+                PatchKey::AddAnim(patch.target, patch.id, file_name)
+            }
+            PatchKind::TxtProjectHeader(_) => PatchKey::TxtProjectHeader,
+            PatchKind::SubTxtHeader(_) => PatchKey::SubTxtHeader,
+        };
+
+        match map.entry(key) {
+            Entry::Occupied(mut entry) => match (&mut entry.get_mut().patch, patch.patch) {
+                (PatchKind::EditAnimSet(existing_edit), PatchKind::EditAnimSet(new_edit))
+                    if new_edit.priority > existing_edit.priority =>
+                {
+                    existing_edit.patch.merge(new_edit.patch);
+                    existing_edit.file_name = new_edit.file_name;
+                    existing_edit.priority = new_edit.priority;
                 }
+                _ => {}
+            },
+            Entry::Vacant(entry) => {
+                entry.insert(patch);
             }
-            map1
-        })
-        .into_values()
-        .collect()
+        }
+    }
+
+    map.into_values().collect()
 }
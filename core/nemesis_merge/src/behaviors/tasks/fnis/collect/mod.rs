@@ -1,6 +1,6 @@
 pub(crate) mod owned;
 
-use std::sync::LazyLock;
+use std::sync::{Arc, LazyLock};
 
 use crate::{
     PriorityMap,
@@ -10,6 +10,7 @@ use crate::{
             AUXBONES, BehaviorEntry, CREATURES, HUMANOID, PLANTS_ACTIVATORS, SKELETONS,
         },
     },
+    config::PatchSource,
     errors::Error,
 };
 
@@ -24,6 +25,7 @@ static ALL_ENTRIES: LazyLock<Vec<&'static BehaviorEntry>> = LazyLock::new(|| {
 });
 
 pub(crate) async fn collect_all_fnis_injections(
+    source: Arc<dyn PatchSource>,
     skyrim_data_dir: &str,
     fnis_entries: &PriorityMap,
 ) -> (Vec<OwnedFnisInjection>, Vec<Error>) {
@@ -36,7 +38,7 @@ pub(crate) async fn collect_all_fnis_injections(
 
     // In manual mode, you need to search everything in the `MO2/mods/*` directory as if it were the meshes directory.
     // That is why `data_dirs` is defined as a Vec.
-    let data_dirs = jwalk_glob::glob_dirs(skyrim_data_dir);
+    let data_dirs = source.glob_dirs(skyrim_data_dir);
 
     #[cfg(feature = "tracing")]
     tracing::debug!(count = data_dirs.len(), "Expanded skyrim_data_dir");
@@ -50,8 +52,8 @@ pub(crate) async fn collect_all_fnis_injections(
             #[cfg(feature = "tracing")]
             tracing::trace!(?animations_dir, "Scanning animations directory");
 
-            let read_dir = match std::fs::read_dir(&animations_dir) {
-                Ok(rd) => rd,
+            let read_dir = match source.read_dir(&animations_dir) {
+                Ok(entries) => entries,
                 Err(_e) => {
                     #[cfg(feature = "tracing")]
                     tracing::trace!(
@@ -63,15 +65,12 @@ pub(crate) async fn collect_all_fnis_injections(
                 }
             };
 
-            for dir_entry in read_dir.flatten() {
-                let Ok(file_type) = dir_entry.file_type() else {
-                    continue;
-                };
-                if !file_type.is_dir() {
+            for dir_entry in read_dir {
+                if !dir_entry.is_dir {
                     continue;
                 }
 
-                let ns_path = dir_entry.path();
+                let ns_path = dir_entry.path;
                 let Some(namespace) = ns_path.file_name().and_then(|n| n.to_str()) else {
                     #[cfg(feature = "tracing")]
                     tracing::trace!(?ns_path, "Skipping namespace dir with non-UTF8 name");
@@ -93,9 +92,10 @@ pub(crate) async fn collect_all_fnis_injections(
 
                 let namespace = namespace.to_string();
                 let entry: &'static BehaviorEntry = entry;
+                let source = Arc::clone(&source);
 
-                handles.spawn(async move {
-                    collect_fnis_injection(&ns_path, entry, &namespace, priority).await
+                handles.spawn_blocking(move || {
+                    collect_fnis_injection(&*source, ns_path, entry, &namespace, priority)
                 });
             }
         }
@@ -123,6 +123,9 @@ pub(crate) async fn collect_all_fnis_injections(
         }
     }
 
+    // The tasks finish in arbitrary order. Sort them so that the generated patches are the same on every run.
+    oks.sort_by_cached_key(|injection| (injection.priority, injection.to_list_path()));
+
     #[cfg(feature = "tracing")]
     tracing::debug!(
         succeeded = oks.len(),
@@ -149,7 +152,12 @@ mod tests {
             .enumerate()
             .map(|(idx, namespace)| (namespace.to_string(), idx))
             .collect();
-        let res = collect_all_fnis_injections("../../dummy/fnis_test_mods/*", &fnis_entries).await;
+        let res = collect_all_fnis_injections(
+            Arc::new(crate::FsPatchSource),
+            "../../dummy/fnis_test_mods/*",
+            &fnis_entries,
+        )
+        .await;
 
         std::fs::write(output_path, format!("{res:#?}")).unwrap();
     }
@@ -31,9 +31,9 @@ use std::{
     sync::atomic::{AtomicUsize, Ordering},
 };
 
-use tokio::fs;
-
-use crate::behaviors::tasks::fnis::patch_gen::generated_behaviors::BehaviorEntry;
+use crate::{
+    behaviors::tasks::fnis::patch_gen::generated_behaviors::BehaviorEntry, config::PatchSource,
+};
 
 /// The necessary information for creating a single FNIS mod as a d_merge patch for hkx.
 /// # Note
@@ -188,13 +188,25 @@ impl OwnedFnisInjection {
     /// assert_eq!(inj.next_class_name_attribute(), "#FNIS_Flyer$1");
     /// assert_eq!(inj.next_class_name_attribute(), "#FNIS_Flyer$2");
     /// ```
+    ///
+    /// # Note
+    /// The ids are assigned in call order, so do not call this within `rayon::par_iter`.
+    /// Allocate them beforehand with [`Self::next_class_name_attributes`] instead,
+    /// otherwise the output differs between runs.
     pub(crate) fn next_class_name_attribute(&self) -> String {
         let idx = &self.current_class_index.fetch_add(1, Ordering::Acquire) + 1;
         format!("#{}${idx}", self.namespace)
     }
 
+    /// Returns `len` new `name` attributes in order.(See [`Self::next_class_name_attribute`])
+    pub(crate) fn next_class_name_attributes(&self, len: usize) -> Vec<String> {
+        (0..len).map(|_| self.next_class_name_attribute()).collect()
+    }
+
     /// Returns a new ID for adding a patch to the new `animationdatasinglefile.txt`.
     /// - `#FNIS_{namespace}${idx}`
+    ///
+    /// Like [`Self::next_class_name_attribute`], do not call this within `rayon::par_iter`.
     pub(crate) fn next_adsf_id(&self) -> String {
         let idx = &self.current_adsf_index.fetch_add(1, Ordering::Acquire) + 1;
         format!("#FNIS_{}${idx}", self.namespace)
@@ -207,6 +219,7 @@ impl OwnedFnisInjection {
 /// reads the corresponding List file, and resolves the path to the
 /// Behavior `.hkx` file.
 ///
+/// * `source` - Where the List file is read from.
 /// * `animations_mod_dir` - The root directory of the FNIS mod to process.
 ///   Must point to:
 ///
@@ -233,7 +246,8 @@ impl OwnedFnisInjection {
 /// - The animation directory is empty (`MissingNameSpace`)
 /// - The List file is missing (`ListMissing`)
 /// - The Behavior file is missing (`BehaviorMissing`)
-pub(crate) async fn collect_fnis_injection<P>(
+pub(crate) fn collect_fnis_injection<P>(
+    source: &dyn PatchSource,
     animations_mod_dir: P,
     behavior_entry: &'static BehaviorEntry,
     namespace: &str,
@@ -244,8 +258,8 @@ where
 {
     let animations_mod_dir = animations_mod_dir.into();
 
-    let list_content = load_fnis_list_file(&animations_mod_dir, behavior_entry, namespace).await?;
-    let behavior_path = find_behavior_file(&animations_mod_dir, behavior_entry, namespace)?;
+    let list_content = load_fnis_list_file(source, &animations_mod_dir, behavior_entry, namespace)?;
+    let behavior_path = find_behavior_file(source, &animations_mod_dir, behavior_entry, namespace)?;
 
     Ok(OwnedFnisInjection {
         animations_mod_dir,
@@ -269,7 +283,8 @@ where
 ///
 /// # Errors
 /// Returns an error if no file is found.
-async fn load_fnis_list_file(
+fn load_fnis_list_file(
+    source: &dyn PatchSource,
     animations_mod_dir: &Path,
     behavior_entry: &'static BehaviorEntry,
     namespace: &str,
@@ -283,9 +298,9 @@ async fn load_fnis_list_file(
 
     // NOTE: Since there are mod files that are not UTF-8, we need to support them.
     let content =
-        fs::read(&list_path_string).await.and_then(auto_charset::decode_to_utf8).map_err(|e| {
-            FnisError::FailedReadingListFile { expected: list_path_string.clone(), source: e }
-        })?;
+        source.read(Path::new(&list_path_string)).and_then(auto_charset::decode_to_utf8).map_err(
+            |e| FnisError::FailedReadingListFile { expected: list_path_string.clone(), source: e },
+        )?;
 
     Ok(content)
 }
@@ -301,6 +316,7 @@ async fn load_fnis_list_file(
 /// # Errors
 /// An error if not found.
 fn find_behavior_file(
+    _source: &dyn PatchSource,
     animations_mod_dir: &Path,
     behavior_entry: &'static BehaviorEntry,
     namespace: &str,
@@ -330,7 +346,7 @@ fn find_behavior_file(
     {
         let mut behaviors_file = parent_dir.join(master_behavior_dir);
         behaviors_file.push(&file_name);
-        if !behaviors_file.exists() {
+        if !_source.is_file(&behaviors_file) {
             tracing::warn!(
                 "FNIS behavior file not found: {}. \
                 Note: MO2 virtual filesystem may cause false negatives in fs::exists().",
@@ -377,16 +393,16 @@ pub enum FnisError {
 mod tests {
     use super::*;
 
-    #[tokio::test]
+    #[test]
     #[ignore = "local only"]
-    async fn test_collect_fnis_injection() {
+    fn test_collect_fnis_injection() {
         use crate::behaviors::tasks::fnis::patch_gen::generated_behaviors::HUMANOID;
 
         let input = "../../dummy/fnis_test_mods/FNIS Flyer SE 7.0/Data/Meshes/actors/character/animations/FNISFlyer";
         let behavior_entry = HUMANOID.get("character").unwrap();
-        let res = collect_fnis_injection(input, behavior_entry, "FNISFlyer", 0)
-            .await
-            .unwrap_or_else(|e| panic!("{e}"));
+        let res =
+            collect_fnis_injection(&crate::FsPatchSource, input, behavior_entry, "FNISFlyer", 0)
+                .unwrap_or_else(|e| panic!("{e}"));
         dbg!(res);
     }
 }
@@ -10,6 +10,7 @@ use serde::Serialize;
 use super::group_names::AAGroupName;
 use crate::{
     behaviors::tasks::fnis::patch_gen::io_jobs::{AnimIoJob, AnimKind, ConversionJob},
+    config::OutputWriter,
     errors::Error,
 };
 
@@ -19,7 +20,7 @@ pub(crate) type BaseMap = rapidhash::fast::RapidHashMap<(Arc<str>, u64), u64>;
 /// Builds the base lookup map from a finalized [`AAConfig`].
 ///
 /// Key: `(prefix, group_id)` — matches the fields in [`FnisAAConfigJob`].
-fn build_base_map(config: &AAConfig) -> BaseMap {
+pub(crate) fn build_base_map(config: &AAConfig) -> BaseMap {
     config
         .mods
         .iter()
@@ -196,38 +197,35 @@ fn compute_crc(mods: &[AAMod]) -> u32 {
 // Output
 // =============================================================================
 
-fn write_aa_config(mods: Vec<AAMod>, output_dir: &Path) -> Result<BaseMap, Error> {
-    let mut mods = mods;
-    compute_bases(&mut mods);
-    let config = AAConfig::new("V07.06.00.0", mods);
+/// Output path of the `fnis_aa` SKSE plugin config. (relative to `output_dir`)
+pub(crate) const AA_CONFIG_INNER_PATH: &str = "SKSE/Plugins/fnis_aa/config.json";
 
-    let path = output_dir.join("SKSE/Plugins/fnis_aa/config.json");
-    let json = sonic_rs::to_string_pretty(&config)
+/// Write `<output_dir>/SKSE/Plugins/fnis_aa/config.json`.
+pub(crate) fn write_aa_config(
+    config: &AAConfig,
+    output_dir: &Path,
+    writer: &OutputWriter,
+) -> Result<(), Error> {
+    let path = output_dir.join(AA_CONFIG_INNER_PATH);
+    let json = sonic_rs::to_string_pretty(config)
         .map_err(|e| Error::JsonError { path: path.clone(), source: e })?;
-    if let Some(parent) = path.parent() {
-        std::fs::create_dir_all(parent)
-            .map_err(|e| Error::FailedIo { path: parent.to_path_buf(), source: e })?;
-    }
-    std::fs::write(&path, &json).map_err(|e| Error::FailedIo { path: path.clone(), source: e })?;
-
-    Ok(build_base_map(&config))
+    writer
+        .write(&path, json.as_bytes())
+        .map_err(|e| Error::FailedIo { path: path.clone(), source: e })
 }
 
 // =============================================================================
 // Public entry point
 // =============================================================================
 
-/// Collects `AAMod` data from a slice of `AnimIoJob` and generates `aa_config.json`.
+/// Collects `AAMod` data from a slice of `AnimIoJob` and builds `aa_config.json`.
 ///
 /// Call this once after all `alt_anim_to_oar` jobs have been collected.
 /// Load order is preserved via `IndexMap` to ensure a stable CRC.
 ///
-/// # Output path
-/// `SKSE/Plugins/fnis_aa/config.json`
-pub(crate) fn build_aa_config_from_jobs(
-    jobs: &[AnimIoJob],
-    output_dir: &Path,
-) -> Result<BaseMap, Error> {
+/// # Note
+/// This does not write anything. See [`write_aa_config`].
+pub(crate) fn build_aa_config_from_jobs(jobs: &[AnimIoJob]) -> AAConfig {
     // IndexMap preserves insertion order = mod load order, giving a stable CRC.
     let mut aa_mods = IndexMap::<_, _, rapidhash::fast::RandomState>::default();
 
@@ -258,7 +256,9 @@ pub(crate) fn build_aa_config_from_jobs(
         }
     }
 
-    write_aa_config(aa_mods.into_values().collect(), output_dir)
+    let mut mods: Vec<_> = aa_mods.into_values().collect();
+    compute_bases(&mut mods);
+    AAConfig::new("V07.06.00.0", mods)
 }
 
 // =============================================================================
@@ -359,7 +359,8 @@ mod tests {
             make_fnis_job("xpe", AAGroupName::Sprint, 0, 1),
         ];
         let output_dir = Path::new("../../dummy/debug");
-        build_aa_config_from_jobs(&jobs, output_dir).unwrap();
+        let writer = OutputWriter::new(output_dir, None);
+        write_aa_config(&build_aa_config_from_jobs(&jobs), output_dir, &writer).unwrap();
     }
 
     // Helper used by tests to inspect intermediate state without file I/O.
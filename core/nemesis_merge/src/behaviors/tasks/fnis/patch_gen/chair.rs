@@ -62,30 +62,33 @@ pub(super) fn new_chair_patches<'a>(
     // e.g. (#FNIS$1, 1)
     let class_index_to_anim_object_map =
         dashmap::DashMap::<_, _, rapidhash::fast::RandomState>::default();
-    one_patches.par_extend(start_animation.anim_objects.par_iter().enumerate().map(
-        |(index, name)| {
-            let new_anim_object_index = owned_data.next_class_name_attribute();
-            class_index_to_anim_object_map.insert(index, new_anim_object_index.clone());
-
-            // One anim object
-            (
-                vec![
-                    Cow::Owned(new_anim_object_index.clone()),
-                    Cow::Borrowed("hkbStringEventPayload"),
-                ],
-                ValueWithPriority {
-                    patch: JsonPatch {
-                        action: Action::Pure { op: Op::Add },
-                        value: simd_json::json_typed!(borrowed, {
-                            "__ptr": new_anim_object_index,
-                            "data": name, // StringPtr
-                        }),
+    let new_anim_object_indexes =
+        owned_data.next_class_name_attributes(start_animation.anim_objects.len());
+    one_patches.par_extend(
+        start_animation.anim_objects.par_iter().enumerate().zip(new_anim_object_indexes).map(
+            |((index, name), new_anim_object_index)| {
+                class_index_to_anim_object_map.insert(index, new_anim_object_index.clone());
+
+                // One anim object
+                (
+                    vec![
+                        Cow::Owned(new_anim_object_index.clone()),
+                        Cow::Borrowed("hkbStringEventPayload"),
+                    ],
+                    ValueWithPriority {
+                        patch: JsonPatch {
+                            action: Action::Pure { op: Op::Add },
+                            value: simd_json::json_typed!(borrowed, {
+                                "__ptr": new_anim_object_index,
+                                "data": name, // StringPtr
+                            }),
+                        },
+                        priority,
                     },
-                    priority,
-                },
-            )
-        },
-    ));
+                )
+            },
+        ),
+    );
 
     // $RI
     one_patches.push((
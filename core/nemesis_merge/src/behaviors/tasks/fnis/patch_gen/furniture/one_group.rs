@@ -37,10 +37,11 @@ pub(crate) fn new_furniture_one_group_patches<'a>(
     // Furniture seq iter
     let total = furniture.animations.len();
 
-    // par animation class indexes
+    // animation class indexes
+    // NOTE: Allocated sequentially, so that the ids do not depend on thread scheduling.
     let all_class_indexes: Vec<[String; 9]> = furniture
         .animations
-        .par_iter()
+        .iter()
         .map(|_| core::array::from_fn(|_| owned_data.next_class_name_attribute()))
         .collect();
 
@@ -130,29 +131,31 @@ pub(crate) fn new_furniture_one_group_patches<'a>(
 
     // Associate the number of times an assigned index occurs with the name of the AnimObject at that time, and use this association to reference the eventID.
     // e.g. (#FNIS$1, 1)
-    one_patches.par_extend(first_animation.anim_objects.par_iter().enumerate().map(
-        |(_index, name)| {
-            let new_anim_object_index = owned_data.next_class_name_attribute();
-
-            // One anim object
-            (
-                vec![
-                    Cow::Owned(new_anim_object_index.clone()),
-                    Cow::Borrowed("hkbStringEventPayload"),
-                ],
-                ValueWithPriority {
-                    patch: JsonPatch {
-                        action: Action::Pure { op: Op::Add },
-                        value: simd_json::json_typed!(borrowed, {
-                            "__ptr": new_anim_object_index,
-                            "data": name, // StringPtr
-                        }),
+    let new_anim_object_indexes =
+        owned_data.next_class_name_attributes(first_animation.anim_objects.len());
+    one_patches.par_extend(
+        first_animation.anim_objects.par_iter().enumerate().zip(new_anim_object_indexes).map(
+            |((_index, name), new_anim_object_index)| {
+                // One anim object
+                (
+                    vec![
+                        Cow::Owned(new_anim_object_index.clone()),
+                        Cow::Borrowed("hkbStringEventPayload"),
+                    ],
+                    ValueWithPriority {
+                        patch: JsonPatch {
+                            action: Action::Pure { op: Op::Add },
+                            value: simd_json::json_typed!(borrowed, {
+                                "__ptr": new_anim_object_index,
+                                "data": name, // StringPtr
+                            }),
+                        },
+                        priority,
                     },
-                    priority,
-                },
-            )
-        },
-    ));
+                )
+            },
+        ),
+    );
 
     // $RI
     one_patches.push({
@@ -244,9 +244,10 @@ fn collect_seq_patch<'a>(
     let files = DashSet::default();
     let events = DashSet::default();
 
+    // NOTE: Not `into_par_iter`: `new_adsf_patch` allocates clip ids, which must be in animation order.
     let adsf_patches: Vec<AdsfPatch<'a>> = sequenced_animation
         .animations
-        .into_par_iter()
+        .into_iter()
         .flat_map(|fnis_animation| {
             let FNISAnimation { flag_set, anim_file, anim_event, .. } = &fnis_animation;
 
@@ -2,13 +2,14 @@
 use rayon::prelude::*;
 
 use crate::{
-    behaviors::tasks::fnis::patch_gen::alternate::FnisAANamespaceConfigJob, errors::Error,
+    behaviors::tasks::fnis::patch_gen::alternate::FnisAANamespaceConfigJob, config::OutputWriter,
+    errors::Error,
 };
 
 /// Writes all static namespace `config.json` files in parallel.
 #[must_use]
-pub(crate) fn run(jobs: Vec<FnisAANamespaceConfigJob>) -> Vec<Error> {
+pub(crate) fn run(jobs: Vec<FnisAANamespaceConfigJob>, writer: &OutputWriter) -> Vec<Error> {
     jobs.into_par_iter()
-        .filter_map(|job| super::write_file(&job.output_path, job.config.as_bytes()).err())
+        .filter_map(|job| super::write_file(writer, &job.output_path, job.config.as_bytes()).err())
         .collect()
 }
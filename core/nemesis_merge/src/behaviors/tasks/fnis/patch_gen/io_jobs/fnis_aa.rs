@@ -7,12 +7,17 @@ use crate::{
     behaviors::tasks::fnis::patch_gen::alternate::{
         FnisAASlotConfigJob, aa_config::BaseMap, oar_json::new_fnis_aa_slot_config_json,
     },
+    config::OutputWriter,
     errors::Error,
 };
 
 /// Resolves each slot's base and writes its `config.json` in parallel.
 #[must_use]
-pub(crate) fn run(jobs: Vec<FnisAASlotConfigJob>, base_map: Option<&BaseMap>) -> Vec<Error> {
+pub(crate) fn run(
+    jobs: Vec<FnisAASlotConfigJob>,
+    base_map: Option<&BaseMap>,
+    writer: &OutputWriter,
+) -> Vec<Error> {
     jobs.into_par_iter()
         .enumerate()
         .filter_map(|(i, job)| {
@@ -37,7 +42,7 @@ pub(crate) fn run(jobs: Vec<FnisAASlotConfigJob>, base_map: Option<&BaseMap>) ->
                 base,
                 fallback_priority,
             );
-            super::write_file(&job.output_path, config.as_bytes()).err()
+            super::write_file(writer, &job.output_path, config.as_bytes()).err()
         })
         .collect()
 }
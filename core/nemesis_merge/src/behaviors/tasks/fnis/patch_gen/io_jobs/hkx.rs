@@ -12,7 +12,7 @@ use crate::{
         collect::owned::OwnedFnisInjection,
         patch_gen::{alternate::group_names::AAGroupName, io_jobs::AnimIoJob},
     },
-    config::OutPutTarget,
+    config::{CancellationToken, OutPutTarget, OutputWriter, PatchSource},
     errors::Error,
 };
 
@@ -59,10 +59,23 @@ pub(crate) enum AnimKind {
 
 /// Runs the full HKX read → convert → write pipeline in parallel.
 #[must_use]
-pub(crate) fn run(jobs: Vec<ConversionJob>, output_target: OutPutTarget) -> Vec<Error> {
+pub(crate) fn run(
+    jobs: Vec<ConversionJob>,
+    output_target: OutPutTarget,
+    cancel_token: &CancellationToken,
+    source: &dyn PatchSource,
+    writer: &OutputWriter,
+) -> Vec<Error> {
     // Stage 1: read
-    let read_results: Vec<Result<ConversionBytes, Error>> =
-        jobs.into_par_iter().filter_map(|job| read(job, output_target)).collect();
+    let read_results: Vec<Result<ConversionBytes, Error>> = jobs
+        .into_par_iter()
+        .filter_map(|job| {
+            if cancel_token.is_cancelled() {
+                return None;
+            }
+            read(job, output_target, source)
+        })
+        .collect();
 
     // Stage 2: convert (in-memory)
     let (converted, mut errors): (Vec<ConversionBytes>, Vec<Error>) =
@@ -75,9 +88,12 @@ pub(crate) fn run(jobs: Vec<ConversionJob>, output_target: OutPutTarget) -> Vec<
         });
 
     // Stage 3: write
-    errors.par_extend(
-        converted.into_par_iter().filter_map(|b| super::write_file(&b.output_path, &b.bytes).err()),
-    );
+    errors.par_extend(converted.into_par_iter().filter_map(|b| {
+        if cancel_token.is_cancelled() {
+            return None;
+        }
+        super::write_file(writer, &b.output_path, &b.bytes).err()
+    }));
 
     errors
 }
@@ -92,10 +108,14 @@ struct ConversionBytes {
     bytes: Vec<u8>,
 }
 
-fn read(job: ConversionJob, output_target: OutPutTarget) -> Option<Result<ConversionBytes, Error>> {
-    use std::{borrow::Cow, fs::File, io::Read, path::Path};
+fn read(
+    job: ConversionJob,
+    output_target: OutPutTarget,
+    source: &dyn PatchSource,
+) -> Option<Result<ConversionBytes, Error>> {
+    use std::borrow::Cow;
 
-    let actual_input: Cow<Path> = if !job.input_path.exists() {
+    let actual_input: Cow<Path> = if !source.is_file(&job.input_path) {
         if matches!(job.kind, AnimKind::FnisAA { .. }) {
             #[cfg(feature = "tracing")]
             tracing::info!(
@@ -103,7 +123,7 @@ fn read(job: ConversionJob, output_target: OutPutTarget) -> Option<Result<Conver
                 "Input file does not exist; skipping (FNIS AltAnim → OAR)."
             );
             return None;
-        } else if let Some(found) = find_case_insensitive(&job.input_path) {
+        } else if let Some(found) = find_case_insensitive(source, &job.input_path) {
             Cow::Owned(found)
         } else {
             return Some(Err(Error::FNISHkxIoError {
@@ -116,29 +136,30 @@ fn read(job: ConversionJob, output_target: OutPutTarget) -> Option<Result<Conver
     };
 
     // --- Step 1: Read only 17 bytes (header) ---
-    let mut file = match File::open(actual_input.as_ref()) {
-        Ok(f) => f,
+    let header = match source.read_head(&actual_input, 17) {
+        Ok(bytes) => bytes,
         Err(e) => {
             return Some(Err(Error::FNISHkxIoError { path: actual_input.into_owned(), source: e }));
         }
     };
-
-    let mut header = [0_u8; 17];
-    if let Err(e) = file.read_exact(&mut header) {
-        return Some(Err(Error::FNISHkxIoError { path: actual_input.into_owned(), source: e }));
-    }
+    let Ok(header) = <[u8; 17]>::try_from(header) else {
+        return Some(Err(Error::FNISHkxIoError {
+            path: actual_input.into_owned(),
+            source: std::io::ErrorKind::UnexpectedEof.into(),
+        }));
+    };
 
     let current_format = match check_header(header, &actual_input, output_target) {
         Ok(f) => f,
         Err(e) => return Some(Err(e)),
     };
 
-    if matches!(job.kind, AnimKind::Standard) && current_format == output_target {
+    if matches!(job.kind, AnimKind::Standard) && current_format == output_target.hkx_format() {
         return None; // If no conversion needed -> skip without full read
     }
 
     // --- Step 2: Read full file only if needed ---
-    let bytes = match std::fs::read(actual_input.as_ref()) {
+    let bytes = match source.read(&actual_input) {
         Ok(b) => b,
         Err(e) => {
             return Some(Err(Error::FNISHkxIoError { path: actual_input.into_owned(), source: e }));
@@ -169,7 +190,7 @@ fn convert(
 
     let header = match output_target {
         OutPutTarget::SkyrimLe => HkxHeader::new_skyrim_le(),
-        OutPutTarget::SkyrimSe => HkxHeader::new_skyrim_se(),
+        OutPutTarget::SkyrimSe | OutPutTarget::SkyrimVr => HkxHeader::new_skyrim_se(),
     };
 
     *bytes = serde_hkx::to_bytes(&class_map, &header)
@@ -223,12 +244,12 @@ fn check_header(
 }
 
 /// Case-insensitive file lookup for Unix systems.
-fn find_case_insensitive(path: &Path) -> Option<PathBuf> {
+fn find_case_insensitive(source: &dyn PatchSource, path: &Path) -> Option<PathBuf> {
     let parent = path.parent()?;
     let file_name = path.file_name()?;
-    std::fs::read_dir(parent).ok()?.find_map(|e| {
-        let e = e.ok()?;
-        e.file_name().eq_ignore_ascii_case(file_name).then(|| e.path())
+    source.read_dir(parent).ok()?.into_iter().find_map(|e| {
+        let is_match = !e.is_dir && e.path.file_name()?.eq_ignore_ascii_case(file_name);
+        is_match.then_some(e.path)
     })
 }
 
@@ -4,14 +4,21 @@ pub(super) mod config;
 pub(super) mod fnis_aa;
 pub(super) mod hkx;
 
-use std::sync::Arc;
+use std::{path::PathBuf, sync::Arc};
 
 use rayon::prelude::*;
 
 pub(super) use self::hkx::{AnimKind, ConversionJob};
 use crate::{
-    behaviors::tasks::fnis::patch_gen::alternate::{FnisAANamespaceConfigJob, FnisAASlotConfigJob},
-    config::OutPutTarget,
+    Config,
+    behaviors::{
+        plan::{FnisIoJobKind, FnisIoJobPlan},
+        tasks::fnis::patch_gen::alternate::{
+            FnisAANamespaceConfigJob, FnisAASlotConfigJob,
+            aa_config::{AA_CONFIG_INNER_PATH, AAConfig, build_base_map, write_aa_config},
+        },
+    },
+    config::{CancellationToken, OutPutTarget, OutputWriter, PatchSource},
     errors::Error,
 };
 
@@ -28,18 +35,35 @@ pub(crate) enum AnimIoJob {
 #[derive(Debug)]
 pub(crate) struct FNISIoJobRunner {
     jobs: Vec<AnimIoJob>,
+    output_dir: PathBuf,
     output_target: OutPutTarget,
-    aa_base_map: Option<super::alternate::aa_config::BaseMap>,
+    /// `fnis_aa/config.json`. None when no FnisAA jobs exist.
+    aa_config: Option<AAConfig>,
+    generate_fnis_esp: bool,
+    cancel_token: CancellationToken,
+    /// Source of the animation and behavior files.
+    source: Arc<dyn PatchSource>,
+    writer: OutputWriter,
 }
 
 impl FNISIoJobRunner {
     #[inline]
-    pub(crate) const fn new(
+    pub(crate) fn new(
         jobs: Vec<AnimIoJob>,
-        output_target: OutPutTarget,
-        aa_base_map: Option<super::alternate::aa_config::BaseMap>,
+        config: &Config,
+        aa_config: Option<AAConfig>,
+        source: Arc<dyn PatchSource>,
     ) -> Self {
-        Self { jobs, output_target, aa_base_map }
+        Self {
+            jobs,
+            output_dir: config.output_dir.clone(),
+            output_target: config.output_target,
+            aa_config,
+            generate_fnis_esp: config.generate_fnis_esp,
+            cancel_token: config.cancel_token.clone(),
+            source,
+            writer: config.output_writer(),
+        }
     }
 
     /// Run the HKX conversion jobs in parallel.
@@ -48,7 +72,7 @@ impl FNISIoJobRunner {
     /// Returns all errors encountered during conversion.
     ///
     /// # Note
-    /// AAConfig is pre-built by the caller; None when no FnisAA jobs exist.
+    /// `fnis_aa/config.json` and `FNIS.esp`(if enabled) are also written here.
     ///
     /// # Returns Errors
     /// Returns a collection of errors if any file:
@@ -56,9 +80,93 @@ impl FNISIoJobRunner {
     /// - Has invalid HKX magic numbers,
     /// - Has a pointer size that cannot be determined.
     #[must_use]
-    #[inline]
     pub(crate) fn convert(self) -> Vec<Error> {
-        run_conversion_jobs(self.jobs, self.output_target, self.aa_base_map.as_ref())
+        let mut errors = vec![];
+        if self.cancel_token.is_cancelled() {
+            return errors;
+        }
+
+        if self.generate_fnis_esp
+            && let Err(e) =
+                super::dummy_esp::save_dummy_esp(&self.output_dir, self.output_target, &self.writer)
+        {
+            errors.push(Error::FNISGenerateEspError { source: e });
+        }
+
+        let aa_base_map = self.aa_config.as_ref().and_then(|aa_config| {
+            match write_aa_config(aa_config, &self.output_dir, &self.writer) {
+                Ok(()) => Some(build_base_map(aa_config)),
+                Err(e) => {
+                    errors.push(e);
+                    None
+                }
+            }
+        });
+
+        errors.extend(run_conversion_jobs(
+            self.jobs,
+            self.output_target,
+            aa_base_map.as_ref(),
+            &self.cancel_token,
+            &*self.source,
+            &self.writer,
+        ));
+        errors
+    }
+
+    /// List the files that [`Self::convert`] would write, without any I/O.
+    ///
+    /// Includes the `female/` mirrors of `male/` FNIS AA animations.
+    #[must_use]
+    pub(crate) fn plan(&self) -> Vec<FnisIoJobPlan> {
+        let mut plans = vec![];
+
+        if self.generate_fnis_esp {
+            plans.push(FnisIoJobPlan {
+                kind: FnisIoJobKind::DummyEsp,
+                input_path: None,
+                output_path: self.output_dir.join("FNIS.esp"),
+            });
+        }
+        if self.aa_config.is_some() {
+            plans.push(FnisIoJobPlan {
+                kind: FnisIoJobKind::FnisAAConfig,
+                input_path: None,
+                output_path: self.output_dir.join(AA_CONFIG_INNER_PATH),
+            });
+        }
+
+        let hkx_plan = |job: &ConversionJob| FnisIoJobPlan {
+            kind: match job.kind {
+                AnimKind::Standard => FnisIoJobKind::Hkx,
+                AnimKind::FnisAA { .. } => FnisIoJobKind::FnisAAHkx,
+            },
+            input_path: Some(job.input_path.clone()),
+            output_path: job.output_path.clone(),
+        };
+
+        for job in &self.jobs {
+            match job {
+                AnimIoJob::Hkx(job) => {
+                    if let Some(female_job) = mirror_male_to_female(job) {
+                        plans.push(hkx_plan(&female_job));
+                    }
+                    plans.push(hkx_plan(job));
+                }
+                AnimIoJob::FnisAANamespaceConfig(job) => plans.push(FnisIoJobPlan {
+                    kind: FnisIoJobKind::FnisAANamespaceConfig,
+                    input_path: None,
+                    output_path: job.output_path.clone(),
+                }),
+                AnimIoJob::FnisAASlotConfig(job) => plans.push(FnisIoJobPlan {
+                    kind: FnisIoJobKind::FnisAASlotConfig,
+                    input_path: None,
+                    output_path: job.output_path.clone(),
+                }),
+            }
+        }
+
+        plans
     }
 }
 
@@ -67,11 +175,17 @@ fn run_conversion_jobs(
     jobs: Vec<AnimIoJob>,
     output_target: OutPutTarget,
     aa_base_map: Option<&super::alternate::aa_config::BaseMap>,
+    cancel_token: &CancellationToken,
+    source: &dyn PatchSource,
+    writer: &OutputWriter,
 ) -> Vec<Error> {
     #[cfg(feature = "tracing")]
     tracing::debug!("jobs to run: {:#?}", jobs);
 
     let (hkx_jobs, namespace_config_jobs, slot_config_jobs) = partition_jobs(jobs);
+    if cancel_token.is_cancelled() {
+        return vec![];
+    }
 
     let mut hkx_errors = vec![];
     let mut namespace_config_errors = vec![];
@@ -79,13 +193,13 @@ fn run_conversion_jobs(
 
     rayon::scope(|s| {
         s.spawn(|_| {
-            hkx_errors = hkx::run(hkx_jobs, output_target);
+            hkx_errors = hkx::run(hkx_jobs, output_target, cancel_token, source, writer);
         });
         s.spawn(|_| {
-            namespace_config_errors = config::run(namespace_config_jobs);
+            namespace_config_errors = config::run(namespace_config_jobs, writer);
         });
         s.spawn(|_| {
-            slot_config_errors = fnis_aa::run(slot_config_jobs, aa_base_map);
+            slot_config_errors = fnis_aa::run(slot_config_jobs, aa_base_map, writer);
         });
     });
 
@@ -129,12 +243,13 @@ fn partition_jobs(
         )
 }
 
-pub(super) fn write_file(output_path: &std::path::Path, bytes: &[u8]) -> Result<(), Error> {
-    if let Some(parent) = output_path.parent() {
-        std::fs::create_dir_all(parent)
-            .map_err(|e| Error::FNISHkxIoError { path: parent.to_path_buf(), source: e })?;
-    }
-    std::fs::write(output_path, bytes)
+pub(super) fn write_file(
+    writer: &OutputWriter,
+    output_path: &std::path::Path,
+    bytes: &[u8],
+) -> Result<(), Error> {
+    writer
+        .write(output_path, bytes)
         .map_err(|e| Error::FNISHkxIoError { path: output_path.into(), source: e })
 }
 
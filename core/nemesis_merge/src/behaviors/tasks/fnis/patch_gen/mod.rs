@@ -11,7 +11,7 @@ mod kill_move;
 mod offset_arm;
 mod pair;
 
-use std::borrow::Cow;
+use std::{borrow::Cow, sync::Arc};
 
 use fnis_list::parse_fnis_list;
 use json_patch::{Action, JsonPatch, JsonPath, Op, ValueWithPriority, json_path};
@@ -22,27 +22,30 @@ use winnow::Parser;
 
 pub(crate) use crate::behaviors::tasks::fnis::patch_gen::gen_list_patch::FnisPatchGenerationError;
 use crate::{
-    behaviors::tasks::{
-        adsf::AdsfPatch,
-        fnis::{
-            collect::owned::OwnedFnisInjection,
-            patch_gen::{
-                gen_list_patch::{OneListPatch, generate_patch},
-                generated_behaviors::{BehaviorEntry, DEFAULT_FEMALE, DRAUGR_SKELETON},
-                global::{
-                    _0_master::{
-                        alternate::new_push_alt_anim_values_seq_patch, new_global_master_patch,
+    behaviors::{
+        conflicts::OneFieldLoser,
+        tasks::{
+            adsf::AdsfPatch,
+            fnis::{
+                collect::owned::OwnedFnisInjection,
+                patch_gen::{
+                    gen_list_patch::{OneListPatch, generate_patch},
+                    generated_behaviors::{BehaviorEntry, DEFAULT_FEMALE, DRAUGR_SKELETON},
+                    global::{
+                        _0_master::{
+                            alternate::new_push_alt_anim_values_seq_patch, new_global_master_patch,
+                        },
+                        mt_behavior::new_mt_global_patch,
                     },
-                    mt_behavior::new_mt_global_patch,
+                    io_jobs::{AnimIoJob, FNISIoJobRunner},
+                    kill_move::calculate_hash,
                 },
-                io_jobs::{AnimIoJob, FNISIoJobRunner},
-                kill_move::calculate_hash,
             },
+            patches::types::{BehaviorGraphDataMap, BehaviorPatchesMap, PatchCollection},
+            templates::key::{THREAD_PERSON_0_MASTER_KEY, THREAD_PERSON_MT_BEHAVIOR_KEY},
         },
-        patches::types::{BehaviorGraphDataMap, BehaviorPatchesMap, PatchCollection},
-        templates::key::{THREAD_PERSON_0_MASTER_KEY, THREAD_PERSON_MT_BEHAVIOR_KEY},
     },
-    config::{Config, ReportType, StatusReportCounter},
+    config::{Config, PatchSource, ReportType, StatusReportCounter},
     errors::{Error, FailedParseFnisModListSnafu},
 };
 
@@ -51,6 +54,7 @@ pub(crate) type JsonPatchPairs<'a> = Vec<(JsonPath<'a>, ValueWithPriority<'a>)>;
 struct LocalAgg<'a> {
     borrowed_patches: BehaviorPatchesMap<'a>,
     behavior_graph_data_map: BehaviorGraphDataMap<'a>,
+    one_losers: Vec<OneFieldLoser<'a>>,
 
     adsf_patches: Vec<AdsfPatch<'a>>,
     furniture_groups: Vec<String>,
@@ -63,6 +67,7 @@ impl<'a> LocalAgg<'a> {
         Self {
             borrowed_patches: BehaviorPatchesMap::default(),
             behavior_graph_data_map: BehaviorGraphDataMap::new(),
+            one_losers: Vec::new(),
             adsf_patches: Vec::new(),
             furniture_groups: Vec::new(),
             conversion_jobs: Vec::new(),
@@ -70,7 +75,9 @@ impl<'a> LocalAgg<'a> {
     }
 
     fn merge(mut self, other: Self) -> Self {
-        self.borrowed_patches.merge(other.borrowed_patches);
+        let one_losers = self.borrowed_patches.merge(other.borrowed_patches);
+        self.one_losers.par_extend(other.one_losers);
+        self.one_losers.par_extend(one_losers);
 
         self.behavior_graph_data_map.0.par_extend(other.behavior_graph_data_map.0);
         self.adsf_patches.par_extend(other.adsf_patches);
@@ -87,9 +94,12 @@ impl<'a> LocalAgg<'a> {
 /// Since this function uses I/O internally for operations like hkx conversion,
 /// do not call this function itself within `rayon::par_iter`.
 /// This is because semaphore locks in MO2 may potentially deadlock.
+///
+/// - `source`: Where the animation and behavior files of the I/O jobs are read from. See [`Config::source`].
 pub(crate) fn collect_borrowed_patches<'a>(
     mods_patches: &'a [OwnedFnisInjection],
     config: &'a Config,
+    source: &Arc<dyn PatchSource>,
 ) -> (PatchCollection<'a>, Vec<AdsfPatch<'a>>, FNISIoJobRunner, Vec<Error>) {
     let reporter = StatusReportCounter::new(
         &config.status_report,
@@ -97,30 +107,34 @@ pub(crate) fn collect_borrowed_patches<'a>(
         mods_patches.len(),
     );
 
-    let (patches, mut errors): (Vec<_>, Vec<_>) =
-        mods_patches.par_iter().partition_map(|owned_data| {
-            match parse_fnis_list
-                .parse(&owned_data.list_content)
-                .map_err(|e| winnow_ext::ReadableError::from_parse(e))
-                .with_context(|_| FailedParseFnisModListSnafu { path: owned_data.to_list_path() })
-                .and_then(|list| {
-                    #[cfg(feature = "tracing")]
-                    tracing::debug!("{}: \n{list:#?}", owned_data.to_list_path().display());
-
-                    generate_patch(owned_data, list, config)
-                        .map_err(|e| Error::FnisPatchGenerationError { source: e })
-                }) {
-                Ok(patches) => Either::Left((owned_data, patches)),
-                Err(err) => {
-                    reporter.increment();
-                    Either::Right(err)
-                }
+    let (patches, errors): (Vec<_>, Vec<_>) = mods_patches.par_iter().partition_map(|owned_data| {
+        if let Err(err) = config.check_cancelled() {
+            return Either::Right(err);
+        }
+
+        match parse_fnis_list
+            .parse(&owned_data.list_content)
+            .map_err(|e| winnow_ext::ReadableError::from_parse(e))
+            .with_context(|_| FailedParseFnisModListSnafu { path: owned_data.to_list_path() })
+            .and_then(|list| {
+                #[cfg(feature = "tracing")]
+                tracing::debug!("{}: \n{list:#?}", owned_data.to_list_path().display());
+
+                generate_patch(owned_data, list, config)
+                    .map_err(|e| Error::FnisPatchGenerationError { source: e })
+            }) {
+            Ok(patches) => Either::Left((owned_data, patches)),
+            Err(err) => {
+                reporter.increment();
+                Either::Right(err)
             }
-        });
+        }
+    });
 
     let LocalAgg {
         borrowed_patches,
         behavior_graph_data_map,
+        one_losers,
         adsf_patches,
         furniture_groups,
         conversion_jobs,
@@ -141,6 +155,7 @@ pub(crate) fn collect_borrowed_patches<'a>(
 
             let borrowed_patches = &mut acc.borrowed_patches;
             let behavior_graph_data_map = &mut acc.behavior_graph_data_map;
+            let one_losers = &mut acc.one_losers;
 
             // Add patches to mt_behavior.xml
             if owned_data.behavior_entry.is_3rd_person_character() {
@@ -153,7 +168,9 @@ pub(crate) fn collect_borrowed_patches<'a>(
                 }
 
                 for (path, patch) in one_mt_behavior_patches {
-                    entry.one.insert(path, patch);
+                    if let Some((path, loser)) = entry.one.insert(path, patch) {
+                        one_losers.push((THREAD_PERSON_MT_BEHAVIOR_KEY, path, loser));
+                    }
                 }
                 for (path, patch) in seq_mt_behavior_patches {
                     entry.seq.insert(path, patch);
@@ -174,12 +191,15 @@ pub(crate) fn collect_borrowed_patches<'a>(
                 }
 
                 // Push Mod Root behavior to master xml
-                let mut entry = borrowed_patches.0.entry(master_template_key).or_default();
+                let mut entry = borrowed_patches.0.entry(master_template_key.clone()).or_default();
                 {
                     let (one_gen, one_state_info, seq_state) =
                         new_injectable_mod_root_behavior(owned_data);
-                    entry.one.insert(one_gen.0, one_gen.1);
-                    entry.one.insert(one_state_info.0, one_state_info.1);
+                    for (path, patch) in [one_gen, one_state_info] {
+                        if let Some((path, loser)) = entry.one.insert(path, patch) {
+                            one_losers.push((master_template_key.clone(), path, loser));
+                        }
+                    }
                     entry.seq.insert(seq_state.0, seq_state.1);
                 }
 
@@ -284,44 +304,25 @@ pub(crate) fn collect_borrowed_patches<'a>(
     }
 
     // fnis_aa/config.json / BDI.json
-    let aa_base_map =
+    // NOTE: Only built here. It is written by `FNISIoJobRunner::convert` so that this function has no side effects.
+    let aa_config =
         if conversion_jobs.par_iter().any(|job| matches!(job, AnimIoJob::FnisAANamespaceConfig(_)))
         {
-            match alternate::aa_config::build_aa_config_from_jobs(&conversion_jobs, &config.output_dir) // Write fnis_aa/config.json
-        {
-            Ok(aa_base_map) => {
-                let seq = new_push_alt_anim_values_seq_patch(0);
-
-                for (path, patch) in seq {
-                    let entry =         borrowed_patches
-                    .0
-                    .entry(THREAD_PERSON_0_MASTER_KEY)
-                    .or_default();
-                    entry.seq.insert(path, patch);
-                }
-                Some(aa_base_map)
-            }
-            Err(e) => {
-                errors.push(e);
-                None
+            let entry = borrowed_patches.0.entry(THREAD_PERSON_0_MASTER_KEY).or_default();
+            for (path, patch) in new_push_alt_anim_values_seq_patch(0) {
+                entry.seq.insert(path, patch);
             }
-        }
+            Some(alternate::aa_config::build_aa_config_from_jobs(&conversion_jobs))
         } else {
             None
         };
     #[cfg(feature = "tracing")]
-    tracing::debug!("aa_base_map = {aa_base_map:#?}");
-
-    if config.generate_fnis_esp
-        && let Err(e) = self::dummy_esp::save_dummy_esp(&config.output_dir, config.output_target)
-    {
-        errors.push(Error::FNISGenerateEspError { source: e });
-    };
+    tracing::debug!("aa_config = {aa_config:#?}");
 
     (
-        PatchCollection { borrowed_patches, behavior_graph_data_map },
+        PatchCollection { borrowed_patches, behavior_graph_data_map, one_losers },
         adsf_patches,
-        FNISIoJobRunner::new(conversion_jobs, config.output_target, aa_base_map),
+        FNISIoJobRunner::new(conversion_jobs, config, aa_config, Arc::clone(source)),
         errors,
     )
 }
@@ -76,14 +76,15 @@ pub(super) fn new_pair_patches<'a>(
 
     // Associate the number of times an assigned index occurs with the name of the AnimObject at that time, and use this association to reference the eventID.
     // e.g. (#FNIS$1, 1)
+    let new_anim_object_indexes =
+        owned_data.next_class_name_attributes(paired_and_kill_animation.anim_objects.len());
     let (active_indexes, passive_indexes, patches) = paired_and_kill_animation
         .anim_objects
         .par_iter()
+        .zip(new_anim_object_indexes)
         .fold(
             || (Vec::new(), Vec::new(), Vec::new()),
-            |mut acc, AnimObject { name, role }| {
-                let new_anim_object_index = owned_data.next_class_name_attribute();
-
+            |mut acc, (AnimObject { name, role }, new_anim_object_index)| {
                 acc.2.push((
                     vec![
                         Cow::Owned(new_anim_object_index.clone()),
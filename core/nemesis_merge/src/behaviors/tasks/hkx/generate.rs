@@ -2,6 +2,7 @@
 use std::{
     fs,
     path::{Path, PathBuf},
+    time::Instant,
 };
 
 use rayon::prelude::*;
@@ -11,8 +12,9 @@ use snafu::ResultExt;
 
 use crate::{
     Config, OutPutTarget,
-    behaviors::tasks::{
-        patches::types::BehaviorGraphDataMap, templates::types::BorrowedTemplateMap,
+    behaviors::{
+        profile::Profiler,
+        tasks::{patches::types::BehaviorGraphDataMap, templates::types::BorrowedTemplateMap},
     },
     config::{ReportType, StatusReportCounter},
     errors::{Error, FailedIoSnafu, HkxSerSnafu, JsonToClassMapSnafu, Result},
@@ -23,25 +25,24 @@ pub(crate) fn generate_hkx_files(
     config: &Config,
     templates: BorrowedTemplateMap<'_>,
     variable_class_map: BehaviorGraphDataMap<'_>,
+    profiler: &Profiler,
 ) -> Result<(), Vec<Error>> {
     let reporter = StatusReportCounter::new(
         &config.status_report,
         ReportType::GeneratingHkxFiles,
         templates.len(),
     );
+    let writer = config.output_writer();
 
     let results = templates
         .into_par_iter()
         .map(|(key, template_json)| {
+            config.check_cancelled()?;
             reporter.increment();
+            let started = Instant::now();
             let inner_path = key.as_meshes_inner_path();
             let mut output_path = config.output_dir.join(inner_path);
 
-            if let Some(output_dir_all) = output_path.parent() {
-                fs::create_dir_all(output_dir_all)
-                    .context(FailedIoSnafu { path: output_dir_all })?;
-            }
-
             let hkx_bytes = {
                 // The error occurring with the following `from_borrowed_value` indicates that the intended JSON
                 // format has not been achieved, suggesting an issue lies with the tool itself.
@@ -103,7 +104,7 @@ pub(crate) fn generate_hkx_files(
                 // Convert to hkx bytes & Replace nemesis id.
                 let header = match config.output_target {
                     OutPutTarget::SkyrimLe => HkxHeader::new_skyrim_le(),
-                    OutPutTarget::SkyrimSe => HkxHeader::new_skyrim_se(),
+                    OutPutTarget::SkyrimSe | OutPutTarget::SkyrimVr => HkxHeader::new_skyrim_se(),
                 };
                 let event_id_map = event_id_map.unwrap_or_else(EventIdMap::new);
                 let variable_id_map = variable_id_map.unwrap_or_else(VariableIdMap::new);
@@ -115,9 +116,12 @@ pub(crate) fn generate_hkx_files(
             };
 
             output_path.set_extension("hkx");
-            fs::write(&output_path, hkx_bytes)
+            writer
+                .write(&output_path, &hkx_bytes)
                 .with_context(|_| FailedIoSnafu { path: output_path.clone() })?;
 
+            profiler.record_template(key.as_str(), started.elapsed());
+
             #[cfg(feature = "tracing")]
             tracing::info!("Generated: {}", output_path.display());
             Ok(())
@@ -0,0 +1,518 @@
+//! Input-hash manifest for incremental generation.
+//!
+//! The hashes of the inputs used for each output are written to `<output_dir>/.d_merge/manifest.json`.
+//! On the next run, outputs whose inputs are unchanged (and which still exist) are skipped.
+//!
+//! # Inputs of each output
+//! - hkx(`TemplateKey`): template bytes, the Nemesis patch files(path, priority, content) for the template,
+//!   FNIS injections(only if FNIS patches the template) and [`global_hash`].
+//! - adsf/asdsf: template bytes, all adsf/asdsf patch files, FNIS injections(adsf only) and [`global_hash`].
+use std::{
+    collections::{BTreeMap, HashSet},
+    path::{Path, PathBuf},
+};
+
+use rapidhash::v3::rapidhash_v3;
+use rayon::prelude::*;
+use snafu::ResultExt as _;
+
+use crate::{
+    Config, OutPutTarget,
+    behaviors::tasks::{
+        fnis::collect::owned::OwnedFnisInjection,
+        patches::{
+            collect::to_template_key,
+            types::{BehaviorPatchesMap, OwnedPatchMap},
+        },
+        templates::{key::TemplateKey, types::OwnedTemplateMap},
+    },
+    errors::{FailedIoSnafu, JsonSnafu, Result},
+};
+
+/// If the structure of the manifest or the way of hashing is changed, increment this.
+const MANIFEST_FORMAT: u32 = 2;
+
+/// Patch file map shared by Nemesis, adsf and asdsf.
+///
+/// - key: full path
+/// - value: (content, priority)
+type PatchFileMap = indexmap::IndexMap<PathBuf, (String, usize), rapidhash::fast::RandomState>;
+
+/// `<output_dir>/.d_merge/manifest.json`
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub(crate) struct Manifest {
+    /// [`MANIFEST_FORMAT`]
+    format: u32,
+
+    /// Hash of all patches and options(excluding resource files).
+    ///
+    /// `None` if the last run had errors.
+    pub inputs: Option<u64>,
+
+    /// Resource file(inner path of `resource_dir`) -> content hash
+    ///
+    /// e.g. `meshes/actors/character/behaviors/0_master.bin`
+    pub resources: BTreeMap<String, u64>,
+
+    /// `TemplateKey` -> input hash of the generated hkx.
+    pub templates: BTreeMap<String, u64>,
+
+    /// Input hash of `animationdatasinglefile.txt`
+    pub adsf: Option<u64>,
+
+    /// Input hash of `animationsetdatasinglefile.txt`
+    pub asdsf: Option<u64>,
+}
+
+impl Manifest {
+    /// Create an empty manifest with the current format.
+    #[inline]
+    pub(crate) const fn new() -> Self {
+        Self {
+            format: MANIFEST_FORMAT,
+            inputs: None,
+            resources: BTreeMap::new(),
+            templates: BTreeMap::new(),
+            adsf: None,
+            asdsf: None,
+        }
+    }
+
+    /// Read the manifest of the last run.
+    ///
+    /// Returns `None` if it does not exist, cannot be parsed or was written by another format.
+    pub(crate) fn load(output_dir: &Path) -> Option<Self> {
+        let bytes = std::fs::read(manifest_path(output_dir)).ok()?;
+        let manifest: Self = sonic_rs::from_slice(&bytes).ok()?;
+        (manifest.format == MANIFEST_FORMAT).then_some(manifest)
+    }
+
+    /// Write the manifest to `<output_dir>/.d_merge/manifest.json`.
+    ///
+    /// # Errors
+    /// If failed to write.
+    pub(crate) fn save(&self, output_dir: &Path) -> Result<()> {
+        let path = manifest_path(output_dir);
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent).context(FailedIoSnafu { path: parent })?;
+        }
+        let json = sonic_rs::to_string_pretty(self).with_context(|_| JsonSnafu { path: &path })?;
+        std::fs::write(&path, json).context(FailedIoSnafu { path })
+    }
+
+    /// Is the hkx output of `key` unchanged since the last run?
+    pub(crate) fn is_template_fresh(&self, key: &str, hash: u64, output_dir: &Path) -> bool {
+        self.templates.get(key) == Some(&hash) && hkx_output_path(output_dir, key).exists()
+    }
+
+    /// Does every output recorded in this manifest still exist, and are its resource files unchanged?
+    pub(crate) fn are_outputs_fresh(&self, config: &Config) -> bool {
+        let output_dir = &config.output_dir;
+        let resources_fresh = self.resources.par_iter().all(|(inner, hash)| {
+            std::fs::read(config.template_path(inner))
+                .is_ok_and(|bytes| rapidhash_v3(&bytes) == *hash)
+        });
+        if !resources_fresh {
+            return false;
+        }
+
+        let templates_exist =
+            self.templates.par_iter().all(|(key, _)| hkx_output_path(output_dir, key).exists());
+        let anim_data_exist = [(self.adsf, ADSF_OUTPUT_PATH), (self.asdsf, ASDSF_OUTPUT_PATH)]
+            .iter()
+            .all(|(hash, inner)| hash.is_none() || output_dir.join(inner).exists());
+
+        templates_exist && anim_data_exist
+    }
+}
+
+/// `<output_dir>/.d_merge/manifest.json`
+pub(crate) fn manifest_path(output_dir: &Path) -> PathBuf {
+    output_dir.join(".d_merge").join("manifest.json")
+}
+
+/// e.g. `<output_dir>/meshes/actors/character/behaviors/0_master.hkx`
+pub(crate) fn hkx_output_path(output_dir: &Path, key: &str) -> PathBuf {
+    let mut path = output_dir.join(key);
+    path.set_extension("hkx");
+    path
+}
+
+pub(crate) const ADSF_OUTPUT_PATH: &str = "meshes/animationdatasinglefile.txt";
+pub(crate) const ASDSF_OUTPUT_PATH: &str = "meshes/animationsetdatasinglefile.txt";
+
+/// Portable, order-sensitive hasher.
+///
+/// `std::hash::Hasher` is not guaranteed to be stable between runs, so each input is hashed
+/// by `rapidhash` v3 and the hashes are hashed again in the written order.
+#[derive(Debug, Default)]
+pub(crate) struct InputHasher(Vec<u8>);
+
+impl InputHasher {
+    #[inline]
+    pub(crate) const fn new() -> Self {
+        Self(Vec::new())
+    }
+
+    #[inline]
+    pub(crate) fn write(&mut self, bytes: &[u8]) -> &mut Self {
+        self.write_u64(rapidhash_v3(bytes))
+    }
+
+    #[inline]
+    pub(crate) fn write_u64(&mut self, value: u64) -> &mut Self {
+        self.0.extend_from_slice(&value.to_le_bytes());
+        self
+    }
+
+    #[inline]
+    pub(crate) fn finish(&self) -> u64 {
+        rapidhash_v3(&self.0)
+    }
+}
+
+/// Hash of the options that affect every output.
+pub(crate) fn global_hash(config: &Config) -> u64 {
+    let output_target = match config.output_target {
+        OutPutTarget::SkyrimSe => 0,
+        OutPutTarget::SkyrimLe => 1,
+        OutPutTarget::SkyrimVr => 2,
+    };
+    let hack = config.hack_options.map_or([0; 3], |hack| {
+        [1, u8::from(hack.cast_ragdoll_event), u8::from(hack.bone_weight_outside_hkparam)]
+    });
+    // Missing(or unreadable) rules are hashed as empty. The error is reported on parsing.
+    let hack_rules = match config.hack_options {
+        Some(_) => std::fs::read(config.hack_rules_path()).unwrap_or_default(),
+        None => Vec::new(),
+    };
+    // Renames the targets of adsf/asdsf patches. Missing(or unreadable) names are hashed as empty too.
+    let vr_project_names = match config.output_target {
+        OutPutTarget::SkyrimVr => std::fs::read(config.vr_project_names_path()).unwrap_or_default(),
+        OutPutTarget::SkyrimSe | OutPutTarget::SkyrimLe => Vec::new(),
+    };
+    // NOTE: debug outputs are written together with hkx, so skipping would leave them outdated.
+    let debug = [
+        u8::from(config.debug.output_patch_json),
+        u8::from(config.debug.output_merged_json),
+        u8::from(config.debug.output_merged_xml),
+    ];
+
+    InputHasher::new()
+        .write_u64(u64::from(MANIFEST_FORMAT))
+        .write_u64(output_target)
+        .write(&hack)
+        .write(&hack_rules)
+        .write(&vr_project_names)
+        .write(&debug)
+        // The skipped templates are not checked, so the errors of `strict_original` would be missed.
+        .write(&[u8::from(config.strict_original)])
+        .finish()
+}
+
+/// Hash of all FNIS injections.
+pub(crate) fn fnis_hash(injections: &[OwnedFnisInjection]) -> u64 {
+    let mut hashes: Vec<_> = injections
+        .par_iter()
+        .map(|injection| {
+            InputHasher::new()
+                .write(injection.to_list_path().as_os_str().as_encoded_bytes())
+                .write(injection.animations_mod_dir.as_os_str().as_encoded_bytes())
+                .write(injection.namespace.as_bytes())
+                .write_u64(injection.priority as u64)
+                .write(injection.list_content.as_bytes())
+                .write(injection.behavior_path.as_bytes())
+                .finish()
+        })
+        .collect();
+    hashes.par_sort_unstable();
+
+    let mut hasher = InputHasher::new();
+    for hash in hashes {
+        hasher.write_u64(hash);
+    }
+    hasher.finish()
+}
+
+/// Hash of (path, priority, content) of patch files, in path order.
+fn patch_files_hash<'a, I>(patches: I) -> u64
+where
+    I: IntoParallelIterator<Item = (&'a PathBuf, &'a (String, usize))>,
+{
+    let mut hashes: Vec<_> = patches
+        .into_par_iter()
+        .map(|(path, (content, priority))| {
+            let hash =
+                InputHasher::new().write_u64(*priority as u64).write(content.as_bytes()).finish();
+            (path, hash)
+        })
+        .collect();
+    hashes.par_sort_unstable_by(|a, b| a.0.cmp(b.0));
+
+    let mut hasher = InputHasher::new();
+    for (path, hash) in hashes {
+        hasher.write(path.as_os_str().as_encoded_bytes()).write_u64(hash);
+    }
+    hasher.finish()
+}
+
+/// Hash of all patches and options(excluding resource files).
+pub(crate) fn inputs_hash(
+    global: u64,
+    fnis: u64,
+    owned_patches: &OwnedPatchMap,
+    adsf_patches: &PatchFileMap,
+    asdsf_patches: &PatchFileMap,
+) -> u64 {
+    InputHasher::new()
+        .write_u64(global)
+        .write_u64(fnis)
+        .write_u64(patch_files_hash(owned_patches))
+        .write_u64(patch_files_hash(adsf_patches))
+        .write_u64(patch_files_hash(asdsf_patches))
+        .finish()
+}
+
+/// Hash of the inputs of `animationdatasinglefile.txt` or `animationsetdatasinglefile.txt`.
+///
+/// - `resource_inner`: e.g. `meshes/animationdatasinglefile.bin`
+///
+/// Returns `(resource hash, input hash)`. `None` if the resource cannot be read.
+pub(crate) fn anim_data_hash(
+    global: u64,
+    config: &Config,
+    resource_inner: &str,
+    patches: &PatchFileMap,
+    fnis: Option<u64>,
+) -> Option<(u64, u64)> {
+    let resource_hash = rapidhash_v3(&std::fs::read(config.template_path(resource_inner)).ok()?);
+
+    let mut hasher = InputHasher::new();
+    hasher.write_u64(global).write_u64(resource_hash).write_u64(patch_files_hash(patches));
+    if let Some(fnis) = fnis {
+        hasher.write_u64(fnis);
+    }
+    Some((resource_hash, hasher.finish()))
+}
+
+/// Input hashes of hkx templates.
+#[derive(Debug, Default)]
+pub(crate) struct TemplateHashes {
+    /// Resource file(inner path of `resource_dir`) -> content hash
+    pub resources: BTreeMap<String, u64>,
+    /// `TemplateKey` -> input hash of all templates to be generated in this run.
+    pub templates: BTreeMap<String, u64>,
+    /// Subset of `templates` that were skipped because they are unchanged since the last run.
+    pub unchanged: BTreeMap<String, u64>,
+}
+
+/// Remove the templates(and their patches) whose inputs are unchanged since the last run and
+/// whose output hkx still exists.
+///
+/// - `fnis_keys`: templates patched by FNIS.
+pub(crate) fn retain_changed_templates(
+    prev: &Manifest,
+    config: &Config,
+    (global, fnis): (u64, u64),
+    fnis_keys: &HashSet<TemplateKey<'static>, rapidhash::fast::RandomState>,
+    owned_patches: &OwnedPatchMap,
+    owned_templates: &mut OwnedTemplateMap,
+    borrowed_patches: &BehaviorPatchesMap<'_>,
+) -> TemplateHashes {
+    // Group patch files by template key. (The path only, no need to parse patches.)
+    let mut patches_by_key: BTreeMap<&str, Vec<(&PathBuf, &(String, usize))>> = BTreeMap::new();
+    let keyed: Vec<_> = owned_patches
+        .par_iter()
+        .filter_map(|(path, value)| {
+            let (key, _) = owned_templates.get_key_value(&to_template_key(path)?)?;
+            Some((key, path, value))
+        })
+        .collect();
+    for (key, path, value) in keyed {
+        patches_by_key.entry(key.as_str()).or_default().push((path, value));
+    }
+
+    let hashes: Vec<_> = owned_templates
+        .par_iter()
+        .map(|(key, bytes)| {
+            let resource_hash = rapidhash_v3(bytes);
+
+            let mut hasher = InputHasher::new();
+            hasher.write_u64(global).write_u64(resource_hash);
+            if let Some(patches) = patches_by_key.get(key.as_str()) {
+                hasher.write_u64(patch_files_hash(patches.par_iter().copied()));
+            }
+            if fnis_keys.contains(key) {
+                hasher.write_u64(fnis);
+            }
+
+            (key.as_str().to_string(), resource_hash, hasher.finish())
+        })
+        .collect();
+
+    let mut template_hashes = TemplateHashes::default();
+    for (key, resource_hash, hash) in hashes {
+        if prev.is_template_fresh(&key, hash, &config.output_dir) {
+            template_hashes.unchanged.insert(key.clone(), hash);
+        }
+        template_hashes.resources.insert(key.clone(), resource_hash);
+        template_hashes.templates.insert(key, hash);
+    }
+
+    if !template_hashes.unchanged.is_empty() {
+        owned_templates.retain(|key, _| !template_hashes.unchanged.contains_key(key.as_str()));
+        borrowed_patches.0.retain(|key, _| !template_hashes.unchanged.contains_key(key.as_str()));
+
+        #[cfg(feature = "tracing")]
+        tracing::info!(
+            "Skipped {} unchanged templates: {:#?}",
+            template_hashes.unchanged.len(),
+            template_hashes.unchanged.keys()
+        );
+    }
+
+    template_hashes
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::{
+        MemoryPatchSource, behavior_gen, is_output_stale,
+        tests::fixture::{self, TEMPLATES},
+    };
+
+    #[test]
+    fn input_hasher_is_order_sensitive() {
+        let ab = InputHasher::new().write(b"a").write(b"b").finish();
+        let ba = InputHasher::new().write(b"b").write(b"a").finish();
+        assert_ne!(ab, ba);
+
+        // Each input is hashed separately, so the boundary matters.
+        let joined = InputHasher::new().write(b"ab").finish();
+        assert_ne!(ab, joined);
+        assert_eq!(ab, InputHasher::new().write(b"a").write(b"b").finish());
+    }
+
+    #[test]
+    fn global_hash_depends_on_options() {
+        let se = Config { output_target: OutPutTarget::SkyrimSe, ..Default::default() };
+        let le = Config { output_target: OutPutTarget::SkyrimLe, ..Default::default() };
+        let vr = Config { output_target: OutPutTarget::SkyrimVr, ..Default::default() };
+        assert_ne!(global_hash(&se), global_hash(&le));
+        assert_ne!(global_hash(&se), global_hash(&vr));
+
+        let hack =
+            Config { hack_options: Some(crate::HackOptions::enable_all()), ..Default::default() };
+        assert_ne!(global_hash(&se), global_hash(&hack));
+    }
+
+    #[test]
+    fn patch_files_hash_ignores_collection_order() {
+        let mut a = PatchFileMap::default();
+        a.insert("b.txt".into(), ("b".into(), 1));
+        a.insert("a.txt".into(), ("a".into(), 0));
+
+        let mut b = PatchFileMap::default();
+        b.insert("a.txt".into(), ("a".into(), 0));
+        b.insert("b.txt".into(), ("b".into(), 1));
+        assert_eq!(patch_files_hash(&a), patch_files_hash(&b));
+
+        // priority change
+        b.insert("b.txt".into(), ("b".into(), 2));
+        assert_ne!(patch_files_hash(&a), patch_files_hash(&b));
+    }
+
+    /// Written over the outputs to see which ones the next run regenerates.
+    const MARKER: &[u8] = b"not regenerated";
+
+    fn fixture_config(dir: &Path, source: MemoryPatchSource) -> Config {
+        Config {
+            resource_dir: dir.join("resource"),
+            output_dir: dir.join("output"),
+            patch_source: Some(Arc::new(source)),
+            ..Default::default()
+        }
+    }
+
+    fn mark_outputs(output_dir: &Path) {
+        for (_, inner_path) in TEMPLATES {
+            std::fs::write(hkx_output_path(output_dir, inner_path), MARKER).unwrap();
+        }
+    }
+
+    /// Whether each output of [`TEMPLATES`] was written after [`mark_outputs`].
+    fn regenerated(output_dir: &Path) -> Vec<bool> {
+        TEMPLATES
+            .iter()
+            .map(|(_, inner_path)| {
+                std::fs::read(hkx_output_path(output_dir, inner_path)).unwrap() != MARKER
+            })
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn second_run_skips_unchanged_templates() {
+        let dir = Path::new("../../dummy/manifest/unchanged");
+        let output_dir = dir.join("output");
+        let _ = std::fs::remove_dir_all(dir);
+        fixture::write_templates(&dir.join("resource"));
+        let mods = [("aaaa", "ModA", 0), ("bbbb", "ModB", 1)];
+
+        let (patches, source) = fixture::mods(&mods);
+        behavior_gen(patches, fixture_config(dir, source)).await.unwrap();
+        let manifest = Manifest::load(&output_dir).unwrap();
+        assert!(manifest.inputs.is_some());
+        assert_eq!(manifest.templates.len(), TEMPLATES.len());
+        mark_outputs(&output_dir);
+
+        let (patches, source) = fixture::mods(&mods);
+        assert!(!is_output_stale(&patches, &fixture_config(dir, source.clone())).await);
+        let profile = behavior_gen(patches, fixture_config(dir, source)).await.unwrap();
+        assert_eq!(regenerated(&output_dir), [false, false]);
+        assert!(profile.slowest_templates.is_empty());
+        assert_eq!(Manifest::load(&output_dir), Some(manifest));
+    }
+
+    #[tokio::test]
+    async fn changed_inputs_regenerate_templates() {
+        let dir = Path::new("../../dummy/manifest/changed");
+        let output_dir = dir.join("output");
+        let _ = std::fs::remove_dir_all(dir);
+        fixture::write_templates(&dir.join("resource"));
+        let mods = [("aaaa", "ModA", 0), ("bbbb", "ModB", 1)];
+
+        let (patches, source) = fixture::mods(&mods);
+        behavior_gen(patches, fixture_config(dir, source)).await.unwrap();
+
+        // Patch file content: only the template of the changed file.
+        mark_outputs(&output_dir);
+        let (patches, mut source) = fixture::mods(&mods);
+        let (template_name, _) = TEMPLATES[0];
+        source.insert(
+            fixture::patch_path("bbbb", template_name, "#0002"),
+            fixture::nemesis_patch("bbbb", "Changed"),
+        );
+        assert!(is_output_stale(&patches, &fixture_config(dir, source.clone())).await);
+        behavior_gen(patches, fixture_config(dir, source.clone())).await.unwrap();
+        assert_eq!(regenerated(&output_dir), [true, false]);
+
+        // Priority
+        mark_outputs(&output_dir);
+        let (mut patches, _) = fixture::mods(&mods);
+        patches.nemesis_entries.insert(fixture::mod_dir("aaaa"), 2);
+        behavior_gen(patches, fixture_config(dir, source.clone())).await.unwrap();
+        assert_eq!(regenerated(&output_dir), [true, true]);
+
+        // Option
+        mark_outputs(&output_dir);
+        let (mut patches, _) = fixture::mods(&mods);
+        patches.nemesis_entries.insert(fixture::mod_dir("aaaa"), 2);
+        let config =
+            Config { output_target: OutPutTarget::SkyrimLe, ..fixture_config(dir, source) };
+        behavior_gen(patches, config).await.unwrap();
+        assert_eq!(regenerated(&output_dir), [true, true]);
+    }
+}
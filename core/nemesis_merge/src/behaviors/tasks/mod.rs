@@ -2,5 +2,6 @@ pub(crate) mod adsf;
 pub(crate) mod asdsf;
 pub(crate) mod fnis;
 pub(crate) mod hkx;
+pub(crate) mod manifest;
 pub(crate) mod patches;
 pub(crate) mod templates;
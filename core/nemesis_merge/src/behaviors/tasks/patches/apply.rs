@@ -1,16 +1,22 @@
 //! Processes a list of Nemesis XML paths and generates JSON output in the specified directory.
-use std::path::Path;
+use std::{collections::HashSet, path::Path, time::Instant};
 
-use json_patch::{apply_one_field, apply_seq_by_priority};
+use json_patch::{
+    JsonPatchError, JsonPath, ValueWithPriority, apply_one_field, apply_seq_by_priority,
+    has_selectors, resolve_selectors,
+};
 use rayon::prelude::*;
 use simd_json::borrowed::Value;
 use snafu::ResultExt;
 
 use crate::{
     Config,
-    behaviors::tasks::{
-        patches::types::{BehaviorPatchesMap, HkxPatchMaps},
-        templates::{key::TemplateKey, types::BorrowedTemplateMap},
+    behaviors::{
+        profile::Profiler,
+        tasks::{
+            patches::types::{BehaviorPatchesMap, HkxPatchMaps},
+            templates::{key::TemplateKey, types::BorrowedTemplateMap},
+        },
     },
     config::{ReportType, StatusReportCounter},
     errors::{Error, PatchSnafu, Result},
@@ -28,6 +34,7 @@ pub(crate) fn apply_patches<'t, 'p: 't>(
     templates: &mut BorrowedTemplateMap<'t>,
     borrowed_patches: BehaviorPatchesMap<'p>,
     config: &Config,
+    profiler: &Profiler,
 ) -> Result<(), Vec<Error>> {
     let status_report = &config.status_report;
     // Optimization: If we don't use the progress bar, there is no need to calculate.
@@ -52,8 +59,13 @@ pub(crate) fn apply_patches<'t, 'p: 't>(
     let (results, updated_templates): (Vec<_>, Vec<_>) = working_set
         .into_par_iter()
         .map(|(key, patches, mut template_value)| {
+            if config.is_cancelled() {
+                return (vec![], (key, template_value));
+            }
+            let started = Instant::now();
             let patch_results =
                 apply_to_one_template(config, &key, &mut template_value, patches, &status_reporter);
+            profiler.record_template(key.as_str(), started.elapsed());
             (patch_results, (key, template_value))
         })
         .unzip();
@@ -89,25 +101,103 @@ fn apply_to_one_template<'a, 'b: 'a>(
 
     let mut results = Vec::with_capacity(patches_len);
 
+    // Apply in path order, not in the (random) hash order of `DashMap`, so that the output is reproducible.
+    let (one_patches, tests, losers) = one_patch_map.into_inner();
+    let mut one_patches: Vec<_> = one_patches.into_iter().collect();
+    one_patches.par_sort_unstable_by(|(a, _), (b, _)| a.cmp(b));
+    let mut tests: Vec<_> = tests.into_iter().collect();
+    tests.par_sort_unstable_by(|(a, _), (b, _)| a.cmp(b));
+    let mut seq_patches: Vec<_> = seq_patch_map.0.into_iter().collect();
+    seq_patches.par_sort_unstable_by(|(a, _), (b, _)| a.cmp(b));
+
+    // Tests are guards, so they are checked against the template before patching.
+    // If one fails, the other patches of the same priority to this template are skipped.
+    let mut failed_priorities = HashSet::new();
+    for (path, tests) in tests {
+        for test in tests {
+            let priority = test.priority;
+            if let Err(_err) = apply_one_field(template_value, path.clone(), test) {
+                #[cfg(feature = "tracing")]
+                tracing::info!(
+                    "[Test] {_err}, skipping the patches of priority {priority} to {}",
+                    key.as_str()
+                );
+                failed_priorities.insert(priority);
+            }
+            status_reporter.increment();
+        }
+    }
+
+    // The patches whose selectors match nothing yet(e.g. an event added by another mod) are retried after the seq patches.
+    let mut deferred = vec![];
+
     // NOTE: Why not use par_iter here?
     // Since the template change targets overlap, locking with Arc<Mutex<T>> will likely slow things down.
-    for (path, patch) in one_patch_map.into_inner() {
-        let result = apply_one_field(template_value, path, patch)
-            .with_context(|_| PatchSnafu { template_name: key.to_string() });
+    for (path, patch) in one_patches {
+        // If the winner is guarded out, fall back to the highest priority loser that is not.
+        let patch = if failed_priorities.contains(&patch.priority) {
+            losers.remove(&path).and_then(|(_, losers)| {
+                losers
+                    .into_iter()
+                    .filter(|loser| !failed_priorities.contains(&loser.priority))
+                    .max_by_key(|loser| loser.priority)
+            })
+        } else {
+            Some(patch)
+        };
+
+        if let Some(patch) = patch {
+            if has_selectors(&path) {
+                if apply_one_field(template_value, path.clone(), patch.clone()).is_err() {
+                    deferred.push((path, patch));
+                }
+            } else {
+                let result = apply_one_field(template_value, path, patch)
+                    .with_context(|_| PatchSnafu { template_name: key.to_string() });
+                results.push(result);
+            }
+        }
         status_reporter.increment();
-        results.push(result);
     }
 
-    for (path, patches) in seq_patch_map.0 {
-        let result = apply_seq_by_priority(key.as_str(), template_value, path, patches)
-            .with_context(|_| PatchSnafu { template_name: key.to_string() });
+    // In path order, the array(e.g. `eventNames`) is patched before the anchors into it(e.g. `eventNames/[?@=="Run"]+1`).
+    for (path, mut patches) in seq_patches {
+        patches.retain(|patch| !failed_priorities.contains(&patch.priority));
+        let result = resolve_seq_path(template_value, path, &mut patches)
+            .and_then(|path| apply_seq_by_priority(key.as_str(), template_value, path, patches));
+        results.push(result.with_context(|_| PatchSnafu { template_name: key.to_string() }));
         status_reporter.increment();
+    }
+
+    for (path, patch) in deferred {
+        let result = apply_one_field(template_value, path, patch)
+            .with_context(|_| PatchSnafu { template_name: key.to_string() });
         results.push(result);
     }
 
     results
 }
 
+/// Resolve the selectors that are kept as written, since they match no element of the template before patching.
+/// (See [`super::resolve::resolve_patch_selectors`])
+///
+/// All the patches have the same path, so that the resolved paths are the same.
+fn resolve_seq_path<'a>(
+    template_value: &Value<'_>,
+    path: JsonPath<'a>,
+    patches: &mut [ValueWithPriority<'_>],
+) -> Result<JsonPath<'a>, JsonPatchError> {
+    if !has_selectors(&path) {
+        return Ok(path);
+    }
+
+    let mut resolved = path.clone();
+    for patch in patches {
+        resolved = resolve_selectors(template_value, path.clone(), &mut patch.patch)?;
+    }
+    Ok(resolved)
+}
+
 /////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
 
 fn write_debug_json_patch(
@@ -133,3 +223,147 @@ fn write_debug_json_patch(
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use json_patch::{Action, JsonPatch, Op, json_path};
+    use simd_json::json_typed;
+
+    use super::*;
+
+    #[test]
+    fn failed_test_skips_same_priority_patches() {
+        let patch = |action, value, priority| ValueWithPriority {
+            patch: JsonPatch { action, value },
+            priority,
+        };
+        let data = json_path!["#0002", "hkbStringEventPayload", "data"];
+        let events = json_path!["#0003", "hkbBehaviorGraphStringData", "eventNames"];
+
+        let patches = HkxPatchMaps::default();
+        // Passed test: applied
+        let test = Action::Pure { op: Op::Test };
+        patches
+            .one
+            .insert(events.clone(), patch(test.clone(), json_typed!(borrowed, ["Original"]), 0));
+        patches
+            .seq
+            .insert(events.clone(), patch(Action::SeqPush, json_typed!(borrowed, ["Zero"]), 0));
+        // Failed test: skipped
+        patches.one.insert(data.clone(), patch(test, "Changed".into(), 1));
+        patches.one.insert(data, patch(Action::Pure { op: Op::Replace }, "First".into(), 1));
+        patches.seq.insert(events, patch(Action::SeqPush, json_typed!(borrowed, ["First"]), 1));
+
+        let mut template = json_typed!(borrowed, {
+            "#0002": { "hkbStringEventPayload": { "data": "Original" } },
+            "#0003": { "hkbBehaviorGraphStringData": { "eventNames": ["Original"] } },
+        });
+        let config = Config::default();
+        let status_reporter =
+            StatusReportCounter::new(&config.status_report, ReportType::ApplyingPatches, 0);
+        let key =
+            TemplateKey::new("meshes/actors/character/characters/defaultmale.bin".into()).unwrap();
+
+        let results =
+            apply_to_one_template(&config, &key, &mut template, patches, &status_reporter);
+        assert!(results.iter().all(Result::is_ok), "{results:?}");
+
+        let expected = json_typed!(borrowed, {
+            "#0002": { "hkbStringEventPayload": { "data": "Original" } },
+            "#0003": { "hkbBehaviorGraphStringData": { "eventNames": ["Original", "Zero"] } },
+        });
+        assert_eq!(template, expected);
+    }
+
+    #[test]
+    fn failed_test_falls_back_to_lower_priority_patch() {
+        let patch = |action, value, priority| ValueWithPriority {
+            patch: JsonPatch { action, value },
+            priority,
+        };
+        let data = json_path!["#0002", "hkbStringEventPayload", "data"];
+        let replace = Action::Pure { op: Op::Replace };
+
+        // mod A (priority 1): replaces unconditionally.
+        let patches = HkxPatchMaps::default();
+        patches.one.insert(data.clone(), patch(replace.clone(), "First".into(), 1));
+        // mod B (priority 2): guarded by a failing test, so mod A's value must be used.
+        patches.one.insert(data.clone(), patch(Action::Pure { op: Op::Test }, "Changed".into(), 2));
+        assert_eq!(
+            patches.one.insert(data, patch(replace, "Second".into(), 2)).map(|l| l.1),
+            Some(1)
+        );
+
+        let mut template = json_typed!(borrowed, {
+            "#0002": { "hkbStringEventPayload": { "data": "Original" } },
+        });
+        let config = Config::default();
+        let status_reporter =
+            StatusReportCounter::new(&config.status_report, ReportType::ApplyingPatches, 0);
+        let key =
+            TemplateKey::new("meshes/actors/character/characters/defaultmale.bin".into()).unwrap();
+
+        let results =
+            apply_to_one_template(&config, &key, &mut template, patches, &status_reporter);
+        assert!(results.iter().all(Result::is_ok), "{results:?}");
+
+        let expected = json_typed!(borrowed, {
+            "#0002": { "hkbStringEventPayload": { "data": "First" } },
+        });
+        assert_eq!(template, expected);
+    }
+
+    #[test]
+    fn resolve_selectors_of_added_elements_when_applied() {
+        use crate::behaviors::tasks::patches::{
+            resolve::resolve_patch_selectors, types::BehaviorPatchesMap,
+        };
+
+        let patch = |action, value, priority| ValueWithPriority {
+            patch: JsonPatch { action, value },
+            priority,
+        };
+        let events = json_path!["#0003", "hkbBehaviorGraphStringData", "eventNames"];
+        let jump =
+            json_path!["#0003", "hkbBehaviorGraphStringData", "eventNames", r#"[?@=="Jump"]"#];
+        let after_jump =
+            json_path!["#0003", "hkbBehaviorGraphStringData", "eventNames", r#"[?@=="Jump"]+1"#];
+
+        // `Jump` is added by another mod(e.g. FNIS), so it is not in the template.
+        let maps = HkxPatchMaps::default();
+        maps.seq.insert(events, patch(Action::SeqPush, json_typed!(borrowed, ["Jump"]), 0));
+        maps.seq.insert(
+            after_jump,
+            patch(Action::Seq { op: Op::Add, range: 0..0 }, json_typed!(borrowed, ["Land"]), 1),
+        );
+        maps.one.insert(jump, patch(Action::Pure { op: Op::Replace }, "Hop".into(), 1));
+
+        let key =
+            TemplateKey::new("meshes/actors/character/characters/defaultmale.bin".into()).unwrap();
+        let templates = BorrowedTemplateMap::default();
+        templates.insert(
+            key.clone(),
+            json_typed!(borrowed, {
+                "#0003": { "hkbBehaviorGraphStringData": { "eventNames": ["Walk"] } },
+            }),
+        );
+        let patches = BehaviorPatchesMap::default();
+        patches.0.insert(key.clone(), maps);
+        assert!(resolve_patch_selectors(&patches, &templates).is_empty());
+
+        let (_, patches) = patches.0.remove(&key).unwrap();
+        let (_, mut template) = templates.remove(&key).unwrap();
+        let config = Config::default();
+        let status_reporter =
+            StatusReportCounter::new(&config.status_report, ReportType::ApplyingPatches, 0);
+
+        let results =
+            apply_to_one_template(&config, &key, &mut template, patches, &status_reporter);
+        assert!(results.iter().all(Result::is_ok), "{results:?}");
+
+        let expected = json_typed!(borrowed, {
+            "#0003": { "hkbBehaviorGraphStringData": { "eventNames": ["Walk", "Hop", "Land"] } },
+        });
+        assert_eq!(template, expected);
+    }
+}
@@ -1,13 +1,18 @@
 use std::{
     borrow::Cow,
+    io,
     path::{Path, PathBuf},
+    sync::Arc,
+    time::Instant,
 };
 
 use json_patch::ValueWithPriority;
-use nemesis_xml::patch::parse_nemesis_patch;
+use nemesis_xml::{
+    hack::HackRules,
+    patch::{NemesisPatch, parse_nemesis_patch_recovering, parse_nemesis_patch_with_rules},
+};
 use rayon::prelude::*;
 use snafu::{OptionExt as _, ResultExt as _};
-use tokio::fs;
 
 use super::paths::{
     collect::{Category, collect_nemesis_paths},
@@ -17,17 +22,20 @@ use crate::{
     Config,
     behaviors::{
         priority_ids::{get_nemesis_id, types::PriorityMap},
+        profile::Profiler,
+        provenance::{PatchOrigin, fnis_sources},
+        stale_patches::PatchOriginal,
         tasks::{
             adsf::types::OwnedAdsfPatchMap,
             asdsf::types::OwnedAsdsfPatchMap,
             patches::types::{OwnedPatchMap, OwnedPatches, PatchCollection},
+            templates::key::TemplateKey,
         },
     },
-    config::{ReportType, StatusReportCounter},
+    config::{PatchSource, ReportType, StatusReportCounter},
     errors::{
         Error, FailedIoSnafu, FailedToCastNemesisPathToTemplateKeySnafu, NemesisXmlErrSnafu, Result,
     },
-    results::filter_results,
 };
 
 struct OwnedPath {
@@ -47,6 +55,7 @@ struct OwnedPath {
 pub(crate) async fn collect_owned_patches(
     nemesis_entries: &PriorityMap,
     config: &Config,
+    source: &Arc<dyn PatchSource>,
 ) -> OwnedPatches {
     fn get_priority_by_path_id(path: &Path, ids: &PriorityMap) -> Option<usize> {
         let id_str = get_nemesis_id(path.to_str()?).ok()?;
@@ -54,16 +63,23 @@ pub(crate) async fn collect_owned_patches(
     }
 
     let mut handles = tokio::task::JoinSet::new();
-    for (category, path) in nemesis_entries.keys().flat_map(collect_nemesis_paths) {
+    for (category, path) in
+        nemesis_entries.keys().flat_map(|dir| collect_nemesis_paths(&**source, dir))
+    {
         let priority = get_priority_by_path_id(&path, nemesis_entries).unwrap_or_else(|| {
             #[cfg(feature = "tracing")]
             tracing::warn!("Not found id from path: Path({})", path.display());
             usize::MAX // todo error handling
         });
 
-        handles.spawn(async move {
-            let content = fs::read_to_string(&path)
-                .await
+        let source = Arc::clone(source);
+        handles.spawn_blocking(move || {
+            let content = source
+                .read(&path)
+                .and_then(|bytes| {
+                    String::from_utf8(bytes)
+                        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+                })
                 .with_context(|_| FailedIoSnafu { path: path.clone() })?;
 
             Ok(OwnedPath { category, path, content, priority })
@@ -79,6 +95,10 @@ pub(crate) async fn collect_owned_patches(
         StatusReportCounter::new(&config.status_report, ReportType::ReadingPatches, handles.len());
 
     while let Some(result) = handles.join_next().await {
+        if config.is_cancelled() {
+            handles.abort_all();
+            break;
+        }
         reporter.increment();
 
         let result = match result {
@@ -107,90 +127,166 @@ pub(crate) async fn collect_owned_patches(
         }
     }
 
+    // The tasks finish in arbitrary order. Sort by path so that the merge order(and the output) is the same on every run.
+    owned_patches.sort_unstable_keys();
+    adsf_patches.0.sort_unstable_keys();
+    asdsf_patches.0.sort_unstable_keys();
+
     OwnedPatches { owned_patches, adsf_patches, asdsf_patches, errors }
 }
 
+/// Resolve the template key from a nemesis patch path without parsing the patch.
+///
+/// - e.g. `/some/path/to/Nemesis_Engine/mod/flinch/_1stperson/0_master/#0106.txt` -> `meshes/actors/character/_1stperson/behaviors/0_master.bin`
+pub(crate) fn to_template_key(path: &Path) -> Option<TemplateKey<'static>> {
+    parse_nemesis_path(path).ok()?.to_template_key()
+}
+
+/// Parse Nemesis patches and merge them into `fnis_patches` by priority.
+///
+/// - `track_sources`: If true, every parsed patch(including FNIS ones) is returned with its file.
+/// - `profiler`: Records the parse time of each patch file.
+///
+/// # Returns
+/// The merged patches(with the one field patches that lost to another priority, including the FNIS ones),
+/// the sources of the patches(empty if not `track_sources`), the `<!-- ORIGINAL -->` blocks(for the stale patch report)
+/// and errors.
 pub(crate) fn collect_borrowed_patches<'a>(
     owned_patches: &'a OwnedPatchMap,
     config: &Config,
     fnis_patches: PatchCollection<'a>,
-) -> (PatchCollection<'a>, Vec<Error>) {
+    track_sources: bool,
+    profiler: &Profiler,
+) -> (PatchCollection<'a>, Vec<PatchOrigin<'a>>, Vec<PatchOriginal<'a>>, Vec<Error>) {
     let PatchCollection {
         borrowed_patches: raw_borrowed_patches,
         behavior_graph_data_map: variable_class_map,
+        mut one_losers,
     } = fnis_patches;
 
+    let mut sources = if track_sources { fnis_sources(&raw_borrowed_patches) } else { Vec::new() };
+
+    let mut errors = vec![];
+    let hack_rules = config.hack_rules().unwrap_or_else(|err| {
+        // Keep going with the built-in rules, so that one broken rule does not fail every patch.
+        errors.push(err);
+        config.hack_options.map(|hack| HackRules::builtin(hack.into())).unwrap_or_default()
+    });
+
     let reporter = StatusReportCounter::new(
         &config.status_report,
         ReportType::ParsingPatches,
         owned_patches.len(),
     );
 
-    let results: Vec<Result<()>> = owned_patches
+    // Parse in parallel, but merge in the order of `owned_patches`(sorted by path).
+    // If merged as soon as parsed, the winner among equal priority one field patches and the order of
+    // seq patches would depend on thread scheduling, and the output would differ between runs.
+    let results: Vec<Result<_>> = owned_patches
         .par_iter()
         .map(|(path, (xml, priority))| {
+            config.check_cancelled()?;
             reporter.increment();
-            // Since we could not make a destructing assignment, we have to write it this way.
-            let priority = *priority;
+            let started = Instant::now();
 
-            let (json_patches, parsed_var_index) =
-                parse_nemesis_patch(xml, config.hack_options.map(Into::into))
+            let NemesisPatch { patches: json_patches, id_index: parsed_var_index, originals } =
+                parse_nemesis_patch_with_rules(xml, &hack_rules)
                     .with_context(|_| NemesisXmlErrSnafu { path })?;
 
-            let key = {
-                let nemesis_path = parse_nemesis_path(path)?;
-
-                let key = nemesis_path
-                    .to_template_key()
-                    .with_context(|| FailedToCastNemesisPathToTemplateKeySnafu { path })?;
-
-                // Store variable class for nemesis variable to replace
-                if let Some(master_behavior_graph_index) = nemesis_path.get_variable_index() {
-                    variable_class_map
-                        .0
-                        .entry(key.clone())
-                        .or_insert(Cow::Borrowed(master_behavior_graph_index));
-                } else if let Some(parsed_var_index) = parsed_var_index {
-                    variable_class_map
-                        .0
-                        .entry(key.clone())
-                        .or_insert(Cow::Owned(parsed_var_index.to_string()));
-                }
+            let nemesis_path = parse_nemesis_path(path)?;
+            let key = nemesis_path
+                .to_template_key()
+                .with_context(|| FailedToCastNemesisPathToTemplateKeySnafu { path })?;
 
-                key
+            // Variable class for nemesis variable to replace
+            let var_index = match nemesis_path.get_variable_index() {
+                Some(master_behavior_graph_index) => {
+                    Some(Cow::Borrowed(master_behavior_graph_index))
+                }
+                None => parsed_var_index.map(|index| Cow::Owned(index.to_string())),
             };
 
-            json_patches.into_par_iter().for_each(|(json_path, value)| {
-                // FIXME: I think that if we lengthen the lock period, we can suppress the race condition, but that will slow down the process.
-                let entry = raw_borrowed_patches.0.entry(key.clone()).or_default();
+            let mut json_patches: Vec<_> = json_patches.into_iter().collect();
+            json_patches.sort_unstable_by(|(a, _), (b, _)| a.cmp(b));
 
-                // Overwrite to match patch structure
-                match &value.action {
-                    json_patch::Action::Pure { .. } => {
-                        let value = ValueWithPriority::new(value, priority);
-                        entry.value().one.insert(json_path, value); // Pure: no add and remove because of single value
-                    }
-                    json_patch::Action::Seq { .. } | json_patch::Action::SeqPush => {
-                        let value = ValueWithPriority::new(value, priority);
-                        entry.value().seq.insert(json_path, value);
-                    }
-                }
-            });
-
-            Ok(())
+            profiler.record_patch_file(path, started.elapsed());
+            Ok((path, *priority, key, var_index, json_patches, originals))
         })
         .collect();
 
-    let errors = match filter_results(results) {
-        Ok(()) => vec![],
-        Err(errors) => errors,
-    };
+    let mut patch_originals = vec![];
+    for result in results {
+        let (path, priority, key, var_index, json_patches, originals) = match result {
+            Ok(parsed) => parsed,
+            Err(Error::NemesisXmlErr { path, source }) => {
+                // Report every error in the file at once, not only the first one.
+                let diagnostics = owned_patches
+                    .get(&path)
+                    .map(|(xml, _)| parse_nemesis_patch_recovering(xml, &hack_rules).1)
+                    .unwrap_or_default();
+                if diagnostics.is_empty() {
+                    errors.push(Error::NemesisXmlErr { path, source });
+                } else {
+                    errors.extend(diagnostics.into_iter().map(|diagnostic| Error::NemesisXmlErr {
+                        path: path.clone(),
+                        source: diagnostic.error,
+                    }));
+                }
+                continue;
+            }
+            Err(err) => {
+                errors.push(err);
+                continue;
+            }
+        };
+
+        if let Some(var_index) = var_index {
+            variable_class_map.0.entry(key.clone()).or_insert(var_index);
+        }
+        patch_originals.extend(originals.into_iter().map(|original| PatchOriginal {
+            template: key.clone(),
+            priority,
+            file: path.as_path(),
+            original,
+        }));
+
+        let entry = raw_borrowed_patches.0.entry(key.clone()).or_default();
+        for (json_path, value) in json_patches {
+            if track_sources {
+                sources.push(PatchOrigin {
+                    template: key.clone(),
+                    path: json_path.clone(),
+                    action: value.action.clone(),
+                    priority,
+                    file: Some(path.as_path()),
+                });
+            }
+
+            // Overwrite to match patch structure
+            match &value.action {
+                json_patch::Action::Pure { .. } => {
+                    // Pure: no add and remove because of single value
+                    let value = ValueWithPriority::new(value, priority);
+                    if let Some((json_path, loser)) = entry.value().one.insert(json_path, value) {
+                        one_losers.push((key.clone(), json_path, loser));
+                    }
+                }
+                json_patch::Action::Seq { .. } | json_patch::Action::SeqPush => {
+                    let value = ValueWithPriority::new(value, priority);
+                    entry.value().seq.insert(json_path, value);
+                }
+            }
+        }
+    }
 
     (
         PatchCollection {
             borrowed_patches: raw_borrowed_patches,
             behavior_graph_data_map: variable_class_map,
+            one_losers,
         },
+        sources,
+        patch_originals,
         errors,
     )
 }
@@ -1,4 +1,5 @@
 pub(crate) mod apply;
 pub(crate) mod collect;
 mod paths;
+pub(crate) mod resolve;
 pub(crate) mod types;
@@ -1,6 +1,6 @@
 use std::path::{Path, PathBuf};
 
-use rayon::prelude::*;
+use crate::config::PatchSource;
 
 pub(crate) enum Category {
     Nemesis,
@@ -10,17 +10,16 @@ pub(crate) enum Category {
 
 /// Collects all relevant file paths within the given ID directory.
 ///
-/// # Errors
-/// Returns an error if path traversal fails.
-pub(crate) fn collect_nemesis_paths(path: impl AsRef<Path>) -> Vec<(Category, PathBuf)> {
-    jwalk::WalkDir::new(path)
+/// Unreadable directories are skipped.
+pub(crate) fn collect_nemesis_paths(
+    source: &dyn PatchSource,
+    path: impl AsRef<Path>,
+) -> Vec<(Category, PathBuf)> {
+    source
+        .walk_files(path.as_ref())
         .into_iter()
-        .par_bridge()
-        .filter_map(|result| {
-            let txt_path = {
-                let path = result.ok()?.path();
-                is_txt_file(&path).then_some(path)?
-            };
+        .filter_map(|path| {
+            let txt_path = is_txt_file(&path).then_some(path)?;
 
             Some(match txt_path {
                 _ if is_nemesis_file(&txt_path) => (Category::Nemesis, txt_path),
@@ -34,10 +33,7 @@ pub(crate) fn collect_nemesis_paths(path: impl AsRef<Path>) -> Vec<(Category, Pa
 
 #[inline]
 fn is_txt_file(path: &Path) -> bool {
-    let is_txt = path.extension().is_some_and(|path| path.eq_ignore_ascii_case("txt"));
-    let is_file = path.is_file();
-
-    is_txt && is_file
+    path.extension().is_some_and(|path| path.eq_ignore_ascii_case("txt"))
 }
 
 /// Check if the file name starts with a `#` and is a file.
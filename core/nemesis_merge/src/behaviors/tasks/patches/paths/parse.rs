@@ -19,6 +19,14 @@
 //! - There is no concept of template name or first-person flag.
 //! - The patch applies freely to arbitrary hkx paths.
 //!
+//! ### Pandora
+//! ```text
+//! Pandora_Engine/mod/<mod_code>/(optional _1stperson/)<template_name>/#<file>.txt
+//! Pandora_Engine/mod/<mod_code>/meshes/<any path>/#<file>.txt
+//! ```
+//! - Pandora Behavior Engine accepts both of the layouts above in the same folder.
+//!   A path is `EngineExt` if it starts with `meshes/`, otherwise `Normal`.
+//!
 //! The parser guarantees which data is available by encoding the difference
 //! in the `NemesisPath` enum variants.
 
@@ -59,12 +67,28 @@ fn path_separator(input: &mut &str) -> ModalResult<()> {
     Ok(())
 }
 
-/// Parse `Nemesis_Engine/mod/<mod_code>/(optional _1stperson/)<template_name>/#<file>.txt`
+/// `Nemesis_Engine` or `Pandora_Engine`
+fn normal_engine<'a>(input: &mut &'a str) -> ModalResult<&'a str> {
+    alt((Caseless("Nemesis_Engine"), Caseless("Pandora_Engine")))
+        .context(StrContext::Expected(StrContextValue::StringLiteral("Nemesis_Engine")))
+        .context(StrContext::Expected(StrContextValue::StringLiteral("Pandora_Engine")))
+        .parse_next(input)
+}
+
+/// `Nemesis_EngineExt` or `Pandora_Engine`
+fn ext_engine<'a>(input: &mut &'a str) -> ModalResult<&'a str> {
+    alt((Caseless("Nemesis_EngineExt"), Caseless("Pandora_Engine")))
+        .context(StrContext::Expected(StrContextValue::StringLiteral("Nemesis_EngineExt")))
+        .context(StrContext::Expected(StrContextValue::StringLiteral("Pandora_Engine")))
+        .parse_next(input)
+}
+
+/// Parse `(Nemesis|Pandora)_Engine/mod/<mod_code>/(optional _1stperson/)<template_name>/#<file>.txt`
 fn parse_normal<'a>(input: &mut &'a str) -> ModalResult<NemesisPath<'a>> {
     seq! {
         NemesisPath::Normal {
-            _: take_until_ext(0.., Caseless("Nemesis_Engine")),
-            _: Caseless("Nemesis_Engine").context(StrContext::Expected(StrContextValue::StringLiteral("Nemesis_Engine"))),
+            _: take_until_ext(0.., normal_engine),
+            _: normal_engine,
             _: path_separator,
             _: Caseless("mod").context(StrContext::Expected(StrContextValue::StringLiteral("mod"))),
             _: path_separator,
@@ -80,12 +104,12 @@ fn parse_normal<'a>(input: &mut &'a str) -> ModalResult<NemesisPath<'a>> {
     .parse_next(input)
 }
 
-/// `Nemesis_EngineExt/mod/<mod_code>/meshes/<any path>/#<file>.txt`
+/// `(Nemesis_EngineExt|Pandora_Engine)/mod/<mod_code>/meshes/<any path>/#<file>.txt`
 fn parse_ext<'a>(input: &mut &'a str) -> ModalResult<NemesisPath<'a>> {
     seq! {
         NemesisPath::EngineExt {
-            _: take_until_ext(0.., Caseless("Nemesis_EngineExt")),
-            _: Caseless("Nemesis_EngineExt").context(StrContext::Expected(StrContextValue::StringLiteral("Nemesis_EngineExt"))),
+            _: take_until_ext(0.., ext_engine),
+            _: ext_engine,
             _: path_separator,
             _: Caseless("mod").context(StrContext::Expected(StrContextValue::StringLiteral("mod"))),
             _: path_separator,
@@ -141,6 +165,18 @@ mod tests {
         );
     }
 
+    #[test]
+    fn pandora() {
+        let p = parse("Pandora_Engine/mod/test/_1stperson/0_master/#0106.txt");
+        assert_eq!(p, NemesisPath::Normal { template_name: "0_master", is_1st_person: true });
+
+        let p = parse("Pandora_Engine/mod/test/meshes/actors/troll/characters/troll.bin/#0029.txt");
+        assert_eq!(
+            p,
+            NemesisPath::EngineExt { meshes_path: "meshes/actors/troll/characters/troll.bin" }
+        );
+    }
+
     #[test]
     fn invalid_missing_engine() {
         let p = Path::new("Engine/mod/a/0_master");
@@ -0,0 +1,197 @@
+//! Resolve the content-addressed selectors(e.g. `[?name=="Run"]`) of the patches against the templates.
+use json_patch::{JsonPatch, JsonPath, has_selectors, resolve_selectors};
+use rayon::prelude::*;
+use simd_json::BorrowedValue;
+
+use crate::{
+    Config,
+    behaviors::{
+        conflicts::OneFieldLoser,
+        provenance::PatchOrigin,
+        tasks::{
+            patches::types::{BehaviorPatchesMap, HkxPatchMaps},
+            templates::{
+                collect::owned,
+                key::TemplateKey,
+                types::{BorrowedTemplateMap, OwnedTemplateMap},
+            },
+        },
+    },
+};
+
+/// Read the templates whose patches have selectors, to resolve them without patching. (e.g. dry-run reports)
+///
+/// The templates that cannot be read are skipped, since the caller reports them anyway.
+pub(crate) fn read_selector_templates(
+    borrowed_patches: &BehaviorPatchesMap<'_>,
+    config: &Config,
+) -> OwnedTemplateMap {
+    let template_names = borrowed_patches
+        .0
+        .par_iter()
+        .filter(|entry| entry.value().has_selectors())
+        .map(|entry| entry.key().clone())
+        .collect();
+    owned::collect_templates(config, template_names).0
+}
+
+/// Resolve the selectors of the patches against the templates before they are patched.
+///
+/// The patches are collected before the templates are loaded, so the one field patches are keyed by the selectors
+/// as written. This re-keys them by the resolved paths and resolves the conflicts by priority again, so that
+/// the patches to the same element by different selectors conflict.
+///
+/// The templates not loaded(e.g. unchanged since the last run) are kept as is.
+///
+/// A selector that matches no element of the template(e.g. an event added by another mod) is kept as written,
+/// and is resolved against the patched template when it is applied. (See `apply_to_one_template`)
+///
+/// # Returns
+/// The discarded one field patches.
+pub(crate) fn resolve_patch_selectors<'a>(
+    borrowed_patches: &BehaviorPatchesMap<'a>,
+    templates: &BorrowedTemplateMap<'_>,
+) -> Vec<OneFieldLoser<'a>> {
+    borrowed_patches
+        .0
+        .par_iter_mut()
+        .filter_map(|mut entry| {
+            if !entry.value().has_selectors() {
+                return None;
+            }
+            let template = templates.get(entry.key())?;
+
+            let key = entry.key().clone();
+            let patches = core::mem::take(entry.value_mut());
+            let (resolved, losers) = resolve_one_template(&key, template.value(), patches);
+            *entry.value_mut() = resolved;
+            Some(losers)
+        })
+        .flatten()
+        .collect()
+}
+
+/// Resolve the selectors of the recorded patches the same way as [`resolve_patch_selectors`],
+/// so that they can be looked up in the resolved patches.
+pub(crate) fn resolve_source_selectors(
+    sources: &mut [PatchOrigin<'_>],
+    templates: &BorrowedTemplateMap<'_>,
+) {
+    sources.par_iter_mut().filter(|source| has_selectors(&source.path)).for_each(|source| {
+        let Some(template) = templates.get(&source.template) else {
+            return;
+        };
+        let mut patch = JsonPatch { action: source.action.clone(), value: Default::default() };
+        if let Ok(path) = resolve_selectors(template.value(), source.path.clone(), &mut patch) {
+            source.path = path;
+            source.action = patch.action;
+        }
+    });
+}
+
+fn resolve_one_template<'a>(
+    key: &TemplateKey<'static>,
+    template: &BorrowedValue<'_>,
+    patches: HkxPatchMaps<'a>,
+) -> (HkxPatchMaps<'a>, Vec<OneFieldLoser<'a>>) {
+    let HkxPatchMaps { one, seq } = patches;
+    let resolved = HkxPatchMaps::default();
+    let mut one_losers = vec![];
+
+    let (one_patches, tests, losers) = one.into_inner();
+    let lists = tests
+        .into_iter()
+        .chain(losers)
+        .flat_map(|(path, list)| list.into_iter().map(move |value| (path.clone(), value)));
+    for (path, mut value) in one_patches.into_iter().chain(lists) {
+        let path = resolve_or_keep(key, template, path, &mut value.patch);
+        if let Some((path, loser)) = resolved.one.insert(path, value) {
+            one_losers.push((key.clone(), path, loser));
+        }
+    }
+
+    for (path, patches) in seq.0 {
+        for mut value in patches {
+            let path = resolve_or_keep(key, template, path.clone(), &mut value.patch);
+            resolved.seq.insert(path, value);
+        }
+    }
+
+    (resolved, one_losers)
+}
+
+/// Resolve the selectors, or keep the path as written if they match no element of the template.
+fn resolve_or_keep<'a>(
+    _key: &TemplateKey<'static>,
+    template: &BorrowedValue<'_>,
+    path: JsonPath<'a>,
+    patch: &mut JsonPatch<'_>,
+) -> JsonPath<'a> {
+    match resolve_selectors(template, path.clone(), patch) {
+        Ok(path) => path,
+        Err(_err) => {
+            #[cfg(feature = "tracing")]
+            tracing::debug!("[{}] {_err}, resolving it when applied", _key.as_str());
+            path
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use json_patch::{Action, JsonPatch, Op, ValueWithPriority, json_path};
+    use simd_json::json_typed;
+
+    use super::*;
+
+    #[test]
+    fn selectors_conflict_with_the_resolved_index() {
+        let key =
+            TemplateKey::new("meshes/actors/character/characters/defaultmale.bin".into()).unwrap();
+        let templates = BorrowedTemplateMap::default();
+        templates.insert(
+            key.clone(),
+            json_typed!(borrowed, {
+                "#0003": { "hkbBehaviorGraphStringData": { "eventNames": ["Walk", "Run"] } }
+            }),
+        );
+
+        let replace = |value, priority| {
+            ValueWithPriority::new(
+                JsonPatch { action: Action::Pure { op: Op::Replace }, value },
+                priority,
+            )
+        };
+        let events = json_path!["#0003", "hkbBehaviorGraphStringData", "eventNames"];
+        let by_index = json_path!["#0003", "hkbBehaviorGraphStringData", "eventNames", "[1]"];
+        let by_name =
+            json_path!["#0003", "hkbBehaviorGraphStringData", "eventNames", r#"[?@=="Run"]"#];
+        let after_walk =
+            json_path!["#0003", "hkbBehaviorGraphStringData", "eventNames", r#"[?@=="Walk"]+1"#];
+
+        let maps = HkxPatchMaps::default();
+        maps.one.insert(by_index.clone(), replace("A".into(), 1));
+        maps.one.insert(by_name, replace("B".into(), 2));
+        maps.seq.insert(
+            after_walk,
+            ValueWithPriority::new(
+                JsonPatch {
+                    action: Action::Seq { op: Op::Add, range: 0..0 },
+                    value: json_typed!(borrowed, ["Jog"]),
+                },
+                1,
+            ),
+        );
+        let patches = BehaviorPatchesMap::default();
+        patches.0.insert(key.clone(), maps);
+
+        let losers = resolve_patch_selectors(&patches, &templates);
+        assert_eq!(losers, vec![(key.clone(), by_index.clone(), 1)]);
+
+        let maps = patches.0.get(&key).unwrap();
+        assert_eq!(maps.one.len(), 1);
+        assert_eq!(maps.one.priority_of(&by_index), Some(2));
+        let seq = maps.seq.0.get(&events).unwrap();
+        assert_eq!(seq[0].patch.action, Action::Seq { op: Op::Add, range: 1..1 });
+    }
+}
@@ -6,8 +6,12 @@ use dashmap::DashMap;
 use indexmap::IndexMap;
 
 pub(crate) use self::patch_map::HkxPatchMaps;
-use crate::behaviors::tasks::{
-    adsf::types::OwnedAdsfPatchMap, asdsf::types::OwnedAsdsfPatchMap, templates::key::TemplateKey,
+use crate::behaviors::{
+    conflicts::OneFieldLoser,
+    tasks::{
+        adsf::types::OwnedAdsfPatchMap, asdsf::types::OwnedAsdsfPatchMap,
+        templates::key::TemplateKey,
+    },
 };
 
 pub(crate) struct OwnedPatches {
@@ -43,6 +47,8 @@ pub(crate) struct PatchCollection<'a> {
     /// Map showing which index of each template contains hkbBehaviorGraphData
     /// Used to replace Nemesis variables such as `$variableID[]$` or `$eventID[]$`.
     pub behavior_graph_data_map: BehaviorGraphDataMap<'a>,
+    /// One field patches that lost to another priority on the same JSON path. (for the conflict report)
+    pub one_losers: Vec<OneFieldLoser<'a>>,
 }
 /// A patch containing references to parsed strings.
 ///
@@ -81,17 +87,24 @@ impl<'a> BehaviorPatchesMap<'a> {
             .sum()
     }
 
-    pub(crate) fn merge(&self, other: Self) {
+    /// # Returns
+    /// The one field patches discarded by another priority.
+    pub(crate) fn merge(&self, other: Self) -> Vec<OneFieldLoser<'a>> {
+        let mut one_losers = vec![];
         for (key, other_maps) in other.0 {
             match self.0.entry(key) {
                 dashmap::Entry::Vacant(v) => {
                     v.insert(other_maps);
                 }
                 dashmap::Entry::Occupied(mut occ) => {
-                    occ.get_mut().merge(other_maps);
+                    let losers = occ.get_mut().merge(other_maps);
+                    one_losers.extend(
+                        losers.into_iter().map(|(path, loser)| (occ.key().clone(), path, loser)),
+                    );
                 }
             }
         }
+        one_losers
     }
 }
 
@@ -3,6 +3,7 @@
 //! This module provides two map structures for managing JSON patches:
 //!
 //! - [`OnePatchMap`]: Maintains a mapping from JSON paths to a **single** value with a priority.
+//!   Test patches are kept beside them, since they guard the other patches instead of writing a value.
 //! - [`SeqPatchMap`]: Maintains a mapping from JSON paths to a **list** of values, supporting parallel insertion and extension.
 //!
 //! Both maps utilize `DashMap` for concurrent access and integrate with the `json_patch` crate.
@@ -10,7 +11,7 @@
 //! These are ideal for use-cases where patches must be merged, prioritized, or combined from multiple threads.
 
 use dashmap::DashMap;
-use json_patch::{JsonPath, ValueWithPriority};
+use json_patch::{Action, JsonPath, Op, ValueWithPriority, has_selectors};
 use rayon::prelude::*;
 
 /// A combined borrowed structure that holds both [`OnePatchMap`] and [`SeqPatchMap`].
@@ -33,10 +34,19 @@ impl<'a> HkxPatchMaps<'a> {
         self.one.len() + self.seq.0.len()
     }
 
+    /// # Returns
+    /// The paths and the priorities of the discarded one field patches. (See [`OnePatchMap::merge`])
     #[inline]
-    pub(crate) fn merge(&self, other: Self) {
-        self.one.merge(other.one);
+    pub(crate) fn merge(&self, other: Self) -> Vec<(JsonPath<'a>, usize)> {
         self.seq.merge(other.seq);
+        self.one.merge(other.one)
+    }
+
+    /// Do any paths have content-addressed selectors? (e.g. `[?name=="Run"]`)
+    pub(crate) fn has_selectors(&self) -> bool {
+        self.one.patches.iter().any(|entry| has_selectors(entry.key()))
+            || self.one.tests.iter().any(|entry| has_selectors(entry.key()))
+            || self.seq.0.iter().any(|entry| has_selectors(entry.key()))
     }
 }
 
@@ -57,8 +67,25 @@ pub(crate) struct OnePatchMap<'a> {
     /// If multiple patches target the same path, only the patch with the
     /// highest priority is retained.
     patches: DashMap<JsonPath<'a>, ValueWithPriority<'a>, rapidhash::fast::RandomState>,
+
+    /// Test patches for each JSON path.
+    ///
+    /// A test does not conflict with the patch of the same path. If it fails, the patches of its priority are skipped.
+    tests: OnePatchListMap<'a>,
+
+    /// Patches discarded by a higher priority patch of the same path.
+    ///
+    /// If the test of the winner fails, the path falls back to the highest priority one of them.
+    losers: OnePatchListMap<'a>,
 }
 
+/// Patch lists for each JSON path. (tests and losers, see [`OnePatchMap::into_inner`])
+pub(crate) type OnePatchListMap<'a> =
+    DashMap<JsonPath<'a>, Vec<ValueWithPriority<'a>>, rapidhash::fast::RandomState>;
+
+/// Key of the test patches in the serialized [`OnePatchMap`]. (Paths start with `#`, so this never collides.)
+const TESTS_KEY: &str = "$tests";
+
 impl<'a> OnePatchMap<'a> {
     /// Inserts a patch into the map.
     ///
@@ -68,40 +95,66 @@ impl<'a> OnePatchMap<'a> {
     /// treated as unique per object id. When multiple class-add patches target
     /// the same object id, only the highest-priority patch is retained.
     ///
+    /// Test patches are kept next to the patch of the path, so they never conflict.
+    ///
     /// This method is safe to call concurrently.
-    pub(crate) fn insert(&self, key: JsonPath<'a>, new_value: ValueWithPriority<'a>) {
+    ///
+    /// # Returns
+    /// The path and the priority of the discarded patch if the path already exists with another priority.
+    pub(crate) fn insert(
+        &self,
+        key: JsonPath<'a>,
+        new_value: ValueWithPriority<'a>,
+    ) -> Option<(JsonPath<'a>, usize)> {
+        if matches!(new_value.patch.action, Action::Pure { op: Op::Test }) {
+            self.tests.entry(key).or_default().push(new_value);
+            return None;
+        }
+
         // Same-path conflict.
         if let Some(mut existing) = self.patches.get_mut(&key) {
-            if new_value.priority > existing.priority {
-                tracing::info!(
-                    "Conflict Path {key:?}: priority {} -> {} (overwritten)",
-                    new_value.priority,
-                    existing.priority,
-                );
-
-                *existing = new_value;
-            }
-
-            return;
+            return match new_value.priority.cmp(&existing.priority) {
+                core::cmp::Ordering::Greater => {
+                    tracing::info!(
+                        "Conflict Path {key:?}: priority {} -> {} (overwritten)",
+                        new_value.priority,
+                        existing.priority,
+                    );
+
+                    let loser = core::mem::replace(&mut *existing, new_value);
+                    let priority = loser.priority;
+                    self.losers.entry(key.clone()).or_default().push(loser);
+                    Some((key, priority))
+                }
+                core::cmp::Ordering::Less => {
+                    let priority = new_value.priority;
+                    self.losers.entry(key.clone()).or_default().push(new_value);
+                    Some((key, priority))
+                }
+                core::cmp::Ordering::Equal => None,
+            };
         }
         self.patches.insert(key, new_value);
+        None
+    }
+
+    /// Returns the priority of the patch currently selected for `key`.
+    pub(crate) fn priority_of(&self, key: &JsonPath<'a>) -> Option<usize> {
+        self.patches.get(key).map(|value| value.priority)
     }
 
     /// Merges another `OnePatchMap` into this one by comparing priorities and keeping the highest.
-    pub(crate) fn merge(&self, other: Self) {
-        for (path, new_val) in other.patches {
-            match self.patches.entry(path) {
-                dashmap::Entry::Occupied(mut occ) => {
-                    let existing = occ.get_mut();
-                    if new_val.priority > existing.priority {
-                        *existing = new_val;
-                    }
-                }
-                dashmap::Entry::Vacant(v) => {
-                    v.insert(new_val);
-                }
-            }
+    ///
+    /// # Returns
+    /// The paths and the priorities of the discarded patches. (Same as [`Self::insert`])
+    pub(crate) fn merge(&self, other: Self) -> Vec<(JsonPath<'a>, usize)> {
+        for (path, tests) in other.tests {
+            self.tests.entry(path).or_default().extend(tests);
         }
+        for (path, losers) in other.losers {
+            self.losers.entry(path).or_default().extend(losers);
+        }
+        other.patches.into_iter().filter_map(|(path, value)| self.insert(path, value)).collect()
     }
 
     /// Inserts patches in parallel without conflict resolution.
@@ -125,16 +178,29 @@ impl<'a> OnePatchMap<'a> {
         self.patches.par_extend(patches);
     }
 
-    /// Returns the number of patches stored in this map.
+    /// Returns the number of patches stored in this map. (including tests)
     pub(crate) fn len(&self) -> usize {
-        self.patches.len()
+        self.patches.len() + self.tests.iter().map(|tests| tests.value().len()).sum::<usize>()
     }
 
-    /// Consumes this map and returns the underlying `DashMap` of patches.
+    /// Iterates over the patches selected for each path.
+    pub(crate) fn iter(
+        &self,
+    ) -> dashmap::iter::Iter<'_, JsonPath<'a>, ValueWithPriority<'a>, rapidhash::fast::RandomState>
+    {
+        self.patches.iter()
+    }
+
+    /// Consumes this map and returns the underlying `DashMap` of patches, the tests and the losers.
+    #[allow(clippy::type_complexity)]
     pub(crate) fn into_inner(
         self,
-    ) -> DashMap<JsonPath<'a>, ValueWithPriority<'a>, rapidhash::fast::RandomState> {
-        self.patches
+    ) -> (
+        DashMap<JsonPath<'a>, ValueWithPriority<'a>, rapidhash::fast::RandomState>,
+        OnePatchListMap<'a>,
+        OnePatchListMap<'a>,
+    ) {
+        (self.patches, self.tests, self.losers)
     }
 }
 
@@ -194,11 +260,21 @@ impl<'a> serde::Serialize for OnePatchMap<'a> {
     {
         use serde::ser::SerializeMap;
 
-        let mut map = serializer.serialize_map(Some(self.patches.len()))?;
+        let has_tests = !self.tests.is_empty();
+        let mut map =
+            serializer.serialize_map(Some(self.patches.len() + usize::from(has_tests)))?;
         for item in self.patches.iter() {
             let joined = item.key().join("/"); // JsonPath -> "a/b/c"
             map.serialize_entry(&joined, item.value())?;
         }
+        if has_tests {
+            let tests: std::collections::HashMap<_, _> = self
+                .tests
+                .iter()
+                .map(|item| (item.key().join("/"), item.value().clone()))
+                .collect();
+            map.serialize_entry(TESTS_KEY, &tests)?;
+        }
         map.end()
     }
 }
@@ -223,15 +299,27 @@ impl<'de: 'a, 'a> serde::Deserialize<'de> for OnePatchMap<'a> {
             where
                 M: serde::de::MapAccess<'de>,
             {
+                let to_json_path = |key: String| {
+                    key.split('/').map(|s| std::borrow::Cow::Owned(s.to_string())).collect()
+                };
+
                 let patches = DashMap::<_, _, rapidhash::fast::RandomState>::default();
-                while let Some((key, value)) =
-                    access.next_entry::<String, ValueWithPriority<'de>>()?
-                {
-                    let json_path =
-                        key.split('/').map(|s| std::borrow::Cow::Owned(s.to_string())).collect();
-                    patches.insert(json_path, value);
+                let mut tests = OnePatchListMap::default();
+                while let Some(key) = access.next_key::<String>()? {
+                    if key == TESTS_KEY {
+                        let values = access.next_value::<std::collections::HashMap<
+                            String,
+                            Vec<ValueWithPriority<'de>>,
+                        >>()?;
+                        tests.extend(
+                            values.into_iter().map(|(key, tests)| (to_json_path(key), tests)),
+                        );
+                    } else {
+                        let value = access.next_value::<ValueWithPriority<'de>>()?;
+                        patches.insert(to_json_path(key), value);
+                    }
                 }
-                Ok(OnePatchMap { patches })
+                Ok(OnePatchMap { patches, tests, losers: OnePatchListMap::default() })
             }
         }
 
@@ -321,4 +409,52 @@ mod tests {
         assert!(map.patches.contains_key(&path1));
         assert!(map.patches.contains_key(&path2));
     }
+
+    #[test]
+    fn should_keep_tests_next_to_the_patch() {
+        let map = OnePatchMap::default();
+
+        let path = json_patch::json_path!["#0002", "hkbStringEventPayload", "data"];
+        let test = |priority| ValueWithPriority {
+            patch: json_patch::JsonPatch {
+                action: json_patch::Action::Pure { op: json_patch::Op::Test },
+                value: "Original".into(),
+            },
+            priority,
+        };
+
+        assert_eq!(map.insert(path.clone(), test(1)), None);
+        assert_eq!(map.insert(path.clone(), value(1)), None);
+        assert_eq!(map.insert(path.clone(), test(2)), None);
+
+        assert_eq!(map.len(), 3);
+        assert_eq!(map.priority_of(&path), Some(1));
+        let (patches, tests, losers) = map.into_inner();
+        assert_eq!(patches.len(), 1);
+        assert_eq!(tests.get(&path).map(|tests| tests.len()), Some(2));
+        assert!(losers.is_empty());
+    }
+
+    #[test]
+    fn should_return_losers_of_merge() {
+        let map = OnePatchMap::default();
+        let other = OnePatchMap::default();
+
+        let path1 = json_patch::json_path!["#0001", "hkbClipGenerator", "playbackSpeed"];
+        let path2 = json_patch::json_path!["#0002", "hkbClipGenerator", "playbackSpeed"];
+        let path3 = json_patch::json_path!["#0003", "hkbClipGenerator", "playbackSpeed"];
+
+        map.insert(path1.clone(), value(1));
+        map.insert(path2.clone(), value(3));
+        other.insert(path1.clone(), value(2));
+        other.insert(path2.clone(), value(2));
+        other.insert(path3.clone(), value(2));
+
+        let mut losers = map.merge(other);
+        losers.sort_unstable();
+        assert_eq!(losers, vec![(path1.clone(), 1), (path2.clone(), 2)]);
+        assert_eq!(map.priority_of(&path1), Some(2));
+        assert_eq!(map.priority_of(&path2), Some(3));
+        assert_eq!(map.priority_of(&path3), Some(2));
+    }
 }
@@ -54,7 +54,7 @@ pub(crate) fn template_xml_to_value(bytes: &[u8], path: &Path) -> Result<Borrowe
     Ok(value)
 }
 
-pub(super) fn template_bin_to_value<'a>(
+pub(crate) fn template_bin_to_value<'a>(
     template_bytes: &'a [u8],
     path: &Path,
 ) -> Result<BorrowedValue<'a>> {
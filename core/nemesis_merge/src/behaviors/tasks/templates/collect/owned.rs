@@ -1,17 +1,19 @@
-use std::{collections::HashSet, path::Path};
+use std::collections::HashSet;
 
 use rayon::{iter::Either, prelude::*};
 
 use crate::{
+    Config,
     behaviors::tasks::templates::{key::TemplateKey, types::OwnedTemplateMap},
     errors::Error,
 };
 
 /// Collect templates path & content map.
 ///
-/// - `template_root`: meshes parent dir. e.g. `assets/templates`. This means search `asserts/templates/meshes/...`
+/// Templates are searched in `config.resource_dir`(e.g. `assets/templates/meshes/...`).
+/// See [`Config::template_path`].
 pub(crate) fn collect_templates(
-    template_root: &Path,
+    config: &Config,
     template_names: HashSet<TemplateKey<'static>, rapidhash::fast::RandomState>,
 ) -> (OwnedTemplateMap, Vec<Error>) {
     template_names
@@ -20,7 +22,7 @@ pub(crate) fn collect_templates(
             // Intended sample:
             // - `../d_merge/asserts/templates/meshes/actors/character/behaviors/0_master.bin`
             // - `../d_merge/asserts/templates/meshes/actors/character/behaviors/0_master.xml`
-            let template_path = template_root.join(template_key.as_meshes_inner_path());
+            let template_path = config.template_path(template_key.as_meshes_inner_path());
 
             if !template_path.exists() || !template_path.is_file() {
                 return Either::Right(Error::NotFoundTemplate {
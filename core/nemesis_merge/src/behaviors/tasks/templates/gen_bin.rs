@@ -80,7 +80,8 @@ fn remove_nemesis_prefix(path: &Path) -> Option<PathBuf> {
     }
 }
 
-fn get_meshes_relative_path(path: &Path) -> Option<PathBuf> {
+/// e.g. `<any>/Meshes/actors/character/behaviors/0_master.xml` -> `meshes/actors/character/behaviors/0_master.xml`
+pub(crate) fn get_meshes_relative_path(path: &Path) -> Option<PathBuf> {
     let components = path.components();
 
     let mut found = false;
@@ -35,6 +35,18 @@ impl TemplateKey<'static> {
 }
 
 impl<'a> TemplateKey<'a> {
+    /// The reverse of [`TemplateKey::from_nemesis_file`].
+    /// e.g. `meshes/actors/character/_1stperson/behaviors/0_master.bin` -> (`0_master`, is_1st_person: `true`)
+    pub(crate) fn to_nemesis_file(&self) -> Option<(&'static str, bool)> {
+        let find = |map: &'static phf::Map<&'static str, &'static str>| {
+            map.into_iter().find_map(|(k, v)| v.eq_ignore_ascii_case(self.as_str()).then_some(*k))
+        };
+
+        find(&NEMESIS_3RD_PERSON_MAP)
+            .map(|name| (name, false))
+            .or_else(|| find(&NEMESIS_1ST_PERSON_MAP).map(|name| (name, true)))
+    }
+
     /// new valid template key.
     /// - UTF-8 path
     /// - starts_with `meshes`
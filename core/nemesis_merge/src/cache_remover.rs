@@ -28,6 +28,8 @@ where
 
 /// Removes the auto `<output dir>/meshes` or `<output dir>/.d_merge/debug` directories.
 ///
+/// The incremental generation manifest(`<output dir>/.d_merge/manifest.json`) is also removed.
+///
 /// # Warning!
 ///
 /// Do not execute this function if the `Skyrim Data` directory is specified as the output directory.
@@ -47,6 +49,8 @@ where
         },
         || {
             let _ = fs::remove_file(output_dir.join(".d_merge").join("d_merge_errors.log"));
+            let _ = fs::remove_file(output_dir.join(".d_merge").join("d_merge_errors.json"));
+            let _ = fs::remove_file(output_dir.join(".d_merge").join("manifest.json"));
             let _ = remove_if_exists(output_dir.join(".d_merge").join(".debug"));
             let _ = fs::remove_file(
                 output_dir.join("SKSE").join("Plugins").join("fnis_aa").join("config.json"),
@@ -0,0 +1,103 @@
+use std::sync::{
+    Arc,
+    atomic::{AtomicBool, Ordering},
+};
+
+/// A token to cancel [`crate::behavior_gen`] from another thread.
+///
+/// Clones share the same state, so keep one clone and pass another one to [`crate::Config`].
+///
+/// # Example
+/// ```
+/// use nemesis_merge::{CancellationToken, Config};
+///
+/// let token = CancellationToken::new();
+/// let config = Config { cancel_token: token.clone(), ..Default::default() };
+///
+/// // e.g. on `Cancel` button click
+/// token.cancel();
+/// assert!(config.cancel_token.is_cancelled());
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken(Arc<AtomicBool>);
+
+impl CancellationToken {
+    /// Create a token that is not cancelled yet.
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Request cancellation.
+    ///
+    /// The running generation stops at the next check point, removes the outputs written so far and
+    /// returns [`crate::errors::Error::Cancelled`].
+    #[inline]
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    /// Returns `true` if [`Self::cancel`] has been called.
+    #[inline]
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::*;
+    use crate::{Config, PatchMaps, Status, behavior_gen, errors::Error, tests::fixture};
+
+    #[tokio::test]
+    async fn cancelled_run_writes_nothing() {
+        let output_dir = PathBuf::from("../../dummy/cancel_behavior_gen/output");
+        let _ = std::fs::remove_dir_all(&output_dir);
+
+        let cancel_token = CancellationToken::new();
+        let config = Config {
+            output_dir: output_dir.clone(),
+            generate_fnis_esp: true,
+            cancel_token: cancel_token.clone(),
+            ..Default::default()
+        };
+        cancel_token.cancel();
+
+        let result = behavior_gen(PatchMaps::default(), config).await;
+        assert!(matches!(result, Err(Error::Cancelled)));
+        assert!(!output_dir.exists());
+    }
+
+    #[tokio::test]
+    async fn cancel_while_generating_hkx_removes_outputs() {
+        let dir = PathBuf::from("../../dummy/cancel_behavior_gen/mid_run");
+        let _ = std::fs::remove_dir_all(&dir);
+        let resource_dir = dir.join("resource");
+        let output_dir = dir.join("output");
+        fixture::write_templates(&resource_dir);
+        let (patches, source) = fixture::mods(&[("aaaa", "ModA", 0)]);
+
+        let cancel_token = CancellationToken::new();
+        let cancel = cancel_token.clone();
+        let config = Config {
+            resource_dir,
+            output_dir: output_dir.clone(),
+            // Cancel after the first template passed its check point, so that it is written and must be removed.
+            status_report: Some(Box::new(move |status| {
+                if matches!(status, Status::GeneratingHkxFiles { index: 1.., .. }) {
+                    cancel.cancel();
+                }
+            })),
+            generate_fnis_esp: true,
+            cancel_token,
+            patch_source: Some(Arc::new(source)),
+            ..Default::default()
+        };
+
+        let result = behavior_gen(patches, config).await;
+        assert!(matches!(result, Err(Error::Cancelled)));
+        assert_eq!(fixture::files_below(&output_dir), Vec::<PathBuf>::new());
+    }
+}
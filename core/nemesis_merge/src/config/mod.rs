@@ -1,12 +1,23 @@
+mod cancel;
 mod options;
 mod reporter;
+mod sink;
+mod source;
 mod status;
+mod vr;
 
+#[cfg(feature = "zip_sink")]
+pub use self::sink::ZipSink;
 pub use self::{
+    cancel::CancellationToken,
     options::{Config, DebugOptions, HackOptions, OutPutTarget},
+    sink::{BsaSink, FsSink, MemorySink, OutputSink},
+    source::{BsaPatchSource, FsPatchSource, MemoryPatchSource, PatchSource, SourceEntry},
     status::Status,
 };
 pub(crate) use self::{
     reporter::{ReportType, StatusReportCounter},
+    sink::OutputWriter,
     status::StatusReporterFn,
+    vr::VrProjectNames,
 };
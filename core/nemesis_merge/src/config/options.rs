@@ -1,6 +1,20 @@
-use std::{fmt, path::PathBuf};
+use std::{
+    fmt, fs, io,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
 
-use crate::{Status, config::StatusReporterFn};
+use nemesis_xml::hack::HackRules;
+use snafu::ResultExt as _;
+
+use crate::{
+    Status,
+    config::{
+        BsaPatchSource, CancellationToken, FsPatchSource, OutputSink, OutputWriter, PatchSource,
+        StatusReporterFn, VrProjectNames, vr::VR_PROJECT_NAMES_FILE,
+    },
+    errors::{Error, FailedIoSnafu, InvalidHackRulesSnafu, JsonSnafu, Result},
+};
 
 /// A configuration structure used to specify various directories and a status report callback.
 ///
@@ -13,6 +27,10 @@ pub struct Config {
     ///
     /// Typically this is a directory like `assets/templates`. The actual patch target directory
     /// should be a subdirectory such as `assets/templates/meshes`.
+    ///
+    /// For [`OutPutTarget::SkyrimVr`], the templates that differ from SE can be placed in
+    /// `<resource_dir>/vr`(e.g. `assets/templates/vr/meshes/...`). They take precedence over the SE ones.
+    /// The behavior project names that differ from SE are read from `<resource_dir>/vr/project_names.json`.
     pub resource_dir: PathBuf,
 
     /// The directory where the output files will be saved.
@@ -34,8 +52,17 @@ pub struct Config {
     /// This setting allows the parser to work around common community patch errors
     /// such as incorrect field names or missing values. Use with caution as it may
     /// mask actual data issues.
+    ///
+    /// When enabled, the rules in `<resource_dir>/hack_rules.json`(if any) are applied in addition to
+    /// the built-in ones. See [`nemesis_xml::hack::HackRules`].
     pub hack_options: Option<HackOptions>,
 
+    /// If true, a Nemesis patch whose `<!-- ORIGINAL -->` block does not match the template is an error
+    /// instead of a warning.
+    ///
+    /// The mismatches are written to `<output_dir>/.d_merge/stale_patches.json` either way. See [`crate::StalePatchReport`].
+    pub strict_original: bool,
+
     /// Options controlling the output of debug artifacts.
     pub debug: DebugOptions,
 
@@ -58,8 +85,33 @@ pub struct Config {
     ///   duplicate animation registrations and bugs.
     pub skyrim_data_dir_glob: Option<String>,
 
+    /// If true, the patches packed in the `*.bsa` directly under the dirs of [`Self::skyrim_data_dir_glob`]
+    /// are also read, as if they were extracted there(loose files take precedence). See [`crate::BsaPatchSource`].
+    ///
+    /// Ignored if [`Self::patch_source`] is set.
+    pub read_bsa: bool,
+
     /// If true, generates a FNIS.esp(dummy ESP) file with the correct version and author information.
     pub generate_fnis_esp: bool,
+
+    /// Cancels the generation when [`CancellationToken::cancel`] is called.
+    ///
+    /// Checked between(and within) each phase: FNIS collection, patch reading/parsing, apply,
+    /// hkx generation and FNIS I/O jobs.
+    pub cancel_token: CancellationToken,
+
+    /// Destination of the generated files. `None` writes them under `output_dir`.
+    ///
+    /// e.g. [`crate::MemorySink`] to keep them in memory, `ZipSink`(feature = "zip_sink") to pack them into an archive.
+    ///
+    /// Manifest, reports and debug output are still written to `<output_dir>/.d_merge`.
+    pub output_sink: Option<Arc<dyn OutputSink>>,
+
+    /// Where the patches(Nemesis/EngineExt files, FNIS list files and animations) are read from.
+    /// `None` reads them from the filesystem(and from the archives if [`Self::read_bsa`]).
+    ///
+    /// e.g. [`crate::MemoryPatchSource`] to feed patches from memory.
+    pub patch_source: Option<Arc<dyn PatchSource>>,
 }
 
 impl Config {
@@ -72,6 +124,108 @@ impl Config {
             f(status);
         }
     }
+
+    /// Returns `true` if the generation has been cancelled.
+    #[inline]
+    pub fn is_cancelled(&self) -> bool {
+        self.cancel_token.is_cancelled()
+    }
+
+    /// Returns [`Error::Cancelled`] if the generation has been cancelled.
+    #[inline]
+    pub(crate) fn check_cancelled(&self) -> Result<()> {
+        if self.is_cancelled() { Err(Error::Cancelled) } else { Ok(()) }
+    }
+
+    /// Writer of the generated files. See [`Self::output_sink`].
+    #[inline]
+    pub(crate) fn output_writer(&self) -> OutputWriter {
+        OutputWriter::new(&self.output_dir, self.output_sink.as_ref())
+    }
+
+    /// Source of the patches. See [`Self::patch_source`] and [`Self::read_bsa`].
+    ///
+    /// # Note
+    /// This opens the archives of [`Self::read_bsa`], so call it once per run and share the result.
+    pub(crate) fn source(&self) -> Arc<dyn PatchSource> {
+        if let Some(source) = &self.patch_source {
+            return Arc::clone(source);
+        }
+        match &self.skyrim_data_dir_glob {
+            Some(glob) if self.read_bsa => Arc::new(BsaPatchSource::from_data_dirs(glob)),
+            _ => Arc::new(FsPatchSource),
+        }
+    }
+
+    /// Path of a template in `resource_dir`.
+    ///
+    /// - `inner_path`: e.g. `meshes/actors/character/behaviors/0_master.bin`
+    ///
+    /// For [`OutPutTarget::SkyrimVr`], `<resource_dir>/vr/<inner_path>` is returned if it exists.
+    pub(crate) fn template_path(&self, inner_path: impl AsRef<Path>) -> PathBuf {
+        let inner_path = inner_path.as_ref();
+        if self.output_target == OutPutTarget::SkyrimVr {
+            let vr_path = self.resource_dir.join("vr").join(inner_path);
+            if vr_path.is_file() {
+                return vr_path;
+            }
+        }
+        self.resource_dir.join(inner_path)
+    }
+
+    /// Path of the user defined hack rules. (`<resource_dir>/hack_rules.json`)
+    #[inline]
+    pub(crate) fn hack_rules_path(&self) -> PathBuf {
+        self.resource_dir.join(HACK_RULES_FILE)
+    }
+
+    /// Rules of the enabled [`Self::hack_options`]: the built-in ones, then `hack_rules.json` if it exists.
+    ///
+    /// # Errors
+    /// If `hack_rules.json` cannot be read or is invalid.
+    pub(crate) fn hack_rules(&self) -> Result<HackRules> {
+        let Some(hack_options) = self.hack_options else {
+            return Ok(HackRules::default());
+        };
+        let mut rules = HackRules::builtin(hack_options.into());
+
+        let path = self.hack_rules_path();
+        let mut json = match fs::read(&path) {
+            Ok(json) => json,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(rules),
+            Err(err) => return Err(err).context(FailedIoSnafu { path }),
+        };
+        rules.extend(HackRules::from_json(&mut json).context(InvalidHackRulesSnafu { path })?);
+        Ok(rules)
+    }
+
+    /// Path of the VR project names. (`<resource_dir>/vr/project_names.json`)
+    #[inline]
+    pub(crate) fn vr_project_names_path(&self) -> PathBuf {
+        self.resource_dir.join("vr").join(VR_PROJECT_NAMES_FILE)
+    }
+
+    /// Project names of [`OutPutTarget::SkyrimVr`] that differ from SE.
+    ///
+    /// Empty for the other targets, or if `project_names.json` does not exist.
+    ///
+    /// # Errors
+    /// If `project_names.json` cannot be read or is invalid.
+    pub(crate) fn vr_project_names(&self) -> Result<VrProjectNames> {
+        if self.output_target != OutPutTarget::SkyrimVr {
+            return Ok(VrProjectNames::default());
+        }
+
+        let path = self.vr_project_names_path();
+        let json = match fs::read(&path) {
+            Ok(json) => json,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => {
+                return Ok(VrProjectNames::default());
+            }
+            Err(err) => return Err(err).context(FailedIoSnafu { path }),
+        };
+        sonic_rs::from_slice(&json).context(JsonSnafu { path })
+    }
 }
 
 // Implements `Debug` for the `Config` struct, omitting the closure field as it cannot be debugged.
@@ -90,11 +244,17 @@ impl fmt::Debug for Config {
     }
 }
 
+/// File name of the user defined hack rules in `resource_dir`.
+const HACK_RULES_FILE: &str = "hack_rules.json";
+
 /// A collection of hack options that enable non-standard parsing behavior.
 ///
 /// These options exist to handle cases where game mods or other tools produce
 /// invalid or inconsistent data. Enabling these may allow parsing to succeed
 /// in otherwise broken scenarios, at the risk of hiding real errors.
+///
+/// Each flag enables built-in rules of [`nemesis_xml::hack::HackRules::builtin`].
+/// Other workarounds can be added to `<resource_dir>/hack_rules.json` without a code change.
 #[derive(Debug, Copy, Clone, Default, serde::Serialize, serde::Deserialize)]
 #[cfg_attr(feature = "ts_serde", serde(rename_all = "camelCase"))]
 pub struct HackOptions {
@@ -155,12 +315,24 @@ pub struct DebugOptions {
     /// This is the final XML representation of the patched and merged data,
     /// just before conversion to the binary `.hkx` format.
     pub output_merged_xml: bool,
+
+    /// If true, outputs `provenance.json` to the `.debug` subdirectory under `<output_dir>/.d_merge`.
+    ///
+    /// This records which mod and patch file(including FNIS generated patches) touched each JSON path.
+    /// See [`crate::ProvenanceReport`].
+    #[serde(default)]
+    pub output_provenance: bool,
 }
 
 impl Default for DebugOptions {
     #[inline]
     fn default() -> Self {
-        Self { output_patch_json: true, output_merged_json: true, output_merged_xml: false }
+        Self {
+            output_patch_json: true,
+            output_merged_json: true,
+            output_merged_xml: false,
+            output_provenance: false,
+        }
     }
 }
 
@@ -168,7 +340,12 @@ impl DebugOptions {
     /// Enable all debug options.
     #[inline]
     pub const fn enable_all() -> Self {
-        Self { output_patch_json: true, output_merged_json: true, output_merged_xml: true }
+        Self {
+            output_patch_json: true,
+            output_merged_json: true,
+            output_merged_xml: true,
+            output_provenance: true,
+        }
     }
 }
 
@@ -177,7 +354,7 @@ impl DebugOptions {
 /// - feature = "ts_serde"
 ///
 ///  ```txt
-///  SkyrimSE | SkyrimLE
+///  SkyrimSE | SkyrimLE | SkyrimVR
 ///  ```
 #[derive(Debug, Clone, Copy, Default, PartialEq, serde::Serialize, serde::Deserialize)]
 pub enum OutPutTarget {
@@ -189,4 +366,110 @@ pub enum OutPutTarget {
     /// Win32
     #[serde(rename = "SkyrimLE")]
     SkyrimLe,
+
+    /// Amd64(same hkx format as SE)
+    ///
+    /// Templates in `<resource_dir>/vr` take precedence over the SE ones. See [`Config::resource_dir`].
+    #[serde(rename = "SkyrimVR")]
+    SkyrimVr,
+}
+
+impl OutPutTarget {
+    /// The target that has the same hkx format.(Skyrim VR uses the SE format.)
+    pub(crate) const fn hkx_format(self) -> Self {
+        match self {
+            Self::SkyrimLe => Self::SkyrimLe,
+            Self::SkyrimSe | Self::SkyrimVr => Self::SkyrimSe,
+        }
+    }
+
+    /// Plugin format of the dummy esp(`FNIS.esp`, the plugin of [`crate::BsaSink`]).
+    ///
+    /// - ref: https://en.uesp.net/wiki/Skyrim_Mod:Mod_File_Format/TES4
+    pub(crate) const fn esp_version(self) -> skyrim_esp::Version {
+        match self {
+            Self::SkyrimLe => skyrim_esp::Version::Form43,
+            Self::SkyrimSe | Self::SkyrimVr => skyrim_esp::Version::Form44,
+        }
+    }
+
+    /// Archive format.(Skyrim VR reads the SE one.)
+    pub(crate) const fn bsa_version(self) -> skyrim_bsa::Version {
+        match self {
+            Self::SkyrimLe => skyrim_bsa::Version::V104,
+            Self::SkyrimSe | Self::SkyrimVr => skyrim_bsa::Version::V105,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn vr_prefers_vr_templates() {
+        let resource_dir = PathBuf::from("../../dummy/vr_templates");
+        let _ = fs::remove_dir_all(&resource_dir);
+        let overridden = "meshes/actors/character/behaviors/0_master.bin";
+        let shared = "meshes/actors/character/behaviors/1hm_behavior.bin";
+        for inner_path in [overridden, shared] {
+            let path = resource_dir.join(inner_path);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, b"se").unwrap();
+        }
+        let vr_path = resource_dir.join("vr").join(overridden);
+        fs::create_dir_all(vr_path.parent().unwrap()).unwrap();
+        fs::write(&vr_path, b"vr").unwrap();
+
+        let vr = Config {
+            resource_dir: resource_dir.clone(),
+            output_target: OutPutTarget::SkyrimVr,
+            ..Default::default()
+        };
+        assert_eq!(vr.template_path(overridden), vr_path);
+        assert_eq!(vr.template_path(shared), resource_dir.join(shared));
+
+        // SE ignores the VR templates.
+        let se = Config { resource_dir: resource_dir.clone(), ..Default::default() };
+        assert_eq!(se.template_path(overridden), resource_dir.join(overridden));
+    }
+
+    #[test]
+    fn vr_uses_se_formats() {
+        assert_eq!(OutPutTarget::SkyrimVr.hkx_format(), OutPutTarget::SkyrimSe);
+        assert_eq!(OutPutTarget::SkyrimSe.hkx_format(), OutPutTarget::SkyrimSe);
+        assert_eq!(OutPutTarget::SkyrimLe.hkx_format(), OutPutTarget::SkyrimLe);
+
+        assert_eq!(OutPutTarget::SkyrimVr.esp_version(), skyrim_esp::Version::Form44);
+        assert_eq!(OutPutTarget::SkyrimSe.esp_version(), skyrim_esp::Version::Form44);
+        assert_eq!(OutPutTarget::SkyrimLe.esp_version(), skyrim_esp::Version::Form43);
+    }
+
+    #[test]
+    fn vr_renames_project_names() {
+        let resource_dir = PathBuf::from("../../dummy/vr_project_names");
+        let _ = fs::remove_dir_all(&resource_dir);
+        let vr = Config {
+            resource_dir: resource_dir.clone(),
+            output_target: OutPutTarget::SkyrimVr,
+            ..Default::default()
+        };
+        // No file: same as SE.
+        assert_eq!(vr.vr_project_names().unwrap(), VrProjectNames::default());
+
+        fs::create_dir_all(resource_dir.join("vr")).unwrap();
+        fs::write(
+            vr.vr_project_names_path(),
+            r#"{ "adsf": { "DefaultMale~1": "VrMale~1" }, "asdsf": { "DefaultMaleData~DefaultMale": "VrMaleData~VrMale" } }"#,
+        )
+        .unwrap();
+        let names = vr.vr_project_names().unwrap();
+        assert_eq!(names.adsf("DefaultMale~1"), "VrMale~1");
+        assert_eq!(names.adsf("FirstPerson~1"), "FirstPerson~1");
+        assert_eq!(names.asdsf("DefaultMaleData~DefaultMale"), "VrMaleData~VrMale");
+
+        // Only for VR.
+        let se = Config { resource_dir, ..Default::default() };
+        assert_eq!(se.vr_project_names().unwrap(), VrProjectNames::default());
+    }
 }
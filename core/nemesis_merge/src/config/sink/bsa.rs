@@ -0,0 +1,159 @@
+use std::{
+    ffi::CStr,
+    fs::File,
+    io::{self, BufWriter, Write as _},
+    path::{Path, PathBuf},
+    sync::{Mutex, PoisonError},
+};
+
+use skyrim_bsa::BsaWriter;
+
+use super::OutputSink;
+use crate::OutPutTarget;
+
+/// Packs the outputs into `<dir>/<name>.bsa`, and writes a dummy `<name>.esp` so that the game loads it.
+///
+/// The archive format follows the target: v104 for LE, v105 for SE/VR. Only `meshes` is packed.
+/// The other files(e.g. `FNIS.esp`, `SKSE/Plugins/fnis_aa/config.json` and the OAR configs) are read
+/// as loose files by the game or the plugins, so they are written under `dir` as is.
+///
+/// The outputs are kept in memory until [`OutputSink::finish`], which [`crate::behavior_gen`] calls at
+/// the end of the run. On partial failure, the archive holds the outputs generated without errors.
+///
+/// # Note
+/// Loose files override archived ones in the game. Remove the previously generated `meshes` in `dir`.
+#[derive(Debug)]
+pub struct BsaSink {
+    dir: PathBuf,
+    name: String,
+    target: OutPutTarget,
+    writer: Mutex<Option<BsaWriter>>,
+}
+
+impl BsaSink {
+    /// Pack the outputs into `<dir>/<name>.bsa` with `<dir>/<name>.esp`.
+    ///
+    /// - `name`: Plugin name without extension. e.g. `d_merge`
+    pub fn new(dir: impl Into<PathBuf>, name: impl Into<String>, target: OutPutTarget) -> Self {
+        // Compressed: `meshes` of all behaviors add up to hundreds of MiB.
+        let writer = BsaWriter::new(target.bsa_version()).compressed(true);
+        Self { dir: dir.into(), name: name.into(), target, writer: Mutex::new(Some(writer)) }
+    }
+
+    /// `<dir>/<name>.<ext>`
+    fn sibling_path(&self, ext: &str) -> PathBuf {
+        self.dir.join(format!("{}.{ext}", self.name))
+    }
+}
+
+impl OutputSink for BsaSink {
+    fn write(&self, inner_path: &Path, bytes: &[u8]) -> io::Result<()> {
+        let components: Vec<_> = inner_path
+            .components()
+            .map(|component| component.as_os_str().to_string_lossy())
+            .collect();
+        let is_meshes = components.len() >= 2 && components[0].eq_ignore_ascii_case("meshes");
+        if !is_meshes {
+            let path = self.dir.join(inner_path);
+            if let Some(parent) = path.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+            return std::fs::write(path, bytes);
+        }
+
+        let path = components.join("/");
+        let bytes = bytes.to_vec();
+        self.writer
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .as_mut()
+            .ok_or_else(finished_error)?
+            .insert(&path, bytes)
+    }
+
+    fn finish(&self) -> io::Result<()> {
+        let writer = self.writer.lock().unwrap_or_else(PoisonError::into_inner).take();
+        let writer = writer.ok_or_else(finished_error)?;
+
+        std::fs::create_dir_all(&self.dir)?;
+        let mut file = BufWriter::new(File::create(self.sibling_path("bsa"))?);
+        writer.write_to(&mut file)?;
+        file.flush()?;
+
+        const AUTHOR: &CStr = c"SARDONYX";
+        const DESCRIPTION: &CStr = c"Dummy esp generated by d_merge to load the behaviors archive.";
+        let config = skyrim_esp::Config {
+            version: self.target.esp_version(),
+            author: Some(AUTHOR),
+            description: Some(DESCRIPTION),
+        };
+        let mut esp = vec![];
+        skyrim_esp::write_dummy_esp(&mut esp, &config)?;
+        std::fs::write(self.sibling_path("esp"), esp)
+    }
+}
+
+fn finished_error() -> io::Error {
+    io::Error::other("The BSA archive has already been finished.")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::{Config, MemoryPatchSource, PatchMaps, behavior_gen, errors::Error};
+
+    #[test]
+    fn pack_outputs_with_plugin() {
+        let dir = Path::new("../../dummy/bsa_sink");
+        let _ = std::fs::remove_dir_all(dir);
+
+        let sink = BsaSink::new(dir, "d_merge", OutPutTarget::SkyrimLe);
+        sink.write(Path::new("meshes/animationdatasinglefile.txt"), b"adsf").unwrap();
+        sink.write(Path::new("FNIS.esp"), b"esp").unwrap();
+        sink.write(Path::new("SKSE/Plugins/fnis_aa/config.json"), b"{}").unwrap();
+        sink.finish().unwrap();
+        assert!(sink.finish().is_err());
+
+        let archive = skyrim_bsa::BsaArchive::open(dir.join("d_merge.bsa")).unwrap();
+        assert_eq!(archive.version(), skyrim_bsa::Version::V104);
+        assert_eq!(archive.paths().collect::<Vec<_>>(), ["meshes/animationdatasinglefile.txt"]);
+        assert_eq!(archive.read("meshes/animationdatasinglefile.txt").unwrap(), b"adsf");
+        assert!(std::fs::read(dir.join("d_merge.esp")).unwrap().starts_with(b"TES4"));
+        assert_eq!(std::fs::read(dir.join("FNIS.esp")).unwrap(), b"esp");
+        // Plugin configs are read as loose files.
+        assert_eq!(std::fs::read(dir.join("SKSE/Plugins/fnis_aa/config.json")).unwrap(), b"{}");
+        assert!(archive.read("SKSE/Plugins/fnis_aa/config.json").is_err());
+    }
+
+    #[tokio::test]
+    async fn partial_failure_writes_archive() {
+        let dir = Path::new("../../dummy/bsa_sink_partial");
+        let _ = std::fs::remove_dir_all(dir);
+
+        // Not UTF-8
+        let mod_dir = "mods/broken/Nemesis_Engine/mod/brok";
+        let source = MemoryPatchSource::new()
+            .with_file(format!("{mod_dir}/0_master/#0001.txt"), [0xff_u8, 0xfe]);
+        let patches = PatchMaps {
+            nemesis_entries: [(mod_dir.to_string(), 0)].into_iter().collect(),
+            ..Default::default()
+        };
+        let config = Config {
+            output_dir: dir.to_path_buf(),
+            generate_fnis_esp: true,
+            output_sink: Some(Arc::new(BsaSink::new(dir, "d_merge", OutPutTarget::SkyrimSe))),
+            patch_source: Some(Arc::new(source)),
+            ..Default::default()
+        };
+
+        let result = behavior_gen(patches, config).await;
+        assert!(matches!(result, Err(Error::FailedToGenerateBehaviors { .. })));
+
+        let archive = skyrim_bsa::BsaArchive::open(dir.join("d_merge.bsa")).unwrap();
+        assert_eq!(archive.version(), skyrim_bsa::Version::V105);
+        assert!(std::fs::read(dir.join("d_merge.esp")).unwrap().starts_with(b"TES4"));
+        assert!(dir.join("FNIS.esp").is_file());
+    }
+}
@@ -0,0 +1,193 @@
+//! Destinations of the generated files.
+//!
+//! Every output of [`crate::behavior_gen`](hkx, adsf/asdsf, FNIS conversions, configs and `FNIS.esp`)
+//! is written through [`OutputSink`]. Metadata under `<output_dir>/.d_merge`(manifest, reports and
+//! debug output) is always written to the filesystem.
+mod bsa;
+#[cfg(feature = "zip_sink")]
+mod zip;
+
+use std::{
+    collections::BTreeMap,
+    fmt, io,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex, PoisonError},
+};
+
+pub use self::bsa::BsaSink;
+#[cfg(feature = "zip_sink")]
+pub use self::zip::ZipSink;
+
+/// A destination of the generated files.
+///
+/// # Example
+/// ```
+/// use std::sync::Arc;
+/// use nemesis_merge::{Config, MemorySink};
+///
+/// let sink = Arc::new(MemorySink::new());
+/// let config = Config { output_sink: Some(sink.clone()), ..Default::default() };
+/// ```
+pub trait OutputSink: Send + Sync {
+    /// Write a generated file.
+    ///
+    /// - `inner_path`: Path relative to `Config::output_dir`.
+    ///   - e.g. `meshes/actors/character/behaviors/0_master.hkx`
+    ///
+    /// # Errors
+    /// If failed to write.
+    fn write(&self, inner_path: &Path, bytes: &[u8]) -> io::Result<()>;
+
+    /// Called once after all outputs have been written.
+    ///
+    /// Also called when some outputs failed to generate(the others are still written), but not when
+    /// the generation is cancelled.
+    ///
+    /// # Errors
+    /// If failed to flush the outputs.
+    fn finish(&self) -> io::Result<()> {
+        Ok(())
+    }
+
+    /// Returns `true` if the outputs are written as files under `Config::output_dir`.
+    ///
+    /// Incremental generation(skipping unchanged outputs) and the cleanup of a cancelled run
+    /// look at the files in `output_dir`, so they are only enabled in that case.
+    fn is_output_dir(&self) -> bool {
+        false
+    }
+}
+
+/// Writes each output as a file under `root`. (default)
+#[derive(Debug, Clone)]
+pub struct FsSink {
+    root: PathBuf,
+}
+
+impl FsSink {
+    /// Write outputs under `root`.
+    #[inline]
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+}
+
+impl OutputSink for FsSink {
+    fn write(&self, inner_path: &Path, bytes: &[u8]) -> io::Result<()> {
+        let path = self.root.join(inner_path);
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(path, bytes)
+    }
+
+    fn is_output_dir(&self) -> bool {
+        true
+    }
+}
+
+/// Keeps every output in memory. (e.g. for tests and previews)
+#[derive(Debug, Default)]
+pub struct MemorySink {
+    /// `inner_path` -> bytes
+    files: Mutex<BTreeMap<PathBuf, Vec<u8>>>,
+}
+
+impl MemorySink {
+    /// Create an empty sink.
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Get the bytes written to `inner_path`.
+    pub fn get(&self, inner_path: impl AsRef<Path>) -> Option<Vec<u8>> {
+        self.files.lock().unwrap_or_else(PoisonError::into_inner).get(inner_path.as_ref()).cloned()
+    }
+
+    /// Paths written so far. (sorted)
+    pub fn paths(&self) -> Vec<PathBuf> {
+        self.files.lock().unwrap_or_else(PoisonError::into_inner).keys().cloned().collect()
+    }
+
+    /// Take all the written files, leaving the sink empty.
+    pub fn take(&self) -> BTreeMap<PathBuf, Vec<u8>> {
+        std::mem::take(&mut *self.files.lock().unwrap_or_else(PoisonError::into_inner))
+    }
+}
+
+impl OutputSink for MemorySink {
+    fn write(&self, inner_path: &Path, bytes: &[u8]) -> io::Result<()> {
+        self.files
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .insert(inner_path.to_path_buf(), bytes.to_vec());
+        Ok(())
+    }
+}
+
+/// [`OutputSink`] bound to `output_dir`, to write with the full output paths used everywhere.
+#[derive(Clone)]
+pub(crate) struct OutputWriter {
+    output_dir: PathBuf,
+    sink: Arc<dyn OutputSink>,
+}
+
+impl OutputWriter {
+    #[inline]
+    pub(crate) fn new(output_dir: &Path, sink: Option<&Arc<dyn OutputSink>>) -> Self {
+        Self {
+            output_dir: output_dir.to_path_buf(),
+            sink: sink.map_or_else(|| Arc::new(FsSink::new(output_dir)) as _, Arc::clone),
+        }
+    }
+
+    /// Write `bytes` to `path`(`<output_dir>/<inner_path>`).
+    ///
+    /// A path outside of `output_dir` is passed to the sink as is.
+    pub(crate) fn write(&self, path: &Path, bytes: &[u8]) -> io::Result<()> {
+        let inner_path = path.strip_prefix(&self.output_dir).unwrap_or(path);
+        self.sink.write(inner_path, bytes)
+    }
+
+    #[inline]
+    pub(crate) fn finish(&self) -> io::Result<()> {
+        self.sink.finish()
+    }
+
+    #[inline]
+    pub(crate) fn is_output_dir(&self) -> bool {
+        self.sink.is_output_dir()
+    }
+}
+
+impl fmt::Debug for OutputWriter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("OutputWriter")
+            .field("output_dir", &self.output_dir)
+            .field("is_output_dir", &self.sink.is_output_dir())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn writer_passes_inner_path() {
+        let sink = Arc::new(MemorySink::new());
+        let dyn_sink: Arc<dyn OutputSink> = sink.clone();
+        let writer = OutputWriter::new(Path::new("out"), Some(&dyn_sink));
+
+        writer.write(Path::new("out/meshes/animationdatasinglefile.txt"), b"adsf").unwrap();
+        writer.write(Path::new("FNIS.esp"), b"esp").unwrap();
+
+        assert_eq!(
+            sink.paths(),
+            [PathBuf::from("FNIS.esp"), PathBuf::from("meshes/animationdatasinglefile.txt")]
+        );
+        assert_eq!(sink.get("FNIS.esp").as_deref(), Some(b"esp".as_slice()));
+        assert!(!writer.is_output_dir());
+    }
+}
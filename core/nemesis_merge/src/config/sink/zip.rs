@@ -0,0 +1,108 @@
+use std::{
+    fmt,
+    fs::File,
+    io::{self, BufWriter, Write as _},
+    path::{Path, PathBuf},
+    sync::{Mutex, PoisonError},
+};
+
+use zip::{CompressionMethod, ZipWriter, write::SimpleFileOptions};
+
+use super::OutputSink;
+
+/// Packs every output into a zip archive, so that it can be installed as a mod as is.
+///
+/// The entries are stored without compression. The archive is completed by [`OutputSink::finish`],
+/// which [`crate::behavior_gen`] calls at the end of the run. On partial failure, the archive holds
+/// the outputs generated without errors.
+pub struct ZipSink {
+    path: PathBuf,
+    writer: Mutex<Option<ZipWriter<BufWriter<File>>>>,
+}
+
+impl ZipSink {
+    /// Create(or truncate) the archive at `path`.
+    ///
+    /// # Errors
+    /// If failed to create the file.
+    pub fn create(path: impl Into<PathBuf>) -> io::Result<Self> {
+        let path = path.into();
+        let file = File::create(&path)?;
+        Ok(Self { path, writer: Mutex::new(Some(ZipWriter::new(BufWriter::new(file)))) })
+    }
+}
+
+impl fmt::Debug for ZipSink {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ZipSink")
+            .field("path", &self.path)
+            // Skip writer
+            .finish()
+    }
+}
+
+impl OutputSink for ZipSink {
+    fn write(&self, inner_path: &Path, bytes: &[u8]) -> io::Result<()> {
+        // Zip entry names are always separated by `/`.
+        let name = inner_path
+            .components()
+            .map(|component| component.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/");
+        let options = SimpleFileOptions::default().compression_method(CompressionMethod::Stored);
+
+        let mut writer = self.writer.lock().unwrap_or_else(PoisonError::into_inner);
+        let writer = writer.as_mut().ok_or_else(finished_error)?;
+        writer.start_file(name, options).map_err(io::Error::other)?;
+        writer.write_all(bytes)
+    }
+
+    fn finish(&self) -> io::Result<()> {
+        let writer = self.writer.lock().unwrap_or_else(PoisonError::into_inner).take();
+        let writer = writer.ok_or_else(finished_error)?;
+        writer.finish().map_err(io::Error::other)?.flush()
+    }
+}
+
+fn finished_error() -> io::Error {
+    io::Error::other("The zip archive has already been finished.")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{path::PathBuf, sync::Arc};
+
+    use super::*;
+    use crate::{Config, MemoryPatchSource, PatchMaps, behavior_gen, errors::Error};
+
+    #[tokio::test]
+    async fn partial_failure_finishes_archive() {
+        let dir = PathBuf::from("../../dummy/zip_sink_partial");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let zip_path = dir.join("d_merge.zip");
+
+        // Not UTF-8
+        let mod_dir = "mods/broken/Nemesis_Engine/mod/brok";
+        let source = MemoryPatchSource::new()
+            .with_file(format!("{mod_dir}/0_master/#0001.txt"), [0xff_u8, 0xfe]);
+        let patches = PatchMaps {
+            nemesis_entries: [(mod_dir.to_string(), 0)].into_iter().collect(),
+            ..Default::default()
+        };
+        let config = Config {
+            output_dir: dir.join("output"),
+            generate_fnis_esp: true,
+            output_sink: Some(Arc::new(ZipSink::create(zip_path.clone()).unwrap())),
+            patch_source: Some(Arc::new(source)),
+            ..Default::default()
+        };
+
+        let result = behavior_gen(patches, config).await;
+        assert!(matches!(result, Err(Error::FailedToGenerateBehaviors { .. })));
+
+        let mut archive = ::zip::ZipArchive::new(File::open(&zip_path).unwrap()).unwrap();
+        assert_eq!(archive.len(), 1);
+        assert_eq!(archive.by_index(0).unwrap().name(), "FNIS.esp");
+    }
+}
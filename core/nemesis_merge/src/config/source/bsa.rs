@@ -0,0 +1,178 @@
+//! Patches packed in BSA archives.
+use std::{
+    collections::HashSet,
+    io,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use skyrim_bsa::{BsaArchive, normalize_path};
+
+use super::{FsPatchSource, PatchSource, SourceEntry};
+
+/// Reads loose files from the filesystem, and the files in BSA archives as if they were extracted
+/// into the dir the archive is mounted at.
+///
+/// Like the game, loose files override archived ones, and a later archive overrides an earlier one.
+/// Paths in archives are case insensitive.
+#[derive(Debug, Clone, Default)]
+pub struct BsaPatchSource {
+    /// (mount dir, archive)
+    archives: Vec<(PathBuf, Arc<BsaArchive>)>,
+}
+
+impl BsaPatchSource {
+    /// Create a source without archives. (same as [`FsPatchSource`])
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Mount `archive` at `data_dir`.
+    ///
+    /// e.g. `meshes/actors/character/animations/FNISFlyer/FNIS_FNISFlyer_List.txt` in the archive is read
+    /// as `<data_dir>/meshes/actors/character/animations/FNISFlyer/FNIS_FNISFlyer_List.txt`.
+    #[must_use]
+    pub fn with_archive(mut self, data_dir: impl Into<PathBuf>, archive: BsaArchive) -> Self {
+        self.archives.push((data_dir.into(), Arc::new(archive)));
+        self
+    }
+
+    /// Mount every `*.bsa` directly under the dirs matched by `skyrim_data_dir_glob`(in name order within a dir).
+    ///
+    /// The vanilla archives(`Skyrim - *.bsa`) have no patches and large indexes, so they are not mounted.
+    /// Archives that cannot be read are skipped.
+    pub fn from_data_dirs(skyrim_data_dir_glob: &str) -> Self {
+        let mut archives = vec![];
+        for data_dir in jwalk_glob::glob_dirs(skyrim_data_dir_glob) {
+            let Ok(entries) = std::fs::read_dir(&data_dir) else {
+                continue;
+            };
+            let mut archive_paths: Vec<_> = entries
+                .flatten()
+                .map(|entry| entry.path())
+                .filter(|path| {
+                    path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("bsa"))
+                        && !is_vanilla_archive(path)
+                        && path.is_file()
+                })
+                .collect();
+            archive_paths.sort_unstable();
+
+            for archive_path in archive_paths {
+                match BsaArchive::open(&archive_path) {
+                    Ok(archive) => archives.push((data_dir.clone(), Arc::new(archive))),
+                    Err(_err) => {
+                        #[cfg(feature = "tracing")]
+                        tracing::warn!("Skipped BSA {}: {_err}", archive_path.display());
+                    }
+                }
+            }
+        }
+        Self { archives }
+    }
+
+    /// Find the archive that has `path`, and the path in it.
+    fn find(&self, path: &Path) -> Option<(&BsaArchive, String)> {
+        self.archives.iter().rev().find_map(|(data_dir, archive)| {
+            let inner = archive_path(data_dir, path)?;
+            archive.contains(&inner).then_some((&**archive, inner))
+        })
+    }
+
+    /// Paths(relative to `dir`, normalized) of the archived files below `dir`, in precedence order.
+    fn entries_below(&self, dir: &Path) -> impl Iterator<Item = &str> {
+        self.archives.iter().rev().flat_map(move |(data_dir, archive)| {
+            let prefix = archive_path(data_dir, dir);
+            prefix.into_iter().flat_map(move |prefix| {
+                let skip = if prefix.is_empty() { 0 } else { prefix.len() + 1 };
+                archive.paths_below(&prefix).map(move |path| &path[skip..]).collect::<Vec<_>>()
+            })
+        })
+    }
+}
+
+impl PatchSource for BsaPatchSource {
+    fn read_dir(&self, dir: &Path) -> io::Result<Vec<SourceEntry>> {
+        let (mut entries, loose_err) = match FsPatchSource.read_dir(dir) {
+            Ok(entries) => (entries, None),
+            Err(err) => (vec![], Some(err)),
+        };
+        let mut names: HashSet<String> = entries
+            .iter()
+            .filter_map(|entry| Some(entry.path.file_name()?.to_str()?.to_lowercase()))
+            .collect();
+
+        for rest in self.entries_below(dir) {
+            let (name, is_dir) = match rest.split_once('/') {
+                Some((name, _)) => (name, true),
+                None => (rest, false),
+            };
+            if names.insert(name.to_string()) {
+                entries.push(SourceEntry { path: dir.join(name), is_dir });
+            }
+        }
+
+        match loose_err {
+            Some(err) if entries.is_empty() => Err(err),
+            _ => Ok(entries),
+        }
+    }
+
+    fn read(&self, path: &Path) -> io::Result<Vec<u8>> {
+        match std::fs::read(path) {
+            Err(err) if err.kind() == io::ErrorKind::NotFound => match self.find(path) {
+                Some((archive, inner)) => archive.read(&inner),
+                None => Err(err),
+            },
+            res => res,
+        }
+    }
+
+    fn is_file(&self, path: &Path) -> bool {
+        path.is_file() || self.find(path).is_some()
+    }
+
+    fn read_head(&self, path: &Path, len: usize) -> io::Result<Vec<u8>> {
+        if path.is_file() {
+            return FsPatchSource.read_head(path, len);
+        }
+
+        let mut bytes = self.read(path)?;
+        if bytes.len() < len {
+            return Err(io::ErrorKind::UnexpectedEof.into());
+        }
+        bytes.truncate(len);
+        Ok(bytes)
+    }
+
+    fn walk_files(&self, dir: &Path) -> Vec<PathBuf> {
+        let mut files = FsPatchSource.walk_files(dir);
+        let mut seen: HashSet<String> =
+            files.iter().filter_map(|path| archive_path(dir, path)).collect();
+
+        for rest in self.entries_below(dir) {
+            if seen.insert(rest.to_string()) {
+                files.push(dir.join(rest));
+            }
+        }
+        files
+    }
+
+    fn glob_dirs(&self, pattern: &str) -> Vec<PathBuf> {
+        jwalk_glob::glob_dirs(pattern)
+    }
+}
+
+/// `path` relative to `data_dir`, normalized as a path in an archive. `None` if not below `data_dir`.
+fn archive_path(data_dir: &Path, path: &Path) -> Option<String> {
+    Some(normalize_path(path.strip_prefix(data_dir).ok()?.to_str()?))
+}
+
+/// e.g. `Skyrim - Animations.bsa`
+fn is_vanilla_archive(path: &Path) -> bool {
+    path.file_name()
+        .and_then(|name| name.to_str())
+        .and_then(|name| name.get(..9))
+        .is_some_and(|prefix| prefix.eq_ignore_ascii_case("skyrim - "))
+}
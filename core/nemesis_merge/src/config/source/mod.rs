@@ -0,0 +1,274 @@
+//! Where the patches are read from.
+//!
+//! Nemesis/EngineExt patch files(the dirs of `PatchMaps::nemesis_entries`), FNIS list files and the
+//! animation/behavior files they reference are all read through [`PatchSource`].
+//! Templates in `resource_dir` are always read from the filesystem.
+mod bsa;
+
+use std::{
+    collections::BTreeMap,
+    fmt,
+    io::{self, Read as _},
+    ops::Bound,
+    path::{Path, PathBuf},
+};
+
+use rayon::prelude::*;
+
+pub use self::bsa::BsaPatchSource;
+
+/// A source of the patch files.
+///
+/// Paths are the ones [`crate::behavior_gen`] would use on disk.
+/// - Nemesis: `<mod_dir>/<template>/#<id>.txt`(`mod_dir` is a key of `PatchMaps::nemesis_entries`)
+/// - EngineExt: `<data_dir>/Nemesis_EngineExt/mod/<id>/meshes/.../#<id>.txt`
+/// - Pandora: `<data_dir>/Pandora_Engine/mod/<id>/` + either of the above.
+/// - FNIS: `<data_dir>/meshes/<base_dir>/animations/<namespace>/FNIS_<namespace>_List.txt` and the files listed in it.
+///
+/// # Example
+/// ```
+/// use std::sync::Arc;
+/// use nemesis_merge::{Config, MemoryPatchSource};
+///
+/// let source = MemoryPatchSource::new().with_file(
+///     "mods/slide/Nemesis_Engine/mod/slide/0_master/#0001.txt",
+///     "<?xml version=\"1.0\" encoding=\"ascii\"?>",
+/// );
+/// let config = Config { patch_source: Some(Arc::new(source)), ..Default::default() };
+/// ```
+pub trait PatchSource: Send + Sync {
+    /// Direct children of `dir`.
+    ///
+    /// # Errors
+    /// If `dir` does not exist or cannot be read.
+    fn read_dir(&self, dir: &Path) -> io::Result<Vec<SourceEntry>>;
+
+    /// Read the whole file.
+    ///
+    /// # Errors
+    /// If `path` does not exist or cannot be read.
+    fn read(&self, path: &Path) -> io::Result<Vec<u8>>;
+
+    /// Returns `true` if `path` is a file.
+    fn is_file(&self, path: &Path) -> bool;
+
+    /// Read the first `len` bytes. (e.g. to check the hkx header before reading the whole file)
+    ///
+    /// # Errors
+    /// If `path` cannot be read or is shorter than `len`.
+    fn read_head(&self, path: &Path, len: usize) -> io::Result<Vec<u8>> {
+        let mut bytes = self.read(path)?;
+        if bytes.len() < len {
+            return Err(io::ErrorKind::UnexpectedEof.into());
+        }
+        bytes.truncate(len);
+        Ok(bytes)
+    }
+
+    /// All files below `dir`, recursively. Unreadable dirs are skipped.
+    fn walk_files(&self, dir: &Path) -> Vec<PathBuf> {
+        let mut files = vec![];
+        let mut dirs = vec![dir.to_path_buf()];
+        while let Some(dir) = dirs.pop() {
+            for entry in self.read_dir(&dir).unwrap_or_default() {
+                if entry.is_dir {
+                    dirs.push(entry.path);
+                } else {
+                    files.push(entry.path);
+                }
+            }
+        }
+        files
+    }
+
+    /// Expand `Config::skyrim_data_dir_glob` into the data dirs.
+    ///
+    /// The default only supports a trailing `/*`(e.g. `MO2/mods/*`) or a plain dir.
+    fn glob_dirs(&self, pattern: &str) -> Vec<PathBuf> {
+        match pattern.strip_suffix("/*").or_else(|| pattern.strip_suffix("\\*")) {
+            Some(parent) => self
+                .read_dir(Path::new(parent))
+                .unwrap_or_default()
+                .into_iter()
+                .filter_map(|entry| entry.is_dir.then_some(entry.path))
+                .collect(),
+            None => vec![PathBuf::from(pattern)],
+        }
+    }
+}
+
+impl fmt::Debug for dyn PatchSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("PatchSource")
+    }
+}
+
+/// An entry of [`PatchSource::read_dir`].
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct SourceEntry {
+    /// `dir` joined with the entry name.
+    pub path: PathBuf,
+    /// `false` for a file.
+    pub is_dir: bool,
+}
+
+/// Reads the patches from the filesystem. (default)
+#[derive(Debug, Clone, Copy, Default)]
+pub struct FsPatchSource;
+
+impl PatchSource for FsPatchSource {
+    fn read_dir(&self, dir: &Path) -> io::Result<Vec<SourceEntry>> {
+        Ok(std::fs::read_dir(dir)?
+            .flatten()
+            .map(|entry| SourceEntry {
+                is_dir: entry.file_type().is_ok_and(|file_type| file_type.is_dir()),
+                path: entry.path(),
+            })
+            .collect())
+    }
+
+    fn read(&self, path: &Path) -> io::Result<Vec<u8>> {
+        std::fs::read(path)
+    }
+
+    fn is_file(&self, path: &Path) -> bool {
+        path.is_file()
+    }
+
+    fn read_head(&self, path: &Path, len: usize) -> io::Result<Vec<u8>> {
+        let mut bytes = vec![0; len];
+        std::fs::File::open(path)?.read_exact(&mut bytes)?;
+        Ok(bytes)
+    }
+
+    fn walk_files(&self, dir: &Path) -> Vec<PathBuf> {
+        jwalk::WalkDir::new(dir)
+            .into_iter()
+            .par_bridge()
+            .filter_map(|entry| {
+                let path = entry.ok()?.path();
+                path.is_file().then_some(path)
+            })
+            .collect()
+    }
+
+    fn glob_dirs(&self, pattern: &str) -> Vec<PathBuf> {
+        jwalk_glob::glob_dirs(pattern)
+    }
+}
+
+/// Keeps the patch files in memory. (e.g. for tests, or patches extracted from an archive)
+///
+/// Dirs are implied by the file paths. Paths are compared as is(no case folding).
+#[derive(Debug, Clone, Default)]
+pub struct MemoryPatchSource {
+    /// path -> bytes
+    files: BTreeMap<PathBuf, Vec<u8>>,
+}
+
+impl MemoryPatchSource {
+    /// Create an empty source.
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add(or replace) a file.
+    pub fn insert(&mut self, path: impl Into<PathBuf>, bytes: impl Into<Vec<u8>>) {
+        self.files.insert(path.into(), bytes.into());
+    }
+
+    /// Builder style [`Self::insert`].
+    #[must_use]
+    pub fn with_file(mut self, path: impl Into<PathBuf>, bytes: impl Into<Vec<u8>>) -> Self {
+        self.insert(path, bytes);
+        self
+    }
+
+    /// Paths of all the files. (sorted)
+    pub fn paths(&self) -> impl Iterator<Item = &Path> {
+        self.files.keys().map(PathBuf::as_path)
+    }
+
+    /// Files below `dir`. (`Path` is ordered by components, so they are contiguous)
+    fn files_below(&self, dir: &Path) -> impl Iterator<Item = &PathBuf> {
+        self.files
+            .range::<Path, _>((Bound::Excluded(dir), Bound::Unbounded))
+            .map(|(path, _)| path)
+            .take_while(move |path| path.starts_with(dir))
+    }
+}
+
+impl<P, B> FromIterator<(P, B)> for MemoryPatchSource
+where
+    P: Into<PathBuf>,
+    B: Into<Vec<u8>>,
+{
+    fn from_iter<I: IntoIterator<Item = (P, B)>>(iter: I) -> Self {
+        Self { files: iter.into_iter().map(|(path, bytes)| (path.into(), bytes.into())).collect() }
+    }
+}
+
+impl PatchSource for MemoryPatchSource {
+    fn read_dir(&self, dir: &Path) -> io::Result<Vec<SourceEntry>> {
+        // path -> is_dir
+        let mut children = BTreeMap::new();
+        for path in self.files_below(dir) {
+            let Ok(rest) = path.strip_prefix(dir) else {
+                continue;
+            };
+            let mut components = rest.components();
+            let Some(name) = components.next() else {
+                continue;
+            };
+            *children.entry(dir.join(name)).or_insert(false) |= components.next().is_some();
+        }
+
+        if children.is_empty() {
+            return Err(io::ErrorKind::NotFound.into());
+        }
+        Ok(children.into_iter().map(|(path, is_dir)| SourceEntry { path, is_dir }).collect())
+    }
+
+    fn read(&self, path: &Path) -> io::Result<Vec<u8>> {
+        self.files.get(path).cloned().ok_or_else(|| io::ErrorKind::NotFound.into())
+    }
+
+    fn is_file(&self, path: &Path) -> bool {
+        self.files.contains_key(path)
+    }
+
+    fn walk_files(&self, dir: &Path) -> Vec<PathBuf> {
+        self.files_below(dir).cloned().collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn memory_source_lists_implied_dirs() {
+        let source: MemoryPatchSource = [
+            ("mods/a/meshes/actors/character/animations/FNISZoo/FNIS_FNISZoo_List.txt", "list"),
+            ("mods/a/meshes/actors/character/animations/FNISZoo/run.hkx", "hkx"),
+            ("mods/b/Nemesis_Engine/mod/slide/0_master/#0001.txt", "patch"),
+            ("mods/b/Nemesis_Engine/mod/slide/0_master/#0002.txt", "patch"),
+        ]
+        .into_iter()
+        .collect();
+
+        assert_eq!(source.glob_dirs("mods/*"), [PathBuf::from("mods/a"), PathBuf::from("mods/b")]);
+
+        let animations = Path::new("mods/a/meshes/actors/character/animations");
+        assert_eq!(
+            source.read_dir(animations).unwrap(),
+            [SourceEntry { path: animations.join("FNISZoo"), is_dir: true }]
+        );
+        assert!(source.read_dir(Path::new("mods/c")).is_err());
+
+        assert_eq!(source.walk_files(Path::new("mods/b/Nemesis_Engine/mod/slide")).len(), 2);
+        assert_eq!(source.read_head(&animations.join("FNISZoo/run.hkx"), 2).unwrap(), b"hk");
+        assert!(source.read_head(&animations.join("FNISZoo/run.hkx"), 4).is_err());
+    }
+}
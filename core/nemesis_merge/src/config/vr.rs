@@ -0,0 +1,42 @@
+use std::collections::HashMap;
+
+/// Behavior project names of Skyrim VR that differ from SE. (`<resource_dir>/vr/project_names.json`)
+///
+/// Nemesis patches for `animationdatasinglefile.txt` and `animationsetdatasinglefile.txt` are
+/// written against the SE project names, so their targets are renamed before they are applied.
+///
+/// - key: target of the SE patch(the dir name of the patch file)
+/// - value: the name in the VR template
+///
+/// ```json
+/// {
+///   "adsf": { "DefaultMale~1": "<VR project>~1" },
+///   "asdsf": { "DefaultMaleData~DefaultMale": "<VR project data>~<VR project>" }
+/// }
+/// ```
+#[derive(Debug, Default, Clone, PartialEq, Eq, serde::Deserialize)]
+pub(crate) struct VrProjectNames {
+    /// e.g. `DefaultMale~1`
+    #[serde(default)]
+    pub adsf: HashMap<String, String>,
+    /// e.g. `DefaultMaleData~DefaultMale`
+    #[serde(default)]
+    pub asdsf: HashMap<String, String>,
+}
+
+impl VrProjectNames {
+    /// Returns the VR name of an adsf target. (or `target` itself if it is the same as SE)
+    #[inline]
+    pub(crate) fn adsf<'a>(&'a self, target: &'a str) -> &'a str {
+        self.adsf.get(target).map_or(target, String::as_str)
+    }
+
+    /// Returns the VR name of an asdsf target. (or `target` itself if it is the same as SE)
+    #[inline]
+    pub(crate) fn asdsf<'a>(&'a self, target: &'a str) -> &'a str {
+        self.asdsf.get(target).map_or(target, String::as_str)
+    }
+}
+
+/// File name of the VR project names in `<resource_dir>/vr`.
+pub(crate) const VR_PROJECT_NAMES_FILE: &str = "project_names.json";
@@ -1,4 +1,5 @@
 //! errors of `This crate`
+pub mod report;
 pub mod writer;
 
 use std::{io, path::PathBuf};
@@ -12,6 +13,9 @@ pub enum Error {
     /// {msg}
     Custom { msg: String },
 
+    /// Behavior generation was cancelled.
+    Cancelled,
+
     /// Applying the FNIS mod patch requires input for config.skyrim_data_dir_glob, but it is not provided.
     MissingSkyrimDataDirGlob,
 
@@ -95,6 +99,39 @@ pub enum Error {
         source: nemesis_xml::error::Error,
     },
 
+    /// Invalid `hack_rules.json`
+    #[snafu(display("Invalid hack rules `{}`: {source}", path.display()))]
+    InvalidHackRules { path: PathBuf, source: nemesis_xml::error::Error },
+
+    /// The `<!-- ORIGINAL -->` block of a Nemesis patch does not match the template. (`Config::strict_original`)
+    #[snafu(display("[Stale Nemesis Patch `{}`] `{template}`: {json_path}\n expected: {expected}\n   actual: {actual}\n", path.display()))]
+    StalePatch {
+        path: PathBuf,
+        template: String,
+        json_path: String,
+        expected: String,
+        actual: String,
+    },
+
+    /// Nemesis XML patch writing error
+    #[snafu(display("[Nemesis XML Patch Writing Error `{}`]:\n{source}\n", path.display()))]
+    NemesisXmlWriteErr {
+        /// edited behavior path
+        path: PathBuf,
+        source: nemesis_xml::error::Error,
+    },
+
+    /// Invalid Nemesis mod id `{id}`. Expected ASCII alphanumerics or `_`, starting with a letter.
+    InvalidNemesisModId { id: String },
+
+    /// No Nemesis template matches the edited behavior.
+    #[snafu(display("No Nemesis template matches the edited behavior: {}", path.display()))]
+    NotFoundNemesisTemplate { path: PathBuf },
+
+    /// Missing the signature of a class in the edited behavior.
+    #[snafu(display("Missing the signature of `{class_name}` in the edited behavior: {}", path.display()))]
+    MissingClassSignature { class_name: String, path: PathBuf },
+
     /// Failed to parse adsf template
     #[snafu(display("[animationdatasinglefile template Parse Error]{}:\n{source}", path.display()))]
     FailedParseAdsfTemplate { source: rmp_serde::decode::Error, path: PathBuf },
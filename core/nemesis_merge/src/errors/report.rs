@@ -0,0 +1,450 @@
+//! Structured error report. (`<output_dir>/.d_merge/d_merge_errors.json`)
+//!
+//! The same errors as `d_merge_errors.log`, but one JSON record per error so that
+//! front ends can group/filter them without parsing the message.
+use std::path::{Component, Path, PathBuf};
+
+use snafu::ResultExt as _;
+
+use crate::{
+    PatchMaps,
+    behaviors::{
+        AsdfPathParseError, AsdsfPathParseError,
+        priority_ids::get_nemesis_id,
+        tasks::fnis::{collect::owned::FnisError, patch_gen::FnisPatchGenerationError},
+    },
+    errors::{AnimPatchErrKind, Error, FailedIoSnafu, JsonSnafu, Result},
+};
+
+/// Errors of the last [`crate::behavior_gen`].
+///
+/// Written to `<output_dir>/.d_merge/d_merge_errors.json`. The order is the same as `d_merge_errors.log`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[cfg_attr(feature = "ts_serde", serde(rename_all = "camelCase"))]
+pub struct ErrorReport {
+    pub errors: Vec<ErrorRecord>,
+}
+
+/// One error.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[cfg_attr(feature = "ts_serde", serde(rename_all = "camelCase"))]
+pub struct ErrorRecord {
+    /// Stable error code. (variant name of [`Error`], e.g. `NemesisXmlErr`)
+    pub code: String,
+    pub category: ErrorCategory,
+    /// File that caused the error, if known.
+    /// - e.g. `<data>/Nemesis_Engine/mod/slide/_1stperson/0_master/#0106.txt`
+    pub path: Option<PathBuf>,
+    /// Mod id(key of [`PatchMaps`]) resolved from `path`.
+    pub mod_id: Option<String>,
+    /// 1-based line number in `path`. Only for parse errors that point to a position.
+    pub line: Option<usize>,
+    /// 1-based column number in `path`. Only for parse errors that point to a position.
+    pub column: Option<usize>,
+    /// Human readable message. (same as `d_merge_errors.log`)
+    pub message: String,
+}
+
+/// Which step of the generation the error came from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorCategory {
+    /// FNIS list parsing, patch generation and hkx conversion.
+    Fnis,
+    /// `animationdatasinglefile.txt` patches.
+    Adsf,
+    /// `animationsetdatasinglefile.txt` patches.
+    Asdsf,
+    /// Nemesis XML patch/path parsing.
+    PatchParse,
+    /// Applying patches to the templates.
+    Apply,
+    /// Template reading.
+    Template,
+    /// hkx (de)serialization.
+    HkxSerialize,
+    /// File system errors.
+    Io,
+    Other,
+}
+
+impl ErrorReport {
+    /// Build the report from the errors of [`crate::behavior_gen`].
+    ///
+    /// Nested errors(e.g. FNIS alternate animation conversion) are flattened into separate records.
+    pub(crate) fn new(errors: &[Error], patches: &PatchMaps) -> Self {
+        let mut records = Vec::with_capacity(errors.len());
+        for err in errors {
+            push_records(err, patches, &mut records);
+        }
+        Self { errors: records }
+    }
+
+    /// Read `<output_dir>/.d_merge/d_merge_errors.json` written by the last [`crate::behavior_gen`].
+    ///
+    /// # Errors
+    /// If the file does not exist or cannot be parsed.
+    pub fn load(output_dir: impl AsRef<Path>) -> Result<Self> {
+        let path = error_report_path(output_dir.as_ref());
+        let bytes = std::fs::read(&path).context(FailedIoSnafu { path: &path })?;
+        sonic_rs::from_slice(&bytes).with_context(|_| JsonSnafu { path })
+    }
+}
+
+/// `<output_dir>/.d_merge/d_merge_errors.json`
+pub(crate) fn error_report_path(output_dir: &Path) -> PathBuf {
+    output_dir.join(".d_merge").join("d_merge_errors.json")
+}
+
+fn push_records(err: &Error, patches: &PatchMaps, records: &mut Vec<ErrorRecord>) {
+    if let Error::FnisPatchGenerationError {
+        source: FnisPatchGenerationError::FailedToConvertAltAnimToOAR { errors },
+    } = err
+    {
+        for err in errors {
+            push_records(err, patches, records);
+        }
+        return;
+    }
+
+    let path = err.path();
+    let message = err.to_string();
+    let (line, column) =
+        if err.has_position() { find_line_column(&message).unzip() } else { (None, None) };
+
+    records.push(ErrorRecord {
+        code: err.code().to_string(),
+        category: err.category(),
+        mod_id: path.and_then(|path| find_mod_id(path, patches)),
+        path: path.map(Path::to_path_buf),
+        line,
+        column,
+        message,
+    });
+}
+
+impl Error {
+    /// Stable error code. (variant name)
+    pub const fn code(&self) -> &'static str {
+        match self {
+            Self::Custom { .. } => "Custom",
+            Self::Cancelled => "Cancelled",
+            Self::MissingSkyrimDataDirGlob => "MissingSkyrimDataDirGlob",
+            Self::FnisError { .. } => "FnisError",
+            Self::FailedParseFnisModList { .. } => "FailedParseFnisModList",
+            Self::FNISHkxIoError { .. } => "FNISHkxIoError",
+            Self::FNISHkxInvalidMagic { .. } => "FNISHkxInvalidMagic",
+            Self::FNISHkxInvalidHeader { .. } => "FNISHkxInvalidHeader",
+            Self::FNISHkxConversionError { .. } => "FNISHkxConversionError",
+            Self::FNISGenerateEspError { .. } => "FNISGenerateEspError",
+            Self::FnisPatchGenerationError { .. } => "FnisPatchGenerationError",
+            Self::FailedIo { .. } => "FailedIo",
+            Self::FailedToReadOwnedPatches { .. } => "FailedToReadOwnedPatches",
+            Self::FailedToReadBorrowedPatches { .. } => "FailedToReadBorrowedPatches",
+            Self::FailedToReadTemplates { .. } => "FailedToReadTemplates",
+            Self::FailedToGenerateBehaviors { .. } => "FailedToGenerateBehaviors",
+            Self::NotFoundTemplate { .. } => "NotFoundTemplate",
+            Self::PatchError { .. } => "PatchError",
+            Self::NemesisXmlErr { .. } => "NemesisXmlErr",
+            Self::InvalidHackRules { .. } => "InvalidHackRules",
+            Self::StalePatch { .. } => "StalePatch",
+            Self::NemesisXmlWriteErr { .. } => "NemesisXmlWriteErr",
+            Self::InvalidNemesisModId { .. } => "InvalidNemesisModId",
+            Self::NotFoundNemesisTemplate { .. } => "NotFoundNemesisTemplate",
+            Self::MissingClassSignature { .. } => "MissingClassSignature",
+            Self::FailedParseAdsfTemplate { .. } => "FailedParseAdsfTemplate",
+            Self::FailedSerializeAdsf { .. } => "FailedSerializeAdsf",
+            Self::FailedSerializeAsdsf { .. } => "FailedSerializeAsdsf",
+            Self::FailedDiffLinesPatch { .. } => "FailedDiffLinesPatch",
+            Self::FailedParseAdsfAnimDataHeaderPatch { .. } => "FailedParseAdsfAnimDataHeaderPatch",
+            Self::FailedParseEditAdsfClipAnimPatch { .. } => "FailedParseEditAdsfClipAnimPatch",
+            Self::FailedParseEditAdsfClipMotionPatch { .. } => "FailedParseEditAdsfClipMotionPatch",
+            Self::FailedParseAsdsfPatch { .. } => "FailedParseAsdsfPatch",
+            Self::FailedParseEditAsdsfPatch { .. } => "FailedParseEditAsdsfPatch",
+            Self::FailedParseAdsfPatch { .. } => "FailedParseAdsfPatch",
+            Self::DedupEventVariableError { .. } => "DedupEventVariableError",
+            Self::HkxSerError { .. } => "HkxSerError",
+            Self::HkxDeError { .. } => "HkxDeError",
+            Self::TemplateError { .. } => "TemplateError",
+            Self::TemplateXmlError { .. } => "TemplateXmlError",
+            Self::JsonToClassMapError { .. } => "JsonToClassMapError",
+            Self::JsonError { .. } => "JsonError",
+            Self::NonUtf8Path { .. } => "NonUtf8Path",
+            Self::UnsupportedTemplatePath { .. } => "UnsupportedTemplatePath",
+            Self::FailedParseNemesisPatchPath { .. } => "FailedParseNemesisPatchPath",
+            Self::FailedToCastNemesisPathToTemplateKey { .. } => {
+                "FailedToCastNemesisPathToTemplateKey"
+            }
+            Self::ParsedAdsfPathError { .. } => "ParsedAdsfPathError",
+            Self::ParsedAsdsfPathError { .. } => "ParsedAsdsfPathError",
+            Self::StripPrefixError { .. } => "StripPrefixError",
+            Self::JwalkErr { .. } => "JwalkErr",
+            Self::JoinError { .. } => "JoinError",
+        }
+    }
+
+    /// Which step of the generation this error came from.
+    pub const fn category(&self) -> ErrorCategory {
+        match self {
+            Self::MissingSkyrimDataDirGlob
+            | Self::FnisError { .. }
+            | Self::FailedParseFnisModList { .. }
+            | Self::FNISHkxIoError { .. }
+            | Self::FNISHkxInvalidMagic { .. }
+            | Self::FNISHkxInvalidHeader { .. }
+            | Self::FNISHkxConversionError { .. }
+            | Self::FNISGenerateEspError { .. }
+            | Self::FnisPatchGenerationError { .. } => ErrorCategory::Fnis,
+
+            Self::FailedDiffLinesPatch { kind: AnimPatchErrKind::Adsf, .. }
+            | Self::FailedParseAdsfTemplate { .. }
+            | Self::FailedSerializeAdsf { .. }
+            | Self::FailedParseAdsfAnimDataHeaderPatch { .. }
+            | Self::FailedParseEditAdsfClipAnimPatch { .. }
+            | Self::FailedParseEditAdsfClipMotionPatch { .. }
+            | Self::FailedParseAdsfPatch { .. }
+            | Self::ParsedAdsfPathError { .. } => ErrorCategory::Adsf,
+
+            Self::FailedDiffLinesPatch { kind: AnimPatchErrKind::Asdsf, .. }
+            | Self::FailedSerializeAsdsf { .. }
+            | Self::FailedParseAsdsfPatch { .. }
+            | Self::FailedParseEditAsdsfPatch { .. }
+            | Self::ParsedAsdsfPathError { .. } => ErrorCategory::Asdsf,
+
+            Self::NemesisXmlErr { .. }
+            | Self::InvalidHackRules { .. }
+            | Self::FailedParseNemesisPatchPath { .. }
+            | Self::FailedToCastNemesisPathToTemplateKey { .. } => ErrorCategory::PatchParse,
+
+            Self::NotFoundTemplate { .. }
+            | Self::PatchError { .. }
+            | Self::StalePatch { .. }
+            | Self::JsonToClassMapError { .. }
+            | Self::DedupEventVariableError { .. } => ErrorCategory::Apply,
+
+            Self::TemplateError { .. }
+            | Self::TemplateXmlError { .. }
+            | Self::UnsupportedTemplatePath { .. } => ErrorCategory::Template,
+
+            Self::HkxSerError { .. } | Self::HkxDeError { .. } => ErrorCategory::HkxSerialize,
+
+            Self::FailedIo { .. }
+            | Self::JsonError { .. }
+            | Self::NonUtf8Path { .. }
+            | Self::StripPrefixError { .. }
+            | Self::JwalkErr { .. } => ErrorCategory::Io,
+
+            Self::Custom { .. }
+            | Self::Cancelled
+            | Self::FailedToReadOwnedPatches { .. }
+            | Self::FailedToReadBorrowedPatches { .. }
+            | Self::FailedToReadTemplates { .. }
+            | Self::FailedToGenerateBehaviors { .. }
+            | Self::NemesisXmlWriteErr { .. }
+            | Self::InvalidNemesisModId { .. }
+            | Self::NotFoundNemesisTemplate { .. }
+            | Self::MissingClassSignature { .. }
+            | Self::JoinError { .. } => ErrorCategory::Other,
+        }
+    }
+
+    /// File that caused the error, if known.
+    ///
+    /// For template errors(`NotFoundTemplate`, `PatchError`), this is the template name.
+    pub fn path(&self) -> Option<&Path> {
+        Some(match self {
+            Self::FailedParseFnisModList { path, .. }
+            | Self::FNISHkxIoError { path, .. }
+            | Self::FailedIo { path, .. }
+            | Self::NemesisXmlErr { path, .. }
+            | Self::InvalidHackRules { path, .. }
+            | Self::StalePatch { path, .. }
+            | Self::NemesisXmlWriteErr { path, .. }
+            | Self::NotFoundNemesisTemplate { path }
+            | Self::MissingClassSignature { path, .. }
+            | Self::FailedParseAdsfTemplate { path, .. }
+            | Self::FailedSerializeAdsf { path, .. }
+            | Self::FailedSerializeAsdsf { path, .. }
+            | Self::FailedDiffLinesPatch { path, .. }
+            | Self::FailedParseAdsfAnimDataHeaderPatch { path, .. }
+            | Self::FailedParseEditAdsfClipAnimPatch { path, .. }
+            | Self::FailedParseEditAdsfClipMotionPatch { path, .. }
+            | Self::FailedParseAsdsfPatch { path, .. }
+            | Self::FailedParseEditAsdsfPatch { path, .. }
+            | Self::FailedParseAdsfPatch { path, .. }
+            | Self::DedupEventVariableError { path, .. }
+            | Self::HkxSerError { path, .. }
+            | Self::HkxDeError { path, .. }
+            | Self::TemplateError { path, .. }
+            | Self::TemplateXmlError { path, .. }
+            | Self::JsonToClassMapError { path, .. }
+            | Self::JsonError { path, .. }
+            | Self::NonUtf8Path { path }
+            | Self::UnsupportedTemplatePath { path }
+            | Self::FailedToCastNemesisPathToTemplateKey { path } => path,
+
+            Self::FNISHkxInvalidMagic { input_path, .. }
+            | Self::FNISHkxInvalidHeader { input_path, .. }
+            | Self::FNISHkxConversionError { input_path, .. } => input_path,
+
+            Self::NotFoundTemplate { template_name } | Self::PatchError { template_name, .. } => {
+                Path::new(template_name)
+            }
+
+            Self::FnisError { source } => match source {
+                FnisError::EmptyAnimPaths { animations_mod_dir }
+                | FnisError::BehaviorParentMissing { animations_mod_dir } => animations_mod_dir,
+                FnisError::FailedReadingListFile { expected, .. } => Path::new(expected),
+                FnisError::BehaviorNotFoundSubDirParent { sub_dir } => sub_dir,
+                FnisError::BehaviorNotFound { path } => path,
+            },
+            Self::FnisPatchGenerationError { source } => match source {
+                FnisPatchGenerationError::UnsupportedPairAndKillMoveForCreature { path }
+                | FnisPatchGenerationError::UnsupportedFurnitureAnimationToCreature { path }
+                | FnisPatchGenerationError::UnsupportedOffsetArmAnimationToCreature { path } => {
+                    path
+                }
+                FnisPatchGenerationError::FailedToConvertAltAnimToOAR { .. } => return None,
+            },
+            Self::ParsedAdsfPathError { source } => match source {
+                AsdfPathParseError::MissingID { .. } => return None,
+                AsdfPathParseError::NonUtf8Path { path }
+                | AsdfPathParseError::MissingAnimationData { path }
+                | AsdfPathParseError::TooShortPathFormat { path }
+                | AsdfPathParseError::SplitTilde { path }
+                | AsdfPathParseError::IndexMustBeNumber { path, .. } => path,
+            },
+            Self::ParsedAsdsfPathError { source } => match source {
+                AsdsfPathParseError::MissingID { .. } => return None,
+                AsdsfPathParseError::NonUtf8Path { path }
+                | AsdsfPathParseError::MissingAnimationSetData { path }
+                | AsdsfPathParseError::TooShortPathFormat { path }
+                | AsdsfPathParseError::InvalidAddAnimSetFileName { path } => path,
+            },
+
+            Self::Custom { .. }
+            | Self::Cancelled
+            | Self::MissingSkyrimDataDirGlob
+            | Self::FNISGenerateEspError { .. }
+            | Self::FailedToReadOwnedPatches { .. }
+            | Self::FailedToReadBorrowedPatches { .. }
+            | Self::FailedToReadTemplates { .. }
+            | Self::FailedToGenerateBehaviors { .. }
+            | Self::FailedParseNemesisPatchPath { .. }
+            | Self::InvalidNemesisModId { .. }
+            | Self::StripPrefixError { .. }
+            | Self::JwalkErr { .. }
+            | Self::JoinError { .. } => return None,
+        })
+    }
+
+    /// Does the message contain a source snippet that points to the error position?
+    const fn has_position(&self) -> bool {
+        matches!(
+            self,
+            Self::FailedParseFnisModList { .. }
+                | Self::NemesisXmlErr { .. }
+                | Self::FailedDiffLinesPatch { .. }
+                | Self::FailedParseAdsfAnimDataHeaderPatch { .. }
+                | Self::FailedParseEditAdsfClipAnimPatch { .. }
+                | Self::FailedParseEditAdsfClipMotionPatch { .. }
+                | Self::FailedParseAsdsfPatch { .. }
+                | Self::FailedParseEditAsdsfPatch { .. }
+                | Self::FailedParseAdsfPatch { .. }
+        )
+    }
+}
+
+/// Resolve the key of [`PatchMaps`] from the path.
+///
+/// - Nemesis: `<data>/Nemesis_Engine/mod/<id>/...` -> `<data>/Nemesis_Engine/mod/<id>`(`Nemesis_EngineExt`, `Pandora_Engine` as well)
+/// - FNIS: `<data>/meshes/actors/<creature>/animations/<namespace>/...` -> `<namespace>`
+fn find_mod_id(path: &Path, patches: &PatchMaps) -> Option<String> {
+    if let Some(id) = path.to_str().and_then(|path| get_nemesis_id(path).ok()) {
+        return Some(id.to_string());
+    }
+
+    let mut components = path.components().filter_map(|component| match component {
+        Component::Normal(name) => name.to_str(),
+        _ => None,
+    });
+    components.find(|name| name.eq_ignore_ascii_case("animations"))?;
+    let namespace = components.next()?;
+    patches.fnis_entries.contains_key(namespace).then(|| namespace.to_string())
+}
+
+/// Find the position of the first annotation in a rendered `ReadableError` snippet.
+///
+/// e.g.
+/// ```txt
+///   |
+/// 3 |     <hkparam name="variableInfos" numelements="1">
+///   |                                   ^ Expected: number
+/// ```
+/// -> `(3, 35)`
+///
+/// `ReadableError` does not expose the position, so this reads it from the snippet text.
+/// Returns `None` if there is no such snippet.
+fn find_line_column(message: &str) -> Option<(usize, usize)> {
+    let mut line_number = None;
+    for line in message.lines() {
+        let Some((gutter, body)) = line.split_once(" |") else {
+            continue;
+        };
+
+        let gutter = gutter.trim();
+        if gutter.is_empty() {
+            // Annotation line: the `^` under the source line.
+            // `body` starts with the same single space as the source line, so this index is 1-based.
+            if let (Some(line_number), Some(column)) = (line_number, body.find('^')) {
+                return Some((line_number, column));
+            }
+        } else {
+            line_number = gutter.parse().ok();
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn line_column_from_snippet() {
+        let message = "\
+[Nemesis XML Patch Parsing Error `#0106.txt`]:
+error: Expected number
+  |
+2 | <hkobject>
+3 |     <hkparam name=\"variableInfos\" numelements=\"a\">
+  |                                                ^ Expected: number
+";
+        assert_eq!(find_line_column(message), Some((3, 48)));
+        assert_eq!(find_line_column("Failed to io: path = a/b | c"), None);
+    }
+
+    #[test]
+    fn record_from_error() {
+        let patches = PatchMaps {
+            fnis_entries: [("FNISFlyer".to_string(), 1)].into_iter().collect(),
+            ..Default::default()
+        };
+        let errors = [
+            Error::NotFoundTemplate { template_name: "meshes/actors/dog/behaviors/x.bin".into() },
+            Error::FnisError {
+                source: FnisError::BehaviorNotFound {
+                    path: "data/meshes/actors/character/animations/FNISFlyer/a.hkx".into(),
+                },
+            },
+        ];
+
+        let ErrorReport { errors } = ErrorReport::new(&errors, &patches);
+        assert_eq!(errors[0].code, "NotFoundTemplate");
+        assert_eq!(errors[0].category, ErrorCategory::Apply);
+        assert_eq!(errors[0].mod_id, None);
+        assert_eq!(errors[1].category, ErrorCategory::Fnis);
+        assert_eq!(errors[1].mod_id.as_deref(), Some("FNISFlyer"));
+        assert_eq!(errors[1].line, None);
+    }
+}
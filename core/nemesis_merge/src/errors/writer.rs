@@ -1,6 +1,9 @@
 use rayon::prelude::*;
 
-use crate::errors::{Error, Result};
+use crate::{
+    PatchMaps,
+    errors::{Error, Result, report::ErrorReport},
+};
 
 fn errors_to_string(errors: &[Error]) -> String {
     let err_len = errors.len();
@@ -20,7 +23,14 @@ fn errors_to_string(errors: &[Error]) -> String {
 ///
 /// - If the `tracing` feature is enabled, it logs the errors using the `tracing` crate.
 /// - Otherwise, it writes the errors to a file named `d_merge_errors.log` in the output directory.
-pub(crate) async fn write_errors(options: &crate::Config, errors: &[Error]) -> Result<()> {
+///
+/// The same errors are also written to `d_merge_errors.json` as [`ErrorReport`].
+pub(crate) async fn write_errors(
+    options: &crate::Config,
+    errors: &[Error],
+    patches: &PatchMaps,
+) -> Result<()> {
+    let report = ErrorReport::new(errors, patches);
     let errors = errors_to_string(errors);
 
     #[cfg(feature = "tracing")]
@@ -31,7 +41,7 @@ pub(crate) async fn write_errors(options: &crate::Config, errors: &[Error]) -> R
         use snafu::ResultExt as _;
         use tokio::fs;
 
-        use crate::errors::FailedIoSnafu;
+        use crate::errors::{FailedIoSnafu, JsonSnafu, report::error_report_path};
 
         let mut error_output = options.output_dir.join(".d_merge");
         let _ = fs::create_dir_all(&error_output).await;
@@ -39,6 +49,13 @@ pub(crate) async fn write_errors(options: &crate::Config, errors: &[Error]) -> R
         fs::write(&error_output, errors)
             .await
             .with_context(|_| FailedIoSnafu { path: error_output })?;
+
+        let report_output = error_report_path(&options.output_dir);
+        let json = sonic_rs::to_string_pretty(&report)
+            .with_context(|_| JsonSnafu { path: report_output.clone() })?;
+        fs::write(&report_output, json)
+            .await
+            .with_context(|_| FailedIoSnafu { path: report_output })?;
     }
 
     Ok(())
@@ -4,9 +4,22 @@ mod config;
 pub mod errors;
 mod results;
 
+#[cfg(feature = "zip_sink")]
+pub use crate::config::ZipSink;
 pub use crate::{
-    behaviors::{PatchMaps, PriorityMap, behavior_gen, create_bin_templates},
-    config::{Config, DebugOptions, HackOptions, OutPutTarget, Status},
+    behaviors::{
+        AnimDataPlan, BehaviorGenPlan, BehaviorGenProfile, ConflictReport, FnisIoJobKind,
+        FnisIoJobPlan, ItemTiming, NemesisModInfo, OneFieldConflict, PatchMaps, PatchTrace,
+        PathExplanation, Phase, PhaseTiming, PriorityMap, ProvenanceReport, ReproducibilityReport,
+        SeqConflict, StalePatch, StalePatchReport, TemplatePlan, behavior_gen,
+        check_reproducibility, collect_provenance, create_bin_templates, generate_nemesis_mod,
+        is_output_stale, plan_behavior_gen,
+    },
+    config::{
+        BsaPatchSource, BsaSink, CancellationToken, Config, DebugOptions, FsPatchSource, FsSink,
+        HackOptions, MemoryPatchSource, MemorySink, OutPutTarget, OutputSink, PatchSource,
+        SourceEntry, Status,
+    },
 };
 
 #[cfg(test)]
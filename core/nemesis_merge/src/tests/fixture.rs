@@ -0,0 +1,135 @@
+//! Tiny templates and Nemesis patches to run [`crate::behavior_gen`] without the real resources.
+
+use std::path::{Path, PathBuf};
+
+use skyrim_anim_parser::{adsf::alt::AltAdsf, asdsf::alt::AltAsdsf};
+
+use crate::{
+    MemoryPatchSource, PatchMaps,
+    behaviors::tasks::{adsf::ADSF_INNER_PATH, asdsf::ASDSF_INNER_PATH},
+};
+
+/// (Nemesis template name, `meshes` inner path) of the templates written by [`write_templates`].
+pub(crate) const TEMPLATES: [(&str, &str); 2] = [
+    ("defaultmale", "meshes/actors/character/characters/defaultmale.bin"),
+    ("defaultfemale", "meshes/actors/character/characters female/defaultfemale.bin"),
+];
+
+/// Write the `.bin` templates of [`TEMPLATES`] and empty adsf/asdsf templates into `resource_dir`.
+///
+/// Each one of [`TEMPLATES`] has
+/// - a `hkbStringEventPayload`(`#0002`) whose `data` is `Original`. (See [`nemesis_patch`])
+/// - a `hkbBehaviorGraphStringData`(`#0003`) whose `eventNames` is `["Original"]`. (See [`push_patch`])
+pub(crate) fn write_templates(resource_dir: &Path) {
+    let template = simd_json::json_typed!(borrowed, {
+        "#0001": {
+            "hkRootLevelContainer": {
+                "__ptr": "#0001",
+                "namedVariants": [{
+                    "name": "hkbStringEventPayload",
+                    "className": "hkbStringEventPayload",
+                    "variant": "#0002",
+                }],
+            },
+        },
+        "#0002": {
+            "hkbStringEventPayload": {
+                "__ptr": "#0002",
+                "data": "Original",
+            },
+        },
+        "#0003": {
+            "hkbBehaviorGraphStringData": {
+                "__ptr": "#0003",
+                "eventNames": ["Original"],
+            },
+        },
+    });
+    let bytes = rmp_serde::to_vec(&template).unwrap();
+
+    for (_, inner_path) in TEMPLATES {
+        let path = resource_dir.join(inner_path);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, &bytes).unwrap();
+    }
+
+    for (inner_path, bytes) in [
+        (ADSF_INNER_PATH, rmp_serde::to_vec(&AltAdsf::default()).unwrap()),
+        (ASDSF_INNER_PATH, rmp_serde::to_vec(&AltAsdsf::default()).unwrap()),
+    ] {
+        let path = resource_dir.join(inner_path);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, bytes).unwrap();
+    }
+}
+
+/// A Nemesis patch that replaces `data` of `#0002` with `data`.
+pub(crate) fn nemesis_patch(mod_code: &str, data: &str) -> String {
+    format!(
+        r##"		<hkobject name="#0002" class="hkbStringEventPayload" signature="0xed04256a">
+<!-- MOD_CODE ~{mod_code}~ OPEN -->
+			<hkparam name="data">{data}</hkparam>
+<!-- ORIGINAL -->
+			<hkparam name="data">Original</hkparam>
+<!-- CLOSE -->
+		</hkobject>
+"##
+    )
+}
+
+/// A Nemesis patch that pushes `event` to `eventNames` of `#0003`.
+pub(crate) fn push_patch(mod_code: &str, event: &str) -> String {
+    format!(
+        r##"		<hkobject name="#0003" class="hkbBehaviorGraphStringData" signature="0xc713064e">
+			<hkparam name="eventNames" numelements="1">
+				<hkcstring>Original</hkcstring>
+<!-- MOD_CODE ~{mod_code}~ OPEN -->
+				<hkcstring>{event}</hkcstring>
+<!-- CLOSE -->
+			</hkparam>
+		</hkobject>
+"##
+    )
+}
+
+/// e.g. `mods/aaaa/Nemesis_Engine/mod/aaaa`
+pub(crate) fn mod_dir(mod_code: &str) -> String {
+    format!("mods/{mod_code}/Nemesis_Engine/mod/{mod_code}")
+}
+
+/// e.g. `mods/aaaa/Nemesis_Engine/mod/aaaa/defaultmale/#0002.txt`
+pub(crate) fn patch_path(mod_code: &str, template_name: &str, id: &str) -> String {
+    format!("{}/{template_name}/{id}.txt", mod_dir(mod_code))
+}
+
+/// Mods that patch every template of [`TEMPLATES`] by [`nemesis_patch`].
+/// - `mods`: (mod_code, `data` to write, priority)
+pub(crate) fn mods(mods: &[(&str, &str, usize)]) -> (PatchMaps, MemoryPatchSource) {
+    let mut patches = PatchMaps::default();
+    let mut source = MemoryPatchSource::new();
+
+    for &(mod_code, data, priority) in mods {
+        for (template_name, _) in TEMPLATES {
+            source.insert(
+                patch_path(mod_code, template_name, "#0002"),
+                nemesis_patch(mod_code, data),
+            );
+        }
+        patches.nemesis_entries.insert(mod_dir(mod_code), priority);
+    }
+
+    (patches, source)
+}
+
+/// All the files below `dir`. (sorted)
+pub(crate) fn files_below(dir: &Path) -> Vec<PathBuf> {
+    let mut files: Vec<_> = jwalk::WalkDir::new(dir)
+        .into_iter()
+        .filter_map(|entry| {
+            let path = entry.ok()?.path();
+            path.is_file().then_some(path)
+        })
+        .collect();
+    files.sort();
+    files
+}
@@ -1,3 +1,4 @@
+pub(crate) mod fixture;
 pub(crate) mod patches_builder;
 mod status;
 
@@ -24,3 +25,20 @@ async fn merge_test() -> Result<(), Box<dyn std::error::Error>> {
     crate::behavior_gen(patches, config).await?;
     Ok(())
 }
+
+/// Generate twice with the same inputs and compare the outputs byte for byte.
+#[tokio::test]
+#[ignore = "local test"]
+async fn reproducible_output_test() -> Result<(), Box<dyn std::error::Error>> {
+    let patches = build_patch_maps(PatchMapsConfig {
+        pattern: "D:/GAME/ModOrganizer Skyrim SE/mods/*",
+        nemesis_excludes: &["dmco", "tkuc"],
+        ..Default::default()
+    });
+    let config = self::status::fastest_config();
+
+    let report = crate::check_reproducibility(&patches, &config).await?;
+    assert!(report.outputs_len > 0);
+    assert!(report.is_reproducible(), "Outputs differ between runs: {:#?}", report.mismatches);
+    Ok(())
+}
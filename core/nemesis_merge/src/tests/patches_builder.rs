@@ -22,7 +22,7 @@ pub(crate) fn build_patch_maps(config: PatchMapsConfig<'_>) -> PatchMaps {
 
     let PatchMapsConfig { pattern, use_vfs, nemesis_excludes, fnis_excludes } = config;
 
-    let mod_list = mod_info::get_all(pattern, use_vfs).unwrap();
+    let mod_list = mod_info::get_all(pattern, use_vfs, false).unwrap();
     let nemesis_excludes: HashSet<&str> = nemesis_excludes.iter().copied().collect();
     let fnis_excludes: HashSet<&str> = fnis_excludes.iter().copied().collect();
 
@@ -36,7 +36,9 @@ pub(crate) fn build_patch_maps(config: PatchMapsConfig<'_>) -> PatchMaps {
                 .unwrap_or(&info.id);
 
             match info.mod_type {
-                ModType::Nemesis | ModType::NemesisExt if !nemesis_excludes.contains(mod_id) => {
+                ModType::Nemesis | ModType::NemesisExt | ModType::Pandora
+                    if !nemesis_excludes.contains(mod_id) =>
+                {
                     Some(Either::Left((info.id.clone(), idx)))
                 }
                 ModType::Fnis if !fnis_excludes.contains(mod_id) => {
@@ -1,4 +1,4 @@
-use crate::{Config, DebugOptions, HackOptions, OutPutTarget, Status};
+use crate::{CancellationToken, Config, DebugOptions, HackOptions, OutPutTarget, Status};
 
 // =======================
 // ANSI Color Constants
@@ -55,14 +55,20 @@ pub(crate) fn fastest_config() -> Config {
         output_dir: "../../dummy/behavior_gen/output".into(),
         status_report: None,
         hack_options: Some(HackOptions::enable_all()),
+        strict_original: false,
         debug: DebugOptions {
             output_patch_json: false,
             output_merged_json: false,
             output_merged_xml: false,
+            output_provenance: false,
         },
         output_target: OutPutTarget::SkyrimSe,
         skyrim_data_dir_glob: Some("../../dummy/fnis_test_mods/*".into()),
+        read_bsa: false,
         generate_fnis_esp: true,
+        cancel_token: CancellationToken::new(),
+        output_sink: None,
+        patch_source: None,
     }
 }
 
@@ -74,13 +80,19 @@ pub(crate) fn slow_debug_config() -> Config {
         status_report: Some(new_color_status_reporter()), // +2s
         // status_report: None,
         hack_options: Some(HackOptions::enable_all()),
+        strict_original: false,
         debug: DebugOptions {
             output_patch_json: true,
             output_merged_json: true,
             output_merged_xml: true,
+            output_provenance: true,
         },
         output_target: OutPutTarget::SkyrimSe,
         skyrim_data_dir_glob: Some("../../dummy/fnis_test_mods/*".into()),
+        read_bsa: false,
         generate_fnis_esp: true,
+        cancel_token: CancellationToken::new(),
+        output_sink: None,
+        patch_source: None,
     }
 }
@@ -18,6 +18,32 @@ pub enum Error {
     /// Unknown field type name: {field_type}
     UnknownFieldType { field_type: String },
 
+    /// Missing field: {field_name}
+    MissingField { field_name: String },
+
+    /// Expected a value of `{expected}`, but got `{value}`
+    UnexpectedValue { expected: String, value: String },
+
+    /// Not found `{ptr}`(`{class_name}`) in the template to write the patch.
+    MissingTemplateObject { ptr: String, class_name: String },
+
+    /// Not found the signature of `{class_name}`
+    MissingSignature { class_name: String },
+
+    /// `{action}` cannot be written as a Nemesis patch: {path}
+    UnsupportedPatch { path: String, action: String },
+
+    /// The range of a seq patch is out of the array.
+    #[snafu(display("The range {range:?} is out of the array(len: {len}): {path}"))]
+    OutOfRangePatch { path: String, range: core::ops::Range<usize>, len: usize },
+
+    /// Failed to parse the hack rules json
+    #[snafu(display("Failed to parse the hack rules: {source}"))]
+    HackRulesJson { source: simd_json::Error },
+
+    /// Invalid hack rule {rule}: {reason}
+    InvalidHackRule { rule: String, reason: &'static str },
+
     /// `<! -- MODE_CODE ~<id>~` and `<! -- CLOSE -->` should be a pair,
     /// but before the `CLOSE` comment comes `<! -- MODE_CODE ~<id>~` has come twice.
     AlreadyPatchMode,
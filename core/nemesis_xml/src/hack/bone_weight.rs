@@ -9,25 +9,30 @@ use crate::helpers::{
     tag::end_tag,
 };
 
-/// Matches `<hkparam name="boneWeights" numelements="0"></hkparam>` (with surrounding whitespace).
-fn bone_weight_empty_hkparam(input: &mut &str) -> ModalResult<()> {
+/// Matches `<hkparam name="{field}" numelements="0"></hkparam>` (with surrounding whitespace),
+/// and peeks that a `MOD_CODE ~{mod_code}~` comment follows.
+///
+/// - `mod_code`: Any mod id if `None`.
+pub(crate) fn empty_field_then_mod_code(
+    input: &mut &str,
+    field: &str,
+    mod_code: Option<&str>,
+) -> ModalResult<()> {
     (
         multispace0,
-        "<hkparam name=\"boneWeights\" numelements=\"0\">",
+        "<hkparam name=\"",
+        field,
+        "\" numelements=\"0\">",
         multispace0,
         "</hkparam>",
         multispace0,
     )
         .void()
-        .parse_next(input)
-}
-
-/// Peeks that after the empty hkparam a `MOD_CODE ~colisc~` comment follows.
-pub(crate) fn bone_weight_then_mod_code(input: &mut &str) -> ModalResult<()> {
-    (
-        bone_weight_empty_hkparam,
-        comment_kind.verify(|kind| matches!(kind, CommentKind::ModCode("colisc"))),
-    )
+        .parse_next(input)?;
+    comment_kind
+        .verify(|kind| {
+            matches!(kind, CommentKind::ModCode(id) if mod_code.is_none_or(|mod_code| *id == mod_code))
+        })
         .void()
         .parse_next(input)
 }
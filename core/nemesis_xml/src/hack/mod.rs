@@ -11,20 +11,24 @@
 //!
 //! ## Hack Behavior
 //! The [`HackOptions`] structs expose flags that enable selective leniency
-//! during parsing. For example, the [`do_hack_cast_ragdoll_event`] function replaces the invalid
-//! `event` field in the ragdoll modifier class with a valid `contactEvent` field of type
+//! during parsing. For example, [`HackOptions::cast_ragdoll_event`] reads the invalid
+//! `event` field in the ragdoll modifier class as a valid `contactEvent` field of type
 //! `Object|hkbEventProperty`.
 //!
 //! These hacks are intended for compatibility with real-world data and modding ecosystems,
 //! and are not recommended for validating strictly well-formed files.
 //!
+//! The parser reads the hacks as [`HackRules`]. The [`HackOptions`] flags are the built-in rules
+//! ([`HackRules::builtin`]), and more rules can be loaded from `hack_rules.json`([`HackRules::from_json`]).
+//!
 //! ## Safety and Limitations
 //! Enabling hacks may allow invalid data to pass silently. Use only when working with trusted
 //! or known input formats, and prefer strict parsing in validation or toolchain scenarios.
 pub(crate) mod bone_weight;
-mod bs_ragdoll_event;
+mod rules;
 
-pub use bs_ragdoll_event::do_hack_cast_ragdoll_event;
+pub(crate) use rules::coerce;
+pub use rules::{HackRule, HackRules};
 
 /// A collection of hack options that enable non-standard parsing behavior.
 ///
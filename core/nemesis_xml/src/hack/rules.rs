@@ -0,0 +1,260 @@
+//! Compatibility hacks as data.
+//!
+//! Each hack used to be a hand-written parser and a `bool` of [`HackOptions`] threaded through every crate.
+//! [`HackRules`] describes them as data, so that a workaround for a new community patch is a line of
+//! `hack_rules.json` in the resource dir.
+//!
+//! ```json
+//! {
+//!   "rules": [
+//!     { "kind": "rename_field", "class": "BSRagdollContactListenerModifier", "from": "event", "to": "contactEvent" },
+//!     { "kind": "coerce_type", "class": "hkbClipGenerator", "field": "playbackSpeed", "from": "I64" },
+//!     { "kind": "mod_code_outside_field", "class": "hkbBoneWeightArray", "field": "boneWeights", "mod_code": "colisc" }
+//!   ]
+//! }
+//! ```
+use simd_json::{BorrowedValue, StaticNode};
+use snafu::ResultExt as _;
+
+use super::HackOptions;
+use crate::{
+    error::{Error, HackRulesJsonSnafu, Result},
+    patch::class_table::{FieldInfo, find_class_info},
+};
+
+/// A workaround for a known mistake in community patches.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum HackRule {
+    /// Read the unknown field `from`(case insensitive) of `class` as the field `to`.
+    ///
+    /// e.g. `event` -> `contactEvent` of `BSRagdollContactListenerModifier`
+    RenameField { class: String, from: String, to: String },
+
+    /// Read the value of the field as `from`(`I64`, `U64`, `F64` or `Bool`), and convert it to the field type.
+    ///
+    /// e.g. `1` for a `F64` field -> `1.0`
+    CoerceType { class: String, field: String, from: String },
+
+    /// Accept the `MOD_CODE` after the empty field instead of inside it, and push the values in it.
+    /// The field must be `Array|F64`.
+    ///
+    /// ```xml
+    /// <hkparam name="boneWeights" numelements="0"></hkparam>
+    /// <!-- MOD_CODE ~colisc~ OPEN -->
+    /// 0.000000 0.100000
+    /// </hkparam>
+    /// <!-- CLOSE -->
+    /// ```
+    ///
+    /// - `mod_code`: Only for this mod id. Any if `None`.
+    ModCodeOutsideField {
+        class: String,
+        field: String,
+        #[serde(default)]
+        mod_code: Option<String>,
+    },
+}
+
+/// Hack rules applied by the patch parser.
+#[derive(Debug, Clone, Default, PartialEq, Eq, serde::Deserialize)]
+pub struct HackRules {
+    pub rules: Vec<HackRule>,
+}
+
+impl HackRules {
+    /// The built-in rules of the enabled [`HackOptions`].
+    pub fn builtin(options: HackOptions) -> Self {
+        let mut rules = Vec::new();
+        if options.cast_ragdoll_event {
+            for (from, to) in [("event", "contactEvent"), ("anotherBoneIndex", "bones")] {
+                rules.push(HackRule::RenameField {
+                    class: "BSRagdollContactListenerModifier".to_string(),
+                    from: from.to_string(),
+                    to: to.to_string(),
+                });
+            }
+        }
+        if options.bone_weight_outside_hkparam {
+            rules.push(HackRule::ModCodeOutsideField {
+                class: "hkbBoneWeightArray".to_string(),
+                field: "boneWeights".to_string(),
+                mod_code: Some("colisc".to_string()),
+            });
+        }
+        Self { rules }
+    }
+
+    /// Parse `hack_rules.json`.
+    ///
+    /// # Errors
+    /// If the json is invalid, or a rule refers to an unknown class/field/type.
+    pub fn from_json(json: &mut [u8]) -> Result<Self> {
+        let rules: Self = simd_json::serde::from_slice(json).context(HackRulesJsonSnafu)?;
+        for rule in &rules.rules {
+            rule.validate()?;
+        }
+        Ok(rules)
+    }
+
+    /// Add the rules of `other` after these.
+    pub fn extend(&mut self, other: Self) {
+        self.rules.extend(other.rules);
+    }
+
+    #[inline]
+    pub const fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    /// Get the (field name, field type) that the unknown field is renamed to.
+    pub(crate) fn rename(
+        &self,
+        class_name: &str,
+        field_name: &str,
+    ) -> Option<(&'static str, &'static str)> {
+        self.rules.iter().find_map(|rule| match rule {
+            HackRule::RenameField { class, from, to }
+                if class.eq_ignore_ascii_case(class_name)
+                    && from.eq_ignore_ascii_case(field_name) =>
+            {
+                field_entry(class_name, to)
+            }
+            _ => None,
+        })
+    }
+
+    /// Get the type that the value of the field is written in.
+    pub(crate) fn coercion(&self, class_name: &str, field_name: &str) -> Option<&'static str> {
+        self.rules.iter().find_map(|rule| match rule {
+            HackRule::CoerceType { class, field, from }
+                if class == class_name && field == field_name =>
+            {
+                scalar_type(from)
+            }
+            _ => None,
+        })
+    }
+
+    /// Get the (field name, mod id) of [`HackRule::ModCodeOutsideField`] for the class.
+    pub(crate) fn mod_code_outside_fields<'a>(
+        &'a self,
+        class_name: &str,
+    ) -> impl Iterator<Item = (&'a str, Option<&'a str>)> {
+        self.rules.iter().filter_map(move |rule| match rule {
+            HackRule::ModCodeOutsideField { class, field, mod_code } if class == class_name => {
+                Some((field.as_str(), mod_code.as_deref()))
+            }
+            _ => None,
+        })
+    }
+}
+
+impl HackRule {
+    fn validate(&self) -> Result<()> {
+        let (class, field, reason) = match self {
+            Self::RenameField { class, to, .. } => {
+                (class, to, field_entry(class, to).is_none().then_some("unknown field `to`"))
+            }
+            Self::CoerceType { class, field, from } => {
+                let reason = match field_entry(class, field) {
+                    None => Some("unknown field"),
+                    Some((_, field_type)) if scalar_type(field_type).is_none() => {
+                        Some("the field is not `I64`, `U64`, `F64` or `Bool`")
+                    }
+                    Some(_) if scalar_type(from).is_none() => {
+                        Some("`from` is not `I64`, `U64`, `F64` or `Bool`")
+                    }
+                    Some(_) => None,
+                };
+                (class, field, reason)
+            }
+            Self::ModCodeOutsideField { class, field, .. } => {
+                let reason = match field_entry(class, field) {
+                    None => Some("unknown field"),
+                    Some((_, "Array|F64")) => None,
+                    Some(_) => Some("the field is not `Array|F64`"),
+                };
+                (class, field, reason)
+            }
+        };
+
+        if find_class_info(class).is_none() {
+            return Err(Error::InvalidHackRule {
+                rule: format!("{self:?}"),
+                reason: "unknown class",
+            });
+        }
+        match reason {
+            Some(reason) => {
+                Err(Error::InvalidHackRule { rule: format!("{self:?}: `{class}.{field}`"), reason })
+            }
+            None => Ok(()),
+        }
+    }
+}
+
+/// Convert the value of `from` type to the field type. Others(e.g. `$eventID[...]$`) are kept as is.
+pub(crate) fn coerce(value: BorrowedValue<'_>, field_type: &str) -> BorrowedValue<'_> {
+    let BorrowedValue::Static(node) = &value else {
+        return value;
+    };
+    let number = match *node {
+        StaticNode::I64(n) => n as f64,
+        StaticNode::U64(n) => n as f64,
+        StaticNode::F64(n) => n,
+        StaticNode::Bool(b) => f64::from(u8::from(b)),
+        StaticNode::Null => return value,
+    };
+
+    match field_type {
+        "I64" => (number as i64).into(),
+        "U64" => (number as u64).into(),
+        "F64" => number.into(),
+        "Bool" => (number.abs() > 0.0).into(),
+        _ => value,
+    }
+}
+
+fn field_entry(class_name: &str, field_name: &str) -> Option<(&'static str, &'static str)> {
+    let field_info: &FieldInfo = find_class_info(class_name)?;
+    field_info.get_entry(field_name).map(|(name, field_type)| (*name, *field_type))
+}
+
+fn scalar_type(field_type: &str) -> Option<&'static str> {
+    ["I64", "U64", "F64", "Bool"].into_iter().find(|scalar| *scalar == field_type)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn load_rules() {
+        let mut json = br#"{
+            "rules": [
+                { "kind": "rename_field", "class": "BSRagdollContactListenerModifier", "from": "event", "to": "contactEvent" },
+                { "kind": "coerce_type", "class": "hkbClipGenerator", "field": "playbackSpeed", "from": "I64" },
+                { "kind": "mod_code_outside_field", "class": "hkbBoneWeightArray", "field": "boneWeights" }
+            ]
+        }"#
+        .to_vec();
+        let rules = HackRules::from_json(&mut json).unwrap_or_else(|e| panic!("{e}"));
+
+        assert_eq!(
+            rules.rename("BSRagdollContactListenerModifier", "EVENT"),
+            Some(("contactEvent", "Object|hkbEventProperty"))
+        );
+        assert_eq!(rules.coercion("hkbClipGenerator", "playbackSpeed"), Some("I64"));
+        assert_eq!(
+            rules.mod_code_outside_fields("hkbBoneWeightArray").collect::<Vec<_>>(),
+            [("boneWeights", None)]
+        );
+        assert_eq!(coerce(BorrowedValue::from(2_i64), "F64"), BorrowedValue::from(2.0));
+
+        let mut json =
+            br#"{ "rules": [{ "kind": "coerce_type", "class": "hkbClipGenerator", "field": "name", "from": "I64" }] }"#
+                .to_vec();
+        assert!(HackRules::from_json(&mut json).is_err());
+    }
+}
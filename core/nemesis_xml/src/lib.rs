@@ -1,4 +1,6 @@
 pub mod error;
 pub mod hack;
 pub mod helpers;
+pub mod lint;
 pub mod patch;
+pub mod ser;
@@ -0,0 +1,600 @@
+//! Lints of one patch file.
+use std::ops::Range;
+
+use serde_hkx::xml::de::parser::type_kind::{boolean, real};
+use winnow::{Parser as _, combinator::alt};
+
+use super::{
+    Scope, Severity,
+    tokens::{Token, Tokens},
+};
+use crate::{
+    hack::{HackRules, bone_weight::empty_field_then_mod_code},
+    helpers::{
+        comment::CommentKind,
+        variable::{event_id, variable_id},
+    },
+    patch::class_table::{FieldInfo, find_class_info, find_json_parser_by},
+    ser::class_object_type,
+};
+
+/// What is found in a file.
+#[derive(Debug, Default)]
+pub(super) struct FileLint<'a> {
+    /// `name` of the root `<hkobject>`s and the spans of the start tags. e.g. `#0052`, `#slide$1`
+    pub(super) objects: Vec<(&'a str, Range<usize>)>,
+    /// `eventNames` of `hkbBehaviorGraphStringData`.
+    pub(super) events: Vec<&'a str>,
+    /// `variableNames` of `hkbBehaviorGraphStringData`.
+    pub(super) variables: Vec<&'a str>,
+    /// Has `hkbBehaviorGraphStringData`, so that `events` and `variables` have all the names.
+    pub(super) has_string_data: bool,
+    /// Names that other files may define.
+    pub(super) references: Vec<Reference<'a>>,
+    pub(super) findings: Vec<Finding>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum ReferenceKind {
+    /// Pointer to an added object. e.g. `#slide$1`
+    Object,
+    /// e.g. `$eventID[slideStart]$`
+    Event,
+    /// e.g. `$variableID[bSlide]$`
+    Variable,
+}
+
+#[derive(Debug)]
+pub(super) struct Reference<'a> {
+    pub(super) kind: ReferenceKind,
+    pub(super) name: &'a str,
+    pub(super) span: Range<usize>,
+}
+
+#[derive(Debug, Clone)]
+pub(super) struct Finding {
+    pub(super) span: Range<usize>,
+    pub(super) severity: Severity,
+    pub(super) message: String,
+}
+
+/// Lint a file by tokens.
+///
+/// - `rules`: The known mistakes that are reported as warnings instead of errors.
+pub(super) fn check_file<'a>(src: &'a str, rules: &HackRules) -> FileLint<'a> {
+    let mut checker = Checker::new(src, rules);
+    for (span, token) in Tokens::new(src) {
+        checker.token(span, &token);
+    }
+    checker.finish()
+}
+
+/// The tags that are open at `offset`, from the root.
+pub(super) fn open_scopes<'a>(src: &'a str, offset: usize, rules: &HackRules) -> Vec<Scope<'a>> {
+    let mut checker = Checker::new(src, rules);
+    for (span, token) in Tokens::new(src).take_while(|(span, _)| span.end <= offset) {
+        checker.token(span, &token);
+    }
+
+    checker
+        .stack
+        .into_iter()
+        .map(|Frame { tag, span, kind }| match kind {
+            FrameKind::Object { class_name, .. } => Scope::Object { class_name, span },
+            FrameKind::Field { name, field_type, .. } => Scope::Field { name, field_type, span },
+            FrameKind::Other => Scope::Other { tag, span },
+        })
+        .collect()
+}
+
+#[derive(Debug)]
+struct Checker<'a, 'r> {
+    src: &'a str,
+    rules: &'r HackRules,
+    stack: Vec<Frame<'a>>,
+    /// `MOD_CODE` waiting for `CLOSE`.
+    patch: Option<OpenPatch<'r>>,
+    lint: FileLint<'a>,
+}
+
+#[derive(Debug)]
+struct Frame<'a> {
+    tag: &'a str,
+    span: Range<usize>,
+    kind: FrameKind<'a>,
+}
+
+#[derive(Debug)]
+enum FrameKind<'a> {
+    /// `<hkobject>`. `field_info` is `None` if the class is unknown.
+    Object { class_name: &'a str, field_info: Option<&'static FieldInfo> },
+    /// `<hkparam>`. `field_type` is `None` if the field is unknown.
+    Field {
+        name: &'a str,
+        field_type: Option<&'static str>,
+        numelements: Option<u64>,
+        counts: ElemCounts,
+    },
+    /// `<hkcstring>` and unknown tags.
+    Other,
+}
+
+/// Number of array elements by the part of the patch.
+#[derive(Debug, Default)]
+struct ElemCounts {
+    /// Out of `MOD_CODE` ~ `CLOSE`.
+    common: u64,
+    /// `MOD_CODE` ~ `ORIGINAL`(or `CLOSE`)
+    new: u64,
+    /// `ORIGINAL` ~ `CLOSE`
+    original: u64,
+}
+
+#[derive(Debug)]
+struct OpenPatch<'r> {
+    span: Range<usize>,
+    /// Depth of the tags where `MOD_CODE` is.
+    depth: usize,
+    is_original: bool,
+    /// The field of the known mistake that `MOD_CODE` comes after its `</hkparam>`. e.g. `boneWeights`
+    /// The stray `</hkparam>` before `CLOSE` is accepted. (See [`crate::hack::HackRule::ModCodeOutsideField`])
+    outside_field: Option<&'r str>,
+}
+
+impl<'a, 'r> Checker<'a, 'r> {
+    fn new(src: &'a str, rules: &'r HackRules) -> Self {
+        Self { src, rules, stack: Vec::new(), patch: None, lint: FileLint::default() }
+    }
+
+    fn token(&mut self, span: Range<usize>, token: &Token<'a>) {
+        match token {
+            Token::Comment(kind) => self.comment(span, kind),
+            Token::StartTag { name, is_empty, .. } => {
+                self.start_tag(span.clone(), *name, token);
+                if *is_empty {
+                    self.end_tag(span, name);
+                }
+            }
+            Token::EndTag(name) => self.end_tag(span, name),
+            Token::Text(text) => self.text(span, *text),
+            Token::Invalid(text) => {
+                self.push(span, Severity::Error, format!("Invalid XML: `{text}`"));
+            }
+        }
+    }
+
+    fn comment(&mut self, span: Range<usize>, kind: &CommentKind<'a>) {
+        match (kind, &mut self.patch) {
+            (CommentKind::ModCode(_), Some(open)) => {
+                let (line, _) = super::line_column(self.src, open.span.start);
+                self.push(
+                    span,
+                    Severity::Error,
+                    format!("`MOD_CODE` before the `CLOSE` of the `MOD_CODE` at line {line}"),
+                );
+            }
+            (CommentKind::ModCode(_), None) => {
+                let outside_field = self.mod_code_outside_field_at(span.start);
+                self.patch = Some(OpenPatch {
+                    span,
+                    depth: self.stack.len(),
+                    is_original: false,
+                    outside_field,
+                });
+            }
+            (CommentKind::Original, Some(open)) if open.is_original => {
+                self.push(span, Severity::Error, "`ORIGINAL` twice before `CLOSE`".to_string());
+            }
+            (CommentKind::Original, Some(open)) => open.is_original = true,
+            (CommentKind::Original | CommentKind::Close, None) => {
+                self.push(span, Severity::Error, "No `MOD_CODE` before this comment".to_string());
+            }
+            (CommentKind::Close, Some(open)) => {
+                if open.depth != self.stack.len() {
+                    let open = open.span.clone();
+                    self.push(
+                        open,
+                        Severity::Error,
+                        "`MOD_CODE` and its `CLOSE` are in different tags".to_string(),
+                    );
+                }
+                self.patch = None;
+            }
+            (CommentKind::Unknown(comment), _) if comment.contains("MOD_CODE") => {
+                self.push(
+                    span,
+                    Severity::Warning,
+                    "Not a patch comment. Expected `<!-- MOD_CODE ~id~ OPEN -->`".to_string(),
+                );
+            }
+            (CommentKind::Unknown(_), _) => {}
+        }
+    }
+
+    /// Get the field if this `MOD_CODE` is right after it. e.g. `<hkparam name="boneWeights" numelements="0"></hkparam>`
+    fn mod_code_outside_field_at(&self, mod_code_start: usize) -> Option<&'r str> {
+        let Some(Frame { kind: FrameKind::Object { class_name, .. }, .. }) = self.stack.last()
+        else {
+            return None;
+        };
+        let before = &self.src[..mod_code_start];
+        if !before.trim_end().ends_with("</hkparam>") {
+            return None;
+        }
+        let start = before.rfind("<hkparam")?;
+
+        // Same condition as the parser hack.
+        self.rules.mod_code_outside_fields(class_name).find_map(|(field, mod_code)| {
+            let mut input = &self.src[start..];
+            empty_field_then_mod_code(&mut input, field, mod_code).is_ok().then_some(field)
+        })
+    }
+
+    fn start_tag(&mut self, span: Range<usize>, name: &'a str, token: &Token<'a>) {
+        let kind = match name {
+            "hkobject" => self.object_start(&span, token),
+            "hkparam" => self.field_start(&span, token),
+            "hkcstring" => {
+                if !matches!(self.field_type(), Some("Array|String")) {
+                    self.push(
+                        span.clone(),
+                        Severity::Error,
+                        "Unexpected `<hkcstring>`".to_string(),
+                    );
+                }
+                self.count_elements(1);
+                FrameKind::Other
+            }
+            _ => {
+                self.push(span.clone(), Severity::Error, format!("Unknown tag `<{name}>`"));
+                FrameKind::Other
+            }
+        };
+        self.stack.push(Frame { tag: name, span, kind });
+    }
+
+    fn object_start(&mut self, span: &Range<usize>, token: &Token<'a>) -> FrameKind<'a> {
+        // Root: `<hkobject name="#0052" class="hkbBehaviorGraphStringData" signature="0xc713064e">`
+        if self.stack.is_empty() {
+            let (Some(ptr), Some(class_name)) = (token.attr("name"), token.attr("class")) else {
+                self.push(
+                    span.clone(),
+                    Severity::Error,
+                    "Expected `<hkobject name=\"...\" class=\"...\" signature=\"...\">`"
+                        .to_string(),
+                );
+                return FrameKind::Object { class_name: "", field_info: None };
+            };
+
+            self.lint.objects.push((ptr, span.clone()));
+            if class_name == "hkbBehaviorGraphStringData" {
+                self.lint.has_string_data = true;
+            }
+            let field_info = find_class_info(class_name);
+            if field_info.is_none() {
+                self.push(span.clone(), Severity::Error, format!("Unknown class `{class_name}`"));
+            }
+            return FrameKind::Object { class_name, field_info };
+        }
+
+        // In a field: `<hkobject>`
+        if !matches!(self.stack.last(), Some(Frame { kind: FrameKind::Field { .. }, .. })) {
+            self.push(span.clone(), Severity::Error, "`<hkobject>` out of `<hkparam>`".to_string());
+        }
+        let class_name = match self.field_type() {
+            Some(field_type) => {
+                let class_name = field_type.strip_prefix("Array|").unwrap_or(field_type);
+                let class_name = class_object_type(class_name);
+                if class_name.is_none() {
+                    self.push(
+                        span.clone(),
+                        Severity::Error,
+                        format!("`<hkobject>` in the field of `{field_type}`"),
+                    );
+                }
+                class_name
+            }
+            None => None,
+        };
+        if matches!(self.field_type(), Some(field_type) if field_type.starts_with("Array|")) {
+            self.count_elements(1);
+        }
+        FrameKind::Object {
+            class_name: class_name.unwrap_or_default(),
+            field_info: class_name.and_then(find_class_info),
+        }
+    }
+
+    fn field_start(&mut self, span: &Range<usize>, token: &Token<'a>) -> FrameKind<'a> {
+        let name = token.attr("name").unwrap_or_default();
+        let numelements = token.attr("numelements").and_then(|len| {
+            let len = len.parse().ok();
+            if len.is_none() {
+                self.push(
+                    span.clone(),
+                    Severity::Error,
+                    "`numelements` is not a number".to_string(),
+                );
+            }
+            len
+        });
+
+        let field_type = match self.stack.last() {
+            Some(Frame { kind: FrameKind::Object { class_name, field_info }, .. }) => {
+                let (class_name, field_info) = (*class_name, *field_info);
+                field_info.and_then(|field_info| {
+                    let field_type = find_json_parser_by(name, field_info);
+                    if field_type.is_none() {
+                        return self.unknown_field(span, class_name, name, field_info);
+                    }
+                    field_type
+                })
+            }
+            _ => {
+                self.push(
+                    span.clone(),
+                    Severity::Error,
+                    "`<hkparam>` out of `<hkobject>`".to_string(),
+                );
+                None
+            }
+        };
+
+        FrameKind::Field { name, field_type, numelements, counts: ElemCounts::default() }
+    }
+
+    /// Report an unknown field, and get the type of it if this is a known mistake.
+    fn unknown_field(
+        &mut self,
+        span: &Range<usize>,
+        class_name: &str,
+        name: &str,
+        field_info: &FieldInfo,
+    ) -> Option<&'static str> {
+        // Same condition as the parser hack.
+        if let Some((correct_name, field_type)) = self.rules.rename(class_name, name) {
+            self.push(
+                span.clone(),
+                Severity::Warning,
+                format!(
+                    "`{name}` is a known mistake for `{correct_name}`. Only accepted by the hack rules"
+                ),
+            );
+            return Some(field_type);
+        }
+
+        let acceptable_fields: Vec<_> = field_info.keys().copied().collect();
+        self.push(
+            span.clone(),
+            Severity::Error,
+            format!(
+                "Unknown field `{name}` of `{class_name}`. Expected one of `[{}]`",
+                acceptable_fields.join(", ")
+            ),
+        );
+        None
+    }
+
+    fn end_tag(&mut self, span: Range<usize>, name: &str) {
+        if self.stack.last().is_some_and(|frame| frame.tag == name) {
+            if let Some(frame) = self.stack.pop() {
+                self.check_numelements(&frame);
+            }
+            return;
+        }
+
+        if name == "hkparam"
+            && let Some(open) = &self.patch
+            && let Some(field) = open.outside_field
+            && open.depth == self.stack.len()
+        {
+            let open = open.span.clone();
+            self.push(
+                open,
+                Severity::Warning,
+                format!(
+                    "`MOD_CODE` out of `{field}` is a known mistake. Only accepted by the hack rules"
+                ),
+            );
+            return;
+        }
+
+        let message = match self.stack.last() {
+            Some(frame) => format!("Expected `</{}>`, but found `</{name}>`", frame.tag),
+            None => format!("Unexpected `</{name}>`"),
+        };
+        self.push(span, Severity::Error, message);
+
+        // Recover by closing the tags up to the same name, if any.
+        if let Some(index) = self.stack.iter().rposition(|frame| frame.tag == name) {
+            self.stack.truncate(index);
+        }
+    }
+
+    fn text(&mut self, span: Range<usize>, text: &'a str) {
+        let Some(frame) = self.stack.last() else {
+            self.push(span, Severity::Error, "Text out of `<hkobject>`".to_string());
+            return;
+        };
+
+        match &frame.kind {
+            FrameKind::Field { field_type: Some(field_type), .. } => {
+                let field_type = *field_type;
+                self.field_text(span, field_type, text);
+            }
+            FrameKind::Field { field_type: None, .. } => {}
+            FrameKind::Object { .. } => {
+                if !self.patch.as_ref().is_some_and(|open| open.outside_field.is_some()) {
+                    self.push(span, Severity::Error, "Text out of `<hkparam>`".to_string());
+                }
+            }
+            FrameKind::Other if frame.tag == "hkcstring" => self.string_name(text),
+            FrameKind::Other => {}
+        }
+    }
+
+    /// Check the values in `<hkparam>` by the field type.
+    fn field_text(&mut self, span: Range<usize>, field_type: &'static str, text: &'a str) {
+        if let Some(elem_type) = field_type.strip_prefix("Array|") {
+            if elem_type.starts_with("Object|") || elem_type == "String" {
+                // Elements are `<hkobject>`, `<hkcstring>` or math types(e.g. `(0.0 0.0 0.0 0.0)`)
+                if class_object_type(elem_type).is_some() || elem_type == "String" {
+                    self.push(span, Severity::Error, format!("Unexpected text in `{field_type}`"));
+                }
+                return;
+            }
+
+            let mut count = 0;
+            for value in text.split_ascii_whitespace() {
+                count += 1;
+                self.check_value(sub_span(text, value, &span), elem_type, value);
+            }
+            self.count_elements(count);
+        } else if class_object_type(field_type).is_some() {
+            self.push(span, Severity::Error, format!("Expected `<hkobject>` of `{field_type}`"));
+        } else if field_type.starts_with("Object|") {
+            // Math types: `(0.000000 0.000000 1.000000 0.000000)`
+            for value in text.split(|c: char| c.is_ascii_whitespace() || c == '(' || c == ')') {
+                if !value.is_empty() {
+                    self.check_value(sub_span(text, value, &span), "F64", value);
+                }
+            }
+        } else {
+            self.check_value(span, field_type, text);
+        }
+    }
+
+    fn check_value(&mut self, span: Range<usize>, value_type: &str, value: &'a str) {
+        let is_valid = match value_type {
+            "I64" | "U64" if value.starts_with('$') => {
+                let reference = alt((
+                    event_id.map(|id: &str| (ReferenceKind::Event, &id[9..id.len() - 2])), // $eventID[name]$
+                    variable_id.map(|id: &str| (ReferenceKind::Variable, &id[12..id.len() - 2])), // $variableID[name]$
+                ))
+                .parse(value);
+                if let Ok((kind, name)) = reference {
+                    self.lint.references.push(Reference { kind, name, span: span.clone() });
+                }
+                reference.is_ok()
+            }
+            "I64" => value.parse::<i64>().is_ok(),
+            "U64" => value.parse::<u64>().is_ok(),
+            "F64" => real.parse(value).is_ok(),
+            "Bool" => boolean.parse(value).is_ok(),
+            "Pointer" => {
+                let is_index = value.strip_prefix('#').is_some_and(|index| {
+                    !index.is_empty() && index.bytes().all(|byte| byte.is_ascii_digit())
+                });
+                if !is_index && value != "null" {
+                    let kind = ReferenceKind::Object;
+                    self.lint.references.push(Reference { kind, name: value, span: span.clone() });
+                }
+                true
+            }
+            _ => true,
+        };
+
+        if !is_valid {
+            self.push(
+                span,
+                Severity::Error,
+                format!("Expected a value of `{value_type}`, but got `{value}`"),
+            );
+        }
+    }
+
+    /// Collect `<hkcstring>` of `hkbBehaviorGraphStringData.eventNames`/`variableNames`.
+    fn string_name(&mut self, name: &'a str) {
+        let [.., object, field, _] = self.stack.as_slice() else {
+            return;
+        };
+        let (
+            FrameKind::Object { class_name: "hkbBehaviorGraphStringData", .. },
+            FrameKind::Field { name: field_name, .. },
+        ) = (&object.kind, &field.kind)
+        else {
+            return;
+        };
+
+        match *field_name {
+            "eventNames" => self.lint.events.push(name),
+            "variableNames" => self.lint.variables.push(name),
+            _ => {}
+        }
+    }
+
+    /// The type of the innermost `<hkparam>`, if it is the current tag.
+    fn field_type(&self) -> Option<&'static str> {
+        match self.stack.last() {
+            Some(Frame { kind: FrameKind::Field { field_type, .. }, .. }) => *field_type,
+            _ => None,
+        }
+    }
+
+    fn count_elements(&mut self, count: u64) {
+        let depth = self.stack.len();
+        let Some(Frame { kind: FrameKind::Field { counts, .. }, .. }) = self.stack.last_mut()
+        else {
+            return;
+        };
+
+        match &self.patch {
+            Some(open) if open.depth == depth && open.is_original => counts.original += count,
+            Some(open) if open.depth == depth => counts.new += count,
+            _ => counts.common += count,
+        }
+    }
+
+    /// `numelements` may be the length either before or after the patch.
+    fn check_numelements(&mut self, frame: &Frame<'a>) {
+        let FrameKind::Field { name, field_type: Some(field_type), numelements: Some(len), counts } =
+            &frame.kind
+        else {
+            return;
+        };
+        // Math types are not counted.
+        let Some(elem_type) = field_type.strip_prefix("Array|") else {
+            return;
+        };
+        if elem_type == "Null"
+            || (elem_type.starts_with("Object|") && class_object_type(elem_type).is_none())
+        {
+            return;
+        }
+
+        let len = *len;
+        let original = counts.common + counts.original;
+        let new = counts.common + counts.new;
+        if len != original && len != new {
+            let message = if original == new {
+                format!("`numelements` is {len}, but `{name}` has {original} element(s)")
+            } else {
+                format!(
+                    "`numelements` is {len}, but `{name}` has {original} element(s)({new} after the patch)"
+                )
+            };
+            self.push(frame.span.clone(), Severity::Warning, message);
+        }
+    }
+
+    fn push(&mut self, span: Range<usize>, severity: Severity, message: String) {
+        self.lint.findings.push(Finding { span, severity, message });
+    }
+
+    fn finish(mut self) -> FileLint<'a> {
+        if let Some(open) = self.patch.take() {
+            self.push(open.span, Severity::Error, "`MOD_CODE` without `CLOSE`".to_string());
+        }
+        while let Some(frame) = self.stack.pop() {
+            let message = format!("`<{}>` is not closed", frame.tag);
+            self.push(frame.span, Severity::Error, message);
+        }
+        self.lint
+    }
+}
+
+/// Span of `sub` that is a part of `text`.
+fn sub_span(text: &str, sub: &str, span: &Range<usize>) -> Range<usize> {
+    let start = span.start + (sub.as_ptr().addr() - text.as_ptr().addr());
+    start..start + sub.len()
+}
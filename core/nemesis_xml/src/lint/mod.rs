@@ -0,0 +1,394 @@
+//! Lints of Nemesis patch files(`#NNNN.txt`) without merging.
+//!
+//! The patch parser tells nothing about the patches that parse but break the merge.
+//! This reads the whole files leniently and reports the following with the positions.
+//! - Unknown classes/fields and values that do not match the field type. (by the class table)
+//! - Unbalanced `MOD_CODE`/`ORIGINAL`/`CLOSE` comments.
+//! - `$eventID[...]$`/`$variableID[...]$` whose name is not in `hkbBehaviorGraphStringData` of the patches.
+//! - Pointers to added objects(e.g. `#slide$1`) that no patch adds.
+//! - `numelements` that matches neither the length before nor after the patch.
+//! - The known mistakes that [`crate::hack::HackRules`] work around.
+mod checker;
+mod tokens;
+
+use std::{
+    collections::{HashMap, HashSet},
+    fmt,
+    ops::Range,
+    path::{Path, PathBuf},
+};
+
+use self::checker::{FileLint, ReferenceKind, check_file, open_scopes};
+use crate::hack::HackRules;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Severity {
+    /// The patch fails to parse or to merge.
+    Error,
+    /// The patch may not work as intended, or only works with a hack option.
+    Warning,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Error => f.write_str("error"),
+            Self::Warning => f.write_str("warning"),
+        }
+    }
+}
+
+/// A finding of the lints.
+///
+/// Displayed as `<path>:<line>:<column>: <severity>: <message>`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    pub path: PathBuf,
+    /// 1 based line number.
+    pub line: usize,
+    /// 1 based column number in chars.
+    pub column: usize,
+    /// Byte range in the file.
+    pub span: Range<usize>,
+    pub severity: Severity,
+    pub message: String,
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let Self { path, line, column, severity, message, .. } = self;
+        write!(f, "{}:{line}:{column}: {severity}: {message}", path.display())
+    }
+}
+
+/// Lint Nemesis patch files.
+///
+/// - `files`: (path, content). e.g. (`Nemesis_Engine/mod/slide/0_master/#0106.txt`, `<hkobject name="#0106" ...`)
+/// - `rules`: The known mistakes that are reported as warnings instead of errors. e.g. `HackRules::builtin(HackOptions::enable_all())`
+///
+/// Names(added objects, events, variables) are looked up in the files of the same directory, as Nemesis does in a template.
+/// The template objects(e.g. `#0052`) are not checked, since a mod has only the patched ones.
+///
+/// # Returns
+/// Sorted by path and position.
+pub fn lint_patches<'a, I>(files: I, rules: &HackRules) -> Vec<Diagnostic>
+where
+    I: IntoIterator<Item = (&'a Path, &'a str)>,
+{
+    let files: Vec<_> =
+        files.into_iter().map(|(path, src)| (path, src, check_file(src, rules))).collect();
+
+    let mut dirs: HashMap<Option<&Path>, Names<'_>> = HashMap::new();
+    for (path, _, lint) in &files {
+        let names = dirs.entry(path.parent()).or_default();
+        names.objects.extend(lint.objects.iter().map(|(name, _)| *name));
+        names.events.extend(&lint.events);
+        names.variables.extend(&lint.variables);
+        names.has_string_data |= lint.has_string_data;
+    }
+
+    let mut diagnostics = Vec::new();
+    for (path, src, lint) in &files {
+        let names = &dirs[&path.parent()];
+        let unresolved = names.unresolved(lint).map(|(span, severity, message)| checker::Finding {
+            span,
+            severity,
+            message,
+        });
+
+        for checker::Finding { span, severity, message } in
+            lint.findings.iter().cloned().chain(unresolved)
+        {
+            let (line, column) = line_column(src, span.start);
+            diagnostics.push(Diagnostic {
+                path: path.to_path_buf(),
+                line,
+                column,
+                span,
+                severity,
+                message,
+            });
+        }
+    }
+
+    diagnostics.sort_by(|a, b| (&a.path, a.span.start).cmp(&(&b.path, b.span.start)));
+    diagnostics
+}
+
+/// A tag that is open at a position. See [`scopes_at`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Scope<'a> {
+    /// `<hkobject>`. `class_name` is empty if it is unknown.
+    Object { class_name: &'a str, span: Range<usize> },
+    /// `<hkparam name="...">`. `field_type` is `None` if the field is unknown.
+    Field { name: &'a str, field_type: Option<&'static str>, span: Range<usize> },
+    /// `<hkcstring>` and unknown tags.
+    Other { tag: &'a str, span: Range<usize> },
+}
+
+/// Get the tags that are open at the byte `offset`, from the root `<hkobject>`.
+///
+/// The class of a nested `<hkobject>` is resolved from the type of the field. (or of the field renamed by `rules`)
+/// This is for the editor features(e.g. completion of the field names).
+pub fn scopes_at<'a>(src: &'a str, offset: usize, rules: &HackRules) -> Vec<Scope<'a>> {
+    open_scopes(src, offset, rules)
+}
+
+/// Get the `name` of the root `<hkobject>`s and the byte ranges of their start tags.
+///
+/// e.g. (`#slide$0`, `<hkobject name="#slide$0" class="hkbStateMachineStateInfo" signature="0xed7f9d0">`)
+pub fn object_definitions(src: &str) -> Vec<(&str, Range<usize>)> {
+    check_file(src, &HackRules::default()).objects
+}
+
+/// Names defined in a directory.
+#[derive(Debug, Default)]
+struct Names<'a> {
+    objects: HashSet<&'a str>,
+    events: HashSet<&'a str>,
+    variables: HashSet<&'a str>,
+    has_string_data: bool,
+}
+
+impl Names<'_> {
+    fn unresolved<'l>(
+        &'l self,
+        lint: &'l FileLint<'_>,
+    ) -> impl Iterator<Item = (Range<usize>, Severity, String)> + 'l {
+        lint.references.iter().filter_map(|reference| {
+            let (names, kind) = match reference.kind {
+                ReferenceKind::Object => (&self.objects, "object"),
+                ReferenceKind::Event => (&self.events, "event"),
+                ReferenceKind::Variable => (&self.variables, "variable"),
+            };
+            if names.contains(reference.name) {
+                return None;
+            }
+
+            let name = reference.name;
+            let finding = match reference.kind {
+                ReferenceKind::Object => {
+                    (Severity::Error, format!("No patch adds the object `{name}`"))
+                }
+                // Without the string data, the name may be in the template.
+                _ if !self.has_string_data => (
+                    Severity::Warning,
+                    format!("No patch defines the {kind} `{name}`. (not found `hkbBehaviorGraphStringData` patch)"),
+                ),
+                _ => (Severity::Error, format!("No patch defines the {kind} `{name}`")),
+            };
+            Some((reference.span.clone(), finding.0, finding.1))
+        })
+    }
+}
+
+/// 1 based (line, column) of the byte offset.
+pub(crate) fn line_column(src: &str, offset: usize) -> (usize, usize) {
+    let before = &src[..offset];
+    let line = before.bytes().filter(|&byte| byte == b'\n').count() + 1;
+    let line_start = before.rfind('\n').map_or(0, |index| index + 1);
+    (line, before[line_start..].chars().count() + 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hack::HackOptions;
+
+    fn lint(files: &[(&str, &str)]) -> Vec<(String, usize, usize, Severity)> {
+        lint_with(files, &HackRules::builtin(HackOptions::enable_all()))
+    }
+
+    fn lint_with(
+        files: &[(&str, &str)],
+        rules: &HackRules,
+    ) -> Vec<(String, usize, usize, Severity)> {
+        lint_patches(files.iter().map(|(path, src)| (Path::new(*path), *src)), rules)
+            .into_iter()
+            .map(|diagnostic| {
+                let Diagnostic { path, line, column, severity, .. } = diagnostic;
+                (path.display().to_string(), line, column, severity)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn valid_patch() {
+        let string_data = r##"
+		<hkobject name="#0052" class="hkbBehaviorGraphStringData" signature="0xc713064e">
+			<hkparam name="eventNames" numelements="1">
+				<hkcstring>attackStart</hkcstring>
+<!-- MOD_CODE ~slide~ OPEN -->
+				<hkcstring>slideStart</hkcstring>
+<!-- CLOSE -->
+			</hkparam>
+			<hkparam name="variableNames" numelements="0"></hkparam>
+		</hkobject>
+"##;
+        let state_machine = r##"
+		<hkobject name="#0106" class="hkbStateMachine" signature="0x816c1dcb">
+			<hkparam name="eventToSendWhenStateOrTransitionChanges">
+				<hkobject>
+<!-- MOD_CODE ~slide~ OPEN -->
+					<hkparam name="id">$eventID[slideStart]$</hkparam>
+<!-- ORIGINAL -->
+					<hkparam name="id">-1</hkparam>
+<!-- CLOSE -->
+					<hkparam name="payload">null</hkparam>
+				</hkobject>
+			</hkparam>
+			<hkparam name="states" numelements="2">
+				#0107
+				#0108
+<!-- MOD_CODE ~slide~ OPEN -->
+				#slide$0
+<!-- CLOSE -->
+			</hkparam>
+			<hkparam name="timeInState">0.000000</hkparam>
+		</hkobject>
+"##;
+        let added = r##"
+		<hkobject name="#slide$0" class="hkbStateMachineStateInfo" signature="0xed7f9d0">
+			<hkparam name="name">Slide</hkparam>
+		</hkobject>
+"##;
+
+        let files = [
+            ("slide/0_master/#0052.txt", string_data),
+            ("slide/0_master/#0106.txt", state_machine),
+            ("slide/0_master/#slide$0.txt", added),
+        ];
+        let diagnostics = lint(&files);
+        assert!(diagnostics.is_empty(), "{diagnostics:?}");
+    }
+
+    #[test]
+    fn report_positions() {
+        let xml = r##"<hkobject name="#0106" class="hkbStateMachine" signature="0x816c1dcb">
+	<hkparam name="startStateId">zero</hkparam>
+	<hkparam name="unknown">0</hkparam>
+	<hkparam name="states" numelements="3">
+		#0107
+<!-- MOD_CODE ~slide~ OPEN -->
+		#slide$9
+	</hkparam>
+<!-- CLOSE -->
+	<hkparam name="wildcardTransitions">$eventID[slideStart]$</hkparam>
+</hkobject>
+"##;
+
+        let expected = [
+            ("#0106.txt".to_string(), 2, 31, Severity::Error), // zero
+            ("#0106.txt".to_string(), 3, 2, Severity::Error),  // unknown field
+            ("#0106.txt".to_string(), 4, 2, Severity::Warning), // numelements
+            ("#0106.txt".to_string(), 6, 1, Severity::Error),  // CLOSE in other tag
+            ("#0106.txt".to_string(), 7, 3, Severity::Error),  // #slide$9
+            ("#0106.txt".to_string(), 10, 38, Severity::Error), // no such object
+        ];
+        assert_eq!(lint(&[("#0106.txt", xml)]), expected);
+    }
+
+    #[test]
+    fn unbalanced_comments() {
+        let xml = r##"
+<hkobject name="#0010" class="hkbProjectData" signature="0x13a39ba7">
+<!-- CLOSE -->
+<!-- MOD_CODE ~a~ OPEN -->
+<!-- MOD_CODE ~b~ OPEN -->
+	<hkparam name="stringData">#0009</hkparam>
+</hkobject>
+"##;
+
+        let expected = [
+            ("#0010.txt".to_string(), 3, 1, Severity::Error), // CLOSE without MOD_CODE
+            ("#0010.txt".to_string(), 4, 1, Severity::Error), // MOD_CODE without CLOSE
+            ("#0010.txt".to_string(), 5, 1, Severity::Error), // MOD_CODE twice
+        ];
+        assert_eq!(lint(&[("#0010.txt", xml)]), expected);
+    }
+
+    #[test]
+    fn unknown_event_without_string_data() {
+        let xml = r##"
+<hkobject name="#0100" class="hkbStateMachineEventPropertyArray" signature="0xb07b4388">
+	<hkparam name="events" numelements="1">
+		<hkobject>
+			<hkparam name="id">$eventID[unknown]$</hkparam>
+			<hkparam name="payload">null</hkparam>
+		</hkobject>
+	</hkparam>
+</hkobject>
+"##;
+        assert_eq!(
+            lint(&[("#0100.txt", xml)]),
+            [("#0100.txt".to_string(), 5, 23, Severity::Warning)]
+        );
+    }
+
+    #[test]
+    fn scopes() {
+        let xml = r##"<hkobject name="#0106" class="hkbStateMachine" signature="0x816c1dcb">
+	<hkparam name="eventToSendWhenStateOrTransitionChanges">
+		<hkobject>
+			<hkparam name="id">-1</hkparam>
+			<hkparam name="payload">null</hkparam>
+		</hkobject>
+	</hkparam>
+</hkobject>
+"##;
+
+        let offset = xml.find("-1").unwrap();
+        let scopes = scopes_at(xml, offset, &HackRules::default());
+        let names: Vec<_> = scopes
+            .iter()
+            .map(|scope| match scope {
+                Scope::Object { class_name, .. } => *class_name,
+                Scope::Field { name, .. } => *name,
+                Scope::Other { tag, .. } => *tag,
+            })
+            .collect();
+        assert_eq!(
+            names,
+            ["hkbStateMachine", "eventToSendWhenStateOrTransitionChanges", "hkbEvent", "id"]
+        );
+        assert!(matches!(scopes[3], Scope::Field { field_type: Some("I64"), .. }));
+
+        let definitions = object_definitions(xml);
+        assert_eq!(definitions, [("#0106", 0..70)]);
+    }
+
+    #[test]
+    fn known_mistakes() {
+        let ragdoll = r##"
+<hkobject name="#2521" class="BSRagdollContactListenerModifier" signature="0x8003d8ce">
+	<hkparam name="event">
+		<hkobject>
+			<hkparam name="id">0</hkparam>
+			<hkparam name="payload">null</hkparam>
+		</hkobject>
+	</hkparam>
+</hkobject>
+"##;
+        let bone_weight = r##"
+<hkobject name="#0119" class="hkbBoneWeightArray" signature="0xcd902b77">
+	<hkparam name="boneWeights" numelements="0"></hkparam>
+<!-- MOD_CODE ~colisc~ OPEN -->
+	0.000000 0.100000
+	</hkparam>
+<!-- CLOSE -->
+</hkobject>
+"##;
+
+        let expected = [
+            ("#0119.txt".to_string(), 4, 1, Severity::Warning),
+            ("#2521.txt".to_string(), 3, 2, Severity::Warning),
+        ];
+        let files = [("#2521.txt", ragdoll), ("#0119.txt", bone_weight)];
+        assert_eq!(lint(&files), expected);
+
+        // Errors without the rules.
+        let diagnostics = lint_with(&files, &HackRules::default());
+        assert!(!diagnostics.is_empty());
+        assert!(diagnostics.iter().all(|(.., severity)| *severity == Severity::Error));
+    }
+}
@@ -0,0 +1,162 @@
+//! XML tokens of a Nemesis patch with their byte ranges.
+//!
+//! Unlike the patch parser, this never stops at an error: a broken token becomes [`Token::Invalid`]
+//! up to the next `<`, so that the rest of the file is still linted.
+use std::ops::Range;
+
+use winnow::{
+    ModalResult, Parser,
+    ascii::{multispace0, multispace1},
+    combinator::{alt, delimited, preceded, repeat, separated_pair},
+    token::{take_till, take_until, take_while},
+};
+
+use crate::helpers::{
+    comment::{CommentKind, close_comment, comment_kind},
+    delimited_multispace0,
+};
+
+#[derive(Debug, Clone, PartialEq)]
+pub(super) enum Token<'a> {
+    /// `<!-- MOD_CODE ~id~ OPEN -->`, `<!-- ORIGINAL -->`, `<!-- CLOSE -->` or others.
+    Comment(CommentKind<'a>),
+    /// e.g. `<hkparam name="states" numelements="2">`
+    StartTag { name: &'a str, attrs: Vec<(&'a str, &'a str)>, is_empty: bool },
+    /// e.g. `</hkparam>`
+    EndTag(&'a str),
+    /// Trimmed text between tags. e.g. `#0052`
+    Text(&'a str),
+    /// Not a token. e.g. `<hkparam name="states`
+    Invalid(&'a str),
+}
+
+impl<'a> Token<'a> {
+    /// Attribute value of a start tag.
+    pub(super) fn attr(&self, key: &str) -> Option<&'a str> {
+        match self {
+            Self::StartTag { attrs, .. } => {
+                attrs.iter().find(|(name, _)| *name == key).map(|(_, value)| *value)
+            }
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub(super) struct Tokens<'a> {
+    input: &'a str,
+    original: &'a str,
+}
+
+impl<'a> Tokens<'a> {
+    pub(super) const fn new(input: &'a str) -> Self {
+        Self { input, original: input }
+    }
+
+    const fn offset(&self) -> usize {
+        self.original.len() - self.input.len()
+    }
+}
+
+impl<'a> Iterator for Tokens<'a> {
+    type Item = (Range<usize>, Token<'a>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.input = self.input.trim_start();
+        if self.input.is_empty() {
+            return None;
+        }
+
+        let start = self.offset();
+        let checkpoint = self.input;
+        let token = if self.input.starts_with("<!--") {
+            comment.map(Token::Comment).parse_next(&mut self.input)
+        } else if self.input.starts_with("</") {
+            end_tag.map(Token::EndTag).parse_next(&mut self.input)
+        } else if self.input.starts_with('<') {
+            start_tag.parse_next(&mut self.input)
+        } else {
+            take_till(1.., '<')
+                .map(|text: &str| Token::Text(text.trim_end()))
+                .parse_next(&mut self.input)
+        };
+
+        let token = token.unwrap_or_else(|_| {
+            self.input = checkpoint;
+            let len = self.input[1..].find('<').map_or(self.input.len(), |index| index + 1);
+            let (invalid, rest) = self.input.split_at(len);
+            self.input = rest;
+            Token::Invalid(invalid.trim_end())
+        });
+
+        let end = start + self.original[start..self.offset()].trim_end().len();
+        Some((start..end, token))
+    }
+}
+
+fn comment<'a>(input: &mut &'a str) -> ModalResult<CommentKind<'a>> {
+    // NOTE: Classify only this comment, since `close_comment` may look for `SERIALIZE_IGNORED` beyond `-->`.
+    let comment = ("<!--", take_until(0.., "-->"), "-->").take().parse_next(input)?;
+    Ok(alt((close_comment, comment_kind)).parse(comment).unwrap_or(CommentKind::Unknown(comment)))
+}
+
+fn start_tag<'a>(input: &mut &'a str) -> ModalResult<Token<'a>> {
+    let name = preceded('<', tag_name).parse_next(input)?;
+    let attrs = repeat(
+        0..,
+        preceded(
+            multispace1,
+            separated_pair(
+                tag_name,
+                delimited_multispace0('='),
+                delimited('"', take_till(0.., '"'), '"'),
+            ),
+        ),
+    )
+    .parse_next(input)?;
+    multispace0.parse_next(input)?;
+    let is_empty = alt(("/>".value(true), ">".value(false))).parse_next(input)?;
+
+    Ok(Token::StartTag { name, attrs, is_empty })
+}
+
+fn end_tag<'a>(input: &mut &'a str) -> ModalResult<&'a str> {
+    delimited("</", delimited_multispace0(tag_name), '>').parse_next(input)
+}
+
+fn tag_name<'a>(input: &mut &'a str) -> ModalResult<&'a str> {
+    take_while(1.., |c: char| c.is_ascii_alphanumeric() || c == '_').parse_next(input)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tokenize() {
+        let xml = r#"<hkparam name="states" numelements="1">
+<!-- MOD_CODE ~slide~ OPEN -->
+    #0052 <hkparam name="x
+<!-- CLOSE --></hkparam>"#;
+
+        let tokens: Vec<_> = Tokens::new(xml).collect();
+        assert_eq!(
+            tokens,
+            [
+                (
+                    0..39,
+                    Token::StartTag {
+                        name: "hkparam",
+                        attrs: vec![("name", "states"), ("numelements", "1")],
+                        is_empty: false,
+                    }
+                ),
+                (40..70, Token::Comment(CommentKind::ModCode("slide"))),
+                (75..80, Token::Text("#0052")),
+                (81..97, Token::Invalid(r#"<hkparam name="x"#)),
+                (98..112, Token::Comment(CommentKind::Close)),
+                (112..122, Token::EndTag("hkparam")),
+            ]
+        );
+    }
+}
@@ -39,6 +39,15 @@ pub(super) struct CurrentState<'xml> {
     /// - Used only when changing Array.
     /// - If start and end of range are the same, index mode
     pub seq_values: Vec<BorrowedValue<'xml>>,
+
+    /// The values after `<!-- ORIGINAL --!>`. (one diff pattern)
+    pub originals: Patches<'xml>,
+
+    /// The values after `<!-- ORIGINAL --!>`. (range diff pattern)
+    pub seq_originals: Vec<BorrowedValue<'xml>>,
+
+    /// Index of the next element in the template array. (Added elements are not counted.)
+    pub seq_template_index: usize,
 }
 
 impl<'de> CurrentState<'de> {
@@ -51,20 +60,33 @@ impl<'de> CurrentState<'de> {
             path: vec![],
             seq_range: None,
             seq_values: vec![],
+            originals: vec![],
+            seq_originals: vec![],
+            seq_template_index: 0,
         }
     }
 
     /// The following is an additional element, so push.
     /// - `<!-- MOD_CODE ~<id>~ --!>` after it is found.
     /// - `<!-- ORIGINAL --!> is not found yet.
+    ///
+    /// After `<!-- ORIGINAL --!>`, the value is kept as the original instead.
     pub(super) fn push_current_patch(&mut self, value: BorrowedValue<'de>) {
-        if self.mode_code.is_some() && !self.is_passed_original {
-            if self.seq_range.is_some() {
-                self.seq_values.push(value);
-            } else {
+        if self.mode_code.is_none() {
+            return;
+        }
+
+        match (self.is_passed_original, self.seq_range.is_some()) {
+            (false, true) => self.seq_values.push(value),
+            (true, true) => self.seq_originals.push(value),
+            (false, false) => {
                 let path = self.path.clone();
                 self.patches.push(CurrentJsonPatch { path, value });
             }
+            (true, false) => {
+                let path = self.path.clone();
+                self.originals.push(CurrentJsonPatch { path, value });
+            }
         }
     }
 
@@ -103,6 +125,13 @@ impl<'de> CurrentState<'de> {
         (op, mem::take(&mut self.patches))
     }
 
+    /// Drop the one field patches of the current `MOD_CODE` block. (for the error recovery)
+    pub(super) fn drop_patches(&mut self) {
+        self.clear_flags();
+        self.patches.clear();
+        self.originals.clear();
+    }
+
     pub(super) const fn increment_range(&mut self) {
         if let Some(ref mut range) = self.seq_range {
             range.end += 1;
@@ -1,5 +1,7 @@
 pub mod class_table;
 mod current_state;
+mod original;
+mod recover;
 
 use std::{collections::HashMap, mem};
 
@@ -19,20 +21,33 @@ use self::{
     class_table::{FieldInfo, find_class_info},
     current_state::{CurrentJsonPatch, CurrentState},
 };
+pub use self::{original::Original, recover::PatchDiagnostic};
 use crate::{
     error::{Error, Result},
-    hack::{HackOptions, do_hack_cast_ragdoll_event},
+    hack::{HackOptions, HackRules, coerce},
     helpers::{
         comment::{CommentKind, close_comment, comment_kind, take_till_close},
         delimited_multispace0,
         ptr::pointer,
-        tag::{PointerType, class_start_tag, end_tag, field_start_tag, start_tag},
+        tag::{
+            PointerType, any_field_start_tag, class_start_tag, end_tag, field_start_tag, start_tag,
+        },
         variable::{event_id, variable_id},
     },
 };
 
 pub type PatchesMap<'a> = HashMap<JsonPath<'a>, JsonPatch<'a>>;
 
+/// Output of [`parse_nemesis_patch_with_rules`].
+#[derive(Debug, Clone, Default)]
+pub struct NemesisPatch<'a> {
+    pub patches: PatchesMap<'a>,
+    /// Root class ptr if `hkbBehaviorGraphStringData` to replace nemesis variable.
+    pub id_index: Option<&'a str>,
+    /// Template values expected by the `<!-- ORIGINAL -->` blocks, in the order of the patch file.
+    pub originals: Vec<Original<'a>>,
+}
+
 /// Parse nemesis xml patch.
 ///
 /// # Return
@@ -44,14 +59,62 @@ pub fn parse_nemesis_patch(
     nemesis_xml: &str,
     hack_options: Option<HackOptions>,
 ) -> Result<(PatchesMap<'_>, Option<&str>)> {
-    let mut patcher_de = PatchDeserializer::new(nemesis_xml, hack_options.unwrap_or_default());
+    let hack_rules = HackRules::builtin(hack_options.unwrap_or_default());
+    let patch = parse_nemesis_patch_with_rules(nemesis_xml, &hack_rules)?;
+    Ok((patch.patches, patch.id_index))
+}
+
+/// Parse nemesis xml patch with the hack rules.
+///
+/// Same as [`parse_nemesis_patch`], but the hacks are given as [`HackRules`]
+/// (e.g. the built-in ones and `hack_rules.json`), and the `<!-- ORIGINAL -->` values are returned as well.
+///
+/// # Errors
+/// Parse failed.
+pub fn parse_nemesis_patch_with_rules<'a>(
+    nemesis_xml: &'a str,
+    hack_rules: &HackRules,
+) -> Result<NemesisPatch<'a>> {
+    let mut patcher_de = PatchDeserializer::new(nemesis_xml, hack_rules);
     patcher_de.root_class().map_err(|err| patcher_de.to_readable_err(err))?;
-    Ok((patcher_de.output_patches, patcher_de.id_index))
+    Ok(NemesisPatch {
+        patches: patcher_de.output_patches,
+        id_index: patcher_de.id_index,
+        originals: patcher_de.output_originals,
+    })
+}
+
+/// Parse nemesis xml patch, and keep going after errors.
+///
+/// On an error, the field(`<hkparam>`) is skipped and the parsing resumes at the next one.
+/// A `MOD_CODE` block with an error is skipped up to its `<!-- CLOSE -->` and dropped as a whole.
+///
+/// # Returns
+/// The patches parsed successfully, and every error with its position(in order of the position).
+pub fn parse_nemesis_patch_recovering<'a>(
+    nemesis_xml: &'a str,
+    hack_rules: &HackRules,
+) -> (NemesisPatch<'a>, Vec<PatchDiagnostic>) {
+    let mut patcher_de = PatchDeserializer::new(nemesis_xml, hack_rules);
+    patcher_de.recovering = true;
+    if let Err(err) = patcher_de.root_class() {
+        // e.g. Unknown root class
+        patcher_de.push_diagnostic(err);
+    }
+
+    let mut diagnostics = mem::take(&mut patcher_de.diagnostics);
+    diagnostics.sort_by_key(|diagnostic| diagnostic.offset);
+    let patch = NemesisPatch {
+        patches: patcher_de.output_patches,
+        id_index: patcher_de.id_index,
+        originals: patcher_de.output_originals,
+    };
+    (patch, diagnostics)
 }
 
 /// Nemesis patch deserializer
-#[derive(Debug, Clone, Default)]
-struct PatchDeserializer<'a> {
+#[derive(Debug)]
+struct PatchDeserializer<'a, 'r> {
     /// mutable pointer to str
     input: &'a str,
     /// This is readonly for error report. Not move position.
@@ -60,17 +123,24 @@ struct PatchDeserializer<'a> {
     /// Output
     // output_patches: Vec<JsonPatch<'a>>,
     output_patches: HashMap<JsonPath<'a>, JsonPatch<'a>>,
+    /// Values of the `<!-- ORIGINAL -->` blocks.
+    output_originals: Vec<Original<'a>>,
 
     /// Enables lenient parsing for known issues in unofficial or modded patches.
     ///
     /// This may fix common mistakes in community patches (e.g., misnamed fields),
     /// but can also hide real data errors.
-    hack_options: HackOptions,
+    hack_rules: &'r HackRules,
+
+    /// Keep going after errors. See [`parse_nemesis_patch_recovering`].
+    recovering: bool,
+    /// Errors kept in the recovering mode.
+    diagnostics: Vec<PatchDiagnostic>,
 
     // /////////////////////////////////////////////////////////////////////////////////////////////////////////////////
     // current state
-    /// N time nested classes fields.
-    field_infos: Vec<&'static FieldInfo>,
+    /// N time nested classes and their fields. (class name, fields)
+    field_infos: Vec<(&'a str, &'static FieldInfo)>,
 
     /// - `<! -- CLOSE --! >`(XML) where it is temporarily stored because the operation type is unknown until a comment is found.
     /// - `<! -- CLOSE --! >` is found, have it added to `output_patches`.
@@ -80,19 +150,26 @@ struct PatchDeserializer<'a> {
     ///
     /// This is needed to replace event/variable ID
     id_index: Option<&'a str>,
+
+    /// Length of `field_infos` when the current `MOD_CODE` block started.
+    block_depth: usize,
     // /////////////////////////////////////////////////////////////////////////////////////////////////////////////////
 }
 
-impl<'de> PatchDeserializer<'de> {
-    fn new(input: &'de str, hack_options: HackOptions) -> Self {
+impl<'de, 'r> PatchDeserializer<'de, 'r> {
+    fn new(input: &'de str, hack_rules: &'r HackRules) -> Self {
         Self {
             input,
             original: input,
             output_patches: HashMap::new(),
-            hack_options,
+            output_originals: Vec::new(),
+            hack_rules,
+            recovering: false,
+            diagnostics: Vec::new(),
             field_infos: Vec::new(),
             current: CurrentState::new(),
             id_index: None,
+            block_depth: 0,
         }
     }
 
@@ -142,14 +219,19 @@ impl<'de> PatchDeserializer<'de> {
 
     // /////////////////////////////////////////////////////////////////////////////////////////////////////////////////
 
-    fn push_current_field_table(&mut self, info: &'static FieldInfo) {
-        self.field_infos.push(info);
+    fn push_current_field_table(&mut self, class_name: &'de str, info: &'static FieldInfo) {
+        self.field_infos.push((class_name, info));
         self.current.field_info = Some(info);
     }
 
     fn pop_current_field_table(&mut self) {
         self.field_infos.pop();
-        self.current.field_info = self.field_infos.last().map(|v| &**v);
+        self.current.field_info = self.field_infos.last().map(|(_, info)| &**info);
+    }
+
+    /// The name of the innermost class.
+    fn current_class_name(&self) -> &'de str {
+        self.field_infos.last().map_or("", |(class_name, _)| class_name)
     }
 
     /// Parse root C++ class(as XML)
@@ -170,19 +252,22 @@ impl<'de> PatchDeserializer<'de> {
         {
             let field_info = find_class_info(class_name)
                 .ok_or_else(|| Error::UnknownClass { class_name: class_name.to_string() })?;
-            self.push_current_field_table(field_info);
+            self.push_current_field_table(class_name, field_info);
         }
 
         let mut obj = Object::new();
         obj.insert("__ptr".into(), ptr_index.into());
         while self.parse_next(opt(end_tag("hkobject")))?.is_none() {
-            if self.hack_options.bone_weight_outside_hkparam
-                && self.try_hack_bone_weight_seq_push()?
-            {
+            if self.try_hack_mod_code_outside_field()? {
                 continue;
             }
 
-            let (field_name, value) = self.field()?;
+            let Some((field_name, value)) = self.field_or_recover()? else {
+                if self.input.is_empty() {
+                    break; // Skipped to EOF
+                }
+                continue;
+            };
             if should_take_in_this {
                 obj.insert(field_name.into(), value);
             }
@@ -212,12 +297,17 @@ impl<'de> PatchDeserializer<'de> {
         {
             let field_info = find_class_info(class_name)
                 .ok_or_else(|| Error::UnknownClass { class_name: class_name.to_string() })?;
-            self.push_current_field_table(field_info);
+            self.push_current_field_table(class_name, field_info);
         }
 
         let mut obj = Object::new();
         while self.parse_next(opt(end_tag("hkobject")))?.is_none() {
-            let (field_name, value) = self.field()?;
+            let Some((field_name, value)) = self.field_or_recover()? else {
+                if self.input.is_empty() {
+                    break; // Skipped to EOF
+                }
+                continue;
+            };
             obj.insert(field_name.into(), value);
         }
         self.pop_current_field_table();
@@ -232,26 +322,30 @@ impl<'de> PatchDeserializer<'de> {
 
         let field_info = self.current.field_info.ok_or(Error::MissingFieldInfo)?;
 
-        let (field_name, field_type, _) =
-            match self.parse_next(field_start_tag(field_info)) {
-                Ok(ret) => ret,
-                Err(err) => {
-                    if self.hack_options.cast_ragdoll_event
-                        && self.current.path.last().is_some_and(|s| {
-                            s.eq_ignore_ascii_case("BSRagdollContactListenerModifier")
-                        })
-                    {
-                        self.parse_next(do_hack_cast_ragdoll_event)?
-                    } else {
-                        return Err(err);
-                    }
-                }
-            };
+        let class_name = self.current_class_name();
+        let (field_name, field_type) = match self.parse_next(field_start_tag(field_info)) {
+            Ok((field_name, field_type, _)) => (field_name, field_type),
+            Err(err) => {
+                // Hack: Renamed field. e.g. `event` -> `contactEvent`
+                let (field_name, _) = self.parse_peek(any_field_start_tag)?;
+                let Some(renamed) = self.hack_rules.rename(class_name, field_name) else {
+                    return Err(err);
+                };
+                self.parse_next(any_field_start_tag)?;
+                renamed
+            }
+        };
 
         self.current.path.push(field_name.into());
 
         let value = {
-            let value = self.parse_value(field_type)?;
+            let value = match self.hack_rules.coercion(class_name, field_name) {
+                Some(value_type) => {
+                    self.parse_next(multispace0)?;
+                    coerce(self.parse_plane_value(value_type)?, field_type)
+                }
+                None => self.parse_value(field_type)?,
+            };
 
             if should_take_in_this {
                 self.current.push_current_patch(value);
@@ -509,6 +603,7 @@ impl<'de> PatchDeserializer<'de> {
 
         let mut index = 0;
         let mut should_take_in_this = false;
+        let outer_template_index = mem::take(&mut self.current.seq_template_index);
         while self.parse_peek(opt(end_tag("hkparam")))?.is_none() {
             // seq start
             let is_start = self.parse_start_maybe_comment()?;
@@ -518,27 +613,7 @@ impl<'de> PatchDeserializer<'de> {
             }
 
             // seq inner
-            let value = if name.starts_with("String") {
-                // <hkcstring>String</hkcstring>
-                self.parse_next(start_tag("hkcstring"))?;
-                let value = self.parse_string_ptr()?;
-                self.parse_next(end_tag("hkcstring"))?;
-                value
-            } else {
-                // NOTE: In the case of nested seq patterns, intermediate indexes
-                // need to be added here because they require a path
-                // (in case of Array|Object|<ClassName>)
-                if self.current.seq_range.is_none() {
-                    self.current.path.push(format!("[{index}]").into()); // only for class
-                }
-                let value = self.parse_value(name)?;
-                // If not a class, remove `[index]` because of non-nesting.
-                //  Array|Object|<ClassName> ...Array|<TypeName> -> pop
-                if self.current.seq_range.is_none() {
-                    self.current.path.pop();
-                }
-                value
-            };
+            let value = self.parse_array_element(name, index)?;
 
             #[cfg(feature = "tracing")]
             {
@@ -551,6 +626,7 @@ impl<'de> PatchDeserializer<'de> {
                 self.current.push_current_patch(value);
             } else {
                 vec.push(value);
+                self.current.seq_template_index += 1;
             };
             index += 1;
             self.current.increment_range();
@@ -560,12 +636,42 @@ impl<'de> PatchDeserializer<'de> {
         }
 
         self.current.seq_range = None;
+        self.current.seq_template_index = outer_template_index;
         Ok(BorrowedValue::Array(Box::new(vec)))
     }
 
+    /// Parse an element of `Array|<name>`.
+    fn parse_array_element(
+        &mut self,
+        name: &'static str,
+        index: usize,
+    ) -> Result<BorrowedValue<'de>> {
+        if name.starts_with("String") {
+            // <hkcstring>String</hkcstring>
+            self.parse_next(start_tag("hkcstring"))?;
+            let value = self.parse_string_ptr()?;
+            self.parse_next(end_tag("hkcstring"))?;
+            return Ok(value);
+        }
+
+        // NOTE: In the case of nested seq patterns, intermediate indexes
+        // need to be added here because they require a path
+        // (in case of Array|Object|<ClassName>)
+        if self.current.seq_range.is_none() {
+            self.current.path.push(format!("[{index}]").into()); // only for class
+        }
+        let value = self.parse_value(name)?;
+        // If not a class, remove `[index]` because of non-nesting.
+        //  Array|Object|<ClassName> ...Array|<TypeName> -> pop
+        if self.current.seq_range.is_none() {
+            self.current.path.pop();
+        }
+        Ok(value)
+    }
+
     ////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
 
-    /// Peeks for the pattern:
+    /// Peeks for the pattern of [`crate::hack::HackRule::ModCodeOutsideField`] of the current class:
     /// ```xml
     /// <hkparam name="boneWeights" numelements="0"></hkparam>
     /// <!-- MOD_CODE ~id~ OPEN -->
@@ -576,19 +682,28 @@ impl<'de> PatchDeserializer<'de> {
     ///
     /// If the pattern matches, consumes all of it and inserts a SeqPush patch.
     /// Returns `true` if the pattern was handled.
-    fn try_hack_bone_weight_seq_push(&mut self) -> Result<bool> {
-        use super::hack::bone_weight::{bone_weight_then_mod_code, parse_floats_till_end_hkparam};
-
-        // Peek: does `<hkparam name="boneWeights" numelements="0"></hkparam>` follow?
-        if self.parse_peek(bone_weight_then_mod_code).is_err() {
+    fn try_hack_mod_code_outside_field(&mut self) -> Result<bool> {
+        use super::hack::bone_weight::{empty_field_then_mod_code, parse_floats_till_end_hkparam};
+
+        // Peek: does `<hkparam name="boneWeights" numelements="0"></hkparam>` and `MOD_CODE` follow?
+        let hack_rules = self.hack_rules;
+        let Some((field, mod_code)) = hack_rules
+            .mod_code_outside_fields(self.current_class_name())
+            .find(|&(field, mod_code)| {
+                self.parse_peek(|input: &mut &'de str| {
+                    empty_field_then_mod_code(input, field, mod_code)
+                })
+                .is_ok()
+            })
+        else {
             return Ok(false);
-        }
+        };
 
-        self.parse_next(bone_weight_then_mod_code)?;
+        self.parse_next(|input: &mut &'de str| empty_field_then_mod_code(input, field, mod_code))?;
         let floats = self.parse_next(parse_floats_till_end_hkparam)?;
 
         let mut patch_path = self.current.path.clone();
-        patch_path.push("boneWeights".into());
+        patch_path.push(field.to_string().into());
 
         self.output_patches.insert(
             patch_path,
@@ -609,6 +724,7 @@ impl<'de> PatchDeserializer<'de> {
             match comment_ty {
                 CommentKind::ModCode(id) => {
                     self.current.mode_code = Some(id);
+                    self.block_depth = self.field_infos.len();
                     self.parse_maybe_close_comment()?; // When close comes here, it is Remove.
                     return Ok(true);
                 }
@@ -635,7 +751,8 @@ impl<'de> PatchDeserializer<'de> {
                             tracing::debug!(?op);
                             tracing::trace!("{:#?}", self.current);
                         }
-                        self.parse_next(take_till_close)?;
+                        let original = self.parse_next(take_till_close)?;
+                        self.parse_original(original);
                         self.extend_output_patches()?;
                     }
                     return Ok(true);
@@ -661,6 +778,17 @@ impl<'de> PatchDeserializer<'de> {
             let path = self.current.path.clone(); // needless clone? replace?
 
             let seq_values = mem::take(&mut self.current.seq_values);
+            let seq_originals = mem::take(&mut self.current.seq_originals);
+            if !seq_originals.is_empty() {
+                // NOTE: `new_range` also counts the elements added before, so it is not the index of the template.
+                let start = self.current.seq_template_index;
+                self.current.seq_template_index += seq_originals.len();
+                self.output_originals.push(Original {
+                    path: path.clone(),
+                    range: Some(start..start + seq_originals.len()),
+                    value: BorrowedValue::Array(Box::new(seq_originals)),
+                });
+            }
             let value = if op == Op::Remove {
                 BorrowedValue::null() // no add
             } else {
@@ -695,6 +823,11 @@ impl<'de> PatchDeserializer<'de> {
 
         // one diff pattern
         let (op, patches) = self.current.take_patches();
+        self.output_originals.extend(
+            mem::take(&mut self.current.originals)
+                .into_iter()
+                .map(|CurrentJsonPatch { path, value }| Original { path, range: None, value }),
+        );
         self.output_patches.par_extend(patches.into_par_iter().map(
             |CurrentJsonPatch { path, value }| {
                 (path, JsonPatch { action: Action::Pure { op }, value })
@@ -1003,6 +1136,165 @@ mod tests {
         assert_eq!(actual, expected);
     }
 
+    #[test]
+    fn keep_originals() {
+        let nemesis_xml = r###"
+		<hkobject name="#0010" class="hkbProjectData" signature="0x13a39ba7">
+			<hkparam name="worldUpWS">(0.000000 <!-- MOD_CODE ~id~ OPEN -->1.000000<!-- ORIGINAL -->0.000000<!-- CLOSE --> 1.000000 0.000000)</hkparam>
+<!-- MOD_CODE ~id~ OPEN -->
+			<hkparam name="stringData">$id</hkparam>
+<!-- ORIGINAL -->
+			<hkparam name="stringData">#0009</hkparam>
+<!-- CLOSE -->
+			<hkparam name="defaultEventMode">EVENT_MODE_IGNORE_FROM_GENERATOR</hkparam>
+		</hkobject>
+"###;
+        let patch = parse_nemesis_patch_with_rules(nemesis_xml, &HackRules::default())
+            .unwrap_or_else(|e| panic!("{e}"));
+        assert_eq!(
+            patch.originals,
+            [
+                Original {
+                    path: json_path!["#0010", "hkbProjectData", "worldUpWS", "y"],
+                    range: None,
+                    value: 0.0.into(),
+                },
+                Original {
+                    path: json_path!["#0010", "hkbProjectData", "stringData"],
+                    range: None,
+                    value: "#0009".into(),
+                },
+            ]
+        );
+
+        let nemesis_xml = r###"
+		<hkobject name="#0009" class="hkbProjectStringData" signature="0x76ad60a">
+			<hkparam name="animationFilenames" numelements="2">
+				<hkcstring>Walk.hkx</hkcstring>
+<!-- MOD_CODE ~id~ OPEN -->
+				<hkcstring>Run.hkx</hkcstring>
+<!-- ORIGINAL -->
+				<hkcstring>Sprint.hkx</hkcstring>
+<!-- CLOSE -->
+			</hkparam>
+			<hkparam name="behaviorFilenames" numelements="0"></hkparam>
+			<hkparam name="characterFilenames" numelements="4">
+				<hkcstring>Characters\DefaultMale.hkx</hkcstring>
+<!-- MOD_CODE ~id~ OPEN -->
+				<hkcstring>PushDummy</hkcstring>
+				<hkcstring>PushDummy</hkcstring>
+<!-- CLOSE -->
+				<hkcstring>Original</hkcstring>
+<!-- MOD_CODE ~id~ OPEN -->
+
+<!-- ORIGINAL -->
+				<hkcstring>Removed</hkcstring>
+				<hkcstring>Removed</hkcstring>
+<!-- CLOSE -->
+			</hkparam>
+			<hkparam name="eventNames" numelements="0"></hkparam>
+			<hkparam name="animationPath"></hkparam>
+			<hkparam name="behaviorPath"></hkparam>
+			<hkparam name="characterPath"></hkparam>
+			<hkparam name="fullPathToSource"></hkparam>
+		</hkobject>
+"###;
+        let patch = parse_nemesis_patch_with_rules(nemesis_xml, &HackRules::default())
+            .unwrap_or_else(|e| panic!("{e}"));
+        assert_eq!(
+            patch.originals,
+            [
+                Original {
+                    path: json_path!["#0009", "hkbProjectStringData", "animationFilenames"],
+                    range: Some(1..2),
+                    value: json_typed!(borrowed, ["Sprint.hkx"]),
+                },
+                Original {
+                    path: json_path!["#0009", "hkbProjectStringData", "characterFilenames"],
+                    range: Some(2..4),
+                    value: json_typed!(borrowed, ["Removed", "Removed"]),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn hack_rules_from_json() {
+        let nemesis_xml = r###"
+		<hkobject name="#0100" class="hkbClipGenerator" signature="0x333b85b9">
+<!-- MOD_CODE ~mod_id~ OPEN -->
+			<hkparam name="PlaybackRate">2</hkparam>
+<!-- ORIGINAL -->
+			<hkparam name="PlaybackRate">1</hkparam>
+<!-- CLOSE -->
+		</hkobject>
+        "###;
+        let mut json = br#"{
+            "rules": [
+                { "kind": "rename_field", "class": "hkbClipGenerator", "from": "playbackRate", "to": "playbackSpeed" },
+                { "kind": "coerce_type", "class": "hkbClipGenerator", "field": "playbackSpeed", "from": "I64" }
+            ]
+        }"#
+        .to_vec();
+        let hack_rules = HackRules::from_json(&mut json).unwrap_or_else(|e| panic!("{e}"));
+
+        let (actual, _) = parse_nemesis_patch_with_rules(nemesis_xml, &hack_rules)
+            .unwrap_or_else(|e| panic!("{e}"));
+
+        let mut expected = HashMap::new();
+        expected.insert(
+            json_path!["#0100", "hkbClipGenerator", "playbackSpeed"],
+            JsonPatch { action: Action::Pure { op: Op::Replace }, value: 2.0.into() },
+        );
+        assert_eq!(actual, expected);
+        assert!(parse_nemesis_patch(nemesis_xml, Some(HackOptions::enable_all())).is_err());
+    }
+
+    #[test]
+    fn recover_from_errors() {
+        let nemesis_xml = r###"
+		<hkobject name="#0010" class="hkbProjectData" signature="0x13a39ba7">
+			<hkparam name="memSizeAndFlags">abc</hkparam>
+			<hkparam name="worldUpWS">(0.000000 0.000000 1.000000 0.000000)</hkparam>
+<!-- MOD_CODE ~id~ OPEN -->
+			<hkparam name="referenceCount">2</hkparam>
+			<hkparam name="unknownField">1</hkparam>
+<!-- ORIGINAL -->
+			<hkparam name="referenceCount">0</hkparam>
+<!-- CLOSE -->
+<!-- MOD_CODE ~id~ OPEN -->
+			<hkparam name="stringData">$id</hkparam>
+<!-- ORIGINAL -->
+			<hkparam name="stringData">#0009</hkparam>
+<!-- CLOSE -->
+			<hkparam name="defaultEventMode">EVENT_MODE_IGNORE_FROM_GENERATOR</hkparam>
+		</hkobject>
+"###;
+        assert!(parse_nemesis_patch(nemesis_xml, None).is_err());
+
+        let (patch, diagnostics) =
+            parse_nemesis_patch_recovering(nemesis_xml, &HackRules::default());
+        let lines: Vec<_> = diagnostics.iter().map(|diagnostic| diagnostic.line).collect();
+        assert_eq!(lines, [3, 7]);
+
+        // The block with the error is dropped.
+        let mut expected = HashMap::new();
+        expected.insert(
+            json_path!["#0010", "hkbProjectData", "stringData"],
+            JsonPatch { action: Action::Pure { op: Op::Replace }, value: "$id".into() },
+        );
+        assert_eq!(patch.patches, expected);
+
+        // Truncated
+        let nemesis_xml = r###"<hkobject name="#0010" class="hkbProjectData" signature="0x13a39ba7">
+			<hkparam name="stringData">#0009"###;
+        let (patch, diagnostics) =
+            parse_nemesis_patch_recovering(nemesis_xml, &HackRules::default());
+        assert!(patch.patches.is_empty());
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].offset, nemesis_xml.len());
+    }
+
     // #[quick_tracing::init(file = "./parse.log", stdio = false)]
     #[ignore = "because we need external test files"]
     #[test]
@@ -0,0 +1,106 @@
+//! Values of the `<!-- ORIGINAL -->` blocks.
+//!
+//! Nemesis only uses the block to know what is replaced(or removed), so a patch written against an older
+//! template(or another mod's output) is applied without any notice. The values are kept to check it.
+use std::ops::Range;
+
+use json_patch::JsonPath;
+use serde_hkx::xml::de::parser::type_kind::real;
+use simd_json::BorrowedValue;
+use winnow::ascii::multispace0;
+
+use super::{PatchDeserializer, class_table::find_json_parser_by, current_state::CurrentJsonPatch};
+use crate::error::Result;
+
+/// A template value expected by an `<!-- ORIGINAL -->` block.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Original<'a> {
+    /// e.g. `["#0106", "hkbStateMachine", "startStateMode"]`
+    pub path: JsonPath<'a>,
+    /// Range of the template array. `None` for one field patches.
+    pub range: Option<Range<usize>>,
+    /// - one field: the value of the field.
+    /// - seq: Array of the elements in `range`.
+    pub value: BorrowedValue<'a>,
+}
+
+impl<'de> PatchDeserializer<'de, '_> {
+    /// Parse the `ORIGINAL` block skipped by a replace patch, and keep its values in `current`.
+    ///
+    /// The block is only used for the check, so it is ignored if it cannot be read.
+    pub(super) fn parse_original(&mut self, original: &'de str) {
+        let mut de = PatchDeserializer::new(original, self.hack_rules);
+        de.field_infos.clone_from(&self.field_infos);
+        de.current.field_info = self.current.field_info;
+
+        if self.current.seq_range.is_some() {
+            if let Some(values) = self.parse_original_elements(de) {
+                self.current.seq_originals.extend(values);
+            }
+        } else if original.trim_start().starts_with("<hkparam") {
+            if let Some(originals) = self.parse_original_fields(de) {
+                self.current.originals.extend(originals);
+            }
+        } else {
+            // e.g. `<!-- MOD_CODE ~id~ OPEN -->1.000000<!-- ORIGINAL -->0.000000<!-- CLOSE -->` in `Vector4`
+            let Some(reals) = de.parse_all(|de| de.parse_next(real)) else {
+                return;
+            };
+            // The paths are only known from the replacing values.
+            if reals.len() == self.current.patches.len() {
+                let originals = self.current.patches.iter().zip(reals).map(|(patch, real)| {
+                    CurrentJsonPatch { path: patch.path.clone(), value: real.into() }
+                });
+                self.current.originals.extend(originals);
+            }
+        }
+    }
+
+    /// Elements of the current `Array|<TypeName>` field.
+    fn parse_original_elements(
+        &self,
+        mut de: PatchDeserializer<'de, '_>,
+    ) -> Option<Vec<BorrowedValue<'de>>> {
+        let field_name = self.current.path.last()?;
+        let field_type = find_json_parser_by(field_name, self.current.field_info?)?;
+        let name = field_type.strip_prefix("Array|")?;
+
+        de.current.path.clone_from(&self.current.path);
+        de.current.seq_range = Some(0..0); // No `[index]` in the path
+        de.parse_all(|de| de.parse_array_element(name, 0))
+    }
+
+    /// Fields of the current class. The path is `<class path>/<field name>`.
+    fn parse_original_fields(
+        &self,
+        mut de: PatchDeserializer<'de, '_>,
+    ) -> Option<Vec<CurrentJsonPatch<'de>>> {
+        // NOTE: `ORIGINAL` comes after the end tag of the replaced field, so the field name is still in the path.
+        let class_path = self.current.path.split_last().map_or(&[][..], |(_, parent)| parent);
+        de.current.path = class_path.to_vec();
+
+        let fields = de.parse_all(|de| de.field())?;
+        Some(
+            fields
+                .into_iter()
+                .map(|(field_name, value)| {
+                    let mut path = class_path.to_vec();
+                    path.push(field_name.into());
+                    CurrentJsonPatch { path, value }
+                })
+                .collect(),
+        )
+    }
+
+    /// Parse until the end of the input. `None` if any of them failed.
+    fn parse_all<O>(&mut self, mut parser: impl FnMut(&mut Self) -> Result<O>) -> Option<Vec<O>> {
+        let mut outputs = vec![];
+        loop {
+            self.parse_next(multispace0).ok()?;
+            if self.input.is_empty() {
+                return Some(outputs);
+            }
+            outputs.push(parser(self).ok()?);
+        }
+    }
+}
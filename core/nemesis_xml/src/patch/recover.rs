@@ -0,0 +1,191 @@
+//! Error recovery of [`super::parse_nemesis_patch_recovering`].
+//!
+//! On an error in a field(`<hkparam>`), the field is undone and skipped as text, and the parsing resumes at
+//! the next field. If the field is in a `MOD_CODE` block, the block is skipped up to its `<!-- CLOSE -->` and
+//! dropped as a whole, since the operation cannot be judged from a part of it.
+use simd_json::BorrowedValue;
+use winnow::Parser as _;
+
+use super::{PatchDeserializer, current_state::CurrentState};
+use crate::{
+    error::{Error, Result},
+    helpers::comment::{CommentKind, close_comment, comment_kind},
+    lint::line_column,
+};
+
+/// An error found by [`super::parse_nemesis_patch_recovering`].
+#[derive(Debug)]
+pub struct PatchDiagnostic {
+    /// Byte offset of the error in the patch.
+    pub offset: usize,
+    /// 1 based line number.
+    pub line: usize,
+    /// 1 based column number in chars.
+    pub column: usize,
+    /// [`Error::ReadableError`] with the snippet of the position.
+    pub error: Error,
+}
+
+/// The state before a field to undo it.
+#[derive(Debug)]
+struct Checkpoint<'a> {
+    input: &'a str,
+    current: CurrentState<'a>,
+    /// Length of `field_infos`.
+    depth: usize,
+}
+
+impl<'de> PatchDeserializer<'de, '_> {
+    /// [`Self::field`], but in the recovering mode, an error is kept in `diagnostics` and the field is skipped.
+    ///
+    /// Returns `None` if skipped.
+    pub(super) fn field_or_recover(&mut self) -> Result<Option<(&'de str, BorrowedValue<'de>)>> {
+        if !self.recovering {
+            return self.field().map(Some);
+        }
+
+        let checkpoint = Checkpoint {
+            input: self.input,
+            current: self.current.clone(),
+            depth: self.field_infos.len(),
+        };
+        match self.field() {
+            Ok(field) => Ok(Some(field)),
+            Err(err) => {
+                self.recover(err, checkpoint);
+                Ok(None)
+            }
+        }
+    }
+
+    fn recover(&mut self, err: Error, Checkpoint { input, current, depth }: Checkpoint<'de>) {
+        self.push_diagnostic(err);
+
+        self.current = current;
+        self.field_infos.truncate(depth);
+        self.input = input;
+
+        let in_block = self.current.mode_code.is_some() && self.block_depth == depth;
+        let starts_block = matches!(self.parse_peek(comment_kind), Ok(CommentKind::ModCode(_)));
+        skip_field(&mut self.input);
+        if in_block || starts_block {
+            skip_till_close(&mut self.input);
+            self.current.drop_patches();
+        }
+
+        // e.g. A stray end tag `</hkparam>`
+        if self.input.len() == input.len() {
+            self.input = self.input.find('>').map_or("", |end| &self.input[end + 1..]);
+        }
+    }
+
+    /// Keep the error with the current position.
+    ///
+    /// The errors at the same position(e.g. the missing end tags at EOF) are kept only once.
+    pub(super) fn push_diagnostic(&mut self, err: Error) {
+        let offset = self.original.len() - self.input.len();
+        if self.diagnostics.last().is_some_and(|diagnostic| diagnostic.offset == offset) {
+            return;
+        }
+
+        let (line, column) = line_column(self.original, offset);
+        let error = self.to_readable_err(err);
+        self.diagnostics.push(PatchDiagnostic { offset, line, column, error });
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Node {
+    Comment,
+    Element,
+    /// An end tag of the parent(not consumed), or EOF.
+    End,
+}
+
+/// Skip the comments(except `CLOSE`) and the next element with its children.
+fn skip_field(input: &mut &str) {
+    while !is_close(input) && skip_node(input) == Node::Comment {}
+}
+
+/// Skip the nodes until `<!-- CLOSE -->`(consumed), or an end tag of the parent.
+fn skip_till_close(input: &mut &str) {
+    loop {
+        if is_close(input) {
+            let _ = close_comment.parse_next(input);
+            return;
+        }
+        if skip_node(input) == Node::End {
+            return;
+        }
+    }
+}
+
+fn is_close(input: &str) -> bool {
+    matches!(close_comment.parse_peek(input), Ok((_, CommentKind::Close)))
+}
+
+/// Skip a comment, or an element with its children, and the text before it.
+fn skip_node(input: &mut &str) -> Node {
+    let mut depth = 0_usize;
+    loop {
+        let Some(start) = input.find('<') else {
+            *input = "";
+            return Node::End;
+        };
+        let rest = &input[start..];
+
+        if rest.starts_with("<!--") {
+            *input = rest.find("-->").map_or("", |end| &rest[end + 3..]);
+            if depth == 0 {
+                return Node::Comment;
+            }
+            continue;
+        }
+
+        if rest.starts_with("</") {
+            if depth == 0 {
+                *input = rest;
+                return Node::End;
+            }
+            depth -= 1;
+        } else if !rest.starts_with("<?") {
+            let tag_end = rest.find('>').unwrap_or(rest.len());
+            if !rest[..tag_end].ends_with('/') {
+                depth += 1;
+            }
+        }
+
+        *input = rest.find('>').map_or("", |end| &rest[end + 1..]);
+        if depth == 0 {
+            return Node::Element;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn skip_nodes() {
+        let mut input = r#"
+<!-- MOD_CODE ~id~ OPEN -->
+			<hkparam name="generators" numelements="1">
+				<hkobject><hkparam name="a">1</hkparam></hkobject>
+			</hkparam>
+<!-- ORIGINAL -->
+			<hkparam name="generators" numelements="0"></hkparam>
+<!-- CLOSE -->
+			<hkparam name="b">1</hkparam>
+		</hkobject>"#;
+
+        skip_field(&mut input);
+        assert!(input.trim_start().starts_with("<!-- ORIGINAL -->"));
+        skip_till_close(&mut input);
+        assert!(input.trim_start().starts_with(r#"<hkparam name="b">"#));
+        skip_field(&mut input);
+        assert_eq!(input.trim_start(), "</hkobject>");
+        assert_eq!(skip_node(&mut input), Node::End);
+        assert_eq!(input.trim_start(), "</hkobject>");
+    }
+}
@@ -0,0 +1,362 @@
+//! Nemesis patch writer.
+//!
+//! Writes class objects as the content of `#NNNN.txt`, marking the changes with
+//! `<!-- MOD_CODE ~id~ OPEN -->`, `<!-- ORIGINAL -->` and `<!-- CLOSE -->` as mod authors do by hand.
+//! The output is read back by [`crate::patch::parse_nemesis_patch`].
+mod patches;
+mod value;
+
+use std::fmt::Write as _;
+
+use simd_json::BorrowedValue;
+
+pub use self::patches::write_patches;
+pub(crate) use self::value::class_object_type;
+use self::value::{
+    as_array, as_object, check_fields, class_info, indent, same_value, write_elements, write_field,
+    write_fields,
+};
+use crate::error::Result;
+
+/// Depth of `<hkobject name="...">` in `#NNNN.txt`. (as in the Nemesis templates)
+const OBJECT_DEPTH: usize = 2;
+
+const ORIGINAL: &str = "<!-- ORIGINAL -->\n";
+const CLOSE: &str = "<!-- CLOSE -->\n";
+
+/// Write a class object that a mod adds. (e.g. `#slide$0.txt`)
+///
+/// - `ptr`: e.g. `#slide$0`
+/// - `fields`: e.g. `{ "__ptr": "#slide$0", "name": "SlideState", ... }`
+///
+/// # Errors
+/// If the class or a field is unknown, or a value does not match the type of its field.
+pub fn write_new_object(
+    ptr: &str,
+    class_name: &str,
+    signature: &str,
+    fields: &BorrowedValue<'_>,
+) -> Result<String> {
+    let field_info = class_info(class_name)?;
+    let fields = as_object(fields, class_name)?;
+
+    let mut out = start_object(ptr, class_name, signature);
+    write_fields(&mut out, field_info, fields, OBJECT_DEPTH + 1)?;
+    end_object(&mut out);
+    Ok(out)
+}
+
+/// Write a patch that changes a class object from `old` into `new`.
+///
+/// Unchanged fields are written as is, and changed fields are marked as follows.
+/// - An array whose change is one range(e.g. states appended to `hkbStateMachine.states`): only that range,
+///   so that it merges with the mods changing the other parts of the array.
+/// - Others: the whole field.
+///
+/// The fields only in `old` are kept, since a patch cannot remove a field.
+///
+/// # Returns
+/// `None` if nothing is changed.
+///
+/// # Errors
+/// If the class or a field is unknown, or a value does not match the type of its field.
+pub fn write_object_patch(
+    ptr: &str,
+    class_name: &str,
+    signature: &str,
+    mod_code: &str,
+    old: &BorrowedValue<'_>,
+    new: &BorrowedValue<'_>,
+) -> Result<Option<String>> {
+    let field_info = class_info(class_name)?;
+    let old_fields = as_object(old, class_name)?;
+    let new_fields = as_object(new, class_name)?;
+    check_fields(field_info, old_fields)?;
+    check_fields(field_info, new_fields)?;
+
+    let depth = OBJECT_DEPTH + 1;
+    let mut out = start_object(ptr, class_name, signature);
+    let mut is_changed = false;
+    for (&name, &field_type) in field_info.entries() {
+        match (old_fields.get(name), new_fields.get(name)) {
+            (Some(old), Some(new)) if !same_value(old, new) => {
+                is_changed = true;
+
+                let seq = field_type.strip_prefix("Array|").and_then(|elem_type| {
+                    let old = as_array(old, field_type).ok()?;
+                    let edit = SeqEdit::new(old, as_array(new, field_type).ok()?)?;
+                    Some((elem_type, old, edit))
+                });
+                if let Some((elem_type, old, edit)) = seq {
+                    write_seq_patch(&mut out, mod_code, name, elem_type, old, &edit, depth)?;
+                } else {
+                    open_mod_code(&mut out, mod_code);
+                    write_field(&mut out, name, field_type, new, depth)?;
+                    out.push_str(ORIGINAL);
+                    write_field(&mut out, name, field_type, old, depth)?;
+                    out.push_str(CLOSE);
+                }
+            }
+            (Some(value), _) => write_field(&mut out, name, field_type, value, depth)?,
+            (None, Some(new)) => {
+                is_changed = true;
+                open_mod_code(&mut out, mod_code);
+                write_field(&mut out, name, field_type, new, depth)?;
+                out.push_str(CLOSE);
+            }
+            (None, None) => {}
+        }
+    }
+    end_object(&mut out);
+
+    Ok(is_changed.then_some(out))
+}
+
+/// Split a class map(`{ "#0001": { "hkbStateMachine": { ... } } }`) into (ptr, class name, fields). (index order)
+pub fn class_objects<'a, 'value>(
+    class_map: &'a BorrowedValue<'value>,
+) -> Vec<(&'a str, &'a str, &'a BorrowedValue<'value>)> {
+    let BorrowedValue::Object(map) = class_map else {
+        return vec![];
+    };
+
+    let mut objects: Vec<_> = map
+        .iter()
+        .filter_map(|(ptr, class)| {
+            let BorrowedValue::Object(class) = class else {
+                return None;
+            };
+            let (class_name, fields) = class.iter().next()?;
+            let (ptr, class_name): (&str, &str) = (ptr, class_name);
+            Some((ptr, class_name, fields))
+        })
+        .collect();
+    // `#10000` comes after `#9999`.
+    objects.sort_unstable_by(|a, b| (a.0.len(), a.0).cmp(&(b.0.len(), b.0)));
+    objects
+}
+
+/// A change of one range of an array: `old[start..start + removed]` -> `values`.
+///
+/// The parser keeps one seq patch per field, and reads a replaced range by the length of the new values.
+/// So an array is patched by range only if its change fits in this.
+#[derive(Debug)]
+struct SeqEdit<'a, 'value> {
+    start: usize,
+    removed: usize,
+    values: &'a [BorrowedValue<'value>],
+}
+
+impl<'a, 'value> SeqEdit<'a, 'value> {
+    /// Trim the common head and tail. `None` if the rest cannot be one change.
+    fn new(old: &[BorrowedValue<'_>], new: &'a [BorrowedValue<'value>]) -> Option<Self> {
+        let head = old.iter().zip(new).take_while(|(old, new)| same_value(old, new)).count();
+        let tail = old[head..]
+            .iter()
+            .rev()
+            .zip(new[head..].iter().rev())
+            .take_while(|(old, new)| same_value(old, new))
+            .count();
+
+        let removed = old.len() - head - tail;
+        let values = &new[head..new.len() - tail];
+        (removed == 0 || values.is_empty() || removed == values.len()).then_some(Self {
+            start: head,
+            removed,
+            values,
+        })
+    }
+}
+
+/// Write an array field with the changed range marked.
+///
+/// - Add: `MOD_CODE`, new elements, `CLOSE` (at the end of the array, it is a push)
+/// - Remove: `MOD_CODE`, `ORIGINAL`, old elements, `CLOSE`
+/// - Replace: `MOD_CODE`, new elements, `ORIGINAL`, old elements, `CLOSE`
+fn write_seq_patch(
+    out: &mut String,
+    mod_code: &str,
+    name: &str,
+    elem_type: &'static str,
+    old: &[BorrowedValue<'_>],
+    edit: &SeqEdit<'_, '_>,
+    depth: usize,
+) -> Result<()> {
+    let end = edit.start + edit.removed;
+
+    // `numelements` of the original, as the markers are inserted into the original.
+    indent(out, depth);
+    let _ = writeln!(out, r#"<hkparam name="{name}" numelements="{}">"#, old.len());
+    write_elements(out, elem_type, &old[..edit.start], depth + 1)?;
+    open_mod_code(out, mod_code);
+    write_elements(out, elem_type, edit.values, depth + 1)?;
+    if edit.removed > 0 {
+        out.push_str(ORIGINAL);
+        write_elements(out, elem_type, &old[edit.start..end], depth + 1)?;
+    }
+    out.push_str(CLOSE);
+    write_elements(out, elem_type, &old[end..], depth + 1)?;
+    indent(out, depth);
+    out.push_str("</hkparam>\n");
+    Ok(())
+}
+
+fn start_object(ptr: &str, class_name: &str, signature: &str) -> String {
+    let mut out = String::new();
+    indent(&mut out, OBJECT_DEPTH);
+    let _ =
+        writeln!(out, r#"<hkobject name="{ptr}" class="{class_name}" signature="{signature}">"#);
+    out
+}
+
+fn end_object(out: &mut String) {
+    indent(out, OBJECT_DEPTH);
+    out.push_str("</hkobject>\n");
+}
+
+fn open_mod_code(out: &mut String, mod_code: &str) {
+    let _ = writeln!(out, "<!-- MOD_CODE ~{mod_code}~ OPEN -->");
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use json_patch::{Action, JsonPatch, Op, json_path};
+    use simd_json::json_typed;
+
+    use super::*;
+    use crate::patch::parse_nemesis_patch;
+
+    const SIGNATURE: &str = "0x816c1dcb";
+
+    #[test]
+    fn roundtrip_object_patch() {
+        let old = json_typed!(borrowed, {
+            "__ptr": "#0001",
+            "name": "Root",
+            "eventToSendWhenStateOrTransitionChanges": { "id": -1, "payload": "#0000" },
+            "startStateId": 0,
+            "states": ["#0002", "#0003"],
+            "timeInState": 0.0
+        });
+        let new = json_typed!(borrowed, {
+            "__ptr": "#0001",
+            "name": "Root",
+            "eventToSendWhenStateOrTransitionChanges": { "id": 5, "payload": "#0000" },
+            "startStateId": 1,
+            "states": ["#0002", "#0003", "#slide$0"],
+            "timeInState": 0.000_000_1 // Not written in 6 decimal places.
+        });
+
+        let patch = write_object_patch("#0001", "hkbStateMachine", SIGNATURE, "slide", &old, &new)
+            .unwrap()
+            .unwrap();
+        let (actual, _) = parse_nemesis_patch(&patch, None).unwrap_or_else(|e| panic!("{e}"));
+
+        let mut expected = HashMap::new();
+        expected.insert(
+            json_path!["#0001", "hkbStateMachine", "eventToSendWhenStateOrTransitionChanges"],
+            JsonPatch {
+                action: Action::Pure { op: Op::Replace },
+                value: json_typed!(borrowed, { "id": 5, "payload": "#0000" }),
+            },
+        );
+        expected.insert(
+            json_path!["#0001", "hkbStateMachine", "startStateId"],
+            JsonPatch { action: Action::Pure { op: Op::Replace }, value: 1.into() },
+        );
+        expected.insert(
+            json_path!["#0001", "hkbStateMachine", "states"],
+            JsonPatch { action: Action::SeqPush, value: json_typed!(borrowed, ["#slide$0"]) },
+        );
+        assert_eq!(actual, expected);
+
+        assert_eq!(
+            write_object_patch("#0001", "hkbStateMachine", SIGNATURE, "slide", &old, &old).unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn roundtrip_seq_ranges() {
+        let old = json_typed!(borrowed, {
+            "animationFilenames": ["a", "b"],
+            "behaviorFilenames": ["a"],
+            "characterFilenames": ["a", "b", "c"],
+            "eventNames": ["e1", "e2", "e3"],
+            "rootPath": ""
+        });
+        let new = json_typed!(borrowed, {
+            "animationFilenames": ["a", "z"],
+            "behaviorFilenames": ["b", "c"],
+            "characterFilenames": ["a", "x", "b", "c"],
+            "eventNames": ["e1", "e3"],
+            "rootPath": ""
+        });
+
+        let patch =
+            write_object_patch("#0009", "hkbProjectStringData", "0x76ad60a", "slide", &old, &new)
+                .unwrap()
+                .unwrap();
+        let (actual, _) = parse_nemesis_patch(&patch, None).unwrap_or_else(|e| panic!("{e}"));
+
+        let mut expected = HashMap::new();
+        expected.insert(
+            json_path!["#0009", "hkbProjectStringData", "animationFilenames"],
+            JsonPatch {
+                action: Action::Seq { op: Op::Replace, range: 1..2 },
+                value: json_typed!(borrowed, ["z"]),
+            },
+        );
+        // Length changed in the middle: the whole field.
+        expected.insert(
+            json_path!["#0009", "hkbProjectStringData", "behaviorFilenames"],
+            JsonPatch {
+                action: Action::Pure { op: Op::Replace },
+                value: json_typed!(borrowed, ["b", "c"]),
+            },
+        );
+        expected.insert(
+            json_path!["#0009", "hkbProjectStringData", "characterFilenames"],
+            JsonPatch {
+                action: Action::Seq { op: Op::Add, range: 1..2 },
+                value: json_typed!(borrowed, ["x"]),
+            },
+        );
+        expected.insert(
+            json_path!["#0009", "hkbProjectStringData", "eventNames"],
+            JsonPatch {
+                action: Action::Seq { op: Op::Remove, range: 1..2 },
+                value: json_typed!(borrowed, null),
+            },
+        );
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn roundtrip_new_object() {
+        let fields = json_typed!(borrowed, {
+            "__ptr": "#slide$0",
+            "name": "SlideState",
+            "states": ["#0002", "#slide$1"],
+            "timeInState": 0.5
+        });
+
+        let xml = write_new_object("#slide$0", "hkbStateMachine", SIGNATURE, &fields).unwrap();
+        let (actual, _) = parse_nemesis_patch(&xml, None).unwrap_or_else(|e| panic!("{e}"));
+
+        let mut expected = HashMap::new();
+        expected.insert(
+            json_path!["#slide$0", "hkbStateMachine"],
+            JsonPatch { action: Action::Pure { op: Op::Add }, value: fields },
+        );
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn unknown_field_is_error() {
+        let fields = json_typed!(borrowed, { "unknown": 0 });
+        assert!(write_new_object("#slide$0", "hkbStateMachine", SIGNATURE, &fields).is_err());
+    }
+}
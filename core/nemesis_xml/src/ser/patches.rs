@@ -0,0 +1,483 @@
+//! [`PatchesMap`] as Nemesis XML.
+use std::{
+    borrow::Cow,
+    collections::{BTreeMap, BTreeSet, HashMap, HashSet},
+    fmt::Write as _,
+};
+
+use json_patch::{Action, JsonPatch, JsonPath, Op};
+use simd_json::{BorrowedValue, borrowed::Object};
+
+use super::{
+    CLOSE, OBJECT_DEPTH, ORIGINAL, SeqEdit, class_objects, end_object, open_mod_code, start_object,
+    value::{
+        as_array, as_object, check_fields, class_info, class_object_type, indent, write_elements,
+        write_field,
+    },
+    write_new_object, write_seq_patch,
+};
+use crate::{
+    error::{Error, Result},
+    patch::{PatchesMap, class_table::FieldInfo},
+};
+
+/// Write patches(e.g. parsed from Nemesis XML, generated from FNIS list) as Nemesis XML.
+///
+/// - `template`: class map of the template that the patches target. e.g. `{ "#0001": { "hkbStateMachine": { ... } } }`
+///   The unchanged fields and the `ORIGINAL` blocks are written from it, so the output can be read by Nemesis as well.
+/// - `signatures`: class name -> signature. e.g. `hkbStateMachine` -> `0x816c1dcb`
+/// - `mod_code`: e.g. `slide`
+///
+/// # Returns
+/// ptr -> content of `<ptr>.txt`. e.g. `#0001` -> `<hkobject name="#0001" ...`
+///
+/// `parse_nemesis_patch` reads the outputs back as the same patches, except for the following.
+/// - A seq add at the end of the array is read as a push.
+/// - A patch to a component of a math type(e.g. `Vector4.x`) is read as a replacement of the whole field,
+///   since Nemesis patches are line based.
+///
+/// # Errors
+/// - If a patch does not fit in the template(unknown object/field, out of range).
+/// - If a patch cannot be written as Nemesis XML(e.g. removing a field).
+/// - If a signature is not found.
+pub fn write_patches(
+    patches: &PatchesMap<'_>,
+    template: &BorrowedValue<'_>,
+    signatures: &HashMap<&str, &str>,
+    mod_code: &str,
+) -> Result<BTreeMap<String, String>> {
+    let mut roots = BTreeSet::new();
+    for (path, patch) in patches {
+        match path.as_slice() {
+            [ptr, class_name, ..] => roots.insert((ptr, class_name)),
+            _ => return Err(unsupported(path, &patch.action)),
+        };
+    }
+
+    let template: HashMap<_, _> = class_objects(template)
+        .into_iter()
+        .map(|(ptr, class_name, fields)| (ptr, (class_name, fields)))
+        .collect();
+    let mut writer = PatchWriter {
+        patches,
+        parents: patches
+            .keys()
+            .flat_map(|path| (1..path.len()).map(move |len| &path[..len]))
+            .collect(),
+        mod_code,
+        written: HashSet::new(),
+    };
+
+    let mut files = BTreeMap::new();
+    for (ptr, class_name) in roots {
+        let signature = signatures
+            .get(&**class_name)
+            .ok_or_else(|| Error::MissingSignature { class_name: class_name.to_string() })?;
+        let mut path = vec![ptr.clone(), class_name.clone()];
+
+        let xml = match patches.get_key_value(&path) {
+            Some((key, JsonPatch { action: Action::Pure { op: Op::Add }, value })) => {
+                writer.written.insert(key);
+                write_new_object(ptr, class_name, signature, value)?
+            }
+            Some((_, patch)) => return Err(unsupported(&path, &patch.action)),
+            None => {
+                let fields = template
+                    .get(&**ptr)
+                    .filter(|(template_class, _)| **template_class == **class_name)
+                    .ok_or_else(|| Error::MissingTemplateObject {
+                        ptr: ptr.to_string(),
+                        class_name: class_name.to_string(),
+                    })?
+                    .1;
+
+                let mut out = start_object(ptr, class_name, signature);
+                let field_info = class_info(class_name)?;
+                let fields = as_object(fields, class_name)?;
+                writer.write_fields(&mut out, &mut path, field_info, fields, OBJECT_DEPTH + 1)?;
+                end_object(&mut out);
+                out
+            }
+        };
+        files.insert(ptr.to_string(), xml);
+    }
+
+    // e.g. A patch to an array element of non class type.
+    if let Some((path, patch)) = patches.iter().find(|(path, _)| !writer.written.contains(path)) {
+        return Err(unsupported(path, &patch.action));
+    }
+    Ok(files)
+}
+
+#[derive(Debug)]
+struct PatchWriter<'a, 'p> {
+    patches: &'a PatchesMap<'p>,
+    /// The paths that have patches under them.
+    parents: HashSet<&'a [Cow<'p, str>]>,
+    mod_code: &'a str,
+    /// The patches already written.
+    written: HashSet<&'a JsonPath<'p>>,
+}
+
+impl<'a, 'p> PatchWriter<'a, 'p> {
+    /// Write `fields` in the order of the class, with the patches under `path` marked.
+    fn write_fields(
+        &mut self,
+        out: &mut String,
+        path: &mut JsonPath<'p>,
+        field_info: &'static FieldInfo,
+        fields: &Object<'_>,
+        depth: usize,
+    ) -> Result<()> {
+        let patches = self.patches;
+
+        check_fields(field_info, fields)?;
+        for (&name, &field_type) in field_info.entries() {
+            path.push(Cow::Borrowed(name));
+            let old = fields.get(name);
+            match (patches.get_key_value(path.as_slice()), old) {
+                (Some((key, patch)), _) => {
+                    self.written.insert(key);
+                    self.write_field_patch(out, path, field_type, old, patch, depth)?;
+                }
+                (None, Some(old)) if self.parents.contains(path.as_slice()) => {
+                    self.write_nested(out, path, name, field_type, old, depth)?;
+                }
+                (None, Some(old)) => write_field(out, name, field_type, old, depth)?,
+                (None, None) if self.parents.contains(path.as_slice()) => {
+                    return Err(Error::MissingField { field_name: path.join("/") });
+                }
+                (None, None) => {}
+            }
+            path.pop();
+        }
+        Ok(())
+    }
+
+    /// Write a field that has a patch on itself.
+    ///
+    /// - `path`: path to the field. e.g. `["#0001", "hkbStateMachine", "states"]`
+    fn write_field_patch(
+        &self,
+        out: &mut String,
+        path: &[Cow<'p, str>],
+        field_type: &'static str,
+        old: Option<&BorrowedValue<'_>>,
+        patch: &JsonPatch<'_>,
+        depth: usize,
+    ) -> Result<()> {
+        let name = path.last().map_or("", |name| &**name);
+        let JsonPatch { action, value } = patch;
+        match (action, field_type.strip_prefix("Array|")) {
+            (Action::Pure { op: Op::Add }, _) => {
+                open_mod_code(out, self.mod_code);
+                write_field(out, name, field_type, value, depth)?;
+                out.push_str(CLOSE);
+            }
+            (Action::Pure { op: Op::Replace }, _) => {
+                open_mod_code(out, self.mod_code);
+                write_field(out, name, field_type, value, depth)?;
+                out.push_str(ORIGINAL);
+                if let Some(old) = old {
+                    write_field(out, name, field_type, old, depth)?;
+                }
+                out.push_str(CLOSE);
+            }
+            (Action::Seq { op, range }, Some(elem_type)) => {
+                let old = match old {
+                    Some(old) => as_array(old, field_type)?,
+                    None => &[],
+                };
+                let (removed, values) = match op {
+                    Op::Add => (0, as_array(value, field_type)?),
+                    Op::Remove => (range.len(), &[][..]),
+                    Op::Replace => (range.len(), as_array(value, field_type)?),
+                    Op::Move | Op::Copy | Op::Test => return Err(unsupported(path, action)),
+                };
+                if range.start + removed > old.len() {
+                    return Err(Error::OutOfRangePatch {
+                        path: path.join("/"),
+                        range: range.clone(),
+                        len: old.len(),
+                    });
+                }
+
+                let edit = SeqEdit { start: range.start, removed, values };
+                write_seq_patch(out, self.mod_code, name, elem_type, old, &edit, depth)?;
+            }
+            (Action::SeqPush, Some(elem_type)) => {
+                let old = match old {
+                    Some(old) => as_array(old, field_type)?,
+                    None => &[],
+                };
+                let edit =
+                    SeqEdit { start: old.len(), removed: 0, values: as_array(value, field_type)? };
+                write_seq_patch(out, self.mod_code, name, elem_type, old, &edit, depth)?;
+            }
+            (action, _) => return Err(unsupported(path, action)),
+        }
+        Ok(())
+    }
+
+    /// Write a field that has patches under it.
+    fn write_nested(
+        &mut self,
+        out: &mut String,
+        path: &mut JsonPath<'p>,
+        name: &str,
+        field_type: &'static str,
+        old: &BorrowedValue<'_>,
+        depth: usize,
+    ) -> Result<()> {
+        let elem_type = field_type.strip_prefix("Array|");
+
+        if let Some(class_name) = class_object_type(field_type) {
+            indent(out, depth);
+            let _ = writeln!(out, r#"<hkparam name="{name}">"#);
+            self.write_class_object(out, path, class_name, old, depth + 1)?;
+        } else if let Some((elem_type, class_name)) =
+            elem_type.and_then(|elem_type| Some((elem_type, class_object_type(elem_type)?)))
+        {
+            let elems = as_array(old, field_type)?;
+            indent(out, depth);
+            let _ = writeln!(out, r#"<hkparam name="{name}" numelements="{}">"#, elems.len());
+            for (index, elem) in elems.iter().enumerate() {
+                path.push(Cow::Owned(format!("[{index}]")));
+                if self.parents.contains(path.as_slice()) {
+                    self.write_class_object(out, path, class_name, elem, depth + 1)?;
+                } else {
+                    write_elements(out, elem_type, core::slice::from_ref(elem), depth + 1)?;
+                }
+                path.pop();
+            }
+        } else if field_type.starts_with("Object|") {
+            return self.write_math_patches(out, path, name, field_type, old, depth);
+        } else {
+            let (key, patch) = self
+                .patches
+                .iter()
+                .find(|(key, _)| key.starts_with(path))
+                .ok_or_else(|| Error::MissingField { field_name: path.join("/") })?;
+            return Err(unsupported(key, &patch.action));
+        }
+
+        indent(out, depth);
+        out.push_str("</hkparam>\n");
+        Ok(())
+    }
+
+    /// Math types(e.g. `Vector4`) are written in one line, so the patches to the components are written
+    /// as a replacement of the whole field.
+    fn write_math_patches(
+        &mut self,
+        out: &mut String,
+        path: &[Cow<'p, str>],
+        name: &str,
+        field_type: &'static str,
+        old: &BorrowedValue<'_>,
+        depth: usize,
+    ) -> Result<()> {
+        let patches = self.patches;
+
+        let mut new = old.clone_static();
+        for (key, patch) in patches.iter().filter(|(key, _)| key.starts_with(path)) {
+            if !matches!(patch.action, Action::Pure { op: Op::Add | Op::Replace }) {
+                return Err(unsupported(key, &patch.action));
+            }
+            let target = key[path.len()..]
+                .iter()
+                .try_fold(&mut new, |value, component| match value {
+                    BorrowedValue::Object(object) => object.get_mut(&**component),
+                    _ => None,
+                })
+                .ok_or_else(|| Error::MissingField { field_name: key.join("/") })?;
+            *target = patch.value.clone_static();
+            self.written.insert(key);
+        }
+
+        open_mod_code(out, self.mod_code);
+        write_field(out, name, field_type, &new, depth)?;
+        out.push_str(ORIGINAL);
+        write_field(out, name, field_type, old, depth)?;
+        out.push_str(CLOSE);
+        Ok(())
+    }
+
+    /// Write `<hkobject>`(a struct in a field) with the patches under `path` marked.
+    fn write_class_object(
+        &mut self,
+        out: &mut String,
+        path: &mut JsonPath<'p>,
+        class_name: &str,
+        value: &BorrowedValue<'_>,
+        depth: usize,
+    ) -> Result<()> {
+        let field_info = class_info(class_name)?;
+        let fields = as_object(value, class_name)?;
+
+        indent(out, depth);
+        out.push_str("<hkobject>\n");
+        self.write_fields(out, path, field_info, fields, depth + 1)?;
+        indent(out, depth);
+        out.push_str("</hkobject>\n");
+        Ok(())
+    }
+}
+
+#[cold]
+fn unsupported(path: &[Cow<'_, str>], action: &Action) -> Error {
+    Error::UnsupportedPatch { path: path.join("/"), action: format!("{action:?}") }
+}
+
+#[cfg(test)]
+mod tests {
+    use json_patch::json_path;
+    use simd_json::json_typed;
+
+    use super::*;
+    use crate::patch::parse_nemesis_patch;
+
+    fn signatures() -> HashMap<&'static str, &'static str> {
+        HashMap::from([
+            ("hkbProjectData", "0x13a39ba7"),
+            ("hkbProjectStringData", "0x76ad60a"),
+            ("hkbStateMachine", "0x816c1dcb"),
+            ("hkbStateMachineStateInfo", "0xed7f9d0"),
+        ])
+    }
+
+    /// Parse all the files into one map.
+    fn parse_all(files: &BTreeMap<String, String>) -> PatchesMap<'_> {
+        files
+            .values()
+            .flat_map(|xml| {
+                parse_nemesis_patch(xml, None).unwrap_or_else(|e| panic!("{e}\n{xml}")).0
+            })
+            .collect()
+    }
+
+    #[test]
+    fn roundtrip_patches() {
+        let template = json_typed!(borrowed, {
+            "#0001": { "hkbStateMachine": {
+                "__ptr": "#0001",
+                "name": "Root",
+                "eventToSendWhenStateOrTransitionChanges": { "id": -1, "payload": "#0000" },
+                "startStateId": 0,
+                "states": ["#0002", "#0003"]
+            } },
+            "#0009": { "hkbProjectStringData": {
+                "__ptr": "#0009",
+                "animationFilenames": ["a", "b"],
+                "characterFilenames": ["a", "b"],
+                "eventNames": ["e1", "e2", "e3"]
+            } }
+        });
+
+        let mut patches = PatchesMap::new();
+        patches.insert(
+            json_path!["#0001", "hkbStateMachine", "startStateId"],
+            JsonPatch { action: Action::Pure { op: Op::Replace }, value: 3.into() },
+        );
+        patches.insert(
+            json_path!["#0001", "hkbStateMachine", "eventToSendWhenStateOrTransitionChanges", "id"],
+            JsonPatch { action: Action::Pure { op: Op::Replace }, value: 5.into() },
+        );
+        patches.insert(
+            json_path!["#0001", "hkbStateMachine", "states"],
+            JsonPatch { action: Action::SeqPush, value: json_typed!(borrowed, ["#slide$0"]) },
+        );
+        patches.insert(
+            json_path!["#0009", "hkbProjectStringData", "animationFilenames"],
+            JsonPatch {
+                action: Action::Seq { op: Op::Replace, range: 0..1 },
+                value: json_typed!(borrowed, ["z"]),
+            },
+        );
+        patches.insert(
+            json_path!["#0009", "hkbProjectStringData", "characterFilenames"],
+            JsonPatch {
+                action: Action::Seq { op: Op::Add, range: 1..2 },
+                value: json_typed!(borrowed, ["x"]),
+            },
+        );
+        patches.insert(
+            json_path!["#0009", "hkbProjectStringData", "eventNames"],
+            JsonPatch {
+                action: Action::Seq { op: Op::Remove, range: 1..3 },
+                value: json_typed!(borrowed, null),
+            },
+        );
+        patches.insert(
+            json_path!["#slide$0", "hkbStateMachineStateInfo"],
+            JsonPatch {
+                action: Action::Pure { op: Op::Add },
+                value: json_typed!(borrowed, { "__ptr": "#slide$0", "name": "Slide", "stateId": 1 }),
+            },
+        );
+
+        let files = write_patches(&patches, &template, &signatures(), "slide").unwrap();
+        assert_eq!(files.keys().collect::<Vec<_>>(), ["#0001", "#0009", "#slide$0"]);
+        assert_eq!(parse_all(&files), patches);
+    }
+
+    #[test]
+    fn math_component_is_whole_field() {
+        let template = json_typed!(borrowed, {
+            "#0010": { "hkbProjectData": {
+                "__ptr": "#0010",
+                "worldUpWS": { "x": 0.0, "y": 0.0, "z": 1.0, "w": 0.0 },
+                "stringData": "#0009",
+                "defaultEventMode": "EVENT_MODE_IGNORE_FROM_GENERATOR"
+            } }
+        });
+
+        let mut patches = PatchesMap::new();
+        patches.insert(
+            json_path!["#0010", "hkbProjectData", "worldUpWS", "y"],
+            JsonPatch { action: Action::Pure { op: Op::Replace }, value: 0.5.into() },
+        );
+
+        let files = write_patches(&patches, &template, &signatures(), "slide").unwrap();
+
+        let mut expected = PatchesMap::new();
+        expected.insert(
+            json_path!["#0010", "hkbProjectData", "worldUpWS"],
+            JsonPatch {
+                action: Action::Pure { op: Op::Replace },
+                value: json_typed!(borrowed, { "x": 0.0, "y": 0.5, "z": 1.0, "w": 0.0 }),
+            },
+        );
+        assert_eq!(parse_all(&files), expected);
+    }
+
+    #[test]
+    fn patch_out_of_template_is_error() {
+        let template = json_typed!(borrowed, {
+            "#0009": { "hkbProjectStringData": { "__ptr": "#0009", "eventNames": ["e1"] } }
+        });
+
+        let mut out_of_range = PatchesMap::new();
+        out_of_range.insert(
+            json_path!["#0009", "hkbProjectStringData", "eventNames"],
+            JsonPatch {
+                action: Action::Seq { op: Op::Remove, range: 1..2 },
+                value: json_typed!(borrowed, null),
+            },
+        );
+        assert!(matches!(
+            write_patches(&out_of_range, &template, &signatures(), "slide"),
+            Err(Error::OutOfRangePatch { .. })
+        ));
+
+        let mut unknown_object = PatchesMap::new();
+        unknown_object.insert(
+            json_path!["#0001", "hkbStateMachine", "startStateId"],
+            JsonPatch { action: Action::Pure { op: Op::Replace }, value: 3.into() },
+        );
+        assert!(matches!(
+            write_patches(&unknown_object, &template, &signatures(), "slide"),
+            Err(Error::MissingTemplateObject { .. })
+        ));
+    }
+}
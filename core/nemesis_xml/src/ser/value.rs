@@ -0,0 +1,336 @@
+//! Field values as Nemesis XML. (same layout as the Nemesis templates)
+use std::fmt::Write as _;
+
+use simd_json::{BorrowedValue, StaticNode, borrowed::Object};
+
+use crate::{
+    error::{Error, Result},
+    patch::class_table::{FieldInfo, find_class_info},
+};
+
+/// The `__ptr` field that every class object has. It is written as the `name` attribute instead.
+const PTR_FIELD: &str = "__ptr";
+
+/// Index of a null pointer.
+const NULL_PTR: &str = "#0000";
+
+pub(super) fn indent(out: &mut String, depth: usize) {
+    for _ in 0..depth {
+        out.push('\t');
+    }
+}
+
+pub(super) fn class_info(class_name: &str) -> Result<&'static FieldInfo> {
+    find_class_info(class_name)
+        .ok_or_else(|| Error::UnknownClass { class_name: class_name.to_string() })
+}
+
+/// Check that `fields` has only the fields of the class. (except `__ptr`)
+pub(super) fn check_fields(field_info: &FieldInfo, fields: &Object<'_>) -> Result<()> {
+    let unknown = fields.keys().find(|name| {
+        let name: &str = name;
+        name != PTR_FIELD && !field_info.contains_key(name)
+    });
+    match unknown {
+        Some(name) => Err(Error::UnknownField {
+            field_name: name.to_string(),
+            acceptable_fields: field_info.keys().copied().collect(),
+        }),
+        None => Ok(()),
+    }
+}
+
+/// Write `fields` in the order of the class.
+pub(super) fn write_fields(
+    out: &mut String,
+    field_info: &'static FieldInfo,
+    fields: &Object<'_>,
+    depth: usize,
+) -> Result<()> {
+    check_fields(field_info, fields)?;
+    for (name, field_type) in field_info.entries() {
+        if let Some(value) = fields.get(*name) {
+            write_field(out, name, *field_type, value, depth)?;
+        }
+    }
+    Ok(())
+}
+
+/// Write `<hkparam name="...">...</hkparam>` and a new line.
+pub(super) fn write_field(
+    out: &mut String,
+    name: &str,
+    field_type: &'static str,
+    value: &BorrowedValue<'_>,
+    depth: usize,
+) -> Result<()> {
+    indent(out, depth);
+    if let Some(elem_type) = field_type.strip_prefix("Array|") {
+        let elems = as_array(value, field_type)?;
+        let _ = write!(out, r#"<hkparam name="{name}" numelements="{}">"#, elems.len());
+        if !elems.is_empty() {
+            out.push('\n');
+            write_elements(out, elem_type, elems, depth + 1)?;
+            indent(out, depth);
+        }
+    } else if let Some(class_name) = class_object_type(field_type) {
+        let _ = writeln!(out, r#"<hkparam name="{name}">"#);
+        write_class_object(out, class_name, value, depth + 1)?;
+        indent(out, depth);
+    } else {
+        let _ = write!(out, r#"<hkparam name="{name}">"#);
+        write_inline(out, field_type, value)?;
+    }
+    out.push_str("</hkparam>\n");
+    Ok(())
+}
+
+/// Write the elements of an array, one per line.
+///
+/// - `elem_type`: e.g. `String`, `Pointer`, `Object|hkbClipTrigger`
+pub(super) fn write_elements(
+    out: &mut String,
+    elem_type: &'static str,
+    elems: &[BorrowedValue<'_>],
+    depth: usize,
+) -> Result<()> {
+    for elem in elems {
+        if elem_type == "String" {
+            indent(out, depth);
+            out.push_str("<hkcstring>");
+            write_inline(out, elem_type, elem)?;
+            out.push_str("</hkcstring>\n");
+        } else if let Some(class_name) = class_object_type(elem_type) {
+            write_class_object(out, class_name, elem, depth)?;
+        } else {
+            indent(out, depth);
+            write_inline(out, elem_type, elem)?;
+            out.push('\n');
+        }
+    }
+    Ok(())
+}
+
+/// Write `<hkobject>` without attributes(a struct in a field) and a new line.
+fn write_class_object(
+    out: &mut String,
+    class_name: &str,
+    value: &BorrowedValue<'_>,
+    depth: usize,
+) -> Result<()> {
+    let field_info = class_info(class_name)?;
+    let fields = as_object(value, class_name)?;
+
+    indent(out, depth);
+    out.push_str("<hkobject>\n");
+    write_fields(out, field_info, fields, depth + 1)?;
+    indent(out, depth);
+    out.push_str("</hkobject>\n");
+    Ok(())
+}
+
+/// `Object|<class name>` except for the math types, which are written in `(x y z w)` form.
+pub(crate) fn class_object_type(field_type: &'static str) -> Option<&'static str> {
+    field_type.strip_prefix("Object|").filter(|name| {
+        !matches!(
+            *name,
+            "Matrix3"
+                | "Matrix4"
+                | "QsTransform"
+                | "Quaternion"
+                | "Rotation"
+                | "Transform"
+                | "Vector4"
+        )
+    })
+}
+
+/// Write a value that fits in one line.
+fn write_inline(out: &mut String, field_type: &str, value: &BorrowedValue<'_>) -> Result<()> {
+    const VECTOR4: [&str; 4] = ["x", "y", "z", "w"];
+    const QUATERNION: [&str; 4] = ["x", "y", "z", "scaler"];
+    const MATRIX3: [&str; 3] = ["x", "y", "z"];
+
+    match field_type.strip_prefix("Object|") {
+        Some("Vector4") => write_vector(out, value, &VECTOR4),
+        Some("Quaternion") => write_vector(out, value, &QUATERNION),
+        Some("Matrix3" | "Rotation") => write_matrix(out, value, &MATRIX3),
+        Some("Matrix4") => write_matrix(out, value, &VECTOR4),
+        Some("QsTransform") => {
+            write_vector(out, get_field(value, "transition")?, &VECTOR4)?;
+            write_vector(out, get_field(value, "quaternion")?, &QUATERNION)?;
+            write_vector(out, get_field(value, "scale")?, &VECTOR4)
+        }
+        Some("Transform") => {
+            write_matrix(out, get_field(value, "rotation")?, &MATRIX3)?;
+            write_vector(out, get_field(value, "transition")?, &VECTOR4)
+        }
+        Some(_) => Err(unexpected(field_type, value)),
+        None => write_scalar(out, field_type, value),
+    }
+}
+
+/// e.g. `(0.000000 0.000000 1.000000 0.000000)`
+fn write_vector(out: &mut String, value: &BorrowedValue<'_>, names: &[&str]) -> Result<()> {
+    out.push('(');
+    for (i, name) in names.iter().enumerate() {
+        if i != 0 {
+            out.push(' ');
+        }
+        let component = get_field(value, name)?;
+        let real = as_real(component).ok_or_else(|| unexpected("F64", component))?;
+        write_real(out, real);
+    }
+    out.push(')');
+    Ok(())
+}
+
+/// e.g. `(1.000000 0.000000 0.000000 0.000000)(0.000000 1.000000 0.000000 0.000000)...`
+fn write_matrix(out: &mut String, value: &BorrowedValue<'_>, rows: &[&str]) -> Result<()> {
+    for row in rows {
+        write_vector(out, get_field(value, row)?, &["x", "y", "z", "w"])?;
+    }
+    Ok(())
+}
+
+fn write_scalar(out: &mut String, field_type: &str, value: &BorrowedValue<'_>) -> Result<()> {
+    match (field_type, value) {
+        ("Null", _) => {}
+        ("Pointer", BorrowedValue::String(ptr)) if *ptr != NULL_PTR => out.push_str(ptr),
+        ("Pointer", BorrowedValue::String(_) | BorrowedValue::Static(StaticNode::Null)) => {
+            out.push_str("null");
+        }
+        ("String", BorrowedValue::String(s)) => write_string(out, s),
+        ("String", BorrowedValue::Static(StaticNode::Null)) => out.push('\u{2400}'),
+        ("F64", value) => {
+            let real = as_real(value).ok_or_else(|| unexpected(field_type, value))?;
+            write_real(out, real);
+        }
+        (_, BorrowedValue::Static(StaticNode::Bool(boolean))) => {
+            let _ = write!(out, "{boolean}");
+        }
+        (_, BorrowedValue::Static(StaticNode::I64(int))) => {
+            let _ = write!(out, "{int}");
+        }
+        (_, BorrowedValue::Static(StaticNode::U64(uint))) => {
+            let _ = write!(out, "{uint}");
+        }
+        // e.g. `$eventID[SlideStart]$` in an integer field
+        (_, BorrowedValue::String(s)) => out.push_str(s),
+        _ => return Err(unexpected(field_type, value)),
+    }
+    Ok(())
+}
+
+/// Havok XML writes reals with 6 decimal places.
+fn write_real(out: &mut String, real: f64) {
+    let _ = write!(out, "{real:.6}");
+}
+
+fn write_string(out: &mut String, s: &str) {
+    for c in s.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            c => out.push(c),
+        }
+    }
+}
+
+/// Are the values the same in Nemesis XML?
+///
+/// Integers are compared regardless of the sign type, and reals up to the 6 decimal places written.
+pub(super) fn same_value(a: &BorrowedValue<'_>, b: &BorrowedValue<'_>) -> bool {
+    match (a, b) {
+        (BorrowedValue::Static(a), BorrowedValue::Static(b)) => same_static(a, b),
+        (BorrowedValue::String(a), BorrowedValue::String(b)) => a == b,
+        (BorrowedValue::Array(a), BorrowedValue::Array(b)) => {
+            a.len() == b.len() && a.iter().zip(b.iter()).all(|(a, b)| same_value(a, b))
+        }
+        (BorrowedValue::Object(a), BorrowedValue::Object(b)) => {
+            a.len() == b.len()
+                && a.iter().all(|(key, a)| b.get(key).is_some_and(|b| same_value(a, b)))
+        }
+        _ => false,
+    }
+}
+
+fn same_static(a: &StaticNode, b: &StaticNode) -> bool {
+    const fn int(node: &StaticNode) -> Option<i128> {
+        match *node {
+            StaticNode::I64(int) => Some(int as i128),
+            StaticNode::U64(uint) => Some(uint as i128),
+            _ => None,
+        }
+    }
+    const fn real(node: &StaticNode) -> Option<f64> {
+        match *node {
+            StaticNode::F64(real) => Some(real),
+            StaticNode::I64(int) => Some(int as f64),
+            StaticNode::U64(uint) => Some(uint as f64),
+            _ => None,
+        }
+    }
+    /// In units of the last decimal place written.
+    const fn round(real: f64) -> i64 {
+        (real * 1_000_000.0).round() as i64
+    }
+
+    match (a, b) {
+        (StaticNode::F64(_), _) | (_, StaticNode::F64(_)) => match (real(a), real(b)) {
+            (Some(a), Some(b)) => round(a) == round(b),
+            _ => false,
+        },
+        _ => match (int(a), int(b)) {
+            (Some(a), Some(b)) => a == b,
+            _ => a == b,
+        },
+    }
+}
+
+fn as_real(value: &BorrowedValue<'_>) -> Option<f64> {
+    match value {
+        BorrowedValue::Static(StaticNode::F64(real)) => Some(*real),
+        BorrowedValue::Static(StaticNode::I64(int)) => Some(*int as f64),
+        BorrowedValue::Static(StaticNode::U64(uint)) => Some(*uint as f64),
+        _ => None,
+    }
+}
+
+pub(super) fn as_array<'a, 'value>(
+    value: &'a BorrowedValue<'value>,
+    field_type: &str,
+) -> Result<&'a [BorrowedValue<'value>]> {
+    match value {
+        BorrowedValue::Array(array) => Ok(array),
+        _ => Err(unexpected(field_type, value)),
+    }
+}
+
+pub(super) fn as_object<'a, 'value>(
+    value: &'a BorrowedValue<'value>,
+    class_name: &str,
+) -> Result<&'a Object<'value>> {
+    match value {
+        BorrowedValue::Object(object) => Ok(object),
+        _ => Err(unexpected(class_name, value)),
+    }
+}
+
+fn get_field<'a, 'value>(
+    value: &'a BorrowedValue<'value>,
+    name: &str,
+) -> Result<&'a BorrowedValue<'value>> {
+    match value {
+        BorrowedValue::Object(object) => {
+            object.get(name).ok_or_else(|| Error::MissingField { field_name: name.to_string() })
+        }
+        _ => Err(unexpected("Object", value)),
+    }
+}
+
+#[cold]
+fn unexpected(expected: &str, value: &BorrowedValue<'_>) -> Error {
+    Error::UnexpectedValue { expected: expected.to_string(), value: format!("{value:?}") }
+}
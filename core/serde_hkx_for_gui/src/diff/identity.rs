@@ -0,0 +1,201 @@
+//! Keys of the objects that do not depend on the `#NNNN` index.
+//!
+//! - Named objects(e.g. generators/modifiers/states) are keyed by class and name: `hkbClipGenerator(Jump)`
+//! - Other objects are keyed by the first field that points to them(BFS from the roots):
+//!   `hkbClipGenerator(Jump).triggers:hkbClipTriggerArray`
+//! - A key that is already taken gets a `~2`, `~3`, ... suffix.
+use std::collections::{HashMap, HashSet, VecDeque};
+
+use serde_json::Value;
+
+/// The `__ptr` field that every object has. It is the index itself, so it is skipped.
+pub(super) const PTR_FIELD: &str = "__ptr";
+
+/// Index of a null pointer.
+const NULL_PTR: &str = "#0000";
+
+/// An object of a class map.
+#[derive(Debug)]
+pub(super) struct Object<'a> {
+    /// e.g. `#0001`
+    pub(super) ptr: &'a str,
+    /// e.g. `hkbStateMachine`
+    pub(super) class: &'a str,
+    pub(super) fields: &'a Value,
+}
+
+/// Split a class map(`{ "#0001": { "hkbStateMachine": { ... } } }`) into objects. (index order)
+pub(super) fn objects(class_map: &Value) -> Vec<Object<'_>> {
+    let Some(map) = class_map.as_object() else {
+        return vec![];
+    };
+
+    let mut objects: Vec<_> = map
+        .iter()
+        .map(|(ptr, value)| match value.as_object() {
+            Some(class) if class.len() == 1 => {
+                let (class, fields) = class.iter().next().unwrap_or((ptr, value));
+                Object { ptr, class, fields }
+            }
+            _ => Object { ptr, class: "", fields: value },
+        })
+        .collect();
+    // `#10000` comes after `#9999`.
+    objects.sort_unstable_by(|a, b| (a.ptr.len(), a.ptr).cmp(&(b.ptr.len(), b.ptr)));
+    objects
+}
+
+/// Returns `ptr -> key` of all the objects.
+pub(super) fn object_keys<'a>(objects: &[Object<'a>]) -> HashMap<&'a str, String> {
+    let index_of: HashMap<&str, usize> =
+        objects.iter().enumerate().map(|(index, object)| (object.ptr, index)).collect();
+
+    let mut has_parent = vec![false; objects.len()];
+    let outgoing: Vec<Vec<(String, usize)>> = objects
+        .iter()
+        .enumerate()
+        .map(|(index, object)| {
+            let mut pointers = vec![];
+            collect_pointers(object.fields, &mut String::new(), &mut pointers);
+            pointers
+                .into_iter()
+                .filter_map(|(path, target)| {
+                    let target = *index_of.get(target)?;
+                    (target != index).then_some((path, target))
+                })
+                .inspect(|(_, target)| has_parent[*target] = true)
+                .collect()
+        })
+        .collect();
+
+    let mut taken = HashSet::new();
+    let mut keys: Vec<Option<String>> = vec![None; objects.len()];
+
+    // Names are the most stable, but only if unique.
+    let names: Vec<_> = objects
+        .iter()
+        .map(|object| {
+            let name = object.fields.get("name")?.as_str()?;
+            Some(format!("{}({name})", object.class))
+        })
+        .collect();
+    let mut name_counts = HashMap::new();
+    for name in names.iter().flatten() {
+        *name_counts.entry(name.as_str()).or_insert(0_usize) += 1;
+    }
+    for (key, name) in keys.iter_mut().zip(&names) {
+        if let Some(name) = name
+            && name_counts.get(name.as_str()) == Some(&1)
+        {
+            taken.insert(name.clone());
+            *key = Some(name.clone());
+        }
+    }
+
+    // Roots first, then the objects only reachable from a cycle.
+    let roots = (0..objects.len()).filter(|&index| !has_parent[index]);
+    let rest = 0..objects.len();
+    let mut visited = vec![false; objects.len()];
+    for root in roots.chain(rest) {
+        if visited[root] {
+            continue;
+        }
+        if keys[root].is_none() {
+            keys[root] = Some(unique(objects[root].class.to_string(), &mut taken));
+        }
+
+        visited[root] = true;
+        let mut queue = VecDeque::from([root]);
+        while let Some(index) = queue.pop_front() {
+            for (path, target) in &outgoing[index] {
+                if visited[*target] {
+                    continue;
+                }
+                if keys[*target].is_none() {
+                    let parent = keys[index].as_deref().unwrap_or_default();
+                    let key = format!("{parent}.{path}:{}", objects[*target].class);
+                    keys[*target] = Some(unique(key, &mut taken));
+                }
+                visited[*target] = true;
+                queue.push_back(*target);
+            }
+        }
+    }
+
+    objects.iter().zip(keys).map(|(object, key)| (object.ptr, key.unwrap_or_default())).collect()
+}
+
+fn unique(key: String, taken: &mut HashSet<String>) -> String {
+    if taken.insert(key.clone()) {
+        return key;
+    }
+    (2..)
+        .map(|n| format!("{key}~{n}"))
+        .find(|candidate| taken.insert(candidate.clone()))
+        .unwrap_or(key)
+}
+
+/// Collect `(field path, pointer)` in field order.
+fn collect_pointers<'a>(value: &'a Value, path: &mut String, out: &mut Vec<(String, &'a str)>) {
+    match value {
+        Value::String(s) if is_ptr(s) => out.push((path.clone(), s)),
+        Value::Array(array) => {
+            for (index, value) in array.iter().enumerate() {
+                let len = path.len();
+                path.push_str(&format!("[{index}]"));
+                collect_pointers(value, path, out);
+                path.truncate(len);
+            }
+        }
+        Value::Object(map) => {
+            for (field, value) in map.iter().filter(|(field, _)| *field != PTR_FIELD) {
+                let len = path.len();
+                if !path.is_empty() {
+                    path.push('.');
+                }
+                path.push_str(field);
+                collect_pointers(value, path, out);
+                path.truncate(len);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// `#0001` and so on, except for the null pointer.
+pub(super) fn is_ptr(s: &str) -> bool {
+    s != NULL_PTR
+        && s.strip_prefix('#')
+            .is_some_and(|index| !index.is_empty() && index.bytes().all(|b| b.is_ascii_digit()))
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn keys_follow_names_and_fields() {
+        let class_map = json!({
+            "#0001": { "hkRootLevelContainer": { "__ptr": "#0001", "variant": "#0003" } },
+            "#0003": { "hkbStateMachine": { "__ptr": "#0003", "name": "Root", "states": ["#0002", "#0004"] } },
+            "#0002": { "hkbStateMachineStateInfo": { "__ptr": "#0002", "name": "Idle", "generator": "#0000" } },
+            "#0004": { "hkbStateMachineStateInfo": { "__ptr": "#0004", "name": "Idle", "generator": "#0005" } },
+            "#0005": { "hkbClipGenerator": { "__ptr": "#0005", "triggers": "#0006" } },
+            "#0006": { "hkbClipTriggerArray": { "__ptr": "#0006" } },
+        });
+        let objects = objects(&class_map);
+        let keys = object_keys(&objects);
+
+        assert_eq!(keys["#0001"], "hkRootLevelContainer");
+        assert_eq!(keys["#0003"], "hkbStateMachine(Root)");
+        // Duplicate names fall back to the graph position.
+        assert_eq!(keys["#0002"], "hkbStateMachine(Root).states[0]:hkbStateMachineStateInfo");
+        assert_eq!(keys["#0004"], "hkbStateMachine(Root).states[1]:hkbStateMachineStateInfo");
+        assert_eq!(
+            keys["#0006"],
+            "hkbStateMachine(Root).states[1]:hkbStateMachineStateInfo.generator:hkbClipGenerator.triggers:hkbClipTriggerArray"
+        );
+    }
+}
@@ -0,0 +1,408 @@
+//! Semantic diff of behavior files(hkx/xml/json).
+//!
+//! Objects are lined up by name and graph position instead of the `#NNNN` index, and pointers are
+//! compared by the key of the object they point to. So renumbering alone is not a change.
+//!
+//! # Example
+//! ```no_run
+//! use std::path::Path;
+//! use serde_hkx_for_gui::diff::diff_files;
+//!
+//! let diff = diff_files(Path::new("vanilla/0_master.xml"), Path::new("output/0_master.hkx"))?;
+//! for object in &diff.changed {
+//!     for field in &object.fields {
+//!         println!("{}: {}", object.key, field.path);
+//!     }
+//! }
+//! # Ok::<(), serde_hkx_for_gui::diff::DiffError>(())
+//! ```
+mod identity;
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    path::{Path, PathBuf},
+};
+
+use rayon::prelude::*;
+use serde_hkx_features::ClassMap;
+use serde_json::Value;
+use snafu::ResultExt as _;
+
+use self::identity::{Object, PTR_FIELD, is_ptr, object_keys, objects};
+
+/// Differences between two behaviors.
+#[derive(Debug, Clone, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BehaviorDiff {
+    /// Objects only in the new one. (sorted by key)
+    pub added: Vec<ObjectRef>,
+    /// Objects only in the old one. (sorted by key)
+    pub removed: Vec<ObjectRef>,
+    /// Objects in both, with different fields. (sorted by key)
+    pub changed: Vec<ObjectDiff>,
+}
+
+impl BehaviorDiff {
+    /// Returns `true` if there is no difference.
+    #[inline]
+    pub const fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+}
+
+/// An object that was added or removed.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ObjectRef {
+    /// Key independent of the index. e.g. `hkbClipGenerator(Jump)`
+    pub key: String,
+    pub class: String,
+    /// e.g. `#0042`
+    pub ptr: String,
+}
+
+/// An object whose fields changed.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ObjectDiff {
+    /// Key independent of the index. e.g. `hkbClipGenerator(Jump)`
+    pub key: String,
+    pub class: String,
+    pub old_ptr: String,
+    pub new_ptr: String,
+    pub fields: Vec<FieldDiff>,
+}
+
+/// A changed field.
+///
+/// Pointers in `old`/`new` are replaced with the key of the object they point to.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FieldDiff {
+    /// e.g. `triggers[2].localTime`
+    pub path: String,
+    /// `None` if added.
+    pub old: Option<Value>,
+    /// `None` if removed.
+    pub new: Option<Value>,
+}
+
+/// Diff two behavior files. The format of each is inferred from its extension(hkx/xml/json).
+///
+/// # Errors
+/// If either file cannot be read or parsed.
+pub fn diff_files(old: &Path, new: &Path) -> Result<BehaviorDiff, DiffError> {
+    let (old, new) = rayon::join(|| read_class_map(old), || read_class_map(new));
+    Ok(diff_class_maps(&old?, &new?))
+}
+
+/// Diff two class maps serialized as JSON(`{ "#0001": { "<class>": { ...fields } } }`).
+pub fn diff_class_maps(old: &Value, new: &Value) -> BehaviorDiff {
+    let old_objects = objects(old);
+    let new_objects = objects(new);
+    let old_keys = object_keys(&old_objects);
+    let new_keys = object_keys(&new_objects);
+
+    fn by_key<'a>(
+        objects: Vec<Object<'a>>,
+        keys: &HashMap<&str, String>,
+    ) -> BTreeMap<String, Object<'a>> {
+        objects
+            .into_iter()
+            .map(|object| (keys.get(object.ptr).cloned().unwrap_or_default(), object))
+            .collect()
+    }
+    let old_objects = by_key(old_objects, &old_keys);
+    let new_objects = by_key(new_objects, &new_keys);
+
+    let object_ref = |key: &str, object: &Object<'_>| ObjectRef {
+        key: key.to_string(),
+        class: object.class.to_string(),
+        ptr: object.ptr.to_string(),
+    };
+
+    let mut diff = BehaviorDiff::default();
+    for (key, old_object) in &old_objects {
+        let Some(new_object) = new_objects.get(key) else {
+            diff.removed.push(object_ref(key, old_object));
+            continue;
+        };
+
+        let old_fields = normalize(old_object.fields, &old_keys);
+        let new_fields = normalize(new_object.fields, &new_keys);
+        if old_fields == new_fields {
+            continue;
+        }
+
+        let mut fields = vec![];
+        diff_fields(&mut String::new(), Some(&old_fields), Some(&new_fields), &mut fields);
+        diff.changed.push(ObjectDiff {
+            key: key.clone(),
+            class: new_object.class.to_string(),
+            old_ptr: old_object.ptr.to_string(),
+            new_ptr: new_object.ptr.to_string(),
+            fields,
+        });
+    }
+    for (key, new_object) in &new_objects {
+        if !old_objects.contains_key(key) {
+            diff.added.push(object_ref(key, new_object));
+        }
+    }
+    diff
+}
+
+/// Replace pointers with the keys of the objects, and drop `__ptr`.
+fn normalize(value: &Value, keys: &HashMap<&str, String>) -> Value {
+    match value {
+        Value::String(s) if is_ptr(s) => {
+            keys.get(s.as_str()).map_or_else(|| value.clone(), |key| Value::String(key.clone()))
+        }
+        Value::Array(array) => array.iter().map(|value| normalize(value, keys)).collect(),
+        Value::Object(map) => map
+            .iter()
+            .filter(|(field, _)| *field != PTR_FIELD)
+            .map(|(field, value)| (field.clone(), normalize(value, keys)))
+            .collect(),
+        _ => value.clone(),
+    }
+}
+
+/// Push the leaf differences. (Arrays are compared index by index.)
+fn diff_fields(
+    path: &mut String,
+    old: Option<&Value>,
+    new: Option<&Value>,
+    out: &mut Vec<FieldDiff>,
+) {
+    match (old, new) {
+        (Some(Value::Object(old)), Some(Value::Object(new))) => {
+            let fields = old.keys().chain(new.keys().filter(|field| !old.contains_key(*field)));
+            for field in fields {
+                let len = path.len();
+                if !path.is_empty() {
+                    path.push('.');
+                }
+                path.push_str(field);
+                diff_fields(path, old.get(field), new.get(field), out);
+                path.truncate(len);
+            }
+        }
+        (Some(Value::Array(old)), Some(Value::Array(new))) => {
+            for index in 0..old.len().max(new.len()) {
+                let len = path.len();
+                path.push_str(&format!("[{index}]"));
+                diff_fields(path, old.get(index), new.get(index), out);
+                path.truncate(len);
+            }
+        }
+        (old, new) if old != new => {
+            out.push(FieldDiff { path: path.clone(), old: old.cloned(), new: new.cloned() });
+        }
+        _ => {}
+    }
+}
+
+/// Read hkx/xml/json as the JSON of its `ClassMap`.
+fn read_class_map(path: &Path) -> Result<Value, DiffError> {
+    fn to_json(classes: ClassMap<'_>) -> Result<Value, serde_hkx_features::error::Error> {
+        serde_json::to_value(&classes).map_err(|e| serde_hkx_features::error::Error::IoError {
+            source: std::io::Error::other(e),
+        })
+    }
+
+    let bytes = std::fs::read(path).with_context(|_| ReadSnafu { path })?;
+    serde_hkx_features::convert::process_serde_with(&bytes, path, to_json, to_json)
+        .with_context(|_| ParseSnafu { path })
+}
+
+/// Diff summary of one file in [`diff_dirs`].
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FileDiffSummary {
+    /// Path relative to the dirs.
+    pub path: PathBuf,
+    pub status: FileStatus,
+    /// Number of added objects.
+    pub added: usize,
+    /// Number of removed objects.
+    pub removed: usize,
+    /// Number of changed objects.
+    pub changed: usize,
+    /// Why the file could not be diffed. (only for [`FileStatus::Error`])
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Status of a file in [`diff_dirs`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum FileStatus {
+    /// Only in the new dir.
+    Added,
+    /// Only in the old dir.
+    Removed,
+    Changed,
+    Unchanged,
+    /// Either file could not be read or parsed.
+    Error,
+}
+
+/// Diff the behavior files(`*.hkx`, `*.xml`, `*.json`) at the same relative paths(case insensitive)
+/// in two dirs, e.g. two output dirs. Hidden dirs(e.g. `.d_merge`) are skipped.
+///
+/// Returns a summary for each file, sorted by path.
+///
+/// # Errors
+/// If either dir cannot be read.
+pub fn diff_dirs(old_dir: &Path, new_dir: &Path) -> Result<Vec<FileDiffSummary>, DiffError> {
+    let old_files = collect_behavior_files(old_dir)?;
+    let new_files = collect_behavior_files(new_dir)?;
+
+    let mut pairs: BTreeMap<&str, (Option<&PathBuf>, Option<&PathBuf>)> = BTreeMap::new();
+    for (key, path) in &old_files {
+        pairs.entry(key).or_default().0 = Some(path);
+    }
+    for (key, path) in &new_files {
+        pairs.entry(key).or_default().1 = Some(path);
+    }
+
+    let summary = |path: &Path, status| FileDiffSummary {
+        path: path.to_path_buf(),
+        status,
+        added: 0,
+        removed: 0,
+        changed: 0,
+        error: None,
+    };
+
+    Ok(pairs
+        .into_par_iter()
+        .filter_map(|(_, pair)| {
+            Some(match pair {
+                (Some(old), Some(new)) => {
+                    match diff_files(&old_dir.join(old), &new_dir.join(new)) {
+                        Ok(diff) => {
+                            let status = if diff.is_empty() {
+                                FileStatus::Unchanged
+                            } else {
+                                FileStatus::Changed
+                            };
+                            FileDiffSummary {
+                                added: diff.added.len(),
+                                removed: diff.removed.len(),
+                                changed: diff.changed.len(),
+                                ..summary(new, status)
+                            }
+                        }
+                        Err(err) => FileDiffSummary {
+                            error: Some(err.to_string()),
+                            ..summary(new, FileStatus::Error)
+                        },
+                    }
+                }
+                (Some(old), None) => summary(old, FileStatus::Removed),
+                (None, Some(new)) => summary(new, FileStatus::Added),
+                (None, None) => return None,
+            })
+        })
+        .collect())
+}
+
+/// Returns `lowercase relative path with '/'` -> relative path.
+fn collect_behavior_files(dir: &Path) -> Result<BTreeMap<String, PathBuf>, DiffError> {
+    fn walk(dir: &Path, root: &Path, out: &mut BTreeMap<String, PathBuf>) -> std::io::Result<()> {
+        for entry in std::fs::read_dir(dir)? {
+            let path = entry?.path();
+            if path.is_dir() {
+                let is_hidden =
+                    path.file_name().is_some_and(|name| name.as_encoded_bytes().starts_with(b"."));
+                if !is_hidden {
+                    walk(&path, root, out)?;
+                }
+                continue;
+            }
+
+            let is_behavior = path.extension().is_some_and(|ext| {
+                ["hkx", "xml", "json"].iter().any(|expected| ext.eq_ignore_ascii_case(expected))
+            });
+            if let (true, Ok(relative)) = (is_behavior, path.strip_prefix(root)) {
+                let key = relative.to_string_lossy().replace('\\', "/").to_lowercase();
+                out.insert(key, relative.to_path_buf());
+            }
+        }
+        Ok(())
+    }
+
+    let mut files = BTreeMap::new();
+    walk(dir, dir, &mut files).with_context(|_| ReadSnafu { path: dir })?;
+    Ok(files)
+}
+
+#[derive(Debug, snafu::Snafu)]
+#[snafu(visibility(pub(crate)))]
+pub enum DiffError {
+    /// Failed to read a file or dir.
+    #[snafu(display("Failed to read {}: {source}", path.display()))]
+    Read { path: PathBuf, source: std::io::Error },
+
+    /// Failed to parse a behavior file.
+    #[snafu(display("{}:\n    {source}", path.display()))]
+    Parse { path: PathBuf, source: serde_hkx_features::error::Error },
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn renumbered_objects_are_unchanged() {
+        let old = json!({
+            "#0001": { "hkbStateMachine": { "__ptr": "#0001", "name": "Root", "states": ["#0002"] } },
+            "#0002": { "hkbStateMachineStateInfo": { "__ptr": "#0002", "name": "Idle", "stateId": 0 } },
+            "#0003": { "hkbClipGenerator": { "__ptr": "#0003", "name": "Old" } },
+        });
+        let new = json!({
+            "#0001": { "hkbStateMachine": { "__ptr": "#0001", "name": "Root", "states": ["#0005", "#0004"] } },
+            "#0004": { "hkbStateMachineStateInfo": { "__ptr": "#0004", "name": "Run", "stateId": 1 } },
+            "#0005": { "hkbStateMachineStateInfo": { "__ptr": "#0005", "name": "Idle", "stateId": 2 } },
+        });
+        let diff = diff_class_maps(&old, &new);
+
+        assert_eq!(diff.added.len(), 1);
+        assert_eq!(diff.added[0].key, "hkbStateMachineStateInfo(Run)");
+        assert_eq!(diff.removed.len(), 1);
+        assert_eq!(diff.removed[0].key, "hkbClipGenerator(Old)");
+
+        assert_eq!(
+            diff.changed,
+            [
+                ObjectDiff {
+                    key: "hkbStateMachine(Root)".into(),
+                    class: "hkbStateMachine".into(),
+                    old_ptr: "#0001".into(),
+                    new_ptr: "#0001".into(),
+                    fields: vec![FieldDiff {
+                        path: "states[1]".into(),
+                        old: None,
+                        new: Some(json!("hkbStateMachineStateInfo(Run)")),
+                    }],
+                },
+                ObjectDiff {
+                    key: "hkbStateMachineStateInfo(Idle)".into(),
+                    class: "hkbStateMachineStateInfo".into(),
+                    old_ptr: "#0002".into(),
+                    new_ptr: "#0005".into(),
+                    fields: vec![FieldDiff {
+                        path: "stateId".into(),
+                        old: Some(json!(0)),
+                        new: Some(json!(2)),
+                    }],
+                },
+            ]
+        );
+        assert!(diff_class_maps(&new, &new).is_empty());
+    }
+}
@@ -1,3 +1,4 @@
+pub mod diff;
 pub mod hash;
 pub mod hkanno;
 mod par_walk_dir;
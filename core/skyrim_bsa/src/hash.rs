@@ -0,0 +1,65 @@
+//! Hashes of the folder/file records, used by the game to look up a file with a binary search.
+//!
+//! - ref: https://en.uesp.net/wiki/Oblivion_Mod:Hash_Calculation
+
+/// Hash of a folder path. e.g. `meshes\actors\character\behaviors`
+///
+/// `path` must be normalized(lowercase), and is hashed with `\` separators.
+pub(crate) fn hash_folder(path: &str) -> u64 {
+    let path = path.replace('/', "\\");
+    hash(path.as_bytes(), b"")
+}
+
+/// Hash of a file name(without folder). e.g. `0_master.hkx`
+///
+/// `name` must be normalized(lowercase).
+pub(crate) fn hash_file(name: &str) -> u64 {
+    let (stem, ext) = match name.rfind('.') {
+        Some(at) => name.split_at(at),
+        None => (name, ""),
+    };
+    hash(stem.as_bytes(), ext.as_bytes())
+}
+
+fn hash(stem: &[u8], ext: &[u8]) -> u64 {
+    let len = stem.len();
+    let mut low = match stem {
+        [] => 0,
+        [first, .., last] => {
+            let second_last = if len > 2 { stem[len - 2] } else { 0 };
+            u32::from(*last)
+                | (u32::from(second_last) << 8)
+                | ((len as u32) << 16)
+                | (u32::from(*first) << 24)
+        }
+        [only] => u32::from(*only) | (1 << 16) | (u32::from(*only) << 24),
+    };
+    low |= match ext {
+        b".kf" => 0x80,
+        b".nif" => 0x8000,
+        b".dds" => 0x8080,
+        b".wav" => 0x8000_0000,
+        _ => 0,
+    };
+
+    let middle = stem.get(1..len.saturating_sub(2)).unwrap_or_default();
+    let high = rolling_hash(middle).wrapping_add(rolling_hash(ext));
+    (u64::from(high) << 32) | u64::from(low)
+}
+
+fn rolling_hash(bytes: &[u8]) -> u32 {
+    bytes.iter().fold(0_u32, |hash, &b| hash.wrapping_mul(0x1003F).wrapping_add(u32::from(b)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hash_known_paths() {
+        assert_eq!(hash_folder("meshes"), 0x322F_3A9A_6D06_6573);
+        assert_eq!(hash_file("0_master.hkx"), 0xC430_08DB_3008_6572);
+        assert_eq!(hash_file("skeleton.nif"), 0x875C_FA7E_7308_EF6E);
+        assert_eq!(hash_folder("meshes/actors"), hash_folder(r"meshes\actors"));
+    }
+}
@@ -0,0 +1,105 @@
+//! # Skyrim BSA archive
+//!
+//! Reads and writes the Bethesda archives(`.bsa`) used by Skyrim LE(v104) and SE(v105).
+//!
+//! ## BSA binary layout
+//!
+//! ```text
+//! ┌─ Header ──────────────────────────────────────────────────────┐
+//! │  magic               [4 bytes]  "BSA\0"                       │
+//! │  version             [4 bytes]  u32 LE — 104(LE) / 105(SE)    │
+//! │  folder_records_at   [4 bytes]  u32 LE — always 36            │
+//! │  archive_flags       [4 bytes]  u32 LE — see `ArchiveFlags`   │
+//! │  folder_count        [4 bytes]  u32 LE                        │
+//! │  file_count          [4 bytes]  u32 LE                        │
+//! │  folder_names_len    [4 bytes]  u32 LE — incl. NUL bytes      │
+//! │  file_names_len      [4 bytes]  u32 LE — incl. NUL bytes      │
+//! │  file_flags          [4 bytes]  u32 LE — content types        │
+//! └───────────────────────────────────────────────────────────────┘
+//! ┌─ Folder record × folder_count ────────────────────────────────┐
+//! │  hash    [8 bytes]  u64 LE                                    │
+//! │  count   [4 bytes]  u32 LE — files in this folder             │
+//! │  (v105)  [4 bytes]  padding                                   │
+//! │  offset  [4 bytes(v104) / 8 bytes(v105)]                      │
+//! │          — offset of the file record block + file_names_len   │
+//! └───────────────────────────────────────────────────────────────┘
+//! ┌─ File record block × folder_count ────────────────────────────┐
+//! │  name    [bzstring]  u8 len + folder name + NUL               │
+//! │  ┌─ File record × count ───────────────────────────────────┐  │
+//! │  │  hash    [8 bytes]  u64 LE                              │  │
+//! │  │  size    [4 bytes]  u32 LE — bit 30: toggle compression │  │
+//! │  │  offset  [4 bytes]  u32 LE — absolute offset of data    │  │
+//! │  └─────────────────────────────────────────────────────────┘  │
+//! └───────────────────────────────────────────────────────────────┘
+//! ┌─ File name block ─────────────────────────────────────────────┐
+//! │  NUL terminated file names, in the order of the file records  │
+//! └───────────────────────────────────────────────────────────────┘
+//! ┌─ File data × file_count ──────────────────────────────────────┐
+//! │  (embed names)  [bstring]  u8 len + full path                 │
+//! │  (compressed)   [4 bytes]  u32 LE — original size             │
+//! │  data           zlib(v104) / lz4 frame(v105) if compressed    │
+//! └───────────────────────────────────────────────────────────────┘
+//! ```
+//!
+//! - ref: https://en.uesp.net/wiki/Skyrim_Mod:Archive_File_Format
+mod hash;
+mod read;
+mod write;
+
+pub use self::{read::BsaArchive, write::BsaWriter};
+
+/// Archive format version.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Version {
+    /// Skyrim LE(zlib)
+    V104,
+    /// Skyrim SE(lz4 frame)
+    V105,
+}
+
+impl Version {
+    /// Version number written in the header.
+    #[inline]
+    pub const fn as_u32(self) -> u32 {
+        match self {
+            Self::V104 => 104,
+            Self::V105 => 105,
+        }
+    }
+
+    /// Parse the version number of the header. `None` if not supported.
+    #[inline]
+    pub const fn from_u32(version: u32) -> Option<Self> {
+        match version {
+            104 => Some(Self::V104),
+            105 => Some(Self::V105),
+            _ => None,
+        }
+    }
+}
+
+/// Bits of `archive_flags`.
+pub(crate) mod archive_flags {
+    pub(crate) const INCLUDE_DIRECTORY_NAMES: u32 = 0x1;
+    pub(crate) const INCLUDE_FILE_NAMES: u32 = 0x2;
+    pub(crate) const COMPRESSED: u32 = 0x4;
+    pub(crate) const EMBED_FILE_NAMES: u32 = 0x100;
+}
+
+/// Bit of the file record `size` that inverts [`archive_flags::COMPRESSED`] for the file.
+pub(crate) const SIZE_COMPRESSION_BIT: u32 = 0x4000_0000;
+/// Bits of the file record `size` that are the size.
+pub(crate) const SIZE_MASK: u32 = 0x3FFF_FFFF;
+
+/// Normalize a path in an archive: lowercase with `/` separators, without leading/trailing `/`.
+///
+/// # Example
+/// ```
+/// assert_eq!(
+///     skyrim_bsa::normalize_path(r"Meshes\Actors\Character\Animations\FNISFlyer\"),
+///     "meshes/actors/character/animations/fnisflyer"
+/// );
+/// ```
+pub fn normalize_path(path: &str) -> String {
+    path.replace('\\', "/").trim_matches('/').to_lowercase()
+}
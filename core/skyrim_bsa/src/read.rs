@@ -0,0 +1,461 @@
+//! BSA reader.
+use std::{
+    collections::BTreeMap,
+    fs::File,
+    io::{self, Read, Seek, SeekFrom},
+    ops::Bound,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use crate::{SIZE_COMPRESSION_BIT, SIZE_MASK, Version, archive_flags, normalize_path};
+
+/// An opened archive.
+///
+/// Only the file index is read on open. File data is read on each [`Self::read`].
+#[derive(Debug, Clone)]
+pub struct BsaArchive {
+    storage: Storage,
+    version: Version,
+    archive_flags: u32,
+    /// normalized path(See [`normalize_path`]) -> entry
+    files: BTreeMap<String, FileEntry>,
+}
+
+#[derive(Debug, Clone)]
+enum Storage {
+    File(PathBuf),
+    Bytes(Arc<[u8]>),
+}
+
+#[derive(Debug, Clone, Copy)]
+struct FileEntry {
+    /// Absolute offset of the data.
+    offset: u64,
+    /// Raw size(incl. embedded name and original size).
+    size: u32,
+    compressed: bool,
+}
+
+impl BsaArchive {
+    /// Open an archive file and read its file index.
+    ///
+    /// # Errors
+    /// If the file cannot be read or is not a v104/v105 BSA.
+    pub fn open(path: impl Into<PathBuf>) -> io::Result<Self> {
+        let path = path.into();
+        let mut reader = io::BufReader::new(File::open(&path)?);
+        let (version, archive_flags, files) = read_index(&mut reader)?;
+        Ok(Self { storage: Storage::File(path), version, archive_flags, files })
+    }
+
+    /// Read an archive from bytes.
+    ///
+    /// # Errors
+    /// If `bytes` is not a v104/v105 BSA.
+    pub fn from_bytes(bytes: impl Into<Arc<[u8]>>) -> io::Result<Self> {
+        let bytes = bytes.into();
+        let (version, archive_flags, files) = read_index(&mut io::Cursor::new(&*bytes))?;
+        Ok(Self { storage: Storage::Bytes(bytes), version, archive_flags, files })
+    }
+
+    /// Path of the archive file. `None` if read from bytes.
+    #[inline]
+    pub fn path(&self) -> Option<&Path> {
+        match &self.storage {
+            Storage::File(path) => Some(path),
+            Storage::Bytes(_) => None,
+        }
+    }
+
+    #[inline]
+    pub const fn version(&self) -> Version {
+        self.version
+    }
+
+    /// Number of files.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.files.len()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.files.is_empty()
+    }
+
+    /// Normalized paths of all the files. (sorted)
+    ///
+    /// e.g. `meshes/actors/character/animations/fnisflyer/fnis_fnisflyer_list.txt`
+    pub fn paths(&self) -> impl Iterator<Item = &str> {
+        self.files.keys().map(String::as_str)
+    }
+
+    /// Normalized paths of the files below `dir`. (sorted)
+    ///
+    /// - `dir`: e.g. `Nemesis_Engine\mod`. Any case and separator. An empty `dir` is the archive root.
+    pub fn paths_below(&self, dir: &str) -> impl Iterator<Item = &str> {
+        let mut prefix = normalize_path(dir);
+        if !prefix.is_empty() {
+            prefix.push('/');
+        }
+        self.files
+            .range::<str, _>((Bound::Included(prefix.as_str()), Bound::Unbounded))
+            .map(|(path, _)| path.as_str())
+            .take_while(move |path| path.starts_with(&prefix))
+    }
+
+    /// Returns `true` if the archive has the file.
+    ///
+    /// - `path`: Any case and separator.
+    #[inline]
+    pub fn contains(&self, path: &str) -> bool {
+        self.files.contains_key(&normalize_path(path))
+    }
+
+    /// Read and decompress a file.
+    ///
+    /// - `path`: Any case and separator. e.g. `Meshes\Actors\Character\Behaviors\0_master.hkx`
+    ///
+    /// # Errors
+    /// If the file is not in the archive, or cannot be read or decompressed.
+    pub fn read(&self, path: &str) -> io::Result<Vec<u8>> {
+        let path = normalize_path(path);
+        let entry = self.files.get(&path).ok_or_else(|| {
+            io::Error::new(io::ErrorKind::NotFound, format!("Not found in the archive: {path}"))
+        })?;
+
+        let raw = match &self.storage {
+            Storage::File(archive_path) => {
+                let mut file = File::open(archive_path)?;
+                let stream_len = file.metadata()?.len();
+                file.seek(SeekFrom::Start(entry.offset))?;
+                read_bytes(&mut file, entry.size as usize, stream_len)?
+            }
+            Storage::Bytes(bytes) => {
+                let start = usize::try_from(entry.offset).map_err(invalid_data)?;
+                start
+                    .checked_add(entry.size as usize)
+                    .and_then(|end| bytes.get(start..end))
+                    .ok_or_else(|| invalid_data("File data is out of the archive"))?
+                    .to_vec()
+            }
+        };
+
+        self.decode(raw, entry.compressed)
+    }
+
+    /// Strip the embedded name and decompress.
+    fn decode(&self, raw: Vec<u8>, compressed: bool) -> io::Result<Vec<u8>> {
+        let mut data = raw.as_slice();
+        if self.archive_flags & archive_flags::EMBED_FILE_NAMES != 0 {
+            let (&name_len, rest) =
+                data.split_first().ok_or_else(|| invalid_data("Missing embedded file name"))?;
+            data =
+                rest.get(name_len as usize..).ok_or_else(|| invalid_data("Truncated file name"))?;
+        }
+
+        if !compressed {
+            return Ok(data.to_vec());
+        }
+
+        let (original_size, payload) = data
+            .split_first_chunk::<4>()
+            .ok_or_else(|| invalid_data("Missing original size of a compressed file"))?;
+        let original_size = u32::from_le_bytes(*original_size) as usize;
+        if original_size > payload.len().saturating_mul(MAX_COMPRESSION_RATIO) {
+            return Err(invalid_data("Original size is too large for the compressed data"));
+        }
+
+        let mut bytes = Vec::with_capacity(original_size);
+        let limit = original_size as u64 + 1; // +1: To detect a longer output.
+        match self.version {
+            Version::V104 => {
+                flate2::read::ZlibDecoder::new(payload).take(limit).read_to_end(&mut bytes)?
+            }
+            Version::V105 => {
+                lz4_flex::frame::FrameDecoder::new(payload).take(limit).read_to_end(&mut bytes)?
+            }
+        };
+        if bytes.len() != original_size {
+            return Err(invalid_data("Decompressed size does not match the original size"));
+        }
+        Ok(bytes)
+    }
+}
+
+/// Upper bound of `original size / compressed size`.(zlib: about 1032, lz4: about 255)
+///
+/// Not to allocate a huge buffer from a broken original size.
+const MAX_COMPRESSION_RATIO: usize = 1032;
+
+/// Size of a file record.
+const FILE_RECORD_LEN: usize = 16;
+
+type Index = (Version, u32, BTreeMap<String, FileEntry>);
+
+/// Read the header, folder records, file records and file names.
+fn read_index<R: Read + Seek>(reader: &mut R) -> io::Result<Index> {
+    let stream_len = reader.seek(SeekFrom::End(0))?;
+    reader.seek(SeekFrom::Start(0))?;
+
+    let mut header = [0_u8; 36];
+    reader.read_exact(&mut header)?;
+    let u32_at = |at: usize| {
+        u32::from_le_bytes([header[at], header[at + 1], header[at + 2], header[at + 3]])
+    };
+
+    if &header[0..4] != b"BSA\0" {
+        return Err(invalid_data("Not a BSA archive(magic mismatch)"));
+    }
+    let version = Version::from_u32(u32_at(4))
+        .ok_or_else(|| invalid_data(format!("Unsupported BSA version: {}", u32_at(4))))?;
+    let folder_records_at = u32_at(8);
+    let flags = u32_at(12);
+    let folder_count = u32_at(16) as usize;
+    let file_count = u32_at(20) as usize;
+    let file_names_len = u32_at(28) as usize;
+
+    let required = archive_flags::INCLUDE_DIRECTORY_NAMES | archive_flags::INCLUDE_FILE_NAMES;
+    if flags & required != required {
+        return Err(invalid_data("Archives without folder/file names are not supported"));
+    }
+
+    // Folder records: only the file count is needed, since the file record blocks follow in the same order.
+    reader.seek(SeekFrom::Start(u64::from(folder_records_at)))?;
+    let folder_record_len = match version {
+        Version::V104 => 16,
+        Version::V105 => 24,
+    };
+    let folder_records_len = folder_count
+        .checked_mul(folder_record_len)
+        .ok_or_else(|| invalid_data("Too many folders"))?;
+    let folder_records = read_bytes(reader, folder_records_len, stream_len)?;
+    let folder_file_counts = folder_records
+        .chunks_exact(folder_record_len)
+        .map(|record| u32::from_le_bytes([record[8], record[9], record[10], record[11]]) as usize);
+
+    // (folder, size, offset) in the order of the file records
+    if file_count.checked_mul(FILE_RECORD_LEN).is_none_or(|len| len as u64 > stream_len) {
+        return Err(invalid_data("Too many files"));
+    }
+    let mut records = Vec::with_capacity(file_count);
+    for count in folder_file_counts {
+        let folder = read_bzstring(reader)?;
+        let block_len = count
+            .checked_mul(FILE_RECORD_LEN)
+            .ok_or_else(|| invalid_data("Too many files in a folder"))?;
+        let block = read_bytes(reader, block_len, stream_len)?;
+        for record in block.chunks_exact(FILE_RECORD_LEN) {
+            let size = u32::from_le_bytes([record[8], record[9], record[10], record[11]]);
+            let offset = u32::from_le_bytes([record[12], record[13], record[14], record[15]]);
+            records.push((folder.clone(), size, offset));
+        }
+    }
+
+    let names = read_bytes(reader, file_names_len, stream_len)?;
+    let names = names.split(|&b| b == 0).map(|name| String::from_utf8_lossy(name));
+
+    let compressed_by_default = flags & archive_flags::COMPRESSED != 0;
+    let files = records
+        .into_iter()
+        .zip(names)
+        .map(|((folder, size, offset), name)| {
+            let path = match folder.as_str() {
+                "" | "." => normalize_path(&name),
+                folder => normalize_path(&format!("{folder}/{name}")),
+            };
+            let entry = FileEntry {
+                offset: u64::from(offset),
+                size: size & SIZE_MASK,
+                compressed: compressed_by_default ^ (size & SIZE_COMPRESSION_BIT != 0),
+            };
+            (path, entry)
+        })
+        .collect();
+
+    Ok((version, flags, files))
+}
+
+/// Read `len` bytes.
+///
+/// The length comes from the header, so check that the stream has them before allocating.
+fn read_bytes<R: Read + Seek>(reader: &mut R, len: usize, stream_len: u64) -> io::Result<Vec<u8>> {
+    let position = reader.stream_position()?;
+    if stream_len.saturating_sub(position) < len as u64 {
+        return Err(invalid_data("Out of the archive"));
+    }
+    let mut bytes = vec![0_u8; len];
+    reader.read_exact(&mut bytes)?;
+    Ok(bytes)
+}
+
+/// Read `u8 len` + string + NUL.
+fn read_bzstring<R: Read>(reader: &mut R) -> io::Result<String> {
+    let mut len = [0_u8; 1];
+    reader.read_exact(&mut len)?;
+    let mut bytes = vec![0_u8; len[0] as usize];
+    reader.read_exact(&mut bytes)?;
+    if bytes.last() == Some(&0) {
+        bytes.pop();
+    }
+    Ok(String::from_utf8_lossy(&bytes).into_owned())
+}
+
+fn invalid_data<E>(err: E) -> io::Error
+where
+    E: Into<Box<dyn std::error::Error + Send + Sync>>,
+{
+    io::Error::new(io::ErrorKind::InvalidData, err)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write as _;
+
+    use super::*;
+
+    /// Build a v104/v105 archive with one folder.(file hashes are not checked by the reader)
+    fn build(version: Version, flags: u32, folder: &str, files: &[(&str, &[u8], bool)]) -> Vec<u8> {
+        let folder_record_len = if version == Version::V104 { 16 } else { 24 };
+        let names_len: usize = files.iter().map(|(name, _, _)| name.len() + 1).sum();
+        let records_at = 36 + folder_record_len;
+        let data_at = records_at + 1 + folder.len() + 1 + files.len() * 16 + names_len;
+
+        let mut out = vec![];
+        out.extend(b"BSA\0");
+        for v in [
+            version.as_u32(),
+            36,
+            flags,
+            1,
+            files.len() as u32,
+            folder.len() as u32 + 1,
+            names_len as u32,
+            0,
+        ] {
+            out.extend(v.to_le_bytes());
+        }
+
+        out.extend(0_u64.to_le_bytes());
+        out.extend((files.len() as u32).to_le_bytes());
+        if version == Version::V105 {
+            out.extend(0_u32.to_le_bytes());
+            out.extend(((records_at + names_len) as u64).to_le_bytes());
+        } else {
+            out.extend(((records_at + names_len) as u32).to_le_bytes());
+        }
+
+        out.push(folder.len() as u8 + 1);
+        out.extend(folder.as_bytes());
+        out.push(0);
+
+        let mut data = vec![];
+        for (_, bytes, compress) in files {
+            let mut raw = vec![];
+            if *compress {
+                raw.extend((bytes.len() as u32).to_le_bytes());
+                match version {
+                    Version::V104 => {
+                        let mut encoder =
+                            flate2::write::ZlibEncoder::new(vec![], flate2::Compression::default());
+                        encoder.write_all(bytes).unwrap();
+                        raw.extend(encoder.finish().unwrap());
+                    }
+                    Version::V105 => {
+                        let mut encoder = lz4_flex::frame::FrameEncoder::new(vec![]);
+                        encoder.write_all(bytes).unwrap();
+                        raw.extend(encoder.finish().unwrap());
+                    }
+                }
+            } else {
+                raw.extend(*bytes);
+            }
+
+            let toggle = if *compress == (flags & archive_flags::COMPRESSED != 0) {
+                0
+            } else {
+                SIZE_COMPRESSION_BIT
+            };
+            out.extend(0_u64.to_le_bytes());
+            out.extend((raw.len() as u32 | toggle).to_le_bytes());
+            out.extend(((data_at + data.len()) as u32).to_le_bytes());
+            data.extend(raw);
+        }
+
+        for (name, _, _) in files {
+            out.extend(name.as_bytes());
+            out.push(0);
+        }
+        out.extend(data);
+        out
+    }
+
+    #[test]
+    fn read_compressed_and_raw_files() {
+        const FLAGS: u32 = archive_flags::INCLUDE_DIRECTORY_NAMES
+            | archive_flags::INCLUDE_FILE_NAMES
+            | archive_flags::COMPRESSED;
+
+        for version in [Version::V104, Version::V105] {
+            let bytes = build(
+                version,
+                FLAGS,
+                r"nemesis_engine\mod\slide\0_master",
+                &[("#0001.txt", b"compressed patch", true), ("#0002.txt", b"raw patch", false)],
+            );
+            let archive = BsaArchive::from_bytes(bytes).unwrap();
+
+            assert_eq!(archive.version(), version);
+            assert_eq!(
+                archive.paths().collect::<Vec<_>>(),
+                [
+                    "nemesis_engine/mod/slide/0_master/#0001.txt",
+                    "nemesis_engine/mod/slide/0_master/#0002.txt"
+                ]
+            );
+            assert_eq!(archive.paths_below(r"Nemesis_Engine\mod\slide").count(), 2);
+            assert_eq!(archive.paths_below("nemesis_engine/mod/slid").count(), 0);
+            assert_eq!(
+                archive.read("Nemesis_Engine/mod/slide/0_master/#0001.txt").unwrap(),
+                b"compressed patch"
+            );
+            assert_eq!(
+                archive.read(r"NEMESIS_ENGINE\mod\slide\0_master\#0002.txt").unwrap(),
+                b"raw patch"
+            );
+            assert!(archive.read("nemesis_engine/mod/slide/0_master/#0003.txt").is_err());
+        }
+    }
+
+    #[test]
+    fn reject_out_of_range_sizes() {
+        const FLAGS: u32 = archive_flags::INCLUDE_DIRECTORY_NAMES
+            | archive_flags::INCLUDE_FILE_NAMES
+            | archive_flags::COMPRESSED;
+        let bytes = build(Version::V105, FLAGS, "meshes", &[("a.hkx", b"compressed", true)]);
+
+        // folder_count, file_count, file_names_len
+        for at in [16, 20, 28] {
+            let mut bytes = bytes.clone();
+            bytes[at..at + 4].copy_from_slice(&u32::MAX.to_le_bytes());
+            let err = BsaArchive::from_bytes(bytes).unwrap_err();
+            assert_eq!(err.kind(), io::ErrorKind::InvalidData, "header offset {at}");
+        }
+
+        // original size of the compressed file(the data follows the file names)
+        let mut bytes = bytes;
+        let data_at = bytes.windows(6).position(|w| w == b"a.hkx\0").unwrap() + 6;
+        bytes[data_at..data_at + 4].copy_from_slice(&u32::MAX.to_le_bytes());
+        let archive = BsaArchive::from_bytes(bytes).unwrap();
+        let err = archive.read("meshes/a.hkx").unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn reject_unsupported_version() {
+        let mut bytes = build(Version::V105, 0x3, "meshes", &[("a.hkx", b"a", false)]);
+        bytes[4..8].copy_from_slice(&103_u32.to_le_bytes());
+        assert!(BsaArchive::from_bytes(bytes).is_err());
+    }
+}
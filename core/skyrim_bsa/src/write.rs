@@ -0,0 +1,287 @@
+//! BSA writer.
+use std::{
+    collections::BTreeMap,
+    io::{self, Write},
+};
+
+use crate::{
+    SIZE_MASK, Version, archive_flags,
+    hash::{hash_file, hash_folder},
+    normalize_path,
+};
+
+/// Builds an archive in memory, then writes it at once with [`Self::write_to`].
+///
+/// # Example
+/// ```
+/// use skyrim_bsa::{BsaArchive, BsaWriter, Version};
+///
+/// let mut writer = BsaWriter::new(Version::V105).compressed(true);
+/// writer.insert(r"Meshes\Actors\Character\Behaviors\0_master.hkx", b"hkx".to_vec())?;
+///
+/// let mut bytes = vec![];
+/// writer.write_to(&mut bytes)?;
+///
+/// let archive = BsaArchive::from_bytes(bytes)?;
+/// assert_eq!(archive.read("meshes/actors/character/behaviors/0_master.hkx")?, b"hkx");
+/// # Ok::<(), std::io::Error>(())
+/// ```
+#[derive(Debug, Clone)]
+pub struct BsaWriter {
+    version: Version,
+    compress: bool,
+    /// folder -> file name -> bytes (normalized)
+    folders: BTreeMap<String, BTreeMap<String, Vec<u8>>>,
+}
+
+impl BsaWriter {
+    /// Create an empty archive. Files are stored without compression by default.
+    #[inline]
+    pub const fn new(version: Version) -> Self {
+        Self { version, compress: false, folders: BTreeMap::new() }
+    }
+
+    /// Compress the files(zlib for v104, lz4 frame for v105).
+    #[inline]
+    #[must_use]
+    pub const fn compressed(mut self, compress: bool) -> Self {
+        self.compress = compress;
+        self
+    }
+
+    /// Add a file, replacing the one already at the same path.
+    ///
+    /// - `path`: Any case and separator. e.g. `Meshes\animationdatasinglefile.txt`
+    ///
+    /// # Errors
+    /// If `path` is directly under the archive root, or the folder/file name exceeds 254 bytes.
+    pub fn insert(&mut self, path: &str, bytes: Vec<u8>) -> io::Result<()> {
+        let path = normalize_path(path);
+        let Some((folder, name)) = path.rsplit_once('/') else {
+            return Err(invalid_input(format!("A file must be in a folder: {path}")));
+        };
+        if folder.len() > 254 || name.len() > 254 {
+            return Err(invalid_input(format!("Too long path for an archive: {path}")));
+        }
+
+        self.folders.entry(folder.to_string()).or_default().insert(name.to_string(), bytes);
+        Ok(())
+    }
+
+    /// Number of files.
+    pub fn len(&self) -> usize {
+        self.folders.values().map(BTreeMap::len).sum()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.folders.is_empty()
+    }
+
+    /// Write the archive.
+    ///
+    /// # Errors
+    /// If failed to compress or write, or the archive exceeds 4 GiB.
+    pub fn write_to<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        // The game looks up the folder/file records with a binary search by hash.
+        let mut folders: Vec<_> = self
+            .folders
+            .iter()
+            .map(|(folder, files)| {
+                let mut files: Vec<_> = files
+                    .iter()
+                    .map(|(name, bytes)| (hash_file(name), name.as_str(), bytes.as_slice()))
+                    .collect();
+                files.sort_unstable_by_key(|(hash, _, _)| *hash);
+                (hash_folder(folder), folder.as_str(), files)
+            })
+            .collect();
+        folders.sort_unstable_by_key(|(hash, _, _)| *hash);
+
+        let file_count = self.len();
+        let folder_names_len: usize = folders.iter().map(|(_, folder, _)| folder.len() + 1).sum();
+        let file_names_len: usize = folders
+            .iter()
+            .flat_map(|(_, _, files)| files.iter().map(|(_, name, _)| name.len() + 1))
+            .sum();
+
+        let mut flags = archive_flags::INCLUDE_DIRECTORY_NAMES | archive_flags::INCLUDE_FILE_NAMES;
+        if self.compress {
+            flags |= archive_flags::COMPRESSED;
+        }
+        let file_flags = folders
+            .iter()
+            .flat_map(|(_, _, files)| files.iter().map(|(_, name, _)| file_flag(name)))
+            .fold(0, |acc, flag| acc | flag);
+
+        let folder_record_len = match self.version {
+            Version::V104 => 16,
+            Version::V105 => 24,
+        };
+        let records_at = 36 + folders.len() * folder_record_len;
+        let data_at =
+            records_at + folders.len() + folder_names_len + file_count * 16 + file_names_len;
+
+        // --- Header -------------------------------------------------------------------------
+        w.write_all(b"BSA\0")?;
+        for value in [
+            self.version.as_u32(),
+            36,
+            flags,
+            to_u32(folders.len())?,
+            to_u32(file_count)?,
+            to_u32(folder_names_len)?,
+            to_u32(file_names_len)?,
+            file_flags,
+        ] {
+            w.write_all(&value.to_le_bytes())?;
+        }
+
+        // --- Folder records -----------------------------------------------------------------
+        let mut block_at = records_at;
+        for (hash, folder, files) in &folders {
+            w.write_all(&hash.to_le_bytes())?;
+            w.write_all(&to_u32(files.len())?.to_le_bytes())?;
+            let offset = block_at + file_names_len;
+            match self.version {
+                Version::V104 => w.write_all(&to_u32(offset)?.to_le_bytes())?,
+                Version::V105 => {
+                    w.write_all(&0_u32.to_le_bytes())?;
+                    w.write_all(&(offset as u64).to_le_bytes())?;
+                }
+            }
+            block_at += 1 + folder.len() + 1 + files.len() * 16;
+        }
+
+        // --- File record blocks -------------------------------------------------------------
+        // The data is encoded here, since the records need its sizes and offsets.
+        let mut data = Vec::with_capacity(file_count);
+        let mut offset = data_at;
+        for (_, folder, files) in &folders {
+            w.write_all(&[folder.len() as u8 + 1])?;
+            w.write_all(folder.replace('/', "\\").as_bytes())?;
+            w.write_all(&[0])?;
+
+            for (hash, _, bytes) in files {
+                let raw = self.encode(bytes)?;
+                // The upper bits of the size are flags.
+                let size = to_u32(raw.len())?;
+                if size & !SIZE_MASK != 0 {
+                    return Err(invalid_input("Too large file for an archive"));
+                }
+                w.write_all(&hash.to_le_bytes())?;
+                w.write_all(&size.to_le_bytes())?;
+                w.write_all(&to_u32(offset)?.to_le_bytes())?;
+                offset += raw.len();
+                data.push(raw);
+            }
+        }
+
+        // --- File name block ----------------------------------------------------------------
+        for (_, _, files) in &folders {
+            for (_, name, _) in files {
+                w.write_all(name.as_bytes())?;
+                w.write_all(&[0])?;
+            }
+        }
+
+        // --- File data ----------------------------------------------------------------------
+        for raw in data {
+            w.write_all(&raw)?;
+        }
+        Ok(())
+    }
+
+    /// Encode the data of a file. (original size + compressed bytes if compressed)
+    fn encode(&self, bytes: &[u8]) -> io::Result<Vec<u8>> {
+        if !self.compress {
+            return Ok(bytes.to_vec());
+        }
+
+        let mut raw = to_u32(bytes.len())?.to_le_bytes().to_vec();
+        match self.version {
+            Version::V104 => {
+                let mut encoder =
+                    flate2::write::ZlibEncoder::new(raw, flate2::Compression::default());
+                encoder.write_all(bytes)?;
+                raw = encoder.finish()?;
+            }
+            Version::V105 => {
+                let mut encoder = lz4_flex::frame::FrameEncoder::new(raw);
+                encoder.write_all(bytes)?;
+                raw = encoder.finish().map_err(io::Error::other)?;
+            }
+        }
+        Ok(raw)
+    }
+}
+
+/// Content type bit of `file_flags` by extension.
+fn file_flag(name: &str) -> u32 {
+    match name.rsplit_once('.').map(|(_, ext)| ext) {
+        Some("nif") => 0x1,
+        Some("dds") => 0x2,
+        Some("xml" | "swf") => 0x4,
+        Some("wav" | "xwm") => 0x8,
+        Some("fuz" | "lip") => 0x10,
+        _ => 0x100,
+    }
+}
+
+fn to_u32(value: usize) -> io::Result<u32> {
+    u32::try_from(value).map_err(|_| invalid_input("The archive exceeds 4 GiB"))
+}
+
+fn invalid_input<E>(err: E) -> io::Error
+where
+    E: Into<Box<dyn std::error::Error + Send + Sync>>,
+{
+    io::Error::new(io::ErrorKind::InvalidInput, err)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::BsaArchive;
+
+    #[test]
+    fn roundtrip_with_reader() {
+        for version in [Version::V104, Version::V105] {
+            for compress in [false, true] {
+                let mut writer = BsaWriter::new(version).compressed(compress);
+                writer.insert("meshes/animationdatasinglefile.txt", b"adsf".to_vec()).unwrap();
+                writer
+                    .insert(r"Meshes\Actors\Character\Behaviors\0_master.hkx", vec![1; 300])
+                    .unwrap();
+                writer
+                    .insert("meshes/actors/character/behaviors/mt_behavior.hkx", vec![2])
+                    .unwrap();
+                assert!(writer.insert("FNIS.esp", vec![]).is_err());
+                assert_eq!(writer.len(), 3);
+
+                let mut bytes = vec![];
+                writer.write_to(&mut bytes).unwrap();
+                let archive = BsaArchive::from_bytes(bytes).unwrap();
+
+                assert_eq!(archive.version(), version);
+                assert_eq!(
+                    archive.paths().collect::<Vec<_>>(),
+                    [
+                        "meshes/actors/character/behaviors/0_master.hkx",
+                        "meshes/actors/character/behaviors/mt_behavior.hkx",
+                        "meshes/animationdatasinglefile.txt",
+                    ]
+                );
+                assert_eq!(archive.read("meshes/animationdatasinglefile.txt").unwrap(), b"adsf");
+                assert_eq!(
+                    archive.read("meshes/actors/character/behaviors/0_master.hkx").unwrap(),
+                    vec![1; 300]
+                );
+                assert_eq!(
+                    archive.read("meshes/actors/character/behaviors/mt_behavior.hkx").unwrap(),
+                    [2]
+                );
+            }
+        }
+    }
+}
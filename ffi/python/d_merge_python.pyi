@@ -137,7 +137,21 @@ class Config:
         r"""
         If true, generates a FNIS.esp(dummy ESP) file with the correct version and author information.
         """
-    def __new__(cls, resource_dir: builtins.str, output_dir: builtins.str, output_target: OutPutTarget, hack_options: typing.Optional[HackOptions] = None, debug: typing.Optional[DebugOptions] = None, skyrim_data_dir_glob: typing.Optional[builtins.str] = None, generate_fnis_esp: typing.Optional[builtins.bool] = None) -> Config:
+    @property
+    def read_bsa(self) -> typing.Optional[builtins.bool]:
+        r"""
+        If true, also reads the patches packed in the `*.bsa` of the Skyrim data dirs.(loose files take precedence)
+        
+        Pass the same value to `load_mods_info`, so that the archived mods are listed.
+        """
+    @read_bsa.setter
+    def read_bsa(self, value: typing.Optional[builtins.bool]) -> None:
+        r"""
+        If true, also reads the patches packed in the `*.bsa` of the Skyrim data dirs.(loose files take precedence)
+        
+        Pass the same value to `load_mods_info`, so that the archived mods are listed.
+        """
+    def __new__(cls, resource_dir: builtins.str, output_dir: builtins.str, output_target: OutPutTarget, hack_options: typing.Optional[HackOptions] = None, debug: typing.Optional[DebugOptions] = None, skyrim_data_dir_glob: typing.Optional[builtins.str] = None, generate_fnis_esp: typing.Optional[builtins.bool] = None, read_bsa: typing.Optional[builtins.bool] = None) -> Config:
         r"""
         Create a new [`Config`].
         
@@ -154,6 +168,7 @@ class Config:
           `animations/<namespace>`. Required only when using FNIS.
         - `generate_fnis_esp`: If true, generates a dummy `FNIS.esp` with correct version and
           author information.
+        - `read_bsa`: If true, also reads the patches packed in the `*.bsa` of the Skyrim data dirs.
         """
 
 @typing.final
@@ -213,7 +228,21 @@ class DebugOptions:
         This is the final XML representation of the patched and merged data,
         just before conversion to the binary `.hkx` format.
         """
-    def __new__(cls, output_patch_json: builtins.bool = False, output_merged_json: builtins.bool = False, output_merged_xml: builtins.bool = False) -> DebugOptions:
+    @property
+    def output_provenance(self) -> builtins.bool:
+        r"""
+        If true, outputs `provenance.json` to the `.debug` subdirectory under `<output_dir>/.d_merge`.
+        
+        This records which mod and patch file touched each JSON path of the merged behaviors.
+        """
+    @output_provenance.setter
+    def output_provenance(self, value: builtins.bool) -> None:
+        r"""
+        If true, outputs `provenance.json` to the `.debug` subdirectory under `<output_dir>/.d_merge`.
+        
+        This records which mod and patch file touched each JSON path of the merged behaviors.
+        """
+    def __new__(cls, output_patch_json: builtins.bool = False, output_merged_json: builtins.bool = False, output_merged_xml: builtins.bool = False, output_provenance: builtins.bool = False) -> DebugOptions:
         r"""
         Create a new [`DebugOptions`].
         
@@ -221,6 +250,7 @@ class DebugOptions:
         - `output_patch_json`: If true, outputs the patch JSON file for debugging.
         - `output_merged_json`: If true, outputs the merged JSON file for debugging.
         - `output_merged_xml`: If true, outputs the merged XML file for debugging.
+        - `output_provenance`: If true, outputs which mod wrote each JSON path for debugging.
         """
 
 @typing.final
@@ -574,6 +604,13 @@ class ModType(enum.Enum):
     A patch for the exact path from meshes (note that the file extension is .bin, not .hkx)
     - e.g., `Nemesis_EngineExt/mod/aaaa/meshes/actors/troll/characters/troll.bin/#0029.txt`
     """
+    Pandora = ...
+    r"""
+    GUI developers must add the following to the paths array in `nemesis_merge::behavior_gen`.
+    - `<skyrim data dir>/Pandora_Engine/mod/aaaa`
+    
+    Pandora Behavior Engine's layout. Both the Nemesis and the NemesisExt patch paths are in the same folder.
+    """
     Fnis = ...
     r"""
     GUI developers must add the following to the paths array in `nemesis_merge::behavior_gen`.
@@ -593,6 +630,10 @@ class OutPutTarget(enum.Enum):
     r"""
     Skyrim Special Edition
     """
+    SkyrimVR = ...
+    r"""
+    Skyrim VR
+    """
 
 @typing.final
 class SerdeHkxStatus(enum.Enum):
@@ -669,7 +710,7 @@ def get_skyrim_data_dir(runtime: OutPutTarget) -> builtins.str:
     Get the skyrim data directory.
     
     # Errors
-    - When the string specified in runtime is not “SkyrimSE” or “SkyrimLE”
+    - When the string specified in runtime is not “SkyrimSE”, “SkyrimLE” or “SkyrimVR”
     - Returns an error if the Skyrim directory cannot be found from registry.
     """
 
@@ -688,10 +729,12 @@ def load_dir_node(dirs: typing.Sequence[builtins.str]) -> builtins.list[DirEntry
     Returns an error message if the directory cannot be loaded or if there are issues reading the path.
     """
 
-def load_mods_info(glob: builtins.str, is_vfs: builtins.bool) -> builtins.list[ModInfo]:
+def load_mods_info(glob: builtins.str, is_vfs: builtins.bool, read_bsa: builtins.bool = False) -> builtins.list[ModInfo]:
     r"""
     Collect both Nemesis and FNIS mods into a single vector.
     
+    If `read_bsa`, the mods packed in the `*.bsa` are also collected. (Same as `Config.read_bsa`)
+    
     # Errors
     Returns an error if glob expansion fails or files cannot be read.
     """
@@ -2,7 +2,8 @@ use std::path::PathBuf;
 
 use mod_info::{ModInfo as RustModInfo, ModType as RustModType};
 use nemesis_merge::{
-    Config as RustConfig, DebugOptions as RustDebugOptions, HackOptions as RustHackOptions,
+    CancellationToken as RustCancellationToken, Config as RustConfig,
+    DebugOptions as RustDebugOptions, HackOptions as RustHackOptions,
     OutPutTarget as RustOutPutTarget, PatchMaps as RustPatchMaps, PriorityMap,
     Status as RustStatus,
 };
@@ -81,6 +82,11 @@ pub struct DebugOptions {
     /// This is the final XML representation of the patched and merged data,
     /// just before conversion to the binary `.hkx` format.
     pub output_merged_xml: bool,
+
+    /// If true, outputs `provenance.json` to the `.debug` subdirectory under `<output_dir>/.d_merge`.
+    ///
+    /// This records which mod and patch file touched each JSON path of the merged behaviors.
+    pub output_provenance: bool,
 }
 #[pyo3_stub_gen::derive::gen_stub_pymethods]
 #[pymethods]
@@ -91,18 +97,21 @@ impl DebugOptions {
     /// - `output_patch_json`: If true, outputs the patch JSON file for debugging.
     /// - `output_merged_json`: If true, outputs the merged JSON file for debugging.
     /// - `output_merged_xml`: If true, outputs the merged XML file for debugging.
+    /// - `output_provenance`: If true, outputs which mod wrote each JSON path for debugging.
     #[new]
     #[pyo3(signature = (
         output_patch_json = false,
         output_merged_json = false,
-        output_merged_xml = false
+        output_merged_xml = false,
+        output_provenance = false
     ))]
     const fn new(
         output_patch_json: bool,
         output_merged_json: bool,
         output_merged_xml: bool,
+        output_provenance: bool,
     ) -> Self {
-        Self { output_patch_json, output_merged_json, output_merged_xml }
+        Self { output_patch_json, output_merged_json, output_merged_xml, output_provenance }
     }
 }
 
@@ -116,6 +125,8 @@ pub enum OutPutTarget {
     SkyrimSE,
     /// Skyrim Special Edition
     SkyrimLE,
+    /// Skyrim VR
+    SkyrimVR,
 }
 
 #[pyo3_stub_gen::derive::gen_stub_pyclass]
@@ -159,6 +170,11 @@ pub struct Config {
 
     /// If true, generates a FNIS.esp(dummy ESP) file with the correct version and author information.
     pub generate_fnis_esp: Option<bool>,
+
+    /// If true, also reads the patches packed in the `*.bsa` of the Skyrim data dirs.(loose files take precedence)
+    ///
+    /// Pass the same value to `load_mods_info`, so that the archived mods are listed.
+    pub read_bsa: Option<bool>,
 }
 #[pyo3_stub_gen::derive::gen_stub_pymethods]
 #[pymethods]
@@ -178,7 +194,9 @@ impl Config {
     ///   `animations/<namespace>`. Required only when using FNIS.
     /// - `generate_fnis_esp`: If true, generates a dummy `FNIS.esp` with correct version and
     ///   author information.
+    /// - `read_bsa`: If true, also reads the patches packed in the `*.bsa` of the Skyrim data dirs.
     #[new]
+    #[allow(clippy::too_many_arguments)]
     #[pyo3(signature = (
         resource_dir,
         output_dir,
@@ -187,6 +205,7 @@ impl Config {
         debug = None,
         skyrim_data_dir_glob = None,
         generate_fnis_esp = None,
+        read_bsa = None,
     ))]
     fn new(
         resource_dir: String,
@@ -196,6 +215,7 @@ impl Config {
         debug: Option<DebugOptions>,
         skyrim_data_dir_glob: Option<String>,
         generate_fnis_esp: Option<bool>,
+        read_bsa: Option<bool>,
     ) -> Self {
         Self {
             resource_dir,
@@ -205,6 +225,7 @@ impl Config {
             debug: debug.unwrap_or_default(),
             skyrim_data_dir_glob,
             generate_fnis_esp,
+            read_bsa,
         }
     }
 }
@@ -233,19 +254,26 @@ impl Config {
             output_target: match self.output_target {
                 OutPutTarget::SkyrimSE => RustOutPutTarget::SkyrimSe,
                 OutPutTarget::SkyrimLE => RustOutPutTarget::SkyrimLe,
+                OutPutTarget::SkyrimVR => RustOutPutTarget::SkyrimVr,
             },
             hack_options: self.hack_options.map(|h| RustHackOptions {
                 cast_ragdoll_event: h.cast_ragdoll_event,
                 bone_weight_outside_hkparam: h.bone_weight_outside_hkparam,
             }),
+            strict_original: false,
             debug: RustDebugOptions {
                 output_patch_json: self.debug.output_patch_json,
                 output_merged_json: self.debug.output_merged_json,
                 output_merged_xml: self.debug.output_merged_xml,
+                output_provenance: self.debug.output_provenance,
             },
             status_report,
             skyrim_data_dir_glob: self.skyrim_data_dir_glob,
+            read_bsa: self.read_bsa.unwrap_or(false),
             generate_fnis_esp: self.generate_fnis_esp.unwrap_or(false),
+            cancel_token: RustCancellationToken::new(),
+            output_sink: None,
+            patch_source: None,
         })
     }
 }
@@ -416,6 +444,12 @@ pub enum ModType {
     /// - e.g., `Nemesis_EngineExt/mod/aaaa/meshes/actors/troll/characters/troll.bin/#0029.txt`
     NemesisExt,
 
+    /// GUI developers must add the following to the paths array in `nemesis_merge::behavior_gen`.
+    /// - `<skyrim data dir>/Pandora_Engine/mod/aaaa`
+    ///
+    /// Pandora Behavior Engine's layout. Both the Nemesis and the NemesisExt patch paths are in the same folder.
+    Pandora,
+
     /// GUI developers must add the following to the paths array in `nemesis_merge::behavior_gen`.
     /// - `<skyrim data dir>/meshes/actors/character/animations/<namespace>`
     Fnis,
@@ -426,6 +460,7 @@ impl From<RustModType> for ModType {
         match value {
             RustModType::Nemesis => Self::Nemesis,
             RustModType::NemesisExt => Self::NemesisExt,
+            RustModType::Pandora => Self::Pandora,
             RustModType::Fnis => Self::Fnis,
         }
     }
@@ -546,12 +581,13 @@ pub fn is_dangerous_remove(output_dir: &str, skyrim_data_dir: &str) -> bool {
 /// Get the skyrim data directory.
 ///
 /// # Errors
-/// - When the string specified in runtime is not “SkyrimSE” or “SkyrimLE”
+/// - When the string specified in runtime is not “SkyrimSE”, “SkyrimLE” or “SkyrimVR”
 /// - Returns an error if the Skyrim directory cannot be found from registry.
 pub fn get_skyrim_data_dir(runtime: OutPutTarget) -> PyResult<String> {
     let runtime = match runtime {
         OutPutTarget::SkyrimSE => Runtime::Se,
         OutPutTarget::SkyrimLE => Runtime::Le,
+        OutPutTarget::SkyrimVR => Runtime::Vr,
     };
 
     skyrim_data_dir::get_skyrim_data_dir(runtime)
@@ -563,10 +599,13 @@ pub fn get_skyrim_data_dir(runtime: OutPutTarget) -> PyResult<String> {
 #[pyo3::pyfunction]
 /// Collect both Nemesis and FNIS mods into a single vector.
 ///
+/// If `read_bsa`, the mods packed in the `*.bsa` are also collected. (Same as `Config.read_bsa`)
+///
 /// # Errors
 /// Returns an error if glob expansion fails or files cannot be read.
-pub fn load_mods_info(glob: String, is_vfs: bool) -> pyo3::PyResult<Vec<ModInfo>> {
-    let infos = mod_info::get_all(&glob, is_vfs)
+#[pyo3(signature = (glob, is_vfs, read_bsa = false))]
+pub fn load_mods_info(glob: String, is_vfs: bool, read_bsa: bool) -> pyo3::PyResult<Vec<ModInfo>> {
+    let infos = mod_info::get_all(&glob, is_vfs, read_bsa)
         .map_err(|e| pyo3::exceptions::PyRuntimeError::new_err(e.to_string()))?;
 
     Ok(infos.into_par_iter().map(Into::into).collect())
@@ -87,7 +87,7 @@ def to_patches(mod_info: list[ModInfo]) -> PatchMaps:
         match mod.mod_type:
             case ModType.Fnis:
                 fnis_entries[mod.id] = priority
-            case ModType.Nemesis | ModType.NemesisExt:
+            case ModType.Nemesis | ModType.NemesisExt | ModType.Pandora:
                 nemesis_entries[mod.id] = priority
 
     return PatchMaps(fnis_entries=fnis_entries, nemesis_entries=nemesis_entries)
@@ -22,6 +22,7 @@ impl App {
 
         let dir = self.settings.current_skyrim_data_dir().to_owned();
         let use_vfs = self.settings.behavior.mode == DataMode::Vfs;
+        let read_bsa = self.settings.behavior.read_bsa;
         let state = Arc::clone(&self.fetch_state);
         let fetched_mod_info = Arc::clone(&self.fetched_mod_info);
 
@@ -30,7 +31,7 @@ impl App {
         std::thread::spawn(move || {
             // NOTE: If rayon saturates all CPU threads the UI freezes.
             // `mod_info::get_all` must not spawn unbounded rayon work internally.
-            let new_state = match mod_info::get_all(&dir, use_vfs) {
+            let new_state = match mod_info::get_all(&dir, use_vfs, read_bsa) {
                 Ok(mod_info) if mod_info.is_empty() => {
                     FetchState::Empty { elapsed: start_time.elapsed() }
                 }
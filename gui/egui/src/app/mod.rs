@@ -105,6 +105,13 @@ pub(crate) struct App {
 
     /// Latest status written by the `nemesis_merge` status-report callback.
     pub patch_status: Arc<PatchProgress>,
+
+    /// Cancels the current patch run. Replaced with a new token on each run.
+    pub patch_cancel_token: nemesis_merge::CancellationToken,
+
+    /// The current (or last) patch run.
+    /// The next run waits for it, so that its cancel cleanup does not remove the new outputs.
+    pub patch_task: Option<tokio::task::JoinHandle<()>>,
 }
 
 impl App {
@@ -153,6 +160,8 @@ impl App {
 
             patch_status: Arc::new(PatchProgress::default()),
             patch_start_time: None,
+            patch_cancel_token: nemesis_merge::CancellationToken::new(),
+            patch_task: None,
         }
     }
 
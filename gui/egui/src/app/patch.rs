@@ -7,7 +7,6 @@ use d_merge_gui_shared::{
     mod_item::to_patches,
     settings::{BehaviorSettings, DataMode},
 };
-use skyrim_data_dir::Runtime;
 
 use crate::app::App;
 
@@ -16,14 +15,24 @@ impl App {
     /// `nemesis_merge::behavior_gen` on the async runtime.
     ///
     /// If [`AppSettings::auto_remove_meshes`] is set, the output `meshes/`
-    /// directory is removed first (see [`App::remove_meshes_dir_all`]).
+    /// directory is removed first (see [`App::meshes_dir_to_remove`]).
+    ///
+    /// A previous run is cancelled and the new run waits for it to finish,
+    /// because the cancelled run removes the outputs written since it started.
     pub(crate) fn patch(&mut self, ctx: &egui::Context) {
+        // Stop the previous run if it is still running.
+        self.patch_cancel_token.cancel();
+        self.patch_cancel_token = nemesis_merge::CancellationToken::new();
+        let prev_task = self.patch_task.take();
+
         self.patch_start_time = Some(std::time::Instant::now());
         self.patch_status.clear();
 
-        if self.settings.behavior.auto_remove_meshes {
-            self.remove_meshes_dir_all();
-        }
+        let remove_meshes_dir = if self.settings.behavior.auto_remove_meshes {
+            self.meshes_dir_to_remove()
+        } else {
+            None
+        };
 
         let (skyrim_data_dir, is_vfs, mod_list) = match self.settings.behavior.mode {
             DataMode::Vfs => {
@@ -36,17 +45,10 @@ impl App {
         let patches = to_patches(skyrim_data_dir, is_vfs, mod_list);
 
         let BehaviorSettings {
-            target_runtime,
-            enable_debug_output,
-            generate_fnis_esp,
-            template_dir,
-            ..
+            enable_debug_output, generate_fnis_esp, read_bsa, template_dir, ..
         } = &self.settings.behavior;
         let enable_debug_output = *enable_debug_output;
-        let output_target = match target_runtime {
-            Runtime::Le => nemesis_merge::OutPutTarget::SkyrimLe,
-            Runtime::Se | Runtime::Vr => nemesis_merge::OutPutTarget::SkyrimSe,
-        };
+        let output_target = self.settings.behavior.output_target();
 
         let patch_status = Arc::clone(&self.patch_status);
 
@@ -54,6 +56,7 @@ impl App {
             output_patch_json: enable_debug_output,
             output_merged_json: enable_debug_output,
             output_merged_xml: enable_debug_output,
+            output_provenance: enable_debug_output,
         };
 
         let ctx = ctx.clone();
@@ -65,12 +68,37 @@ impl App {
                 patch_status.apply(status, &ctx);
             })),
             hack_options: Some(nemesis_merge::HackOptions::enable_all()),
+            strict_original: false,
             debug,
             skyrim_data_dir_glob: Some(skyrim_data_dir.clone()),
+            read_bsa: *read_bsa,
             generate_fnis_esp: *generate_fnis_esp,
+            cancel_token: self.patch_cancel_token.clone(),
+            output_sink: None,
+            patch_source: None,
         };
 
-        self.async_rt.spawn(nemesis_merge::behavior_gen(patches, config));
+        self.patch_task = Some(self.async_rt.spawn(async move {
+            if let Some(prev_task) = prev_task {
+                // The result was already reported by its `status_report`.
+                let _ = prev_task.await;
+            }
+            if let Some(output_dir) = remove_meshes_dir {
+                nemesis_merge::cache_remover::remove_meshes_dir_all(output_dir);
+            }
+            // Errors are reported through `status_report`.
+            let _ = nemesis_merge::behavior_gen(patches, config).await;
+        }));
+    }
+
+    /// Requests cancellation of the current patch run.
+    ///
+    /// `behavior_gen` stops at its next check point and removes the outputs written so far.
+    pub(crate) fn cancel_patch(&mut self) {
+        self.patch_cancel_token.cancel();
+        self.patch_start_time = None;
+        self.patch_status.phase.store(0, Ordering::Relaxed);
+        self.notify_info(nemesis_merge::errors::Error::Cancelled.to_string());
     }
 
     /// Polls the patch status written by the `status_report` callback and
@@ -97,11 +125,11 @@ impl App {
         }
     }
 
-    /// Removes `<output_dir>/meshes` (and the debug cache) before patching.
+    /// Returns the output directory whose `meshes` (and the debug cache) should be removed before patching.
     ///
-    /// Skipped with a warning when the output directory equals the Skyrim data
+    /// `None` with a warning when the output directory equals the Skyrim data
     /// directory, because deleting `meshes/` there would destroy installed mods.
-    fn remove_meshes_dir_all(&mut self) {
+    fn meshes_dir_to_remove(&mut self) -> Option<String> {
         let output_dir = self.settings.current_output_dir().to_owned();
         let skyrim_data_dir = self.settings.current_skyrim_data_dir();
 
@@ -110,14 +138,14 @@ impl App {
                 "0/6: `auto remove meshes` is enabled but output dir equals the Skyrim data \
                  directory — skipping to avoid destroying installed mods."
             );
-            return;
+            return None;
         }
 
         self.notify_info(format!(
             "0/6: {} `{output_dir}/meshes`",
             self.i18n.t(I18nKey::RemovingMeshesMessage)
         ));
-        nemesis_merge::cache_remover::remove_meshes_dir_all(output_dir);
+        Some(output_dir)
     }
 }
 
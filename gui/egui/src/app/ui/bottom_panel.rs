@@ -14,7 +14,8 @@ impl App {
     /// Renders the main bottom panel (log controls, patch button, help toggle).
     ///
     /// The patch button is disabled while a fetch is in progress to prevent
-    /// launching a patch against a stale mod list.
+    /// launching a patch against a stale mod list, and turns into a cancel
+    /// button while a patch is running.
     pub(crate) fn ui_bottom_panel(&mut self, ctx: &egui::Context) {
         let panel = themed_top_bottom_panel(
             egui::TopBottomPanel::bottom("bottom_panel"),
@@ -42,14 +43,21 @@ impl App {
                 });
 
                 let is_fetching = matches!(*self.fetch_state.read(), FetchState::Fetching);
+                let is_patching = self.patch_start_time.is_some();
                 ui.add_enabled_ui(!is_fetching, |ui| {
                     let label = if is_fetching {
                         self.i18n.t(I18nKey::PatchFetchingButton)
+                    } else if is_patching {
+                        self.i18n.t(I18nKey::PatchCancelButton)
                     } else {
                         self.i18n.t(I18nKey::PatchButton)
                     };
                     if ui.add_sized([120.0, 40.0], patch_button(label)).clicked() {
-                        self.patch(ui.ctx());
+                        if is_patching {
+                            self.cancel_patch();
+                        } else {
+                            self.patch(ui.ctx());
+                        }
                     }
                 });
 
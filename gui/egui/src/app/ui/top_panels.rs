@@ -102,8 +102,9 @@ impl App {
         });
     }
 
-    /// debug output, auto-remove meshes, generate FNIS ESP, auto-run,
+    /// debug output, auto-remove meshes, generate FNIS ESP, read BSA, auto-run,
     fn ui_behavior_options(&mut self, ui: &mut egui::Ui) {
+        let read_bsa = self.settings.behavior.read_bsa;
         for (value, label, hover) in [
             (
                 &mut self.settings.behavior.enable_debug_output,
@@ -120,10 +121,16 @@ impl App {
                 I18nKey::GenerateFnisEspLabel,
                 I18nKey::GenerateFnisEspHover,
             ),
+            (&mut self.settings.behavior.read_bsa, I18nKey::ReadBsaLabel, I18nKey::ReadBsaHover),
             (&mut self.settings.behavior.auto_run, I18nKey::AutoRun, I18nKey::AutoRunHover),
         ] {
             checkbox(ui, value, self.i18n.t(label)).on_hover_text(self.i18n.t(hover));
         }
+
+        // The archived mods are listed only while reading them.
+        if self.settings.behavior.read_bsa != read_bsa {
+            self.update_mod_list();
+        }
     }
 
     /// Renders the theme combo box (System / Dark / Light).
@@ -250,6 +250,7 @@ pub enum I18nKey {
     ///
     /// - Nemesis: Nemesis_Engine/mod/*/info.ini
     /// - NemesisExt: Nemesis_EngineExt/mod/*/info.ini
+    /// - Pandora: Pandora_Engine/mod/*/info.ini (or info.xml)
     /// - FNIS: meshes/**/animations/*/FNIS_*_List.txt
     ModsListTitleHover,
 
@@ -259,7 +260,7 @@ pub enum I18nKey {
     /// Sort the mod list according to the following rules
     ///
     /// 1. Group mods by type in the following order:
-    ///    Nemesis -> NemesisExt -> FNIS
+    ///    Nemesis -> NemesisExt -> Pandora -> FNIS
     /// 2. Sort mods alphabetically within each type.
     /// 3. Reassign priorities sequentially starting from `0`.
     /// 4. In manual mode, disable the check for duplicate ID rows.
@@ -287,9 +288,21 @@ pub enum I18nKey {
     /// Patch
     PatchButton,
 
+    /// Cancel
+    PatchCancelButton,
+
     /// Updating list...
     PatchFetchingButton,
 
+    /// Read patches packed in the `*.bsa` of the Skyrim data directory, as if they were loose files.
+    ///
+    /// # Notes
+    /// Loose files take precedence. The vanilla archives(`Skyrim - *.bsa`) are not read.
+    ReadBsaHover,
+
+    /// Read BSA
+    ReadBsaLabel,
+
     /// Reload
     ReloadButton,
 
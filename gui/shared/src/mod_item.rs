@@ -94,6 +94,14 @@ pub fn to_patches(skyrim_data_dir: &str, is_vfs: bool, mod_infos: &[ModItem]) ->
                     };
                     Either::Left((id, priority))
                 }
+                ModType::Pandora => {
+                    let id = if is_vfs {
+                        format!("{skyrim_data_dir}/Pandora_Engine/mod/{id}")
+                    } else {
+                        id.clone()
+                    };
+                    Either::Left((id, priority))
+                }
                 ModType::Fnis => {
                     let id = id.clone();
                     Either::Right((id, priority))
@@ -105,7 +113,7 @@ pub fn to_patches(skyrim_data_dir: &str, is_vfs: bool, mod_infos: &[ModItem]) ->
 
 /// Reorder priorities:
 /// 1. Sort mods by type:
-///    Nemesis -> NemesisExt -> FNIS
+///    Nemesis -> NemesisExt -> Pandora -> FNIS
 /// 2. Sort mods alphabetically within each type.
 /// 3. Reassign priorities sequentially starting from `0`.
 pub fn reorder_mods_priorities(mods: &mut [ModItem]) {
@@ -115,7 +123,8 @@ pub fn reorder_mods_priorities(mods: &mut [ModItem]) {
         match mod_type {
             ModType::Nemesis => 0,
             ModType::NemesisExt => 1,
-            ModType::Fnis => 2,
+            ModType::Pandora => 2,
+            ModType::Fnis => 3,
         }
     }
 
@@ -25,7 +25,8 @@ pub struct BehaviorSettings {
     ///
     /// Controls which HKX format is emitted:
     /// - [`Runtime::Le`] — 32-bit legacy format
-    /// - [`Runtime::Se`] / [`Runtime::Vr`] — 64-bit SE/AE/VR format
+    /// - [`Runtime::Se`] — 64-bit SE/AE format
+    /// - [`Runtime::Vr`] — 64-bit format, preferring the VR specific templates
     pub target_runtime: Runtime,
 
     /// Enable all mods and run the patch automatically after every mod-list
@@ -56,6 +57,13 @@ pub struct BehaviorSettings {
     /// behavior files.
     pub generate_fnis_esp: bool,
 
+    /// Also read the Nemesis/FNIS patches packed in the `*.bsa` of the Skyrim
+    /// data directory.
+    ///
+    /// Loose files take precedence, like in the game.
+    /// See [`nemesis_merge::Config::read_bsa`].
+    pub read_bsa: bool,
+
     /// Directory containing the HKX template files to patch.
     ///
     /// Typically `./assets/templates`.  The actual merge target is the
@@ -72,11 +80,23 @@ impl Default for BehaviorSettings {
             auto_remove_meshes: false,
             enable_debug_output: false,
             generate_fnis_esp: false,
+            read_bsa: false,
             template_dir: "./assets/templates".into(),
         }
     }
 }
 
+impl BehaviorSettings {
+    /// The [`nemesis_merge::OutPutTarget`] of [`Self::target_runtime`].
+    pub const fn output_target(&self) -> nemesis_merge::OutPutTarget {
+        match self.target_runtime {
+            Runtime::Le => nemesis_merge::OutPutTarget::SkyrimLe,
+            Runtime::Se => nemesis_merge::OutPutTarget::SkyrimSe,
+            Runtime::Vr => nemesis_merge::OutPutTarget::SkyrimVr,
+        }
+    }
+}
+
 /// Selects how mod directories are discovered.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
 #[serde(rename_all = "lowercase")]
@@ -94,3 +114,20 @@ pub enum DataMode {
     /// segment is used as the key to avoid collisions.
     Manual,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn runtime_maps_to_output_target() {
+        for (target_runtime, expected) in [
+            (Runtime::Le, nemesis_merge::OutPutTarget::SkyrimLe),
+            (Runtime::Se, nemesis_merge::OutPutTarget::SkyrimSe),
+            (Runtime::Vr, nemesis_merge::OutPutTarget::SkyrimVr),
+        ] {
+            let settings = BehaviorSettings { target_runtime, ..Default::default() };
+            assert_eq!(settings.output_target(), expected);
+        }
+    }
+}
@@ -95,6 +95,7 @@ impl From<OldSettings> for Settings {
                 auto_remove_meshes: old.auto_remove_meshes,
                 enable_debug_output: old.enable_debug_output,
                 generate_fnis_esp: old.generate_fnis_esp,
+                read_bsa: false,
                 template_dir: old.template_dir,
                 mode: old.mode,
             },
@@ -84,21 +84,33 @@ impl Settings {
     ///
     /// # Errors
     /// failed to read json
+    #[inline]
     pub fn load() -> Result<Self, String> {
-        let path = std::path::Path::new(SETTINGS_PATH);
+        Self::load_from(SETTINGS_PATH)
+    }
+
+    /// Loads settings from an arbitrary `path`.
+    ///
+    /// Same as [`Self::load`], but for callers (e.g. the CLI) that do not run
+    /// from the Skyrim data directory.
+    ///
+    /// # Errors
+    /// failed to read json
+    pub fn load_from(path: impl AsRef<std::path::Path>) -> Result<Self, String> {
+        let path = path.as_ref();
 
         if !path.exists() {
             return Ok(Self::default());
         }
 
         let text = std::fs::read_to_string(path)
-            .map_err(|e| format!("Failed to read {SETTINGS_PATH}: {e}"))?;
+            .map_err(|e| format!("Failed to read {}: {e}", path.display()))?;
 
         let mut settings: Self = if let Ok(settings) = sonic_rs::from_str(&text) {
             settings
         } else {
             let old: compat_old::OldSettings = sonic_rs::from_str(&text)
-                .map_err(|e| format!("Failed to parse {SETTINGS_PATH}: {e}"))?;
+                .map_err(|e| format!("Failed to parse {}: {e}", path.display()))?;
             old.into()
         };
         settings.app_version =
@@ -3,7 +3,8 @@ pub(crate) mod mod_info_loader;
 use std::path::PathBuf;
 
 use nemesis_merge::{
-    Config, DebugOptions, HackOptions, OutPutTarget, PatchMaps, Status, behavior_gen,
+    CancellationToken, Config, DebugOptions, HackOptions, OutPutTarget, PatchMaps, Status,
+    behavior_gen,
 };
 use once_cell::sync::Lazy;
 use snafu::ResultExt as _;
@@ -15,7 +16,8 @@ use crate::{
     error::NotFoundResourceDirSnafu,
 };
 
-static PATCH_TASK: Lazy<Mutex<Option<JoinHandle<()>>>> = Lazy::new(|| Mutex::new(None));
+static PATCH_TASK: Lazy<Mutex<Option<(JoinHandle<()>, CancellationToken)>>> =
+    Lazy::new(|| Mutex::new(None));
 
 #[derive(Debug, Default, serde::Serialize, serde::Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -33,6 +35,9 @@ pub(crate) struct GuiPatchOptions {
     /// entries will not be detected and the process will fail.
     skyrim_data_dir_glob: Option<String>,
 
+    /// If true, also reads the patches packed in the `*.bsa` of the Skyrim data dirs.(loose files take precedence)
+    read_bsa: Option<bool>,
+
     /// If true, generates a FNIS.esp(dummy ESP) file with the correct version and author information.
     pub generate_fnis_esp: Option<bool>,
 }
@@ -56,6 +61,8 @@ pub(crate) async fn patch(
         .context(NotFoundResourceDirSnafu)
         .or_else(|err| bail!(err))?;
 
+    let cancel_token = CancellationToken::new();
+    let task_cancel_token = cancel_token.clone();
     let handle = tauri::async_runtime::spawn(async move {
         let status_report = options.use_progress_reporter.then(|| {
             // NOTE: This is necessary because the coercion does not happen automatically through `Option` returned by `then()`.
@@ -70,16 +77,21 @@ pub(crate) async fn patch(
             resource_dir,
             status_report,
             hack_options: options.hack_options,
+            strict_original: false,
             debug: options.debug,
             output_target: options.output_target,
             skyrim_data_dir_glob: options.skyrim_data_dir_glob,
+            read_bsa: options.read_bsa.unwrap_or(false),
             generate_fnis_esp: options.generate_fnis_esp.unwrap_or(false),
+            cancel_token: task_cancel_token,
+            output_sink: None,
+            patch_source: None,
         };
 
         let _ = time!("[patch]", behavior_gen(patches, config).await);
     });
 
-    PATCH_TASK.lock().await.replace(handle);
+    PATCH_TASK.lock().await.replace((handle, cancel_token));
     Ok(())
 }
 
@@ -107,8 +119,10 @@ async fn cancel_patch_inner() -> Result<(), String> {
         guard.take()
     };
 
-    if let Some(handle) = handle {
-        handle.abort();
+    if let Some((handle, cancel_token)) = handle {
+        // NOTE: Do not `abort`: dropping the future skips the removal of the outputs written so far.
+        // `behavior_gen` stops at its next check point and cleans up before returning.
+        cancel_token.cancel();
         if let Err(err) = handle.await {
             tracing::error!("patch task panicked: {err}");
         }
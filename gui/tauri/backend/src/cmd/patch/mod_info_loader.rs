@@ -8,12 +8,19 @@ use crate::{cmd::bail, error::NotFoundSkyrimDataDirSnafu};
 
 /// Mod info readers
 ///
+/// If `read_bsa`, the mods packed in the `*.bsa` are also listed. (Same as the `readBsa` patch option)
+///
 /// # glob samples
 /// - Steam VFS: `D:/Steam/steamapps/common/Skyrim Special Edition/Data`
 /// - MO2: `D:/GAME/ModOrganizer Skyrim SE/mods/*`
 #[tauri::command]
-pub(crate) fn load_mods_info(glob: &str, is_vfs_mode: bool) -> Result<Vec<ModInfo>, String> {
-    let info = mod_info::get_all(glob, is_vfs_mode).or_else(|err| bail!(err))?;
+pub(crate) fn load_mods_info(
+    glob: &str,
+    is_vfs_mode: bool,
+    read_bsa: Option<bool>,
+) -> Result<Vec<ModInfo>, String> {
+    let info = mod_info::get_all(glob, is_vfs_mode, read_bsa.unwrap_or(false))
+        .or_else(|err| bail!(err))?;
     Ok(info)
 }
 
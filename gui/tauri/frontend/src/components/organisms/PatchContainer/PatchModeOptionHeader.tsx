@@ -20,6 +20,13 @@ export const PatchModeOptionHeader = () => {
     [setPatchOptions],
   );
 
+  const handleReadBsa = useCallback(
+    (_: React.ChangeEvent<HTMLInputElement>, checked: boolean) => {
+      setPatchOptions((prev) => ({ ...prev, readBsa: checked }));
+    },
+    [setPatchOptions],
+  );
+
   return (
     <FormControl>
       <FormGroup row>
@@ -32,6 +39,11 @@ export const PatchModeOptionHeader = () => {
           label='FNIS.esp'
           control={<Checkbox checked={patchOptions.generateFnisEsp} onChange={handleGenerateFnisEsp} />}
         />
+
+        <FormControlLabel
+          label='BSA'
+          control={<Checkbox checked={patchOptions.readBsa ?? false} onChange={handleReadBsa} />}
+        />
       </FormGroup>
     </FormControl>
   );
@@ -64,6 +64,7 @@ export const PatchOptionsDialog = () => {
               >
                 <MenuItem value='SkyrimSE'>{t('patch.output_targets.skyrim_se')}</MenuItem>
                 <MenuItem value='SkyrimLE'>{t('patch.output_targets.skyrim_le')}</MenuItem>
+                <MenuItem value='SkyrimVR'>{t('patch.output_targets.skyrim_vr')}</MenuItem>
               </Select>
               <FormHelperText sx={{ mt: 1 }}>{t('patch.output_target_help')}</FormHelperText>
             </FormGroup>
@@ -5,17 +5,19 @@ import { loadModsInfo } from '@/services/api/patch';
 import { mergeModInfoList } from '@/services/api/patch/mod_item';
 
 export const useFetchModInfo = () => {
-  const { isVfsMode, vfsSkyrimDataDir, skyrimDataDir, setModList, setVfsModList, setFetchIsEmpty } = usePatchContext();
+  const { isVfsMode, vfsSkyrimDataDir, skyrimDataDir, patchOptions, setModList, setVfsModList, setFetchIsEmpty } =
+    usePatchContext();
+  const readBsa = patchOptions.readBsa ?? false;
 
   const [loading, setLoading] = useState(false);
 
-  const fetchMods = useDebouncedCallback(async (dir: string, mode: boolean) => {
+  const fetchMods = useDebouncedCallback(async (dir: string, mode: boolean, readBsa: boolean) => {
     if (!dir.trim()) return;
 
     setLoading(true);
 
     try {
-      const fetched = await loadModsInfo(dir.trim(), mode);
+      const fetched = await loadModsInfo(dir.trim(), mode, readBsa);
 
       if (fetched.length > 0) {
         setFetchIsEmpty(false);
@@ -36,8 +38,8 @@ export const useFetchModInfo = () => {
   useEffect(() => {
     const dir = isVfsMode ? vfsSkyrimDataDir : skyrimDataDir;
 
-    fetchMods(dir, isVfsMode);
-  }, [isVfsMode, vfsSkyrimDataDir, skyrimDataDir]);
+    fetchMods(dir, isVfsMode, readBsa);
+  }, [isVfsMode, vfsSkyrimDataDir, skyrimDataDir, readBsa]);
 
   return { loading };
 };
@@ -19,7 +19,7 @@ const EguiModItemSchema = z.object({
   name: z.string(),
   site: z.string(),
   priority: z.number(),
-  mod_type: z.enum(['nemesis', 'fnis', 'nemesis_ext']),
+  mod_type: z.enum(['nemesis', 'fnis', 'nemesis_ext', 'pandora']),
 });
 const EguiModListSchema = z.array(EguiModItemSchema);
 
@@ -78,7 +78,7 @@ export function convertEguiSettings(settings: EguiSettings): Cache {
         outputMergedJson: settings.enable_debug_output ?? false,
         outputMergedXml: settings.enable_debug_output ?? false,
       },
-      outputTarget: settings.target_runtime === 'SE' ? 'SkyrimSE' : 'SkyrimLE',
+      outputTarget: convertRuntimeToOutputTarget(settings.target_runtime ?? 'SE'),
       autoRemoveMeshes: settings.auto_remove_meshes ?? false,
       generateFnisEsp: settings.generate_fnis_esp ?? false,
       useProgressReporter: true,
@@ -186,15 +186,18 @@ export const convertOutputTargetToRuntime = (runtime: PatchOptions['outputTarget
       return 'SE';
     case 'SkyrimLE':
       return 'LE';
+    case 'SkyrimVR':
+      return 'VR';
   }
 };
 
 export const convertRuntimeToOutputTarget = (runtime: 'SE' | 'LE' | 'VR'): PatchOptions['outputTarget'] => {
   switch (runtime) {
     case 'SE':
-    case 'VR':
       return 'SkyrimSE';
     case 'LE':
       return 'SkyrimLE';
+    case 'VR':
+      return 'SkyrimVR';
   }
 };
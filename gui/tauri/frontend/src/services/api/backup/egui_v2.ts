@@ -15,6 +15,7 @@ export const V2ConfigSchema = z.object({
     auto_remove_meshes: z.boolean(),
     enable_debug_output: z.boolean(),
     generate_fnis_esp: z.boolean(),
+    read_bsa: z.boolean().optional(),
     template_dir: z.string(),
   }),
 
@@ -66,6 +67,7 @@ export function v2ToCache(v2: V2Config): Cache {
       outputTarget: convertRuntimeToOutputTarget(v2.behavior.target_runtime),
       autoRemoveMeshes: v2.behavior.auto_remove_meshes,
       generateFnisEsp: v2.behavior.generate_fnis_esp,
+      readBsa: v2.behavior.read_bsa ?? false,
       debug: {
         outputPatchJson: v2.behavior.enable_debug_output,
         outputMergedJson: v2.behavior.enable_debug_output,
@@ -126,6 +128,7 @@ export function cacheToV2(cache: Cache): V2Config {
         false,
 
       generate_fnis_esp: patchOptions?.generateFnisEsp ?? false,
+      read_bsa: patchOptions?.readBsa ?? false,
 
       template_dir: './assets/templates',
     },
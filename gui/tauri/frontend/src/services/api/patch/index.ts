@@ -10,6 +10,8 @@ export async function getSkyrimDir(runtime: PatchOptions['outputTarget']) {
     switch (runtime) {
       case 'SkyrimLE':
         return await invoke<string>('get_skyrim_data_dir', { runtime: 'LE' });
+      case 'SkyrimVR':
+        return await invoke<string>('get_skyrim_data_dir', { runtime: 'VR' });
       default:
         return await invoke<string>('get_skyrim_data_dir', { runtime: 'SE' });
     }
@@ -30,7 +32,7 @@ export const ModItemSchema = z.object({
   /** NOTE: egui doesn't have this field, so it may be empty string. */
   author: z.string().optional().catch(''),
   site: z.string(),
-  mod_type: z.enum(['nemesis', 'nemesis_ext', 'fnis']),
+  mod_type: z.enum(['nemesis', 'nemesis_ext', 'pandora', 'fnis']),
   /** NOTE: egui doesn't have this field, so it may be empty string. */
   auto: z.string().optional().catch(''),
 
@@ -59,13 +61,15 @@ export type PatchMaps = {
 
 /**
  * Load mods `info.ini`
+ * @param readBsa - Also list the mods packed in `*.bsa`. Must be the same as `PatchOptions.readBsa`.
  * @throws Error
  */
-export async function loadModsInfo(skyrimDataDir: string, isVfsMode: boolean) {
+export async function loadModsInfo(skyrimDataDir: string, isVfsMode: boolean, readBsa = false) {
   if (isTauri()) {
     return await invoke<FetchedModInfo[]>('load_mods_info', {
       glob: skyrimDataDir,
       isVfsMode,
+      readBsa,
     });
   }
 
@@ -83,7 +87,7 @@ export type PatchOptions = {
     outputMergedJson: boolean;
     outputMergedXml: boolean;
   };
-  outputTarget: 'SkyrimSE' | 'SkyrimLE';
+  outputTarget: 'SkyrimSE' | 'SkyrimLE' | 'SkyrimVR';
   /** Delete the meshes in the output destination each time the patch is run. */
   autoRemoveMeshes: boolean;
   /** Report progress status +2s */
@@ -94,6 +98,8 @@ export type PatchOptions = {
    * entries will not be detected and the process will fail.
    **/
   skyrimDataDirGlob?: string;
+  /** If true, also reads the patches packed in the `*.bsa` of the Skyrim data dirs.(loose files take precedence) */
+  readBsa?: boolean;
   /**
    * If true, generates a FNIS.esp(dummy ESP) file with the correct version and author information.
    */
@@ -111,10 +117,11 @@ export const patchOptionsSchema = z
       outputMergedJson: z.boolean(),
       outputMergedXml: z.boolean(),
     }),
-    outputTarget: z.union([z.literal('SkyrimSE'), z.literal('SkyrimLE')]),
+    outputTarget: z.union([z.literal('SkyrimSE'), z.literal('SkyrimLE'), z.literal('SkyrimVR')]),
     autoRemoveMeshes: z.boolean(),
     useProgressReporter: z.boolean(),
     skyrimDataDirGlob: z.optional(z.string()),
+    readBsa: z.optional(z.boolean()),
     generateFnisEsp: z.optional(z.boolean()),
   })
   .catch({
@@ -130,6 +137,7 @@ export const patchOptionsSchema = z
     outputTarget: 'SkyrimSE',
     autoRemoveMeshes: true,
     useProgressReporter: true,
+    readBsa: false,
     generateFnisEsp: false,
   } as const satisfies PatchOptions);
 
@@ -69,6 +69,11 @@ export const toPatches = (vfsSkyrimDataDir: string, isVfsMode: boolean, modInfos
         nemesisEntries[path] = mod.priority;
         break;
       }
+      case 'pandora': {
+        const path = isVfsMode ? `${vfsSkyrimDataDir}/Pandora_Engine/mod/${mod.id}` : mod.id;
+        nemesisEntries[path] = mod.priority;
+        break;
+      }
       case 'fnis': {
         // Note that duplicates may cause malfunctions due to FNIS specifications.
         const path = mod.id;
@@ -0,0 +1,108 @@
+//! Completion of the field names and the enum values.
+use lsp_types::{CompletionItem, CompletionItemKind};
+use nemesis_xml::{
+    hack::HackRules,
+    lint::{Scope, scopes_at},
+    patch::class_table::find_class_info,
+};
+
+use crate::{document::start_tag_at, enums::enum_values};
+
+/// Get the completions at the byte offset.
+///
+/// - `<hkparam name="|">`: The fields of the class.
+/// - `<hkparam name="startStateMode">|</hkparam>`: The enum values of the field.
+pub(crate) fn completions(src: &str, offset: usize, rules: &HackRules) -> Vec<CompletionItem> {
+    match start_tag_at(src, offset) {
+        Some((tag, tag_start)) if is_in_name_attr(&tag[..offset - tag_start]) => {
+            field_names(src, tag_start, rules)
+        }
+        Some(_) => Vec::new(),
+        None => enum_values_at(src, offset, rules),
+    }
+}
+
+/// Is the end of the tag in the value of `name`? e.g. `<hkparam name="sta`
+fn is_in_name_attr(tag: &str) -> bool {
+    tag.strip_prefix("<hkparam")
+        .and_then(|attrs| attrs.rsplit_once("name=\""))
+        .is_some_and(|(_, value)| !value.contains('"'))
+}
+
+fn field_names(src: &str, tag_start: usize, rules: &HackRules) -> Vec<CompletionItem> {
+    let Some(Scope::Object { class_name, .. }) = scopes_at(src, tag_start, rules).pop() else {
+        return Vec::new();
+    };
+    let Some(field_info) = find_class_info(class_name) else {
+        return Vec::new();
+    };
+
+    field_info
+        .entries()
+        .map(|(&name, &field_type)| CompletionItem {
+            label: name.to_string(),
+            kind: Some(CompletionItemKind::FIELD),
+            detail: Some(field_type.to_string()),
+            ..Default::default()
+        })
+        .collect()
+}
+
+fn enum_values_at(src: &str, offset: usize, rules: &HackRules) -> Vec<CompletionItem> {
+    let scopes = scopes_at(src, offset, rules);
+    let [
+        ..,
+        Scope::Object { class_name, .. },
+        Scope::Field { name, field_type: Some("String"), .. },
+    ] = scopes.as_slice()
+    else {
+        return Vec::new();
+    };
+    let Some(values) = enum_values(class_name, name) else {
+        return Vec::new();
+    };
+
+    values
+        .iter()
+        .map(|value| CompletionItem {
+            label: (*value).to_string(),
+            kind: Some(CompletionItemKind::ENUM_MEMBER),
+            detail: Some(format!("{class_name}.{name}")),
+            ..Default::default()
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn complete_fields_and_enum_values() {
+        let src = r##"<hkobject name="#0106" class="hkbStateMachine" signature="0x816c1dcb">
+	<hkparam name="startStateMode"></hkparam>
+	<hkparam name="
+</hkobject>"##;
+        let labels = |offset: usize| -> Vec<_> {
+            completions(src, offset, &HackRules::default())
+                .into_iter()
+                .map(|item| item.label)
+                .collect()
+        };
+
+        let values = labels(src.find("></hkparam>").unwrap() + 1);
+        assert_eq!(
+            values,
+            [
+                "START_STATE_MODE_DEFAULT",
+                "START_STATE_MODE_SYNC",
+                "START_STATE_MODE_RANDOM",
+                "START_STATE_MODE_CHOOSER",
+            ]
+        );
+
+        let fields = labels(src.rfind("name=\"").unwrap() + 6);
+        assert!(fields.iter().any(|field| field == "startStateMode"), "{fields:?}");
+        assert!(labels(src.find("class=").unwrap()).is_empty());
+    }
+}
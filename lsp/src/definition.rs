@@ -0,0 +1,25 @@
+//! Go to the definitions of the objects.
+use std::path::Path;
+
+use lsp_types::{Location, Range, Url};
+use nemesis_xml::lint::object_definitions;
+
+use crate::document::{Documents, to_position, word_at};
+
+/// Get the file that defines the object at the byte offset. e.g. `#0107`, `#slide$0`
+///
+/// The objects are looked up in the patch files of the same directory(template) as `path`.
+pub(crate) fn definition(
+    documents: &Documents,
+    path: &Path,
+    src: &str,
+    offset: usize,
+) -> Option<Location> {
+    let (word, _) = word_at(src, offset).filter(|(word, _)| word.starts_with('#'))?;
+
+    documents.siblings(path).into_iter().find_map(|(path, text)| {
+        let (_, span) = object_definitions(&text).into_iter().find(|(name, _)| *name == word)?;
+        let range = Range::new(to_position(&text, span.start), to_position(&text, span.end));
+        Some(Location::new(Url::from_file_path(&path).ok()?, range))
+    })
+}
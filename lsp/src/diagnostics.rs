@@ -0,0 +1,57 @@
+//! Diagnostics by the lints of `nemesis_xml`.
+use std::{collections::HashMap, path::Path};
+
+use lsp_types::{Diagnostic, DiagnosticSeverity, PublishDiagnosticsParams, Range, Url};
+use nemesis_xml::{
+    hack::HackRules,
+    lint::{Severity, lint_patches},
+};
+
+use crate::document::{Documents, to_position};
+
+/// Lint the patch files in the same directory as `path`.
+///
+/// # Returns
+/// The diagnostics of each file. A file without findings has empty ones to clear the old.
+pub(crate) fn lint_dir(
+    documents: &Documents,
+    path: &Path,
+    rules: &HackRules,
+) -> Vec<PublishDiagnosticsParams> {
+    let files = documents.siblings(path);
+    let mut diagnostics: HashMap<&Path, Vec<Diagnostic>> =
+        files.iter().map(|(path, _)| (path.as_path(), Vec::new())).collect();
+
+    let sources: HashMap<_, _> = files.iter().map(|(path, src)| (path.as_path(), src)).collect();
+    for diagnostic in
+        lint_patches(files.iter().map(|(path, src)| (path.as_path(), src.as_str())), rules)
+    {
+        let Some((path, src)) = sources.get_key_value(diagnostic.path.as_path()) else {
+            continue;
+        };
+        let range = Range::new(
+            to_position(src, diagnostic.span.start),
+            to_position(src, diagnostic.span.end),
+        );
+        let severity = match diagnostic.severity {
+            Severity::Error => DiagnosticSeverity::ERROR,
+            Severity::Warning => DiagnosticSeverity::WARNING,
+        };
+
+        diagnostics.entry(*path).or_default().push(Diagnostic {
+            range,
+            severity: Some(severity),
+            source: Some("d_merge".to_string()),
+            message: diagnostic.message,
+            ..Default::default()
+        });
+    }
+
+    diagnostics
+        .into_iter()
+        .filter_map(|(path, diagnostics)| {
+            let uri = Url::from_file_path(path).ok()?;
+            Some(PublishDiagnosticsParams::new(uri, diagnostics, None))
+        })
+        .collect()
+}
@@ -0,0 +1,150 @@
+//! Texts of the patch files and the conversion of the positions.
+use std::{
+    collections::HashMap,
+    fs,
+    ops::Range,
+    path::{Path, PathBuf},
+};
+
+use lsp_types::Position;
+
+/// Texts of the files opened in the editor. The others are read from the disk.
+#[derive(Debug, Default)]
+pub(crate) struct Documents {
+    open: HashMap<PathBuf, String>,
+}
+
+impl Documents {
+    pub(crate) fn open(&mut self, path: PathBuf, text: String) {
+        self.open.insert(path, text);
+    }
+
+    pub(crate) fn close(&mut self, path: &Path) {
+        self.open.remove(path);
+    }
+
+    pub(crate) fn get(&self, path: &Path) -> Option<&str> {
+        self.open.get(path).map(String::as_str)
+    }
+
+    /// Get the patch files(`#*.txt`) in the same directory as `path`, which Nemesis treats as one template.
+    ///
+    /// The unsaved texts in the editor take precedence over the disk.
+    pub(crate) fn siblings(&self, path: &Path) -> Vec<(PathBuf, String)> {
+        let Some(dir) = path.parent() else {
+            return Vec::new();
+        };
+
+        let mut paths: Vec<_> = fs::read_dir(dir)
+            .into_iter()
+            .flatten()
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .chain(self.open.keys().filter(|open| open.parent() == Some(dir)).cloned())
+            .filter(|path| is_patch_file(path))
+            .collect();
+        paths.sort();
+        paths.dedup();
+
+        paths
+            .into_iter()
+            .filter_map(|path| {
+                let text = match self.get(&path) {
+                    Some(text) => text.to_string(),
+                    None => fs::read_to_string(&path).ok()?,
+                };
+                Some((path, text))
+            })
+            .collect()
+    }
+}
+
+/// Is this a Nemesis patch file? e.g. `#0106.txt`, `#slide$0.txt`
+pub(crate) fn is_patch_file(path: &Path) -> bool {
+    path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("txt"))
+        && path.file_name().is_some_and(|name| name.to_string_lossy().starts_with('#'))
+}
+
+/// Byte offset -> LSP position(0 based line, UTF-16 column)
+pub(crate) fn to_position(src: &str, offset: usize) -> Position {
+    let before = &src[..offset.min(src.len())];
+    let line = before.bytes().filter(|&byte| byte == b'\n').count();
+    let line_start = before.rfind('\n').map_or(0, |index| index + 1);
+    let character = before[line_start..].encode_utf16().count();
+    Position::new(to_u32(line), to_u32(character))
+}
+
+/// LSP position(0 based line, UTF-16 column) -> Byte offset
+///
+/// Out of range positions are clamped to the end of the line or the text.
+pub(crate) fn to_offset(src: &str, position: Position) -> usize {
+    let line_start: usize =
+        src.split_inclusive('\n').take(position.line as usize).map(str::len).sum();
+
+    let mut utf16 = 0;
+    for (index, c) in src[line_start..].char_indices() {
+        if utf16 >= position.character as usize || c == '\n' {
+            return line_start + index;
+        }
+        utf16 += c.len_utf16();
+    }
+    src.len()
+}
+
+/// Get the word at the offset. e.g. `#slide$0`, `hkbStateMachine`, `startStateMode`
+pub(crate) fn word_at(src: &str, offset: usize) -> Option<(&str, Range<usize>)> {
+    const fn is_word(c: char) -> bool {
+        c.is_ascii_alphanumeric() || matches!(c, '#' | '$' | '_')
+    }
+
+    let offset = offset.min(src.len());
+    let start = src[..offset]
+        .char_indices()
+        .rev()
+        .find(|&(_, c)| !is_word(c))
+        .map_or(0, |(index, c)| index + c.len_utf8());
+    let end = src[offset..].find(|c: char| !is_word(c)).map_or(src.len(), |index| offset + index);
+    (start < end).then(|| (&src[start..end], start..end))
+}
+
+/// Get the start tag that contains the offset. e.g. (`<hkparam name="mode"`, start of it)
+///
+/// The tag may not be closed by `>` yet while typing.
+pub(crate) fn start_tag_at(src: &str, offset: usize) -> Option<(&str, usize)> {
+    let offset = offset.min(src.len());
+    let start = src[..offset].rfind('<')?;
+    let end = src[offset..].find(['<', '>']).map_or(src.len(), |index| offset + index);
+    let tag = &src[start..end];
+    (!tag[..offset - start].contains('>') && !tag.starts_with("</") && !tag.starts_with("<!"))
+        .then_some((tag, start))
+}
+
+fn to_u32(n: usize) -> u32 {
+    u32::try_from(n).unwrap_or(u32::MAX)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn convert_positions() {
+        let src = "<hkparam>\n\t\u{3042}#0052\r\n</hkparam>";
+        let offset = src.find('#').unwrap();
+
+        let position = to_position(src, offset);
+        assert_eq!(position, Position::new(1, 2));
+        assert_eq!(to_offset(src, position), offset);
+
+        assert_eq!(to_offset(src, Position::new(1, 100)), src.find('\r').unwrap() + 1);
+        assert_eq!(to_offset(src, Position::new(9, 0)), src.len());
+        assert_eq!(word_at(src, offset + 2), Some(("#0052", offset..offset + 5)));
+    }
+
+    #[test]
+    fn find_start_tag() {
+        let src = r#"<hkobject><hkparam name="sta</hkobject>"#;
+        let offset = src.find("sta").unwrap();
+        assert_eq!(start_tag_at(src, offset), Some((r#"<hkparam name="sta"#, 10)));
+        assert_eq!(start_tag_at(src, src.len()), None);
+    }
+}
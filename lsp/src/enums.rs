@@ -0,0 +1,92 @@
+//! Enum values of the `String` fields.
+//!
+//! The class table only knows that these fields are `String`, so the variants are listed here.
+
+const CLONE_STATE: &[&str] =
+    &["CLONE_STATE_DEFAULT", "CLONE_STATE_TEMPLATE", "CLONE_STATE_CLONE", "CLONE_STATE_SHARABLE"];
+
+const BLEND_CURVE: &[&str] = &[
+    "BLEND_CURVE_SMOOTH",
+    "BLEND_CURVE_LINEAR",
+    "BLEND_CURVE_LINEAR_TO_SMOOTH",
+    "BLEND_CURVE_SMOOTH_TO_LINEAR",
+];
+
+const TRANSITION_EFFECT_SELF_TRANSITION_MODE: &[&str] = &[
+    "SELF_TRANSITION_MODE_CONTINUE_IF_CYCLIC_BLEND_IF_ACYCLIC",
+    "SELF_TRANSITION_MODE_CONTINUE",
+    "SELF_TRANSITION_MODE_RESET",
+    "SELF_TRANSITION_MODE_BLEND",
+];
+
+const TRANSITION_EFFECT_EVENT_MODE: &[&str] = &[
+    "EVENT_MODE_DEFAULT",
+    "EVENT_MODE_PROCESS_ALL",
+    "EVENT_MODE_IGNORE_FROM_GENERATOR",
+    "EVENT_MODE_IGNORE_TO_GENERATOR",
+];
+
+/// Get the enum values of the field, if it is an enum.
+pub(crate) fn enum_values(class_name: &str, field_name: &str) -> Option<&'static [&'static str]> {
+    Some(match (class_name, field_name) {
+        (_, "cloneState") => CLONE_STATE,
+        ("hkbClipGenerator", "mode") => &[
+            "MODE_SINGLE_PLAY",
+            "MODE_LOOPING",
+            "MODE_USER_CONTROLLED",
+            "MODE_PING_PONG",
+            "MODE_COUNT",
+        ],
+        ("hkbStateMachine", "startStateMode") => &[
+            "START_STATE_MODE_DEFAULT",
+            "START_STATE_MODE_SYNC",
+            "START_STATE_MODE_RANDOM",
+            "START_STATE_MODE_CHOOSER",
+        ],
+        ("hkbStateMachine", "selfTransitionMode") => &[
+            "SELF_TRANSITION_MODE_NO_TRANSITION",
+            "SELF_TRANSITION_MODE_TRANSITION_TO_START_STATE",
+            "SELF_TRANSITION_MODE_FORCE_TRANSITION_TO_START_STATE",
+        ],
+        ("hkbBlendingTransitionEffect" | "hkbGeneratorTransitionEffect", "selfTransitionMode") => {
+            TRANSITION_EFFECT_SELF_TRANSITION_MODE
+        }
+        (
+            "hkbBlendingTransitionEffect" | "hkbGeneratorTransitionEffect",
+            "eventMode" | "defaultEventMode",
+        ) => TRANSITION_EFFECT_EVENT_MODE,
+        ("hkbBlendingTransitionEffect", "endMode") => &[
+            "END_MODE_NONE",
+            "END_MODE_TRANSITION_UNTIL_END_OF_FROM_GENERATOR",
+            "END_MODE_CAP_DURATION_AT_END_OF_FROM_GENERATOR",
+        ],
+        ("hkbBlendingTransitionEffect", "blendCurve")
+        | ("BSCyclicBlendTransitionGenerator", "eBlendCurve") => BLEND_CURVE,
+        ("hkbVariableInfo", "type") => &[
+            "VARIABLE_TYPE_INVALID",
+            "VARIABLE_TYPE_BOOL",
+            "VARIABLE_TYPE_INT8",
+            "VARIABLE_TYPE_INT16",
+            "VARIABLE_TYPE_INT32",
+            "VARIABLE_TYPE_REAL",
+            "VARIABLE_TYPE_POINTER",
+            "VARIABLE_TYPE_VECTOR3",
+            "VARIABLE_TYPE_VECTOR4",
+            "VARIABLE_TYPE_QUATERNION",
+        ],
+        ("hkbVariableBindingSetBinding", "bindingType") => {
+            &["BINDING_TYPE_VARIABLE", "BINDING_TYPE_CHARACTER_PROPERTY"]
+        }
+        ("hkbRoleAttribute", "role") => &[
+            "ROLE_DEFAULT",
+            "ROLE_FILE_NAME",
+            "ROLE_BONE_INDEX",
+            "ROLE_BONE_INDEX_MAP",
+            "ROLE_EVENT_ID",
+            "ROLE_VARIABLE_INDEX",
+            "ROLE_ATTRIBUTE_INDEX",
+            "ROLE_TIME",
+        ],
+        _ => return None,
+    })
+}
@@ -0,0 +1,24 @@
+//! errors of `This crate`
+
+/// Language server Error
+#[derive(Debug, snafu::Snafu)]
+#[snafu(visibility(pub(crate)))]
+pub(crate) enum Error {
+    /// The client broke the protocol(e.g. `initialize` was not the first request)
+    #[snafu(display("LSP protocol error: {source}"))]
+    Protocol { source: lsp_server::ProtocolError },
+
+    /// Failed to (de)serialize the message params
+    #[snafu(display("Failed to (de)serialize the message of `{method}`: {source}"))]
+    Json { source: serde_json::Error, method: String },
+
+    /// The client closed the connection before `exit`.
+    #[snafu(display("The connection to the client is closed."))]
+    Disconnected,
+
+    /// Failed to join the stdio threads
+    #[snafu(display("Failed to join the stdio threads: {source}"))]
+    IoThreads { source: std::io::Error },
+}
+
+pub(crate) type Result<T, E = Error> = core::result::Result<T, E>;
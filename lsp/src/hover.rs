@@ -0,0 +1,87 @@
+//! Hover docs of the classes and the fields.
+use std::fmt::Write as _;
+
+use lsp_types::{Hover, HoverContents, MarkupContent, MarkupKind, Range};
+use nemesis_xml::{
+    hack::HackRules,
+    lint::{Scope, scopes_at},
+    patch::class_table::{FieldInfo, find_class_info, find_json_parser_by},
+};
+
+use crate::{
+    document::{start_tag_at, to_position, word_at},
+    enums::enum_values,
+};
+
+/// Get the hover doc at the byte offset.
+///
+/// - `<hkobject class="|">`: The fields of the class and their types.
+/// - `<hkparam name="|">`: The type of the field(and the enum values if any).
+pub(crate) fn hover(src: &str, offset: usize, rules: &HackRules) -> Option<Hover> {
+    let (word, range) = word_at(src, offset)?;
+    let (tag, tag_start) = start_tag_at(src, offset)?;
+    let attr = &src[tag_start..range.start];
+
+    let markdown = if tag.starts_with("<hkparam") && attr.ends_with("name=\"") {
+        let Some(Scope::Object { class_name, .. }) = scopes_at(src, tag_start, rules).pop() else {
+            return None;
+        };
+        let field_type = find_json_parser_by(word, find_class_info(class_name)?)?;
+        field_doc(class_name, word, field_type)
+    } else if tag.starts_with("<hkobject") && attr.ends_with("class=\"") {
+        class_doc(word, find_class_info(word)?)
+    } else {
+        return None;
+    };
+
+    Some(Hover {
+        contents: HoverContents::Markup(MarkupContent {
+            kind: MarkupKind::Markdown,
+            value: markdown,
+        }),
+        range: Some(Range::new(to_position(src, range.start), to_position(src, range.end))),
+    })
+}
+
+fn field_doc(class_name: &str, field_name: &str, field_type: &str) -> String {
+    let mut doc = format!("`{class_name}.{field_name}`: `{field_type}`");
+    if let Some(values) = enum_values(class_name, field_name) {
+        doc.push_str("\n\nValues:");
+        for value in values {
+            let _ = write!(doc, "\n- `{value}`");
+        }
+    }
+    doc
+}
+
+fn class_doc(class_name: &str, field_info: &FieldInfo) -> String {
+    let mut doc = format!("`{class_name}`\n\n| field | type |\n| --- | --- |");
+    for (name, field_type) in field_info.entries() {
+        let _ = write!(doc, "\n| `{name}` | `{field_type}` |");
+    }
+    doc
+}
+
+#[cfg(test)]
+mod tests {
+    use lsp_types::Position;
+
+    use super::*;
+
+    #[test]
+    fn hover_field_type() {
+        let src = r##"<hkobject name="#0106" class="hkbStateMachine" signature="0x816c1dcb">
+	<hkparam name="startStateMode">START_STATE_MODE_DEFAULT</hkparam>
+</hkobject>"##;
+
+        let hover =
+            hover(src, src.find("startStateMode").unwrap() + 1, &HackRules::default()).unwrap();
+        let HoverContents::Markup(MarkupContent { value, .. }) = hover.contents else {
+            panic!("Expected markdown");
+        };
+        assert!(value.starts_with("`hkbStateMachine.startStateMode`: `String`"), "{value}");
+        assert_eq!(hover.range, Some(Range::new(Position::new(1, 16), Position::new(1, 30))));
+
+        assert!(super::hover(src, src.find("class").unwrap(), &HackRules::default()).is_none());
+    }
+}
@@ -0,0 +1,27 @@
+//! Language server for Nemesis patch files(`#NNNN.txt`) over stdio.
+//!
+//! - Diagnostics on open/save by the lints of `nemesis_xml`.
+//! - Completion of the field names in `<hkparam name="...">` and the enum values.
+//! - Hover docs of the classes and the field types.
+//! - Go to definition of the objects(`#NNNN`, `#<mod>$N`) in the same mod folder.
+mod completion;
+mod definition;
+mod diagnostics;
+mod document;
+mod enums;
+mod error;
+mod hover;
+mod server;
+
+use std::process::ExitCode;
+
+fn main() -> ExitCode {
+    // NOTE: stdout is the transport, so errors go to stderr.
+    match server::run() {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            eprintln!("{err}");
+            ExitCode::FAILURE
+        }
+    }
+}
@@ -0,0 +1,254 @@
+//! The message loop over stdio.
+use std::path::{Path, PathBuf};
+
+use lsp_server::{Connection, ErrorCode, Message, Notification, Request, RequestId, Response};
+use lsp_types::{
+    CompletionOptions, HoverProviderCapability, OneOf, SaveOptions, ServerCapabilities,
+    TextDocumentPositionParams, TextDocumentSyncCapability, TextDocumentSyncKind,
+    TextDocumentSyncOptions, TextDocumentSyncSaveOptions,
+    notification::{
+        DidChangeTextDocument, DidCloseTextDocument, DidOpenTextDocument, DidSaveTextDocument,
+        Notification as LspNotification, PublishDiagnostics,
+    },
+    request::{Completion, GotoDefinition, HoverRequest, Request as LspRequest},
+};
+use nemesis_xml::hack::{HackOptions, HackRules};
+use snafu::ResultExt as _;
+
+use crate::{
+    completion::completions,
+    definition::definition,
+    diagnostics::lint_dir,
+    document::{Documents, to_offset},
+    error::{DisconnectedSnafu, Error, IoThreadsSnafu, JsonSnafu, ProtocolSnafu, Result},
+    hover::hover,
+};
+
+/// Serve until the client sends `exit`.
+///
+/// # Errors
+/// The client broke the protocol or the connection.
+pub(crate) fn run() -> Result<()> {
+    let (connection, io_threads) = Connection::stdio();
+
+    let capabilities =
+        serde_json::to_value(capabilities()).context(JsonSnafu { method: "initialize" })?;
+    connection.initialize(capabilities).context(ProtocolSnafu)?;
+
+    Server {
+        connection: &connection,
+        documents: Documents::default(),
+        hack_rules: HackRules::builtin(HackOptions::enable_all()),
+    }
+    .main_loop()?;
+
+    drop(connection);
+    io_threads.join().context(IoThreadsSnafu)
+}
+
+fn capabilities() -> ServerCapabilities {
+    ServerCapabilities {
+        text_document_sync: Some(TextDocumentSyncCapability::Options(TextDocumentSyncOptions {
+            open_close: Some(true),
+            change: Some(TextDocumentSyncKind::FULL),
+            save: Some(TextDocumentSyncSaveOptions::SaveOptions(SaveOptions {
+                include_text: Some(true),
+            })),
+            ..Default::default()
+        })),
+        completion_provider: Some(CompletionOptions {
+            trigger_characters: Some(vec!["\"".to_string(), ">".to_string()]),
+            ..Default::default()
+        }),
+        hover_provider: Some(HoverProviderCapability::Simple(true)),
+        definition_provider: Some(OneOf::Left(true)),
+        ..Default::default()
+    }
+}
+
+#[derive(Debug)]
+struct Server<'a> {
+    connection: &'a Connection,
+    documents: Documents,
+    /// The known mistakes that are linted as warnings, and whose fields are resolved.
+    hack_rules: HackRules,
+}
+
+impl Server<'_> {
+    fn main_loop(&mut self) -> Result<()> {
+        let connection = self.connection;
+        for message in &connection.receiver {
+            match message {
+                Message::Request(request) => {
+                    if connection.handle_shutdown(&request).context(ProtocolSnafu)? {
+                        return Ok(());
+                    }
+                    self.request(request)?;
+                }
+                Message::Notification(notification) => self.notification(notification)?,
+                Message::Response(_) => {}
+            }
+        }
+        Ok(())
+    }
+
+    fn request(&self, request: Request) -> Result<()> {
+        let Request { id, method, params } = request;
+
+        // A request with bad params is answered with an error, so that the server keeps running.
+        let response = match method.as_str() {
+            Completion::METHOD => match request_params::<Completion>(params) {
+                Ok(params) => {
+                    let items = self
+                        .at(&params.text_document_position)
+                        .map(|(_, src, offset)| completions(src, offset, &self.hack_rules));
+                    Response::new_ok(id, items)
+                }
+                Err(err) => invalid_params(id, &err),
+            },
+            HoverRequest::METHOD => match request_params::<HoverRequest>(params) {
+                Ok(params) => {
+                    let hover = self
+                        .at(&params.text_document_position_params)
+                        .and_then(|(_, src, offset)| hover(src, offset, &self.hack_rules));
+                    Response::new_ok(id, hover)
+                }
+                Err(err) => invalid_params(id, &err),
+            },
+            GotoDefinition::METHOD => match request_params::<GotoDefinition>(params) {
+                Ok(params) => {
+                    let location = self.at(&params.text_document_position_params).and_then(
+                        |(path, src, offset)| definition(&self.documents, &path, src, offset),
+                    );
+                    Response::new_ok(id, location)
+                }
+                Err(err) => invalid_params(id, &err),
+            },
+            _ => Response::new_err(
+                id,
+                ErrorCode::MethodNotFound as i32,
+                format!("Unsupported request: {method}"),
+            ),
+        };
+        self.send(response.into())
+    }
+
+    fn notification(&mut self, notification: Notification) -> Result<()> {
+        let Notification { method, params } = notification;
+
+        match method.as_str() {
+            DidOpenTextDocument::METHOD => {
+                let Some(params) = notification_params::<DidOpenTextDocument>(params) else {
+                    return Ok(());
+                };
+                if let Ok(path) = params.text_document.uri.to_file_path() {
+                    self.documents.open(path.clone(), params.text_document.text);
+                    self.publish_diagnostics(&path)?;
+                }
+            }
+            DidChangeTextDocument::METHOD => {
+                let Some(params) = notification_params::<DidChangeTextDocument>(params) else {
+                    return Ok(());
+                };
+                // `TextDocumentSyncKind::FULL`: The last change has the whole text.
+                if let (Ok(path), Some(change)) = (
+                    params.text_document.uri.to_file_path(),
+                    params.content_changes.into_iter().last(),
+                ) {
+                    self.documents.open(path, change.text);
+                }
+            }
+            DidSaveTextDocument::METHOD => {
+                let Some(params) = notification_params::<DidSaveTextDocument>(params) else {
+                    return Ok(());
+                };
+                if let Ok(path) = params.text_document.uri.to_file_path() {
+                    if let Some(text) = params.text {
+                        self.documents.open(path.clone(), text);
+                    }
+                    self.publish_diagnostics(&path)?;
+                }
+            }
+            DidCloseTextDocument::METHOD => {
+                let Some(params) = notification_params::<DidCloseTextDocument>(params) else {
+                    return Ok(());
+                };
+                if let Ok(path) = params.text_document.uri.to_file_path() {
+                    self.documents.close(&path);
+                }
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// Lint the directory of the file, since the names are resolved in it.
+    fn publish_diagnostics(&self, path: &Path) -> Result<()> {
+        for params in lint_dir(&self.documents, path, &self.hack_rules) {
+            let notification = Notification::new(PublishDiagnostics::METHOD.to_string(), params);
+            self.send(notification.into())?;
+        }
+        Ok(())
+    }
+
+    /// Get the open document and the byte offset of the position.
+    fn at(&self, position: &TextDocumentPositionParams) -> Option<(PathBuf, &str, usize)> {
+        let path = position.text_document.uri.to_file_path().ok()?;
+        let src = self.documents.get(&path)?;
+        let offset = to_offset(src, position.position);
+        Some((path, src, offset))
+    }
+
+    fn send(&self, message: Message) -> Result<()> {
+        self.connection.sender.send(message).map_err(|_| DisconnectedSnafu.build())
+    }
+}
+
+fn request_params<R: LspRequest>(params: serde_json::Value) -> Result<R::Params> {
+    serde_json::from_value(params).context(JsonSnafu { method: R::METHOD })
+}
+
+fn invalid_params(id: RequestId, err: &Error) -> Response {
+    Response::new_err(id, ErrorCode::InvalidParams as i32, err.to_string())
+}
+
+/// A notification has no response, so bad params are logged and ignored.
+fn notification_params<N: LspNotification>(params: serde_json::Value) -> Option<N::Params> {
+    match serde_json::from_value(params).context(JsonSnafu { method: N::METHOD }) {
+        Ok(params) => Some(params),
+        Err(err) => {
+            // NOTE: stdout is the transport, so errors go to stderr.
+            eprintln!("{err}");
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bad_params_keep_the_server_running() {
+        let (connection, client) = Connection::memory();
+        let mut server = Server {
+            connection: &connection,
+            documents: Documents::default(),
+            hack_rules: HackRules::default(),
+        };
+        let bad_params = serde_json::json!({ "textDocument": 0 });
+
+        let notification =
+            Notification::new(DidOpenTextDocument::METHOD.to_string(), bad_params.clone());
+        assert!(server.notification(notification).is_ok());
+        assert!(client.receiver.try_recv().is_err());
+
+        let request =
+            Request::new(RequestId::from(1), HoverRequest::METHOD.to_string(), bad_params);
+        assert!(server.request(request).is_ok());
+        let Ok(Message::Response(response)) = client.receiver.try_recv() else {
+            panic!("Expected a response");
+        };
+        assert_eq!(response.error.map(|err| err.code), Some(ErrorCode::InvalidParams as i32));
+    }
+}